@@ -0,0 +1,198 @@
+use std::path::{Path, PathBuf};
+
+use crypto_crawler::{Compression, CrawlJob, FileFormat};
+use serde::Deserialize;
+
+/// Where messages are written.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub(crate) enum OutputConfig {
+    /// One JSON message per line
+    Stdout,
+    /// Hourly files written by `FileSink`
+    File {
+        /// `data_dir` by default
+        dir: Option<PathBuf>,
+        #[serde(default)]
+        format: FileFormat,
+        #[serde(default)]
+        compression: Compression,
+        /// Uncompressed bytes per file, unlimited by default
+        max_size: Option<u64>,
+    },
+    /// Hourly files written by `ParquetSink`
+    #[cfg(feature = "parquet")]
+    Parquet {
+        /// `data_dir` by default
+        dir: Option<PathBuf>,
+        #[serde(default)]
+        mode: crypto_crawler::ParquetMode,
+        row_group_size: Option<usize>,
+    },
+    /// One JSON message per line to a TCP server
+    Tcp { addr: String },
+    /// One JSON message per line to a Unix domain socket
+    #[cfg(unix)]
+    Unix { path: PathBuf },
+    /// `XADD` to a Redis stream
+    Redis {
+        /// `127.0.0.1:6379` by default
+        #[serde(default = "default_redis_addr")]
+        addr: String,
+        key: String,
+        /// Approximate maximum length of the stream, unlimited by default
+        max_len: Option<usize>,
+    },
+}
+
+fn default_redis_addr() -> String {
+    "127.0.0.1:6379".to_string()
+}
+
+/// The config file, in TOML or YAML format by its extension.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct Config {
+    /// Lock files and output files, the system temporary directory by default
+    pub(crate) data_dir: Option<PathBuf>,
+    /// How many times RESTful requests are tried, 5 by default
+    pub(crate) rest_retry_count: Option<u32>,
+    /// A `socks5://` proxy of websocket and RESTful connections
+    pub(crate) proxy: Option<String>,
+    /// Seconds between checks of listed and delisted symbols, 3600 by default
+    pub(crate) symbol_discovery_interval: Option<u64>,
+    #[serde(default)]
+    pub(crate) jobs: Vec<CrawlJob>,
+    #[serde(default = "default_outputs")]
+    pub(crate) outputs: Vec<OutputConfig>,
+}
+
+fn default_outputs() -> Vec<OutputConfig> {
+    vec![OutputConfig::Stdout]
+}
+
+impl Config {
+    pub(crate) fn parse(path: &Path, text: &str) -> Result<Self, String> {
+        let config: Config = match path.extension().and_then(|ext| ext.to_str()) {
+            Some("toml") => toml::from_str(text).map_err(|err| err.to_string())?,
+            Some("yaml" | "yml") => serde_yaml::from_str(text).map_err(|err| err.to_string())?,
+            _ => return Err(format!("{} is neither .toml nor .yaml", path.display())),
+        };
+        if let Some(proxy) = config.proxy.as_deref() {
+            check_proxy(proxy)?;
+        }
+        Ok(config)
+    }
+
+    pub(crate) fn load(path: &Path) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|err| format!("Failed to read {}, {}", path.display(), err))?;
+        Self::parse(path, &text)
+    }
+}
+
+// Websocket clients connect through SOCKS5 proxies only
+fn check_proxy(proxy: &str) -> Result<(), String> {
+    let url = reqwest::Url::parse(proxy).map_err(|err| format!("Invalid proxy {proxy}, {err}"))?;
+    if url.scheme() != "socks5" || url.host_str().is_none() {
+        return Err(format!("Unsupported proxy {proxy}, only socks5://host:port is supported"));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use crypto_crawler::{Compression, CrawlJob, FileFormat, MarketType, MessageType};
+
+    use super::{Config, OutputConfig};
+
+    #[test]
+    fn test_toml() {
+        let text = r#"
+data_dir = "/data"
+rest_retry_count = 3
+symbol_discovery_interval = 600
+
+[[jobs]]
+exchange = "binance"
+market_type = "spot"
+msg_type = "trade"
+symbols = ["BTCUSDT", "ETHUSDT"]
+
+[[jobs]]
+exchange = "okx"
+market_type = "linear_swap"
+msg_type = "candlestick"
+intervals = [60]
+
+[[outputs]]
+type = "file"
+compression = "zstd"
+"#;
+        let config = Config::parse(Path::new("crawler.toml"), text).unwrap();
+        assert_eq!(Some(Path::new("/data").to_path_buf()), config.data_dir);
+        assert_eq!(Some(3), config.rest_retry_count);
+        assert_eq!(Some(600), config.symbol_discovery_interval);
+        assert_eq!(
+            vec![
+                CrawlJob::new("binance", MarketType::Spot, MessageType::Trade)
+                    .with_symbols(&["BTCUSDT".to_string(), "ETHUSDT".to_string()]),
+                CrawlJob::new("okx", MarketType::LinearSwap, MessageType::Candlestick)
+                    .with_intervals(&[60]),
+            ],
+            config.jobs
+        );
+        assert_eq!(
+            vec![OutputConfig::File {
+                dir: None,
+                format: FileFormat::Tsv,
+                compression: Compression::Zstd,
+                max_size: None
+            }],
+            config.outputs
+        );
+    }
+
+    #[test]
+    fn test_yaml() {
+        let text = r#"
+proxy: socks5://127.0.0.1:9050
+jobs:
+  - exchange: bitmex
+    market_type: unknown
+    msg_type: l2_event
+outputs:
+  - type: stdout
+  - type: redis
+    key: bitmex
+    max_len: 10000
+"#;
+        let config = Config::parse(Path::new("crawler.yaml"), text).unwrap();
+        assert_eq!(Some("socks5://127.0.0.1:9050".to_string()), config.proxy);
+        assert_eq!(
+            vec![CrawlJob::new("bitmex", MarketType::Unknown, MessageType::L2Event)],
+            config.jobs
+        );
+        assert_eq!(
+            vec![
+                OutputConfig::Stdout,
+                OutputConfig::Redis {
+                    addr: "127.0.0.1:6379".to_string(),
+                    key: "bitmex".to_string(),
+                    max_len: Some(10000)
+                }
+            ],
+            config.outputs
+        );
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert!(Config::parse(Path::new("crawler.json"), "{}").is_err());
+        assert!(Config::parse(Path::new("crawler.toml"), "retry = 3").is_err());
+        assert!(Config::parse(Path::new("crawler.yaml"), "proxy: http://127.0.0.1:8080").is_err());
+        assert!(Config::parse(Path::new("crawler.yaml"), "proxy: 127.0.0.1:9050").is_err());
+    }
+}
@@ -0,0 +1,145 @@
+//! Runs crawl jobs listed in a TOML or YAML config file.
+//!
+//! ```text
+//! crypto-crawler crawler.toml
+//! ```
+//!
+//! Send SIGHUP to reload jobs from the file, which stops removed jobs and
+//! starts new ones. Other settings take effect after restarting.
+mod config;
+mod output;
+
+use std::path::Path;
+
+use crypto_crawler::{
+    set_data_dir, set_rest_retry_count, set_symbol_discovery_interval, spawn_sink, Crawler,
+};
+use log::*;
+
+use config::Config;
+
+// Receives SIGHUP on Unix, never on other platforms
+struct Hangup {
+    #[cfg(unix)]
+    signal: tokio::signal::unix::Signal,
+}
+
+impl Hangup {
+    fn new() -> Self {
+        Hangup {
+            #[cfg(unix)]
+            signal: tokio::signal::unix::signal(tokio::signal::unix::SignalKind::hangup()).unwrap(),
+        }
+    }
+
+    async fn recv(&mut self) {
+        #[cfg(unix)]
+        self.signal.recv().await;
+        #[cfg(not(unix))]
+        std::future::pending::<()>().await;
+    }
+}
+
+// Stops jobs removed from the file and starts the others
+fn reload(path: &Path, current: &Config, crawler: &mut Crawler) {
+    let config = match Config::load(path) {
+        Ok(config) => config,
+        Err(err) => {
+            error!("{}, keeping the current jobs", err);
+            return;
+        }
+    };
+    if config.data_dir != current.data_dir
+        || config.rest_retry_count != current.rest_retry_count
+        || config.proxy != current.proxy
+        || config.symbol_discovery_interval != current.symbol_discovery_interval
+        || config.outputs != current.outputs
+    {
+        warn!("Only jobs are reloaded, other settings take effect after restarting");
+    }
+
+    let removed = crawler
+        .jobs()
+        .enumerate()
+        .filter(|(_, job)| !config.jobs.contains(job))
+        .map(|(id, _)| id)
+        .collect::<Vec<usize>>();
+    for id in removed {
+        crawler.stop(id);
+    }
+    for job in config.jobs {
+        let existing = crawler.jobs().position(|existing| *existing == job);
+        let id = match existing {
+            Some(id) => id,
+            None => match crawler.add(job) {
+                Ok(id) => id,
+                Err(err) => {
+                    error!("{}", err);
+                    continue;
+                }
+            },
+        };
+        crawler.start(id);
+    }
+    info!("Reloaded {}", path.display());
+}
+
+async fn run(path: &Path, config: Config, mut crawler: Crawler) {
+    crawler.start_all();
+    let mut hangup = Hangup::new();
+    loop {
+        tokio::select! {
+            _ = hangup.recv() => reload(path, &config, &mut crawler),
+            _ = tokio::signal::ctrl_c() => break,
+        }
+    }
+    crawler.stop_all();
+}
+
+fn main() {
+    env_logger::init();
+    let path = match std::env::args().nth(1) {
+        Some(path) => std::path::PathBuf::from(path),
+        None => {
+            eprintln!("Usage: crypto-crawler <config.toml|config.yaml>");
+            std::process::exit(1);
+        }
+    };
+    let config = Config::load(&path).unwrap_or_else(|err| {
+        eprintln!("{err}");
+        std::process::exit(1);
+    });
+
+    if let Some(dir) = config.data_dir.clone() {
+        set_data_dir(dir);
+    }
+    if let Some(count) = config.rest_retry_count {
+        set_rest_retry_count(count);
+    }
+    if let Some(secs) = config.symbol_discovery_interval {
+        set_symbol_discovery_interval(std::time::Duration::from_secs(secs.max(1)));
+    }
+    if let Some(proxy) = config.proxy.as_ref() {
+        // read by websocket and RESTful clients
+        std::env::set_var("https_proxy", proxy);
+    }
+
+    let data_dir = config.data_dir.clone().unwrap_or_else(std::env::temp_dir);
+    let sink = output::create_sink(&config.outputs, &data_dir).unwrap_or_else(|err| {
+        eprintln!("Failed to open outputs, {err}");
+        std::process::exit(1);
+    });
+    let (tx, writer) = spawn_sink(sink);
+
+    let runtime = tokio::runtime::Builder::new_multi_thread().enable_all().build().unwrap();
+    runtime.block_on(async {
+        let crawler =
+            Crawler::builder().jobs(config.jobs.clone()).build(tx).unwrap_or_else(|err| {
+                eprintln!("{err}");
+                std::process::exit(1);
+            });
+        run(&path, config, crawler).await;
+    });
+    runtime.shutdown_background();
+    _ = writer.join();
+}
@@ -0,0 +1,56 @@
+use std::{io, path::Path};
+
+use crypto_crawler::{FanoutSink, FileSink, Message, RedisSink, Sink, TcpSink};
+
+use crate::config::OutputConfig;
+
+struct Stdout;
+
+impl Sink for Stdout {
+    fn write(&mut self, msg: &Message) -> io::Result<()> {
+        println!("{msg}");
+        Ok(())
+    }
+}
+
+/// Creates a sink writing to all outputs, files are in `data_dir` by default.
+pub(crate) fn create_sink(outputs: &[OutputConfig], data_dir: &Path) -> io::Result<FanoutSink> {
+    let mut sink = FanoutSink::new();
+    for output in outputs {
+        sink = match output {
+            OutputConfig::Stdout => sink.sink(Stdout),
+            OutputConfig::File { dir, format, compression, max_size } => {
+                let builder = FileSink::builder(dir.as_deref().unwrap_or(data_dir))
+                    .format(*format)
+                    .compression(*compression);
+                let builder = match max_size {
+                    Some(max_size) => builder.max_size(*max_size),
+                    None => builder,
+                };
+                sink.sink(builder.build())
+            }
+            #[cfg(feature = "parquet")]
+            OutputConfig::Parquet { dir, mode, row_group_size } => {
+                let builder =
+                    crypto_crawler::ParquetSink::builder(dir.as_deref().unwrap_or(data_dir))
+                        .mode(*mode);
+                let builder = match row_group_size {
+                    Some(row_group_size) => builder.row_group_size(*row_group_size),
+                    None => builder,
+                };
+                sink.sink(builder.build())
+            }
+            OutputConfig::Tcp { addr } => sink.sink(TcpSink::connect(addr)?),
+            #[cfg(unix)]
+            OutputConfig::Unix { path } => sink.sink(crypto_crawler::UnixSink::connect(path)?),
+            OutputConfig::Redis { addr, key, max_len } => {
+                let redis = RedisSink::connect(addr, key)?;
+                match max_len {
+                    Some(max_len) => sink.sink(redis.with_max_len(*max_len)),
+                    None => sink.sink(redis),
+                }
+            }
+        };
+    }
+    Ok(sink)
+}
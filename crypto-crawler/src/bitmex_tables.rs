@@ -0,0 +1,408 @@
+//! Local copies of BitMEX tables maintained from websocket messages.
+//!
+//! BitMEX publishes tables such as `orderBookL2`, `instrument` and `funding`
+//! as a stream of actions, see <https://www.bitmex.com/app/wsAPI#Response-Format>.
+//! A `partial` contains the whole table and its keys, following `insert`,
+//! `update` and `delete` actions change rows identified by these keys.
+
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    fmt,
+};
+
+use log::*;
+use serde_json::{Map, Value};
+
+/// The action of a table message.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TableAction {
+    Partial,
+    Insert,
+    Update,
+    Delete,
+}
+
+impl TableAction {
+    fn parse(action: &str) -> Option<Self> {
+        match action {
+            "partial" => Some(TableAction::Partial),
+            "insert" => Some(TableAction::Insert),
+            "update" => Some(TableAction::Update),
+            "delete" => Some(TableAction::Delete),
+            _ => None,
+        }
+    }
+}
+
+/// Summary of a table message applied by `BitmexTables::apply()`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TableUpdate {
+    pub table: String,
+    pub action: TableAction,
+    /// Symbols with changed rows, sorted
+    pub symbols: Vec<String>,
+    /// Rows dropped because no partial of their symbol has been received
+    pub before_partial: usize,
+}
+
+/// Changes of a table since the previous `BitmexTables::take_diff()`.
+///
+/// If `reset` is true, a new partial has been received since then, and the
+/// consumer should start over from `BitmexTables::snapshot()` instead.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TableDiff {
+    pub reset: bool,
+    /// Rows which didn't exist before
+    pub inserted: Vec<Map<String, Value>>,
+    /// Complete rows after updates
+    pub updated: Vec<Map<String, Value>>,
+    /// Key columns of removed rows
+    pub deleted: Vec<Map<String, Value>>,
+}
+
+impl TableDiff {
+    pub fn is_empty(&self) -> bool {
+        !self.reset
+            && self.inserted.is_empty()
+            && self.updated.is_empty()
+            && self.deleted.is_empty()
+    }
+}
+
+/// Errors of `BitmexTables::apply()`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TableError {
+    /// Not a valid table message.
+    Invalid(String),
+    /// An update or delete of a missing row, or an insert of an existing row.
+    ///
+    /// The local table of `symbol` is discarded until the next partial, which
+    /// needs to unsubscribe and subscribe again.
+    Inconsistent { table: String, symbol: String, key: String },
+}
+
+impl fmt::Display for TableError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TableError::Invalid(msg) => write!(f, "Invalid table message {msg}"),
+            TableError::Inconsistent { table, symbol, key } => {
+                write!(f, "Table {table} of {symbol} is inconsistent at key {key}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for TableError {}
+
+type RowKey = Vec<String>;
+
+#[derive(Default)]
+struct SymbolTable {
+    rows: BTreeMap<RowKey, Map<String, Value>>,
+    // changed keys since the last diff, and whether they existed back then
+    dirty: BTreeMap<RowKey, bool>,
+    reset: bool,
+}
+
+impl SymbolTable {
+    fn touch(&mut self, key: &RowKey) {
+        if !self.dirty.contains_key(key) {
+            self.dirty.insert(key.clone(), self.rows.contains_key(key));
+        }
+    }
+}
+
+#[derive(Default)]
+struct Table {
+    keys: Vec<String>,
+    // received a partial without a symbol filter, which covers all symbols
+    all_symbols: bool,
+    symbols: HashMap<String, SymbolTable>,
+}
+
+impl Table {
+    fn row_key(&self, row: &Map<String, Value>) -> RowKey {
+        self.keys
+            .iter()
+            .map(|key| row.get(key).map(|x| x.to_string()).unwrap_or_default())
+            .collect()
+    }
+}
+
+fn symbol_of(row: &Map<String, Value>) -> String {
+    row.get("symbol").and_then(|x| x.as_str()).unwrap_or_default().to_string()
+}
+
+/// Maintains BitMEX tables from the messages of their channels.
+///
+/// Rows are grouped by their `symbol` column, tables without this column are
+/// stored under the empty symbol. Actions of a symbol received before its
+/// partial are dropped and counted in `TableUpdate::before_partial`.
+///
+/// ```
+/// use crypto_crawler::BitmexTables;
+///
+/// let mut tables = BitmexTables::new();
+/// tables.apply(r#"{"table":"orderBookL2","action":"partial","keys":["symbol","id","side"],"data":[{"symbol":"XBTUSD","id":8799010000,"side":"Sell","size":100,"price":9900}],"filter":{"symbol":"XBTUSD"}}"#).unwrap();
+/// tables.apply(r#"{"table":"orderBookL2","action":"update","data":[{"symbol":"XBTUSD","id":8799010000,"side":"Sell","size":250}]}"#).unwrap();
+/// let rows = tables.snapshot("orderBookL2", "XBTUSD").unwrap();
+/// assert_eq!(250, rows[0]["size"]);
+/// assert_eq!(9900, rows[0]["price"]);
+/// ```
+#[derive(Default)]
+pub struct BitmexTables {
+    tables: HashMap<String, Table>,
+}
+
+impl BitmexTables {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Applies a websocket message.
+    ///
+    /// Returns `Ok(None)` for messages which are not table messages, e.g.,
+    /// subscription acks and info messages.
+    pub fn apply(&mut self, msg: &str) -> Result<Option<TableUpdate>, TableError> {
+        let obj = serde_json::from_str::<Map<String, Value>>(msg)
+            .map_err(|_| TableError::Invalid(msg.to_string()))?;
+        let (table_name, action) = match (obj.get("table"), obj.get("action")) {
+            (Some(table), Some(action)) => (table, action),
+            _ => return Ok(None),
+        };
+        let table_name =
+            table_name.as_str().ok_or_else(|| TableError::Invalid(msg.to_string()))?.to_string();
+        let action = action
+            .as_str()
+            .and_then(TableAction::parse)
+            .ok_or_else(|| TableError::Invalid(msg.to_string()))?;
+        let data = obj
+            .get("data")
+            .and_then(|x| x.as_array())
+            .ok_or_else(|| TableError::Invalid(msg.to_string()))?;
+        let mut rows = Vec::with_capacity(data.len());
+        for row in data {
+            rows.push(row.as_object().ok_or_else(|| TableError::Invalid(msg.to_string()))?);
+        }
+
+        let table = self.tables.entry(table_name.clone()).or_default();
+        let mut symbols = HashSet::<String>::new();
+        let mut before_partial = 0;
+
+        if action == TableAction::Partial {
+            table.keys = obj
+                .get("keys")
+                .and_then(|x| x.as_array())
+                .map(|keys| keys.iter().filter_map(|x| x.as_str()).map(|x| x.to_string()).collect())
+                .unwrap_or_default();
+            let filter_symbol = obj
+                .get("filter")
+                .and_then(|x| x.get("symbol"))
+                .and_then(|x| x.as_str())
+                .map(|x| x.to_string());
+            match filter_symbol.as_ref() {
+                Some(symbol) => {
+                    symbols.insert(symbol.clone());
+                }
+                None => {
+                    table.all_symbols = true;
+                    symbols.extend(table.symbols.keys().cloned());
+                }
+            }
+            symbols.extend(rows.iter().map(|row| symbol_of(row)));
+            for symbol in symbols.iter() {
+                table
+                    .symbols
+                    .insert(symbol.clone(), SymbolTable { reset: true, ..Default::default() });
+            }
+            for row in rows {
+                let key = table.row_key(row);
+                let symbol_table = table.symbols.get_mut(&symbol_of(row)).unwrap();
+                symbol_table.rows.insert(key, row.clone());
+            }
+        } else {
+            for row in rows {
+                let symbol = symbol_of(row);
+                let key = table.row_key(row);
+                if !table.symbols.contains_key(&symbol) {
+                    if table.all_symbols && action == TableAction::Insert {
+                        // a new symbol of a table with a partial of all symbols
+                        table.symbols.insert(symbol.clone(), Default::default());
+                    } else {
+                        before_partial += 1;
+                        continue;
+                    }
+                }
+                let symbol_table = table.symbols.get_mut(&symbol).unwrap();
+                symbol_table.touch(&key);
+                let consistent = match action {
+                    TableAction::Insert => {
+                        symbol_table.rows.insert(key.clone(), row.clone()).is_none()
+                    }
+                    TableAction::Update => match symbol_table.rows.get_mut(&key) {
+                        Some(existing) => {
+                            for (field, value) in row {
+                                existing.insert(field.clone(), value.clone());
+                            }
+                            true
+                        }
+                        None => false,
+                    },
+                    TableAction::Delete => symbol_table.rows.remove(&key).is_some(),
+                    TableAction::Partial => unreachable!(),
+                };
+                if !consistent {
+                    table.symbols.remove(&symbol);
+                    table.all_symbols = false;
+                    return Err(TableError::Inconsistent {
+                        table: table_name,
+                        symbol,
+                        key: key.join(","),
+                    });
+                }
+                symbols.insert(symbol);
+            }
+            if before_partial > 0 {
+                warn!(
+                    "Dropped {} rows of {} received before the partial",
+                    before_partial, table_name
+                );
+            }
+        }
+
+        let mut symbols = symbols.into_iter().collect::<Vec<String>>();
+        symbols.sort();
+        Ok(Some(TableUpdate { table: table_name, action, symbols, before_partial }))
+    }
+
+    /// Symbols of a table which have received a partial.
+    pub fn symbols(&self, table: &str) -> Vec<String> {
+        let mut symbols = self
+            .tables
+            .get(table)
+            .map(|table| table.symbols.keys().cloned().collect::<Vec<String>>())
+            .unwrap_or_default();
+        symbols.sort();
+        symbols
+    }
+
+    /// All rows of a symbol ordered by the keys of the table, returns `None`
+    /// if no partial of this symbol has been received.
+    pub fn snapshot(&self, table: &str, symbol: &str) -> Option<Vec<Map<String, Value>>> {
+        let symbol_table = self.tables.get(table)?.symbols.get(symbol)?;
+        Some(symbol_table.rows.values().cloned().collect())
+    }
+
+    /// Changes of a symbol since the previous call, returns `None` if no
+    /// partial of this symbol has been received.
+    ///
+    /// Multiple actions on the same row are merged, e.g., a row inserted and
+    /// then deleted doesn't appear at all.
+    pub fn take_diff(&mut self, table: &str, symbol: &str) -> Option<TableDiff> {
+        let table = self.tables.get_mut(table)?;
+        let keys = &table.keys;
+        let symbol_table = table.symbols.get_mut(symbol)?;
+        let mut diff = TableDiff { reset: symbol_table.reset, ..Default::default() };
+        symbol_table.reset = false;
+        for (key, existed) in std::mem::take(&mut symbol_table.dirty) {
+            match (symbol_table.rows.get(&key), existed) {
+                (Some(row), false) => diff.inserted.push(row.clone()),
+                (Some(row), true) => diff.updated.push(row.clone()),
+                (None, true) => diff.deleted.push(
+                    keys.iter()
+                        .zip(key.iter())
+                        .map(|(name, value)| {
+                            (name.clone(), serde_json::from_str(value).unwrap_or(Value::Null))
+                        })
+                        .collect(),
+                ),
+                (None, false) => (),
+            }
+        }
+        Some(diff)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BitmexTables, TableAction, TableError};
+
+    const PARTIAL: &str = r#"{"table":"orderBookL2","action":"partial","keys":["symbol","id","side"],"types":{"symbol":"symbol","id":"long","side":"symbol","size":"long","price":"float","timestamp":"timestamp"},"filter":{"symbol":"XBTUSD"},"data":[{"symbol":"XBTUSD","id":8799010000,"side":"Sell","size":100,"price":9900,"timestamp":"2023-02-21T17:20:00.123Z"},{"symbol":"XBTUSD","id":8799010050,"side":"Buy","size":300,"price":9899.5,"timestamp":"2023-02-21T17:20:00.123Z"}]}"#;
+
+    #[test]
+    fn test_order_book_l2() {
+        let mut tables = BitmexTables::new();
+        assert_eq!(
+            None,
+            tables.apply(r#"{"success":true,"subscribe":"orderBookL2:XBTUSD"}"#).unwrap()
+        );
+
+        // before the partial
+        let update = tables.apply(r#"{"table":"orderBookL2","action":"update","data":[{"symbol":"XBTUSD","id":8799010000,"side":"Sell","size":50}]}"#).unwrap().unwrap();
+        assert_eq!(1, update.before_partial);
+        assert!(update.symbols.is_empty());
+        assert!(tables.snapshot("orderBookL2", "XBTUSD").is_none());
+
+        let update = tables.apply(PARTIAL).unwrap().unwrap();
+        assert_eq!(TableAction::Partial, update.action);
+        assert_eq!(vec!["XBTUSD".to_string()], update.symbols);
+        assert_eq!(2, tables.snapshot("orderBookL2", "XBTUSD").unwrap().len());
+        let diff = tables.take_diff("orderBookL2", "XBTUSD").unwrap();
+        assert!(diff.reset);
+
+        tables.apply(r#"{"table":"orderBookL2","action":"update","data":[{"symbol":"XBTUSD","id":8799010000,"side":"Sell","size":250,"timestamp":"2023-02-21T17:20:01.123Z"}]}"#).unwrap();
+        tables.apply(r#"{"table":"orderBookL2","action":"insert","data":[{"symbol":"XBTUSD","id":8799010100,"side":"Buy","size":10,"price":9899,"timestamp":"2023-02-21T17:20:01.123Z"}]}"#).unwrap();
+        tables.apply(r#"{"table":"orderBookL2","action":"delete","data":[{"symbol":"XBTUSD","id":8799010050,"side":"Buy"}]}"#).unwrap();
+        // inserted and deleted between two diffs
+        tables.apply(r#"{"table":"orderBookL2","action":"insert","data":[{"symbol":"XBTUSD","id":8799010150,"side":"Buy","size":10,"price":9898.5,"timestamp":"2023-02-21T17:20:01.123Z"}]}"#).unwrap();
+        tables.apply(r#"{"table":"orderBookL2","action":"delete","data":[{"symbol":"XBTUSD","id":8799010150,"side":"Buy"}]}"#).unwrap();
+
+        let rows = tables.snapshot("orderBookL2", "XBTUSD").unwrap();
+        assert_eq!(2, rows.len());
+        assert_eq!(250, rows[0]["size"]);
+        assert_eq!(9900, rows[0]["price"]);
+        assert_eq!(8799010100u64, rows[1]["id"]);
+
+        let diff = tables.take_diff("orderBookL2", "XBTUSD").unwrap();
+        assert!(!diff.reset);
+        assert_eq!(1, diff.inserted.len());
+        assert_eq!(1, diff.updated.len());
+        assert_eq!(250, diff.updated[0]["size"]);
+        assert_eq!(1, diff.deleted.len());
+        assert_eq!(8799010050u64, diff.deleted[0]["id"]);
+        assert_eq!("Buy", diff.deleted[0]["side"]);
+        assert!(tables.take_diff("orderBookL2", "XBTUSD").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_inconsistent() {
+        let mut tables = BitmexTables::new();
+        tables.apply(PARTIAL).unwrap();
+        let err = tables
+            .apply(r#"{"table":"orderBookL2","action":"update","data":[{"symbol":"XBTUSD","id":1,"side":"Buy","size":50}]}"#)
+            .unwrap_err();
+        assert!(matches!(err, TableError::Inconsistent { .. }));
+        // discarded until the next partial
+        assert!(tables.snapshot("orderBookL2", "XBTUSD").is_none());
+        tables.apply(PARTIAL).unwrap();
+        assert_eq!(2, tables.snapshot("orderBookL2", "XBTUSD").unwrap().len());
+    }
+
+    #[test]
+    fn test_all_symbols() {
+        let mut tables = BitmexTables::new();
+        tables.apply(r#"{"table":"instrument","action":"partial","keys":["symbol"],"filter":{},"data":[{"symbol":"XBTUSD","state":"Open","markPrice":9900.1},{"symbol":"ETHUSD","state":"Open","markPrice":650.2}]}"#).unwrap();
+        tables.apply(r#"{"table":"instrument","action":"update","data":[{"symbol":"XBTUSD","markPrice":9901.5}]}"#).unwrap();
+        // a new listing
+        let update = tables.apply(r#"{"table":"instrument","action":"insert","data":[{"symbol":"SOLUSD","state":"Open","markPrice":20.1}]}"#).unwrap().unwrap();
+        assert_eq!(0, update.before_partial);
+        assert_eq!(
+            vec!["ETHUSD".to_string(), "SOLUSD".to_string(), "XBTUSD".to_string()],
+            tables.symbols("instrument")
+        );
+        let rows = tables.snapshot("instrument", "XBTUSD").unwrap();
+        assert_eq!(9901.5, rows[0]["markPrice"]);
+        assert_eq!("Open", rows[0]["state"]);
+        assert!(tables.apply("not json").is_err());
+    }
+}
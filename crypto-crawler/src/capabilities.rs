@@ -0,0 +1,412 @@
+use std::fmt;
+
+use crypto_market_type::{get_market_types, MarketType};
+use crypto_msg_type::MessageType;
+
+// Exchanges of each message type, all crawl_*() functions check arguments
+// against this table before crawling
+const CHANNELS: &[(MessageType, &[&str])] = &[
+    (
+        MessageType::Trade,
+        &[
+            "binance",
+            "bitfinex",
+            "bitget",
+            "bithumb",
+            "bitmex",
+            "bitstamp",
+            "bitz",
+            "bybit",
+            "coinbase_pro",
+            "deribit",
+            "dydx",
+            "ftx",
+            "gate",
+            "huobi",
+            "kraken",
+            "kucoin",
+            "mexc",
+            "okx",
+            "zb",
+            "zbg",
+        ],
+    ),
+    (
+        MessageType::L2Event,
+        &[
+            "binance",
+            "bitfinex",
+            "bitget",
+            "bithumb",
+            "bitmex",
+            "bitstamp",
+            "bitz",
+            "bybit",
+            "coinbase_pro",
+            "deribit",
+            "dydx",
+            "ftx",
+            "gate",
+            "huobi",
+            "kraken",
+            "kucoin",
+            "mexc",
+            "okx",
+            "zb",
+            "zbg",
+        ],
+    ),
+    (
+        MessageType::L2Snapshot,
+        &[
+            "binance",
+            "bitfinex",
+            "bithumb",
+            "bitmex",
+            "bitstamp",
+            "bitz",
+            "bybit",
+            "coinbase_pro",
+            "deribit",
+            "dydx",
+            "ftx",
+            "gate",
+            "huobi",
+            "kraken",
+            "kucoin",
+            "mexc",
+            "okx",
+            "zb",
+            "zbg",
+        ],
+    ),
+    (
+        MessageType::L2TopK,
+        &[
+            "binance", "bitget", "bitmex", "bitstamp", "deribit", "gate", "huobi", "kucoin",
+            "mexc", "okx", "zb",
+        ],
+    ),
+    (MessageType::L3Event, &["bitfinex", "bitstamp", "coinbase_pro"]),
+    (MessageType::L3Snapshot, &["bitfinex", "bitstamp", "coinbase_pro", "kucoin"]),
+    (
+        MessageType::BBO,
+        &["binance", "bitmex", "deribit", "ftx", "gate", "huobi", "kraken", "kucoin", "okx"],
+    ),
+    (
+        MessageType::Ticker,
+        &[
+            "binance",
+            "bitfinex",
+            "bitget",
+            "bithumb",
+            "bitz",
+            "bybit",
+            "coinbase_pro",
+            "deribit",
+            "gate",
+            "huobi",
+            "kraken",
+            "kucoin",
+            "mexc",
+            "okx",
+            "zb",
+            "zbg",
+        ],
+    ),
+    (
+        MessageType::Candlestick,
+        &[
+            "binance", "bitfinex", "bitget", "bitmex", "bitz", "bybit", "deribit", "gate", "huobi",
+            "kraken", "kucoin", "mexc", "okx", "zb", "zbg",
+        ],
+    ),
+    (MessageType::FundingRate, &["binance", "bitmex", "huobi", "okx"]),
+    (
+        MessageType::OpenInterest,
+        &[
+            "binance", "bitget", "bitz", "bybit", "deribit", "dydx", "ftx", "gate", "huobi",
+            "kucoin", "okx", "zbg",
+        ],
+    ),
+];
+
+/// Errors of invalid arguments, returned before crawling starts.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CrawlError {
+    UnknownExchange(String),
+    UnsupportedMarketType {
+        exchange: String,
+        market_type: MarketType,
+    },
+    /// `supported_msg_types()` lists message types of a market.
+    UnsupportedMsgType {
+        exchange: String,
+        market_type: MarketType,
+        msg_type: MessageType,
+    },
+    /// Symbols not trading in the market.
+    InvalidSymbols {
+        exchange: String,
+        market_type: MarketType,
+        symbols: Vec<String>,
+    },
+    /// `sync_l2_orderbook()` doesn't support the market.
+    UnsupportedOrderBookSync {
+        exchange: String,
+        market_type: MarketType,
+    },
+}
+
+impl fmt::Display for CrawlError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CrawlError::UnknownExchange(exchange) => write!(f, "Unknown exchange {exchange}"),
+            CrawlError::UnsupportedMarketType { exchange, market_type } => {
+                write!(f, "{exchange} does NOT have the {market_type} market type")
+            }
+            CrawlError::UnsupportedMsgType { exchange, market_type, msg_type } => {
+                write!(f, "{exchange} {market_type} does NOT have {msg_type} messages")
+            }
+            CrawlError::InvalidSymbols { exchange, market_type, symbols } => {
+                write!(f, "Invalid symbols of {exchange} {market_type}: {}", symbols.join(","))
+            }
+            CrawlError::UnsupportedOrderBookSync { exchange, market_type } => {
+                write!(f, "Orderbook sync of {exchange} {market_type} is not supported")
+            }
+        }
+    }
+}
+
+impl std::error::Error for CrawlError {}
+
+// Whether websocket channels of the market can be crawled
+fn has_websocket(exchange: &str, market_type: MarketType) -> bool {
+    crate::crawlers::ws_client_constructor(exchange, market_type).is_some()
+}
+
+fn market_types(exchange: &str, msg_type: MessageType) -> Vec<MarketType> {
+    let mut market_types = get_market_types(exchange);
+    match msg_type {
+        MessageType::L2Snapshot | MessageType::L3Snapshot => (),
+        MessageType::OpenInterest => {
+            market_types.retain(|market_type| *market_type != MarketType::Spot);
+            if matches!(exchange, "deribit" | "ftx" | "kucoin") {
+                // all symbols in one request
+                market_types.push(MarketType::Unknown);
+            }
+        }
+        _ => market_types.retain(|market_type| has_websocket(exchange, *market_type)),
+    }
+
+    // channels missing in some markets
+    let missing: &[MarketType] = match (exchange, msg_type) {
+        ("gate", MessageType::BBO | MessageType::L2TopK) => {
+            &[MarketType::InverseFuture, MarketType::LinearFuture]
+        }
+        ("kraken", MessageType::BBO | MessageType::Candlestick) => {
+            &[MarketType::InverseFuture, MarketType::InverseSwap]
+        }
+        ("mexc", MessageType::Ticker) => &[MarketType::Spot],
+        ("zb", MessageType::L2Event) => &[MarketType::Spot],
+        _ => &[],
+    };
+    market_types.retain(|market_type| !missing.contains(market_type));
+
+    if msg_type == MessageType::FundingRate {
+        market_types.retain(|market_type| match exchange {
+            "bitmex" => matches!(market_type, MarketType::InverseSwap | MarketType::QuantoSwap),
+            _ => matches!(market_type, MarketType::InverseSwap | MarketType::LinearSwap),
+        });
+    }
+    if exchange == "bitmex" {
+        // all symbols of a channel
+        market_types.push(MarketType::Unknown);
+    }
+    market_types
+}
+
+fn is_known_exchange(exchange: &str) -> bool {
+    CHANNELS.iter().any(|(_, exchanges)| exchanges.contains(&exchange))
+}
+
+/// Message types that `crawl_*` functions can crawl from a market.
+///
+/// `MarketType::Unknown` of BitMEX means all symbols in one subscription, so
+/// do Deribit, FTX and KuCoin open interests.
+pub fn supported_msg_types(exchange: &str, market_type: MarketType) -> Vec<MessageType> {
+    if !is_known_exchange(exchange) {
+        return Vec::new();
+    }
+    CHANNELS
+        .iter()
+        .filter(|(msg_type, exchanges)| {
+            exchanges.contains(&exchange)
+                && market_types(exchange, *msg_type).contains(&market_type)
+        })
+        .map(|(msg_type, _)| *msg_type)
+        .collect()
+}
+
+pub(crate) fn check_msg_type(
+    exchange: &str,
+    market_type: MarketType,
+    msg_type: MessageType,
+) -> Result<(), CrawlError> {
+    if !is_known_exchange(exchange) {
+        return Err(CrawlError::UnknownExchange(exchange.to_string()));
+    }
+    if market_type != MarketType::Unknown && !get_market_types(exchange).contains(&market_type) {
+        return Err(CrawlError::UnsupportedMarketType {
+            exchange: exchange.to_string(),
+            market_type,
+        });
+    }
+    if supported_msg_types(exchange, market_type).contains(&msg_type) {
+        Ok(())
+    } else {
+        Err(CrawlError::UnsupportedMsgType {
+            exchange: exchange.to_string(),
+            market_type,
+            msg_type,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crypto_market_type::{get_market_types, MarketType};
+    use crypto_msg_type::MessageType;
+    use crypto_ws_mock::{MockExchange, MockServer};
+    use tokio::time::timeout;
+
+    use super::{check_msg_type, supported_msg_types, CrawlError, CHANNELS};
+    use crate::crawlers::{panic_message, ws_client_constructor};
+
+    #[test]
+    fn test_supported_msg_types() {
+        assert_eq!(
+            vec![
+                MessageType::Trade,
+                MessageType::L2Event,
+                MessageType::L2Snapshot,
+                MessageType::L2TopK,
+                MessageType::BBO,
+                MessageType::Ticker,
+                MessageType::Candlestick,
+                MessageType::FundingRate,
+                MessageType::OpenInterest,
+            ],
+            supported_msg_types("binance", MarketType::LinearSwap)
+        );
+        assert!(
+            !supported_msg_types("binance", MarketType::Spot).contains(&MessageType::FundingRate)
+        );
+        assert!(supported_msg_types("bitmex", MarketType::Unknown).contains(&MessageType::Trade));
+        assert!(!supported_msg_types("bybit", MarketType::Spot).contains(&MessageType::Trade));
+        assert!(supported_msg_types("unknown", MarketType::Spot).is_empty());
+    }
+
+    #[test]
+    fn test_check_msg_type() {
+        assert_eq!(
+            Ok(()),
+            check_msg_type("okx", MarketType::InverseSwap, MessageType::FundingRate)
+        );
+        assert_eq!(
+            Err(CrawlError::UnknownExchange("unknown".to_string())),
+            check_msg_type("unknown", MarketType::Spot, MessageType::Trade)
+        );
+        assert_eq!(
+            Err(CrawlError::UnsupportedMarketType {
+                exchange: "bitstamp".to_string(),
+                market_type: MarketType::LinearSwap
+            }),
+            check_msg_type("bitstamp", MarketType::LinearSwap, MessageType::Trade)
+        );
+        assert_eq!(
+            Err(CrawlError::UnsupportedMsgType {
+                exchange: "okx".to_string(),
+                market_type: MarketType::Spot,
+                msg_type: MessageType::FundingRate
+            }),
+            check_msg_type("okx", MarketType::Spot, MessageType::FundingRate)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_crawl_unsupported() {
+        let (tx, _rx) = std::sync::mpsc::channel();
+        assert_eq!(
+            Err(CrawlError::UnsupportedMsgType {
+                exchange: "kraken".to_string(),
+                market_type: MarketType::InverseSwap,
+                msg_type: MessageType::BBO
+            }),
+            crate::crawl_bbo("kraken", MarketType::InverseSwap, None, tx).await
+        );
+    }
+
+    // CHANNELS agrees with websocket clients, whose missing channels panic with
+    // "does NOT have"
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_channels_of_ws_clients() {
+        let server = MockServer::start(MockExchange::new(|_| Vec::new())).await;
+        let msg_types = [
+            MessageType::Trade,
+            MessageType::L2Event,
+            MessageType::L2TopK,
+            MessageType::L3Event,
+            MessageType::BBO,
+            MessageType::Ticker,
+        ];
+        let mut exchanges =
+            CHANNELS.iter().flat_map(|(_, exchanges)| exchanges.iter()).collect::<Vec<_>>();
+        exchanges.sort();
+        exchanges.dedup();
+
+        let mut mismatches = Vec::new();
+        for exchange in exchanges {
+            for market_type in get_market_types(exchange) {
+                let Some(constructor) = ws_client_constructor(exchange, market_type) else {
+                    continue;
+                };
+                let supported = supported_msg_types(exchange, market_type);
+                for msg_type in msg_types {
+                    let url = server.url().to_string();
+                    let subscribe = tokio::task::spawn(async move {
+                        let (tx, _rx) = std::sync::mpsc::channel();
+                        let ws_client = constructor(tx, Some(url)).await;
+                        let symbols = vec!["BTCUSDT".to_string()];
+                        match msg_type {
+                            MessageType::Trade => ws_client.subscribe_trade(&symbols).await,
+                            MessageType::L2Event => ws_client.subscribe_orderbook(&symbols).await,
+                            MessageType::L2TopK => {
+                                ws_client.subscribe_orderbook_topk(&symbols).await
+                            }
+                            MessageType::L3Event => {
+                                ws_client.subscribe_l3_orderbook(&symbols).await
+                            }
+                            MessageType::BBO => ws_client.subscribe_bbo(&symbols).await,
+                            _ => ws_client.subscribe_ticker(&symbols).await,
+                        }
+                    });
+                    let has_channel = match timeout(Duration::from_secs(5), subscribe).await {
+                        Ok(Ok(())) => true,
+                        Ok(Err(err)) if err.is_panic() => {
+                            !panic_message(err.into_panic()).contains("does NOT have")
+                        }
+                        // other failures, e.g., ZBG symbols not found, are not about channels
+                        _ => continue,
+                    };
+                    if has_channel != supported.contains(&msg_type) {
+                        mismatches.push(format!("{exchange} {market_type} {msg_type}"));
+                    }
+                }
+            }
+        }
+        assert!(mismatches.is_empty(), "mismatched channels: {}", mismatches.join(", "));
+    }
+}
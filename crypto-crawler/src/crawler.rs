@@ -0,0 +1,412 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{RecvTimeoutError, Sender},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+use crypto_market_type::MarketType;
+use crypto_msg_type::MessageType;
+use serde::{Deserialize, Serialize};
+use tokio::{runtime::Handle, task::AbortHandle};
+
+use crate::{capabilities, crawlers, CrawlError, CrawlStatus, Message, SymbolEvent};
+
+/// A crawl job, i.e., arguments of a `crawl_*` function.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CrawlJob {
+    pub exchange: String,
+    pub market_type: MarketType,
+    pub msg_type: MessageType,
+    /// None or empty means all symbols
+    #[serde(default)]
+    pub symbols: Option<Vec<String>>,
+    /// Candlestick intervals in seconds, None means the default intervals
+    #[serde(default)]
+    pub intervals: Option<Vec<usize>>,
+}
+
+impl CrawlJob {
+    pub fn new(exchange: &str, market_type: MarketType, msg_type: MessageType) -> Self {
+        CrawlJob {
+            exchange: exchange.to_string(),
+            market_type,
+            msg_type,
+            symbols: None,
+            intervals: None,
+        }
+    }
+
+    pub fn with_symbols(mut self, symbols: &[String]) -> Self {
+        self.symbols = Some(symbols.to_vec());
+        self
+    }
+
+    pub fn with_intervals(mut self, intervals: &[usize]) -> Self {
+        self.intervals = Some(intervals.to_vec());
+        self
+    }
+
+    // Candlestick intervals of symbols, fetches symbols if only intervals are given
+    fn symbol_interval_list(&self) -> Option<Vec<(String, usize)>> {
+        let symbols = self.symbols.clone().filter(|symbols| !symbols.is_empty());
+        if symbols.is_none() && self.intervals.is_none() {
+            return None;
+        }
+        let symbols = symbols.unwrap_or_else(|| {
+            tokio::task::block_in_place(|| {
+                crawlers::fetch_symbols_retry(&self.exchange, self.market_type)
+            })
+        });
+        let intervals = self.intervals.clone().unwrap_or_else(|| {
+            crawlers::get_candlestick_intervals(&self.exchange, self.market_type)
+        });
+        Some(
+            symbols
+                .into_iter()
+                .flat_map(|symbol| {
+                    intervals.iter().map(move |interval| (symbol.clone(), *interval))
+                })
+                .collect(),
+        )
+    }
+}
+
+/// The state of a crawl job.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum JobStatus {
+    /// Not started yet.
+    Idle,
+    Running,
+    /// Stopped by `Crawler::stop()`.
+    Stopped,
+    /// The crawl function returned, e.g., fetching symbols failed.
+    Finished,
+    /// The crawl function returned an error or panicked.
+    Failed(String),
+}
+
+/// Builds a `Crawler` from a list of jobs.
+#[derive(Default)]
+pub struct CrawlerBuilder {
+    jobs: Vec<CrawlJob>,
+    status_tx: Option<Sender<CrawlStatus>>,
+    event_tx: Option<Sender<SymbolEvent>>,
+}
+
+impl CrawlerBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn job(mut self, job: CrawlJob) -> Self {
+        self.jobs.push(job);
+        self
+    }
+
+    pub fn jobs(mut self, jobs: impl IntoIterator<Item = CrawlJob>) -> Self {
+        self.jobs.extend(jobs);
+        self
+    }
+
+    /// Sends state changes of crawl tasks of all jobs to `tx`, see
+    /// `report_status_to()`.
+    pub fn status_sender(mut self, tx: Sender<CrawlStatus>) -> Self {
+        self.status_tx = Some(tx);
+        self
+    }
+
+    /// Sends symbols listed or delisted, found by all jobs, to `tx`, see
+    /// `report_symbol_events_to()`.
+    pub fn symbol_event_sender(mut self, tx: Sender<SymbolEvent>) -> Self {
+        self.event_tx = Some(tx);
+        self
+    }
+
+    /// Checks all jobs by `supported_msg_types()` without starting any of
+    /// them, messages of all jobs will be sent to `tx`.
+    ///
+    /// Jobs run on the current tokio runtime, or a new one if there is none.
+    pub fn build(self, tx: Sender<Message>) -> Result<Crawler, CrawlError> {
+        for job in self.jobs.iter() {
+            capabilities::check_msg_type(&job.exchange, job.market_type, job.msg_type)?;
+        }
+        let (runtime, handle) = match Handle::try_current() {
+            Ok(handle) => (None, handle),
+            Err(_) => {
+                let runtime =
+                    tokio::runtime::Builder::new_multi_thread().enable_all().build().unwrap();
+                let handle = runtime.handle().clone();
+                (Some(runtime), handle)
+            }
+        };
+        let jobs = self.jobs.into_iter().map(JobState::new).collect();
+        Ok(Crawler {
+            runtime,
+            handle,
+            tx,
+            status_tx: self.status_tx,
+            event_tx: self.event_tx,
+            jobs,
+        })
+    }
+}
+
+struct JobState {
+    job: CrawlJob,
+    status: Arc<Mutex<JobStatus>>,
+    stop: Arc<AtomicBool>,
+    task: Option<AbortHandle>,
+}
+
+impl JobState {
+    fn new(job: CrawlJob) -> Self {
+        JobState {
+            job,
+            status: Arc::new(Mutex::new(JobStatus::Idle)),
+            stop: Arc::new(AtomicBool::new(false)),
+            task: None,
+        }
+    }
+}
+
+/// Runs multiple crawl jobs with one output channel.
+///
+/// Jobs are identified by their indices in the builder. All jobs share the
+/// same REST and websocket rate limits as other crawls in the process.
+pub struct Crawler {
+    runtime: Option<tokio::runtime::Runtime>,
+    handle: Handle,
+    tx: Sender<Message>,
+    status_tx: Option<Sender<CrawlStatus>>,
+    event_tx: Option<Sender<SymbolEvent>>,
+    jobs: Vec<JobState>,
+}
+
+impl Crawler {
+    pub fn builder() -> CrawlerBuilder {
+        CrawlerBuilder::new()
+    }
+
+    pub fn jobs(&self) -> impl Iterator<Item = &CrawlJob> {
+        self.jobs.iter().map(|state| &state.job)
+    }
+
+    /// Adds a job without starting it, returns its id.
+    pub fn add(&mut self, job: CrawlJob) -> Result<usize, CrawlError> {
+        capabilities::check_msg_type(&job.exchange, job.market_type, job.msg_type)?;
+        self.jobs.push(JobState::new(job));
+        Ok(self.jobs.len() - 1)
+    }
+
+    /// Starts the job `id`, does nothing if it is running.
+    ///
+    /// Panics if `id` is out of range, and so do `stop()` and `status()`.
+    pub fn start(&mut self, id: usize) {
+        let state = &mut self.jobs[id];
+        if *state.status.lock().unwrap() == JobStatus::Running {
+            return;
+        }
+        state.stop = Arc::new(AtomicBool::new(false));
+        *state.status.lock().unwrap() = JobStatus::Running;
+
+        // Forwards messages until the job is stopped, dropping job_rx makes
+        // RESTful crawls exit on their next message
+        let (job_tx, job_rx) = std::sync::mpsc::channel::<Message>();
+        {
+            let stop = state.stop.clone();
+            let tx = self.tx.clone();
+            std::thread::spawn(move || loop {
+                match job_rx.recv_timeout(Duration::from_secs(1)) {
+                    Ok(msg) => {
+                        if stop.load(Ordering::Acquire) || tx.send(msg).is_err() {
+                            break;
+                        }
+                    }
+                    Err(RecvTimeoutError::Timeout) => {
+                        if stop.load(Ordering::Acquire) {
+                            break;
+                        }
+                    }
+                    Err(RecvTimeoutError::Disconnected) => break,
+                }
+            });
+        }
+
+        let task = self.handle.spawn(run_job_with_listeners(
+            state.job.clone(),
+            job_tx,
+            self.status_tx.clone(),
+            self.event_tx.clone(),
+        ));
+        state.task = Some(task.abort_handle());
+        let status = state.status.clone();
+        let stop = state.stop.clone();
+        self.handle.spawn(async move {
+            let result = task.await;
+            // stop() has updated the status, and the job may be running again
+            if stop.load(Ordering::Acquire) {
+                return;
+            }
+            *status.lock().unwrap() = match result {
+                Ok(Ok(())) => JobStatus::Finished,
+                Ok(Err(err)) => JobStatus::Failed(err.to_string()),
+                Err(err) if err.is_cancelled() => JobStatus::Stopped,
+                Err(err) => JobStatus::Failed(crawlers::panic_message(err.into_panic())),
+            };
+        });
+    }
+
+    /// Stops the job `id`, which can be started again.
+    pub fn stop(&mut self, id: usize) {
+        let state = &mut self.jobs[id];
+        state.stop.store(true, Ordering::Release);
+        if let Some(task) = state.task.take() {
+            task.abort();
+        }
+        let mut status = state.status.lock().unwrap();
+        if *status == JobStatus::Running {
+            *status = JobStatus::Stopped;
+        }
+    }
+
+    pub fn status(&self, id: usize) -> JobStatus {
+        self.jobs[id].status.lock().unwrap().clone()
+    }
+
+    pub fn start_all(&mut self) {
+        for id in 0..self.jobs.len() {
+            self.start(id);
+        }
+    }
+
+    pub fn stop_all(&mut self) {
+        for id in 0..self.jobs.len() {
+            self.stop(id);
+        }
+    }
+}
+
+impl Drop for Crawler {
+    fn drop(&mut self) {
+        self.stop_all();
+        if let Some(runtime) = self.runtime.take() {
+            runtime.shutdown_background();
+        }
+    }
+}
+
+// Runs the job in scopes of listeners if any
+async fn run_job_with_listeners(
+    job: CrawlJob,
+    tx: Sender<Message>,
+    status_tx: Option<Sender<CrawlStatus>>,
+    event_tx: Option<Sender<SymbolEvent>>,
+) -> Result<(), CrawlError> {
+    match (status_tx, event_tx) {
+        (Some(status_tx), Some(event_tx)) => {
+            crawlers::report_status_to(
+                status_tx,
+                crawlers::report_symbol_events_to(event_tx, run_job(job, tx)),
+            )
+            .await
+        }
+        (Some(status_tx), None) => crawlers::report_status_to(status_tx, run_job(job, tx)).await,
+        (None, Some(event_tx)) => {
+            crawlers::report_symbol_events_to(event_tx, run_job(job, tx)).await
+        }
+        (None, None) => run_job(job, tx).await,
+    }
+}
+
+async fn run_job(job: CrawlJob, tx: Sender<Message>) -> Result<(), CrawlError> {
+    let exchange = job.exchange.as_str();
+    let market_type = job.market_type;
+    let symbols = job.symbols.as_deref();
+    match job.msg_type {
+        MessageType::Trade => crate::crawl_trade(exchange, market_type, symbols, tx).await,
+        MessageType::L2Event => crate::crawl_l2_event(exchange, market_type, symbols, tx).await,
+        MessageType::L3Event => crate::crawl_l3_event(exchange, market_type, symbols, tx).await,
+        MessageType::BBO => crate::crawl_bbo(exchange, market_type, symbols, tx).await,
+        MessageType::L2TopK => crate::crawl_l2_topk(exchange, market_type, symbols, tx).await,
+        MessageType::Ticker => crate::crawl_ticker(exchange, market_type, symbols, tx).await,
+        MessageType::FundingRate => {
+            crate::crawl_funding_rate(exchange, market_type, symbols, tx).await
+        }
+        MessageType::Candlestick => {
+            let symbol_interval_list = job.symbol_interval_list();
+            crate::crawl_candlestick(exchange, market_type, symbol_interval_list.as_deref(), tx)
+                .await
+        }
+        MessageType::L2Snapshot | MessageType::L3Snapshot | MessageType::OpenInterest => {
+            // RESTful crawls block
+            let result = tokio::task::spawn_blocking(move || {
+                let exchange = job.exchange.as_str();
+                let symbols = job.symbols.as_deref();
+                match job.msg_type {
+                    MessageType::L2Snapshot => {
+                        crate::crawl_l2_snapshot(exchange, market_type, symbols, tx)
+                    }
+                    MessageType::L3Snapshot => {
+                        crate::crawl_l3_snapshot(exchange, market_type, symbols, tx)
+                    }
+                    _ => crate::crawl_open_interest(exchange, market_type, tx),
+                }
+            })
+            .await;
+            match result {
+                Ok(result) => result,
+                Err(err) => std::panic::resume_unwind(err.into_panic()),
+            }
+        }
+        msg_type => unreachable!("{msg_type} is rejected by CrawlerBuilder::build()"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crypto_market_type::MarketType;
+    use crypto_msg_type::MessageType;
+
+    use super::{CrawlJob, Crawler, JobStatus};
+    use crate::CrawlError;
+
+    #[test]
+    fn test_build() {
+        let (tx, _rx) = std::sync::mpsc::channel();
+        let crawler = Crawler::builder()
+            .job(CrawlJob::new("binance", MarketType::Spot, MessageType::Trade))
+            .job(
+                CrawlJob::new("okx", MarketType::LinearSwap, MessageType::Candlestick)
+                    .with_symbols(&["BTC-USDT-SWAP".to_string()])
+                    .with_intervals(&[60]),
+            )
+            .build(tx)
+            .unwrap();
+        assert_eq!(2, crawler.jobs().count());
+        assert_eq!(JobStatus::Idle, crawler.status(0));
+        assert_eq!(
+            Some(vec![("BTC-USDT-SWAP".to_string(), 60)]),
+            crawler.jobs().nth(1).unwrap().symbol_interval_list()
+        );
+    }
+
+    #[test]
+    fn test_build_invalid() {
+        let (tx, _rx) = std::sync::mpsc::channel();
+        let result = Crawler::builder()
+            .job(CrawlJob::new("binance", MarketType::Spot, MessageType::Trade))
+            .job(CrawlJob::new("binance", MarketType::Spot, MessageType::FundingRate))
+            .build(tx);
+        assert_eq!(
+            Some(CrawlError::UnsupportedMsgType {
+                exchange: "binance".to_string(),
+                market_type: MarketType::Spot,
+                msg_type: MessageType::FundingRate
+            }),
+            result.err()
+        );
+    }
+}
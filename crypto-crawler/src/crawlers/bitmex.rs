@@ -1,7 +1,4 @@
-use super::{
-    crawl_candlestick_ext, crawl_event,
-    utils::{check_args, fetch_symbols_retry},
-};
+use super::{crawl_candlestick_ext, crawl_event, utils::fetch_symbols_retry};
 use crate::{crawlers::utils::create_conversion_thread, msg::Message};
 use crypto_market_type::MarketType;
 use crypto_msg_type::MessageType;
@@ -92,19 +89,7 @@ pub(crate) async fn crawl_funding_rate(
         // crawl all symbols
         crawl_all(MessageType::FundingRate, tx).await;
     } else {
-        let is_empty = match symbols {
-            Some(list) => {
-                if list.is_empty() {
-                    true
-                } else {
-                    check_args(EXCHANGE_NAME, market_type, list);
-                    false
-                }
-            }
-            None => true,
-        };
-
-        let real_symbols = if is_empty {
+        let real_symbols = if symbols.is_none_or(|list| list.is_empty()) {
             tokio::task::block_in_place(move || fetch_symbols_retry(EXCHANGE_NAME, market_type))
         } else {
             symbols.unwrap().to_vec()
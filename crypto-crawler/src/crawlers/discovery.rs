@@ -0,0 +1,176 @@
+use std::{collections::HashSet, future::Future, sync::mpsc::Sender};
+
+use crypto_market_type::MarketType;
+use crypto_msg_type::MessageType;
+
+/// Whether a symbol was listed or delisted.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SymbolChange {
+    Listed,
+    Delisted,
+}
+
+/// A symbol found listed or delisted by a crawl, see
+/// `report_symbol_events_to()`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SymbolEvent {
+    pub exchange: String,
+    pub market_type: MarketType,
+    /// The message type of the crawl which found the change
+    pub msg_type: MessageType,
+    pub symbol: String,
+    pub change: SymbolChange,
+}
+
+tokio::task_local! {
+    static EVENT_SENDER: Sender<SymbolEvent>;
+}
+
+/// Runs `f`, sending symbols listed or delisted, found by crawls started by
+/// it, to `tx`.
+///
+/// Crawls of all symbols, i.e., `symbols` is `None` or empty, check listed
+/// symbols periodically, subscribe new symbols and unsubscribe delisted ones.
+/// Every crawl sends its own events, so a symbol of a market crawled for
+/// trades and L2 events is sent twice with different `msg_type`.
+pub async fn report_symbol_events_to<F: Future>(tx: Sender<SymbolEvent>, f: F) -> F::Output {
+    EVENT_SENDER.scope(tx, f).await
+}
+
+/// The listener of crawls started in the current scope of
+/// `report_symbol_events_to()`.
+pub(super) fn symbol_event_sender() -> Option<Sender<SymbolEvent>> {
+    EVENT_SENDER.try_with(|tx| tx.clone()).ok()
+}
+
+// Symbols of a crawl, updated by the latest listed symbols
+pub(super) struct Listings {
+    exchange: String,
+    market_type: MarketType,
+    msg_type: MessageType,
+    subscribed: HashSet<String>,
+    // subscribed symbols missing in the latest listed symbols
+    missing: HashSet<String>,
+    tx: Option<Sender<SymbolEvent>>,
+}
+
+impl Listings {
+    pub(super) fn new(
+        exchange: &str,
+        market_type: MarketType,
+        msg_type: MessageType,
+        symbols: &[String],
+        tx: Option<Sender<SymbolEvent>>,
+    ) -> Self {
+        Listings {
+            exchange: exchange.to_string(),
+            market_type,
+            msg_type,
+            subscribed: symbols.iter().cloned().collect(),
+            missing: HashSet::new(),
+            tx,
+        }
+    }
+
+    /// Returns listed and delisted symbols.
+    ///
+    /// A symbol is delisted after missing twice in a row, since exchanges
+    /// sometimes return incomplete lists. An empty list is ignored, which is
+    /// returned after failed requests.
+    pub(super) fn update(&mut self, latest: &[String]) -> (Vec<String>, Vec<String>) {
+        if latest.is_empty() {
+            return (Vec::new(), Vec::new());
+        }
+        let latest_set = latest.iter().collect::<HashSet<&String>>();
+        let listed = latest
+            .iter()
+            .filter(|symbol| !self.subscribed.contains(*symbol))
+            .cloned()
+            .collect::<Vec<String>>();
+        let mut delisted = Vec::new();
+        let mut missing = HashSet::new();
+        for symbol in self.subscribed.iter().filter(|symbol| !latest_set.contains(symbol)) {
+            if self.missing.contains(symbol) {
+                delisted.push(symbol.clone());
+            } else {
+                missing.insert(symbol.clone());
+            }
+        }
+        delisted.sort();
+        self.missing = missing;
+        for symbol in delisted.iter() {
+            self.subscribed.remove(symbol);
+        }
+        self.subscribed.extend(listed.iter().cloned());
+
+        self.send(&listed, SymbolChange::Listed);
+        self.send(&delisted, SymbolChange::Delisted);
+        (listed, delisted)
+    }
+
+    fn send(&self, symbols: &[String], change: SymbolChange) {
+        if let Some(tx) = self.tx.as_ref() {
+            for symbol in symbols {
+                _ = tx.send(SymbolEvent {
+                    exchange: self.exchange.clone(),
+                    market_type: self.market_type,
+                    msg_type: self.msg_type,
+                    symbol: symbol.clone(),
+                    change,
+                });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crypto_market_type::MarketType;
+    use crypto_msg_type::MessageType;
+
+    use super::{Listings, SymbolChange};
+
+    fn to_strings(symbols: &[&str]) -> Vec<String> {
+        symbols.iter().map(|symbol| symbol.to_string()).collect()
+    }
+
+    #[test]
+    fn test_update() {
+        let (tx, rx) = std::sync::mpsc::channel();
+        let mut listings = Listings::new(
+            "binance",
+            MarketType::Spot,
+            MessageType::Trade,
+            &to_strings(&["BTCUSDT", "ETHUSDT", "LUNAUSDT"]),
+            Some(tx),
+        );
+
+        let (listed, delisted) = listings.update(&to_strings(&["BTCUSDT", "ETHUSDT", "SUIUSDT"]));
+        assert_eq!(to_strings(&["SUIUSDT"]), listed);
+        assert!(delisted.is_empty());
+        // failed requests return nothing
+        assert_eq!((Vec::new(), Vec::new()), listings.update(&[]));
+        let (listed, delisted) = listings.update(&to_strings(&["BTCUSDT", "SUIUSDT"]));
+        assert!(listed.is_empty());
+        assert_eq!(to_strings(&["LUNAUSDT"]), delisted);
+        // listed again
+        let (listed, delisted) = listings.update(&to_strings(&["BTCUSDT", "SUIUSDT", "ETHUSDT"]));
+        assert!(listed.is_empty());
+        assert!(delisted.is_empty());
+        let (listed, _) = listings.update(&to_strings(&["BTCUSDT", "SUIUSDT", "LUNAUSDT"]));
+        assert_eq!(to_strings(&["LUNAUSDT"]), listed);
+
+        let events = rx
+            .try_iter()
+            .map(|event| (event.symbol, event.change))
+            .collect::<Vec<(String, SymbolChange)>>();
+        assert_eq!(
+            vec![
+                ("SUIUSDT".to_string(), SymbolChange::Listed),
+                ("LUNAUSDT".to_string(), SymbolChange::Delisted),
+                ("LUNAUSDT".to_string(), SymbolChange::Listed),
+            ],
+            events
+        );
+    }
+}
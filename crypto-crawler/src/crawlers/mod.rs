@@ -1,5 +1,9 @@
+mod discovery;
+mod supervisor;
+mod symbol;
 #[macro_use]
 mod utils;
+mod watchdog;
 
 pub(super) mod binance;
 pub(super) mod bitmex;
@@ -10,8 +14,12 @@ pub(super) mod okx;
 pub(super) mod zb;
 pub(super) mod zbg;
 
+pub use discovery::{report_symbol_events_to, SymbolChange, SymbolEvent};
+pub(super) use supervisor::panic_message;
+pub use supervisor::{report_status_to, CrawlStatus, TaskState};
 pub use utils::fetch_symbols_retry;
 pub(super) use utils::{
-    crawl_candlestick_ext, crawl_event, crawl_open_interest, crawl_snapshot,
-    create_ws_client_symbol,
+    check_symbols, crawl_candlestick_ext, crawl_event, crawl_open_interest, crawl_snapshot,
+    create_ws_client_symbol, get_candlestick_intervals, get_cooldown_time_per_request,
+    ws_client_constructor,
 };
@@ -0,0 +1,229 @@
+use std::{
+    any::Any,
+    future::Future,
+    sync::mpsc::Sender,
+    time::{Duration, Instant},
+};
+
+use crypto_market_type::MarketType;
+use crypto_msg_type::MessageType;
+use log::*;
+
+// Backoff before restarting a failed task, doubled after every failure
+const MIN_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_BACKOFF: Duration = Duration::from_secs(300);
+// A task running longer than this before failing starts from MIN_BACKOFF again
+const HEALTHY_DURATION: Duration = Duration::from_secs(600);
+
+/// The state of a crawl task.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TaskState {
+    /// The task started, `restarts` is the number of restarts so far.
+    Started { restarts: u32 },
+    /// The task failed and will be restarted after `backoff`.
+    Failed { error: String, backoff: Duration },
+    /// The task exited normally and will not be restarted, e.g., the receiver
+    /// of messages was dropped.
+    Stopped,
+}
+
+/// A state change of a crawl task, see `report_status_to()`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CrawlStatus {
+    pub exchange: String,
+    pub market_type: MarketType,
+    pub msg_type: MessageType,
+    /// `chunk-N` for the Nth websocket connection of a crawl
+    pub task: String,
+    pub state: TaskState,
+}
+
+tokio::task_local! {
+    static STATUS_SENDER: Sender<CrawlStatus>;
+}
+
+/// Runs `f`, sending state changes of crawl tasks started by it to `tx`.
+///
+/// Every websocket connection of a crawl is a task, a failed task is
+/// restarted with exponential backoff while the other tasks keep running.
+/// Crawls in different scopes report to their own listeners.
+///
+/// ```no_run
+/// use crypto_crawler::{crawl_trade, report_status_to, MarketType};
+///
+/// # async fn example() {
+/// let (tx, _rx) = std::sync::mpsc::channel();
+/// let (status_tx, _status_rx) = std::sync::mpsc::channel();
+/// report_status_to(status_tx, crawl_trade("binance", MarketType::Spot, None, tx)).await.unwrap();
+/// # }
+/// ```
+pub async fn report_status_to<F: Future>(tx: Sender<CrawlStatus>, f: F) -> F::Output {
+    STATUS_SENDER.scope(tx, f).await
+}
+
+/// The listener of crawls started in the current scope of `report_status_to()`.
+pub(super) fn status_sender() -> Option<Sender<CrawlStatus>> {
+    STATUS_SENDER.try_with(|tx| tx.clone()).ok()
+}
+
+#[derive(Clone)]
+pub(super) struct StatusReporter {
+    exchange: String,
+    market_type: MarketType,
+    msg_type: MessageType,
+    tx: Option<Sender<CrawlStatus>>,
+}
+
+impl StatusReporter {
+    pub(super) fn new(
+        exchange: &str,
+        market_type: MarketType,
+        msg_type: MessageType,
+        tx: Option<Sender<CrawlStatus>>,
+    ) -> Self {
+        StatusReporter { exchange: exchange.to_string(), market_type, msg_type, tx }
+    }
+
+    fn report(&self, task: &str, state: TaskState) {
+        if let Some(tx) = self.tx.as_ref() {
+            // a crawl keeps running after its listener has gone
+            _ = tx.send(CrawlStatus {
+                exchange: self.exchange.clone(),
+                market_type: self.market_type,
+                msg_type: self.msg_type,
+                task: task.to_string(),
+                state,
+            });
+        }
+    }
+}
+
+pub(crate) fn panic_message(panic: Box<dyn Any + Send>) -> String {
+    if let Some(msg) = panic.downcast_ref::<&str>() {
+        msg.to_string()
+    } else if let Some(msg) = panic.downcast_ref::<String>() {
+        msg.clone()
+    } else {
+        "unknown panic".to_string()
+    }
+}
+
+// Aborts the task when the supervisor itself is aborted
+struct AbortOnDrop<T>(tokio::task::JoinHandle<T>);
+
+impl<T> Drop for AbortOnDrop<T> {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
+/// Runs the task returned by `start` until it exits normally, restarts it if
+/// it fails or panics.
+pub(super) async fn supervise<F, Fut>(reporter: StatusReporter, task: String, mut start: F)
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<(), String>> + Send + 'static,
+{
+    let mut restarts = 0;
+    let mut backoff = MIN_BACKOFF;
+    loop {
+        reporter.report(&task, TaskState::Started { restarts });
+        let started_at = Instant::now();
+        let mut handle = AbortOnDrop(tokio::task::spawn(start()));
+        let error = match (&mut handle.0).await {
+            Ok(Ok(())) => {
+                reporter.report(&task, TaskState::Stopped);
+                break;
+            }
+            Ok(Err(error)) => error,
+            Err(err) if err.is_cancelled() => break,
+            Err(err) => panic_message(err.into_panic()),
+        };
+        if started_at.elapsed() >= HEALTHY_DURATION {
+            backoff = MIN_BACKOFF;
+        }
+        error!(
+            "{} {} {} {} failed, restarting in {:?}, {}",
+            reporter.exchange, reporter.market_type, reporter.msg_type, task, backoff, error
+        );
+        reporter.report(&task, TaskState::Failed { error, backoff });
+        tokio::time::sleep(backoff).await;
+        backoff = (backoff * 2).min(MAX_BACKOFF);
+        restarts += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::{
+            atomic::{AtomicU32, Ordering},
+            Arc,
+        },
+        time::Duration,
+    };
+
+    use crypto_market_type::MarketType;
+    use crypto_msg_type::MessageType;
+
+    use super::{report_status_to, status_sender, supervise, StatusReporter, TaskState};
+
+    #[tokio::test(start_paused = true)]
+    async fn test_supervise() {
+        let (tx, rx) = std::sync::mpsc::channel();
+        let reporter =
+            StatusReporter::new("binance", MarketType::Spot, MessageType::Trade, Some(tx));
+        let attempts = Arc::new(AtomicU32::new(0));
+        supervise(reporter, "chunk-0".to_string(), || {
+            let attempts = attempts.clone();
+            async move {
+                match attempts.fetch_add(1, Ordering::SeqCst) {
+                    0 => panic!("connection lost"),
+                    1 => Err("connection closed".to_string()),
+                    _ => Ok(()),
+                }
+            }
+        })
+        .await;
+
+        let states = rx.try_iter().map(|status| status.state).collect::<Vec<TaskState>>();
+        assert_eq!(
+            vec![
+                TaskState::Started { restarts: 0 },
+                TaskState::Failed {
+                    error: "connection lost".to_string(),
+                    backoff: Duration::from_secs(1)
+                },
+                TaskState::Started { restarts: 1 },
+                TaskState::Failed {
+                    error: "connection closed".to_string(),
+                    backoff: Duration::from_secs(2)
+                },
+                TaskState::Started { restarts: 2 },
+                TaskState::Stopped,
+            ],
+            states
+        );
+    }
+
+    #[tokio::test]
+    async fn test_report_status_to() {
+        assert!(status_sender().is_none());
+        let report = |exchange: &'static str| async move {
+            StatusReporter::new(exchange, MarketType::Spot, MessageType::Trade, status_sender())
+                .report("chunk-0", TaskState::Stopped);
+        };
+        // concurrent crawls with their own listeners
+        let (binance_tx, binance_rx) = std::sync::mpsc::channel();
+        let (okx_tx, okx_rx) = std::sync::mpsc::channel();
+        tokio::join!(
+            report_status_to(binance_tx, report("binance")),
+            report_status_to(okx_tx, report("okx"))
+        );
+        let exchanges = |rx: std::sync::mpsc::Receiver<super::CrawlStatus>| {
+            rx.try_iter().map(|status| status.exchange).collect::<Vec<String>>()
+        };
+        assert_eq!(vec!["binance".to_string()], exchanges(binance_rx));
+        assert_eq!(vec!["okx".to_string()], exchanges(okx_rx));
+    }
+}
@@ -0,0 +1,479 @@
+use std::{borrow::Cow, fmt, marker::PhantomData};
+
+use crypto_market_type::MarketType;
+use serde::{
+    de::{
+        value::{MapAccessDeserializer, SeqAccessDeserializer},
+        IgnoredAny, MapAccess, SeqAccess, Visitor,
+    },
+    Deserialize, Deserializer,
+};
+
+// An object or an array of objects, e.g., `data` of Deribit messages
+enum OneOrMany<T> {
+    One(T),
+    Many(Vec<T>),
+}
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for OneOrMany<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct OneOrManyVisitor<T>(PhantomData<T>);
+
+        impl<'de, T: Deserialize<'de>> Visitor<'de> for OneOrManyVisitor<T> {
+            type Value = OneOrMany<T>;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("an object or an array of objects")
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, map: A) -> Result<Self::Value, A::Error> {
+                T::deserialize(MapAccessDeserializer::new(map)).map(OneOrMany::One)
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, seq: A) -> Result<Self::Value, A::Error> {
+                Vec::deserialize(SeqAccessDeserializer::new(seq)).map(OneOrMany::Many)
+            }
+        }
+
+        deserializer.deserialize_any(OneOrManyVisitor(PhantomData))
+    }
+}
+
+impl<T> OneOrMany<T> {
+    // The symbol of all objects, `ALL` if they have different symbols
+    fn symbol<'a>(self, symbol: impl Fn(T) -> Option<Cow<'a, str>>) -> Option<String> {
+        match self {
+            OneOrMany::One(item) => symbol(item).map(String::from),
+            OneOrMany::Many(items) => {
+                let mut symbols = items.into_iter().map(symbol);
+                let first = symbols.next()??;
+                if symbols.all(|other| other.as_deref() == Some(first.as_ref())) {
+                    Some(first.into())
+                } else {
+                    Some("ALL".to_string())
+                }
+            }
+        }
+    }
+}
+
+// The first element of an array, the rest is skipped
+struct First<T>(T);
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for First<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct FirstVisitor<T>(PhantomData<T>);
+
+        impl<'de, T: Deserialize<'de>> Visitor<'de> for FirstVisitor<T> {
+            type Value = First<T>;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a non-empty array")
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+                let first = seq
+                    .next_element()?
+                    .ok_or_else(|| serde::de::Error::invalid_length(0, &self))?;
+                while seq.next_element::<IgnoredAny>()?.is_some() {}
+                Ok(First(first))
+            }
+        }
+
+        deserializer.deserialize_seq(FirstVisitor(PhantomData))
+    }
+}
+
+// A message of Binance combined streams, e.g.,
+// {"stream":"btcusdt@aggTrade","data":{"e":"aggTrade","s":"BTCUSDT",...}}
+#[derive(Deserialize)]
+struct BinanceMsg<'a> {
+    #[serde(borrow)]
+    stream: Cow<'a, str>,
+    #[serde(borrow)]
+    data: OneOrMany<BinanceData<'a>>,
+}
+
+#[derive(Deserialize)]
+struct BinanceData<'a> {
+    #[serde(borrow)]
+    s: Option<Cow<'a, str>>,
+}
+
+// Channel info of Bitfinex messages, e.g.,
+// [{"channel":"trades","symbol":"tBTCUSD"},"te",[...]]
+#[derive(Deserialize)]
+struct BitfinexChannel<'a> {
+    #[serde(borrow)]
+    symbol: Option<Cow<'a, str>>,
+    // e.g., trade:1m:tBTCUSD
+    #[serde(borrow)]
+    key: Option<Cow<'a, str>>,
+}
+
+// e.g., {"arg":{"instType":"sp","channel":"trade","instId":"BTCUSDT"},"data":[...]}
+#[derive(Deserialize)]
+struct BitgetMsg<'a> {
+    #[serde(borrow)]
+    arg: BitgetArg<'a>,
+    #[serde(rename = "data")]
+    _data: IgnoredAny,
+}
+
+#[derive(Deserialize)]
+struct BitgetArg<'a> {
+    #[serde(borrow, rename = "instType")]
+    inst_type: Cow<'a, str>,
+    #[serde(borrow, rename = "instId")]
+    inst_id: Cow<'a, str>,
+}
+
+// Messages with symbols in `data`, e.g.,
+// {"table":"trade","action":"insert","data":[{"symbol":"XBTUSD",...}]}
+#[derive(Deserialize)]
+struct DataMsg<'a> {
+    #[serde(borrow)]
+    data: OneOrMany<DataSymbol<'a>>,
+}
+
+#[derive(Deserialize)]
+struct DataSymbol<'a> {
+    #[serde(borrow)]
+    symbol: Option<Cow<'a, str>>,
+}
+
+// Messages with a channel or topic, e.g., {"topic":"publicTrade.BTCUSDT","data":[...]}
+#[derive(Deserialize)]
+struct ChannelMsg<'a> {
+    #[serde(borrow, alias = "topic")]
+    channel: Cow<'a, str>,
+    #[serde(rename = "data")]
+    _data: IgnoredAny,
+}
+
+// e.g., {"method":"subscription","params":{"channel":"trades.BTC-PERPETUAL.100ms","data":[...]}}
+#[derive(Deserialize)]
+struct DeribitMsg<'a> {
+    #[serde(borrow)]
+    params: DeribitParams<'a>,
+}
+
+#[derive(Deserialize)]
+struct DeribitParams<'a> {
+    #[serde(borrow)]
+    channel: Cow<'a, str>,
+    #[serde(borrow)]
+    data: OneOrMany<DeribitData<'a>>,
+}
+
+#[derive(Deserialize)]
+struct DeribitData<'a> {
+    #[serde(borrow)]
+    instrument_name: Option<Cow<'a, str>>,
+}
+
+// e.g., {"type":"channel_data","id":"BTC-USD","channel":"v3_trades","contents":{...}}
+#[derive(Deserialize)]
+struct DydxMsg<'a> {
+    #[serde(borrow)]
+    id: Cow<'a, str>,
+    #[serde(rename = "contents")]
+    _contents: IgnoredAny,
+}
+
+// e.g., {"channel":"spot.trades","event":"update","result":{"currency_pair":"BTC_USDT",...}}
+#[derive(Deserialize)]
+struct GateMsg<'a> {
+    #[serde(borrow)]
+    result: OneOrMany<GateResult<'a>>,
+}
+
+#[derive(Deserialize)]
+struct GateResult<'a> {
+    #[serde(borrow)]
+    currency_pair: Option<Cow<'a, str>>,
+    #[serde(borrow)]
+    contract: Option<Cow<'a, str>>,
+    #[serde(borrow)]
+    s: Option<Cow<'a, str>>,
+    // candlesticks, e.g., 1m_BTC_USDT
+    #[serde(borrow)]
+    n: Option<Cow<'a, str>>,
+}
+
+// e.g., {"ch":"market.btcusdt.trade.detail","ts":1677000000118,"tick":{...}}
+#[derive(Deserialize)]
+struct HuobiMsg<'a> {
+    #[serde(borrow, alias = "topic")]
+    ch: Cow<'a, str>,
+}
+
+// e.g., {"type":"message","topic":"/market/match:BTC-USDT","subject":"trade.l3match","data":{...}}
+#[derive(Deserialize)]
+struct KucoinMsg<'a> {
+    #[serde(borrow)]
+    topic: Cow<'a, str>,
+    #[serde(borrow)]
+    subject: Option<Cow<'a, str>>,
+    #[serde(rename = "data")]
+    _data: IgnoredAny,
+}
+
+// A message of OKX v5 websocket APIs, e.g.,
+// {"arg":{"channel":"trades","instId":"BTC-USDT"},"data":[...]}
+#[derive(Deserialize)]
+struct OkxMsg<'a> {
+    #[serde(borrow)]
+    arg: OkxArg<'a>,
+    #[serde(rename = "data")]
+    _data: IgnoredAny,
+}
+
+#[derive(Deserialize)]
+struct OkxArg<'a> {
+    #[serde(borrow, rename = "instId")]
+    inst_id: Cow<'a, str>,
+}
+
+// Messages with a top level symbol, e.g., {"product_id":"BTC-USD","type":"match",...}
+#[derive(Deserialize)]
+struct SymbolMsg<'a> {
+    #[serde(borrow, alias = "product_id")]
+    symbol: Cow<'a, str>,
+}
+
+fn extract<'a, T: Deserialize<'a>>(json: &'a str) -> Option<T> {
+    serde_json::from_str::<T>(json).ok()
+}
+
+/// Extracts the exchange symbol of a websocket message, `ALL` if it contains
+/// multiple symbols, or `None` if it has no symbol or is unknown.
+///
+/// Only fields of symbols are parsed, the data of messages is skipped.
+pub(super) fn extract_symbol(
+    exchange: &str,
+    market_type: MarketType,
+    json: &str,
+) -> Option<String> {
+    match exchange {
+        "binance" => extract::<BinanceMsg>(json).and_then(|msg| {
+            // e.g., !miniTicker@arr
+            if msg.stream.ends_with("@arr") {
+                Some("ALL".to_string())
+            } else {
+                // partial depth streams have no symbol in data
+                let stream = msg.stream;
+                msg.data
+                    .symbol(|data| data.s)
+                    .or_else(|| stream.split_once('@').map(|(symbol, _)| symbol.to_uppercase()))
+            }
+        }),
+        "bitfinex" => extract::<First<BitfinexChannel>>(json).and_then(|First(channel)| {
+            match (channel.symbol, channel.key) {
+                (Some(symbol), _) => Some(symbol.into()),
+                (None, Some(key)) => key.splitn(3, ':').nth(2).map(String::from),
+                (None, None) => None,
+            }
+        }),
+        "bitget" => extract::<BitgetMsg>(json).and_then(|msg| {
+            let suffix = match msg.arg.inst_type.as_ref() {
+                "sp" => "SPBL",
+                "mc" if msg.arg.inst_id.ends_with("USDT") => "UMCBL",
+                "mc" => "DMCBL",
+                _ => return None,
+            };
+            Some(format!("{}_{suffix}", msg.arg.inst_id))
+        }),
+        "bithumb" | "bitmex" => {
+            extract::<DataMsg>(json).and_then(|msg| msg.data.symbol(|data| data.symbol))
+        }
+        // e.g., live_trades_btcusd
+        "bitstamp" => extract::<ChannelMsg>(json)
+            .and_then(|msg| msg.channel.rsplit('_').next().map(String::from)),
+        // e.g., orderbook.50.BTCUSDT
+        "bybit" => extract::<ChannelMsg>(json)
+            .and_then(|msg| msg.channel.rsplit('.').next().map(String::from)),
+        "coinbase_pro" | "mexc" => extract::<SymbolMsg>(json).map(|msg| msg.symbol.into()),
+        "deribit" => extract::<DeribitMsg>(json).and_then(|msg| {
+            match msg.params.channel.strip_prefix("chart.trades.") {
+                // e.g., chart.trades.BTC-PERPETUAL.1
+                Some(rest) => rest.split('.').next().map(String::from),
+                None => msg.params.data.symbol(|data| data.instrument_name),
+            }
+        }),
+        "dydx" => extract::<DydxMsg>(json).map(|msg| msg.id.into()),
+        "gate" => extract::<GateMsg>(json).and_then(|msg| {
+            msg.result.symbol(|result| {
+                result.currency_pair.or(result.contract).or(result.s).or_else(|| {
+                    let n = result.n?;
+                    n.split_once('_').map(|(_, symbol)| symbol.to_string().into())
+                })
+            })
+        }),
+        "huobi" => extract::<HuobiMsg>(json).and_then(|msg| {
+            if msg.ch == "public.*.funding_rate" {
+                Some("ALL".to_string())
+            } else {
+                msg.ch.split('.').nth(1).map(String::from)
+            }
+        }),
+        // e.g., [337,[[...]],"trade","XBT/USD"]
+        "kraken" if market_type == MarketType::Spot => json
+            .strip_suffix("\"]")
+            .and_then(|rest| rest.rsplit_once('"'))
+            .map(|(_, symbol)| symbol.to_string()),
+        "kraken" => extract::<SymbolMsg>(json).map(|msg| msg.symbol.into()),
+        "kucoin" => extract::<KucoinMsg>(json).and_then(|msg| {
+            if msg.topic == "/market/ticker:all" {
+                return msg.subject.map(String::from);
+            }
+            let (channel, symbol) = msg.topic.rsplit_once(':')?;
+            if channel.contains("/candle") {
+                // e.g., /market/candles:BTC-USDT_1hour
+                symbol.rsplit_once('_').map(|(symbol, _)| symbol.to_string())
+            } else {
+                Some(symbol.to_string())
+            }
+        }),
+        "okx" => extract::<OkxMsg>(json).map(|msg| msg.arg.inst_id.into()),
+        // e.g., btcusdt_trades of spot, BTC_USDT.Trade of swap markets
+        "zb" => extract::<ChannelMsg>(json).and_then(|msg| {
+            let separator = if msg.channel.contains('.') { '.' } else { '_' };
+            msg.channel.split(separator).next().map(String::from)
+        }),
+        // e.g., ["T","329","1677000000","BTC_USDT","bid","24512.3","0.0042"]
+        "zbg" if market_type == MarketType::Spot => {
+            extract::<Vec<Cow<str>>>(json).and_then(|arr| {
+                let index = match arr.first()?.as_ref() {
+                    "T" | "E" => 3,
+                    "K" | "AE" => 2,
+                    _ => return None,
+                };
+                arr.get(index).map(|symbol| symbol.to_lowercase())
+            })
+        }
+        // swap markets of ZBG have contract IDs only
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crypto_market_type::MarketType;
+
+    use super::extract_symbol;
+
+    #[test]
+    fn test_extract_symbol() {
+        let messages = [
+            (
+                "binance",
+                MarketType::Spot,
+                r#"{"stream":"btcusdt@aggTrade","data":{"e":"aggTrade","E":1679299200000,"s":"BTCUSDT","a":1,"p":"28000.00","q":"0.1","f":1,"l":1,"T":1679299200000,"m":true,"M":true}}"#,
+                Some("BTCUSDT"),
+            ),
+            (
+                "binance",
+                MarketType::LinearSwap,
+                r#"{"stream":"!bookTicker","data":{"e":"bookTicker","u":1,"s":"ETHUSDT","b":"1800.00","B":"1","a":"1800.01","A":"2","T":1679299200000,"E":1679299200000}}"#,
+                Some("ETHUSDT"),
+            ),
+            (
+                "binance",
+                MarketType::Spot,
+                r#"{"stream":"!ticker@arr","data":[{"e":"24hrTicker","s":"BTCUSDT"},{"e":"24hrTicker","s":"ETHUSDT"}]}"#,
+                Some("ALL"),
+            ),
+            (
+                "binance",
+                MarketType::Spot,
+                r#"{"stream":"btcusdt@depth5","data":{"lastUpdateId":1,"bids":[["28000.00","1"]],"asks":[["28000.01","1"]]}}"#,
+                Some("BTCUSDT"),
+            ),
+            (
+                "bitfinex",
+                MarketType::Spot,
+                r#"[{"channel":"trades","symbol":"tBTCUSD"},"te",[1303442217,1677000000118,0.0042,24512]]"#,
+                Some("tBTCUSD"),
+            ),
+            (
+                "bitget",
+                MarketType::Spot,
+                r#"{"action":"snapshot","arg":{"instType":"sp","channel":"trade","instId":"BTCUSDT"},"data":[["1677000000118","24512.30","0.0042","buy"]]}"#,
+                Some("BTCUSDT_SPBL"),
+            ),
+            (
+                "bitmex",
+                MarketType::Unknown,
+                r#"{"table":"funding","action":"partial","data":[{"symbol":"XBTUSD","fundingRate":0.0001},{"symbol":"ETHUSD","fundingRate":0.0001}]}"#,
+                Some("ALL"),
+            ),
+            (
+                "deribit",
+                MarketType::InverseSwap,
+                r#"{"jsonrpc":"2.0","method":"subscription","params":{"channel":"trades.BTC-PERPETUAL.100ms","data":[{"trade_seq":1,"instrument_name":"BTC-PERPETUAL","price":24512.5}]}}"#,
+                Some("BTC-PERPETUAL"),
+            ),
+            (
+                "gate",
+                MarketType::LinearSwap,
+                r#"{"time":1677000000,"channel":"futures.candlesticks","event":"update","result":[{"t":1677000000,"v":10,"c":"24512.5","h":"24512.5","l":"24512.5","o":"24512.5","n":"1m_BTC_USDT"}]}"#,
+                Some("BTC_USDT"),
+            ),
+            (
+                "huobi",
+                MarketType::Spot,
+                r#"{"ch":"market.btcusdt.trade.detail","ts":1677000000118,"tick":{"id":1,"data":[]}}"#,
+                Some("btcusdt"),
+            ),
+            (
+                "kraken",
+                MarketType::Spot,
+                r#"[337,[["24512.30000","0.00420000","1677000000.118302","b","l",""]],"trade","XBT/USD"]"#,
+                Some("XBT/USD"),
+            ),
+            (
+                "okx",
+                MarketType::Spot,
+                r#"{"arg":{"channel":"trades","instId":"BTC-USDT"},"data":[{"instId":"BTC-USDT","tradeId":"1","px":"28000","sz":"0.1","side":"buy","ts":"1679299200000"}]}"#,
+                Some("BTC-USDT"),
+            ),
+            (
+                "kucoin",
+                MarketType::Spot,
+                r#"{"type":"message","topic":"/market/ticker:all","subject":"BTC-USDT","data":{"bestAsk":"28000.1","bestAskSize":"1","bestBid":"28000","bestBidSize":"1","price":"28000","sequence":"1","size":"0.1","time":1679299200000}}"#,
+                Some("BTC-USDT"),
+            ),
+            (
+                "kucoin",
+                MarketType::Spot,
+                r#"{"type":"message","topic":"/market/candles:BTC-USDT_1hour","subject":"trade.candles.update","data":{}}"#,
+                Some("BTC-USDT"),
+            ),
+            (
+                "zb",
+                MarketType::Spot,
+                r#"{"dataType":"trades","data":[],"channel":"btcusdt_trades"}"#,
+                Some("btcusdt"),
+            ),
+            (
+                "zbg",
+                MarketType::Spot,
+                r#"["T","329","1677000000","BTC_USDT","bid","24512.3","0.0042"]"#,
+                Some("btc_usdt"),
+            ),
+            ("okx", MarketType::Spot, r#"{"event":"subscribe"}"#, None),
+            ("kucoin", MarketType::Spot, r#"{"id":"1","type":"welcome"}"#, None),
+            ("kraken", MarketType::Spot, r#"{"event":"heartbeat"}"#, None),
+            ("deribit", MarketType::InverseSwap, r#"{"jsonrpc":"2.0","id":1,"result":[]}"#, None),
+            ("zbg", MarketType::InverseSwap, r#"["future_tick",{"contractId":1}]"#, None),
+        ];
+        for (exchange, market_type, json, expected) in messages {
+            assert_eq!(
+                expected.map(String::from),
+                extract_symbol(exchange, market_type, json),
+                "{exchange} {json}"
+            );
+        }
+    }
+}
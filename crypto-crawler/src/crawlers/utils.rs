@@ -1,22 +1,33 @@
 use std::{
-    sync::{mpsc::Sender, Arc},
-    time::{Duration, SystemTime, UNIX_EPOCH},
+    future::Future,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::Sender,
+        Arc,
+    },
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
 };
 
-use crate::utils::{REST_LOCKS, WS_LOCKS};
-use crypto_market_type::{get_market_types, MarketType};
+use crate::utils::{rest_retry_count, symbol_discovery_interval, REST_LOCKS, WS_LOCKS};
+use crypto_market_type::MarketType;
 use crypto_markets::fetch_symbols;
 use crypto_rest_client::{fetch_l2_snapshot, fetch_l3_snapshot, fetch_open_interest};
 use crypto_ws_client::*;
 use log::*;
 
-use crate::{get_hot_spot_symbols, utils::cmc_rank::sort_by_cmc_rank, Message, MessageType};
+use super::{
+    discovery::{symbol_event_sender, Listings},
+    supervisor::{status_sender, supervise, StatusReporter},
+    symbol::extract_symbol,
+    watchdog::{self, ActivityTracker, Remedy},
+};
+use crate::{
+    get_hot_spot_symbols, utils::cmc_rank::sort_by_cmc_rank, CrawlError, Message, MessageType,
+};
 
 pub fn fetch_symbols_retry(exchange: &str, market_type: MarketType) -> Vec<String> {
-    let retry_count = std::env::var("REST_RETRY_COUNT")
-        .unwrap_or_else(|_| "5".to_string())
-        .parse::<i64>()
-        .unwrap();
+    let retry_count = rest_retry_count();
     let cooldown_time = get_cooldown_time_per_request(exchange, market_type);
     let lock = REST_LOCKS.get(exchange).unwrap().get(&market_type).unwrap().clone();
     let mut symbols = Vec::<String>::new();
@@ -53,27 +64,26 @@ pub fn fetch_symbols_retry(exchange: &str, market_type: MarketType) -> Vec<Strin
     symbols
 }
 
-pub(super) fn check_args(exchange: &str, market_type: MarketType, symbols: &[String]) {
-    let market_types = get_market_types(exchange);
-    if !market_types.contains(&market_type) {
-        panic!("{exchange} does NOT have the {market_type} market type");
-    }
-
+pub(crate) fn check_symbols(
+    exchange: &str,
+    market_type: MarketType,
+    symbols: &[String],
+) -> Result<(), CrawlError> {
     let valid_symbols = fetch_symbols_retry(exchange, market_type);
     let invalid_symbols: Vec<String> =
         symbols.iter().filter(|symbol| !valid_symbols.contains(symbol)).cloned().collect();
-    if !invalid_symbols.is_empty() {
-        panic!(
-            "Invalid symbols: {}, {} {} available trading symbols are {}",
-            invalid_symbols.join(","),
-            exchange,
+    if invalid_symbols.is_empty() {
+        Ok(())
+    } else {
+        Err(CrawlError::InvalidSymbols {
+            exchange: exchange.to_string(),
             market_type,
-            valid_symbols.join(",")
-        );
+            symbols: invalid_symbols,
+        })
     }
 }
 
-fn get_cooldown_time_per_request(exchange: &str, market_type: MarketType) -> Duration {
+pub(crate) fn get_cooldown_time_per_request(exchange: &str, market_type: MarketType) -> Duration {
     let millis = match exchange {
         "binance" => 500,    // spot weitht 1200, contract weight 2400
         "bitget" => 100,     // 20 requests per 2 seconds
@@ -109,17 +119,18 @@ pub(crate) fn crawl_snapshot(
     msg_type: MessageType, // L2Snapshot or L3Snapshot
     symbols: Option<&[String]>,
     tx: Sender<Message>,
-) {
-    let is_empty = match symbols {
-        Some(list) => {
-            if list.is_empty() {
-                true
-            } else {
-                check_args(exchange, market_type, list);
-                false
-            }
+) -> Result<(), CrawlError> {
+    let is_empty = symbols.is_none_or(|list| list.is_empty());
+    let fetch_snapshot = match msg_type {
+        MessageType::L2Snapshot => fetch_l2_snapshot,
+        MessageType::L3Snapshot => fetch_l3_snapshot,
+        _ => {
+            return Err(CrawlError::UnsupportedMsgType {
+                exchange: exchange.to_string(),
+                market_type,
+                msg_type,
+            });
         }
-        None => true,
     };
 
     let cooldown_time = get_cooldown_time_per_request(exchange, market_type);
@@ -148,11 +159,7 @@ pub(crate) fn crawl_snapshot(
             if !lock_.owns_lock() {
                 lock_.lock().unwrap();
             }
-            let resp = match msg_type {
-                MessageType::L2Snapshot => fetch_l2_snapshot(exchange, market_type, symbol, None),
-                MessageType::L3Snapshot => fetch_l3_snapshot(exchange, market_type, symbol, None),
-                _ => panic!("msg_type must be L2Snapshot or L3Snapshot"),
-            };
+            let resp = fetch_snapshot(exchange, market_type, symbol, None);
             // Cooldown after each request, and make all other processes wait
             // on the lock to avoid parallel requests, thus avoid 429 error
             std::thread::sleep(cooldown_time);
@@ -200,10 +207,15 @@ pub(crate) fn crawl_snapshot(
         }
         std::thread::sleep(cooldown_time * 2); // if real_symbols is empty, CPU will be 100% without this line
     }
+    Ok(())
 }
 
 /// Crawl open interests of all trading symbols.
-pub(crate) fn crawl_open_interest(exchange: &str, market_type: MarketType, tx: Sender<Message>) {
+pub(crate) fn crawl_open_interest(
+    exchange: &str,
+    market_type: MarketType,
+    tx: Sender<Message>,
+) -> Result<(), CrawlError> {
     let cooldown_time = get_cooldown_time_per_request(exchange, market_type);
 
     let lock = REST_LOCKS.get(exchange).unwrap().get(&market_type).unwrap().clone();
@@ -309,10 +321,17 @@ pub(crate) fn crawl_open_interest(exchange: &str, market_type: MarketType, tx: S
                     }
                 }
             }
-            _ => panic!("{exchange} does NOT have open interest RESTful API"),
+            _ => {
+                return Err(CrawlError::UnsupportedMsgType {
+                    exchange: exchange.to_string(),
+                    market_type,
+                    msg_type: MessageType::OpenInterest,
+                });
+            }
         }
         std::thread::sleep(cooldown_time * 2); // if real_symbols is empty, CPU will be 100% without this line
     }
+    Ok(())
 }
 
 async fn subscribe_with_lock(
@@ -321,7 +340,7 @@ async fn subscribe_with_lock(
     msg_type: MessageType,
     symbols: Vec<String>,
     ws_client: Arc<dyn WSClient + Send + Sync>,
-) {
+) -> Result<(), CrawlError> {
     match msg_type {
         MessageType::BBO => ws_client.subscribe_bbo(&symbols).await,
         MessageType::Trade => ws_client.subscribe_trade(&symbols).await,
@@ -329,8 +348,9 @@ async fn subscribe_with_lock(
         MessageType::L3Event => ws_client.subscribe_l3_orderbook(&symbols).await,
         MessageType::L2TopK => ws_client.subscribe_orderbook_topk(&symbols).await,
         MessageType::Ticker => ws_client.subscribe_ticker(&symbols).await,
-        _ => panic!("{exchange} {market_type} does NOT have {msg_type} websocket channel"),
+        _ => return Err(CrawlError::UnsupportedMsgType { exchange, market_type, msg_type }),
     };
+    Ok(())
 }
 
 fn get_connection_interval_ms(exchange: &str, _market_type: MarketType) -> Option<u64> {
@@ -367,103 +387,136 @@ fn get_num_subscriptions_per_connection(exchange: &str, market_type: MarketType)
     }
 }
 
-async fn create_ws_client_internal(
+// Returns a boxed future, so that all constructors have the same type, the
+// URL is None except in tests
+type NewWSClient = fn(
+    Sender<String>,
+    Option<String>,
+) -> Pin<Box<dyn Future<Output = Arc<dyn WSClient + Send + Sync>> + Send>>;
+
+macro_rules! new_ws_client {
+    ($client:ident) => {
+        |tx, url| {
+            Box::pin(async move {
+                let ws_client: Arc<dyn WSClient + Send + Sync> =
+                    Arc::new($client::new(tx, url.as_deref()).await);
+                ws_client
+            })
+        }
+    };
+}
+
+// The websocket client of each market, None if there is no such client
+pub(crate) fn ws_client_constructor(
     exchange: &str,
     market_type: MarketType,
-    tx: Sender<String>,
-) -> Arc<dyn WSClient + Send + Sync> {
-    match exchange {
+) -> Option<NewWSClient> {
+    let constructor: NewWSClient = match exchange {
         "binance" => match market_type {
-            MarketType::Spot => Arc::new(BinanceSpotWSClient::new(tx, None).await),
+            MarketType::Spot => new_ws_client!(BinanceSpotWSClient),
             MarketType::InverseFuture | MarketType::InverseSwap => {
-                Arc::new(BinanceInverseWSClient::new(tx, None).await)
+                new_ws_client!(BinanceInverseWSClient)
             }
             MarketType::LinearFuture | MarketType::LinearSwap => {
-                Arc::new(BinanceLinearWSClient::new(tx, None).await)
+                new_ws_client!(BinanceLinearWSClient)
             }
-            MarketType::EuropeanOption => Arc::new(BinanceOptionWSClient::new(tx, None).await),
-            _ => panic!("Binance does NOT have the {market_type} market type"),
+            MarketType::EuropeanOption => new_ws_client!(BinanceOptionWSClient),
+            _ => return None,
         },
-        "bitfinex" => Arc::new(BitfinexWSClient::new(tx, None).await),
+        "bitfinex" => new_ws_client!(BitfinexWSClient),
         "bitget" => match market_type {
-            MarketType::Spot => Arc::new(BitgetSpotWSClient::new(tx, None).await),
+            MarketType::Spot => new_ws_client!(BitgetSpotWSClient),
             MarketType::InverseFuture | MarketType::InverseSwap | MarketType::LinearSwap => {
-                Arc::new(BitgetSwapWSClient::new(tx, None).await)
+                new_ws_client!(BitgetSwapWSClient)
             }
-            _ => panic!("Bitget does NOT have the {market_type} market type"),
+            _ => return None,
         },
-        "bithumb" => Arc::new(BithumbWSClient::new(tx, None).await),
-        "bitmex" => Arc::new(BitmexWSClient::new(tx, None).await),
-        "bitstamp" => Arc::new(BitstampWSClient::new(tx, None).await),
+        "bithumb" => new_ws_client!(BithumbWSClient),
+        "bitmex" => new_ws_client!(BitmexWSClient),
+        "bitstamp" => new_ws_client!(BitstampWSClient),
         "bitz" => match market_type {
-            MarketType::Spot => Arc::new(BitzSpotWSClient::new(tx, None).await),
-            _ => panic!("Bitz does NOT have the {market_type} market type"),
+            MarketType::Spot => new_ws_client!(BitzSpotWSClient),
+            _ => return None,
         },
         "bybit" => match market_type {
             MarketType::InverseFuture | MarketType::InverseSwap => {
-                Arc::new(BybitInverseWSClient::new(tx, None).await)
+                new_ws_client!(BybitInverseWSClient)
             }
-            MarketType::LinearSwap => Arc::new(BybitLinearSwapWSClient::new(tx, None).await),
-            _ => panic!("Bybit does NOT have the {market_type} market type"),
+            MarketType::LinearSwap => new_ws_client!(BybitLinearSwapWSClient),
+            _ => return None,
         },
-        "coinbase_pro" => Arc::new(CoinbaseProWSClient::new(tx, None).await),
-        "deribit" => Arc::new(DeribitWSClient::new(tx, None).await),
+        "coinbase_pro" => new_ws_client!(CoinbaseProWSClient),
+        "deribit" => new_ws_client!(DeribitWSClient),
         "dydx" => match market_type {
-            MarketType::LinearSwap => Arc::new(DydxSwapWSClient::new(tx, None).await),
-            _ => panic!("dYdX does NOT have the {market_type} market type"),
+            MarketType::LinearSwap => new_ws_client!(DydxSwapWSClient),
+            _ => return None,
         },
-        "ftx" => Arc::new(FtxWSClient::new(tx, None).await),
+        "ftx" => new_ws_client!(FtxWSClient),
         "gate" => match market_type {
-            MarketType::Spot => Arc::new(GateSpotWSClient::new(tx, None).await),
-            MarketType::InverseSwap => Arc::new(GateInverseSwapWSClient::new(tx, None).await),
-            MarketType::LinearSwap => Arc::new(GateLinearSwapWSClient::new(tx, None).await),
-            MarketType::InverseFuture => Arc::new(GateInverseFutureWSClient::new(tx, None).await),
-            MarketType::LinearFuture => Arc::new(GateLinearFutureWSClient::new(tx, None).await),
-            _ => panic!("Gate does NOT have the {market_type} market type"),
+            MarketType::Spot => new_ws_client!(GateSpotWSClient),
+            MarketType::InverseSwap => new_ws_client!(GateInverseSwapWSClient),
+            MarketType::LinearSwap => new_ws_client!(GateLinearSwapWSClient),
+            MarketType::InverseFuture => new_ws_client!(GateInverseFutureWSClient),
+            MarketType::LinearFuture => new_ws_client!(GateLinearFutureWSClient),
+            _ => return None,
         },
         "huobi" => match market_type {
-            MarketType::Spot => Arc::new(HuobiSpotWSClient::new(tx, None).await),
-            MarketType::InverseFuture => Arc::new(HuobiFutureWSClient::new(tx, None).await),
-            MarketType::LinearSwap => Arc::new(HuobiLinearSwapWSClient::new(tx, None).await),
-            MarketType::InverseSwap => Arc::new(HuobiInverseSwapWSClient::new(tx, None).await),
-            MarketType::EuropeanOption => Arc::new(HuobiOptionWSClient::new(tx, None).await),
-            _ => panic!("Huobi does NOT have the {market_type} market type"),
+            MarketType::Spot => new_ws_client!(HuobiSpotWSClient),
+            MarketType::InverseFuture => new_ws_client!(HuobiFutureWSClient),
+            MarketType::LinearSwap => new_ws_client!(HuobiLinearSwapWSClient),
+            MarketType::InverseSwap => new_ws_client!(HuobiInverseSwapWSClient),
+            MarketType::EuropeanOption => new_ws_client!(HuobiOptionWSClient),
+            _ => return None,
         },
         "kraken" => match market_type {
-            MarketType::Spot => Arc::new(KrakenSpotWSClient::new(tx, None).await),
+            MarketType::Spot => new_ws_client!(KrakenSpotWSClient),
             MarketType::InverseFuture | MarketType::InverseSwap => {
-                Arc::new(KrakenFuturesWSClient::new(tx, None).await)
+                new_ws_client!(KrakenFuturesWSClient)
             }
-            _ => panic!("Kraken does NOT have the {market_type} market type"),
+            _ => return None,
         },
         "kucoin" => match market_type {
-            MarketType::Spot => Arc::new(KuCoinSpotWSClient::new(tx, None).await),
+            MarketType::Spot => new_ws_client!(KuCoinSpotWSClient),
             MarketType::InverseSwap | MarketType::LinearSwap | MarketType::InverseFuture => {
-                Arc::new(KuCoinSwapWSClient::new(tx, None).await)
+                new_ws_client!(KuCoinSwapWSClient)
             }
-            _ => panic!("KuCoin does NOT have the {market_type} market type"),
+            _ => return None,
         },
         "mexc" => match market_type {
-            MarketType::Spot => Arc::new(MexcSpotWSClient::new(tx, None).await),
+            MarketType::Spot => new_ws_client!(MexcSpotWSClient),
             MarketType::LinearSwap | MarketType::InverseSwap => {
-                Arc::new(MexcSwapWSClient::new(tx, None).await)
+                new_ws_client!(MexcSwapWSClient)
             }
-            _ => panic!("MEXC does NOT have the {market_type} market type"),
+            _ => return None,
         },
-        "okx" => Arc::new(OkxWSClient::new(tx, None).await),
+        "okx" => new_ws_client!(OkxWSClient),
         "zb" => match market_type {
-            MarketType::Spot => Arc::new(ZbSpotWSClient::new(tx, None).await),
-            MarketType::LinearSwap => Arc::new(ZbSwapWSClient::new(tx, None).await),
-            _ => panic!("ZB does NOT have the {market_type} market type"),
+            MarketType::Spot => new_ws_client!(ZbSpotWSClient),
+            MarketType::LinearSwap => new_ws_client!(ZbSwapWSClient),
+            _ => return None,
         },
         "zbg" => match market_type {
-            MarketType::Spot => Arc::new(ZbgSpotWSClient::new(tx, None).await),
+            MarketType::Spot => new_ws_client!(ZbgSpotWSClient),
             MarketType::InverseSwap | MarketType::LinearSwap => {
-                Arc::new(ZbgSwapWSClient::new(tx, None).await)
+                new_ws_client!(ZbgSwapWSClient)
             }
-            _ => panic!("ZBG does NOT have the {market_type} market type"),
+            _ => return None,
         },
-        _ => panic!("Unknown exchange {exchange}"),
+        _ => return None,
+    };
+    Some(constructor)
+}
+
+async fn create_ws_client_internal(
+    exchange: &str,
+    market_type: MarketType,
+    tx: Sender<String>,
+) -> Result<Arc<dyn WSClient + Send + Sync>, CrawlError> {
+    match ws_client_constructor(exchange, market_type) {
+        Some(constructor) => Ok(constructor(tx, None).await),
+        None => {
+            Err(CrawlError::UnsupportedMarketType { exchange: exchange.to_string(), market_type })
+        }
     }
 }
 
@@ -472,8 +525,17 @@ async fn create_ws_client(
     market_type: MarketType,
     msg_type: MessageType,
     tx: Sender<Message>,
-) -> Arc<dyn WSClient + Send + Sync> {
-    let tx = create_conversion_thread(exchange.to_string(), msg_type, market_type, tx);
+    tracker: Option<Arc<ActivityTracker>>,
+    receiver_dropped: Arc<AtomicBool>,
+) -> Result<Arc<dyn WSClient + Send + Sync>, CrawlError> {
+    let tx = create_tracking_conversion_thread(
+        exchange.to_string(),
+        msg_type,
+        market_type,
+        tx,
+        tracker,
+        receiver_dropped,
+    );
     if let Some(interval) = get_connection_interval_ms(exchange, market_type) {
         let lock = WS_LOCKS.get(exchange).unwrap().get(&market_type).unwrap().clone();
         let mut lock = lock.lock().await;
@@ -508,7 +570,7 @@ pub(crate) async fn create_ws_client_symbol(
     exchange: &str,
     market_type: MarketType,
     tx: Sender<String>,
-) -> Arc<dyn WSClient + Send + Sync> {
+) -> Result<Arc<dyn WSClient + Send + Sync>, CrawlError> {
     let tx = create_parser_thread(exchange.to_string(), market_type, tx);
     create_ws_client_internal(exchange, market_type, tx).await
 }
@@ -516,47 +578,48 @@ pub(crate) async fn create_ws_client_symbol(
 #[derive(Clone)]
 struct EmptyStruct {} // for stop channel
 
-fn create_symbol_discovery_thread(
+// Changes of topics found by symbol discovery
+enum TopicChange<T> {
+    Add(Vec<T>),
+    // topics of these symbols
+    Remove(Vec<String>),
+}
+
+fn create_symbol_discovery_thread<T: Topic>(
     exchange: String,
     market_type: MarketType,
-    subscribed_symbols: Vec<String>,
+    // subscribed symbols, and the listener of symbol events
+    mut listings: Listings,
+    to_topics: impl Fn(Vec<String>) -> Vec<T> + Send + 'static,
     mut stop_ch_rx: tokio::sync::broadcast::Receiver<EmptyStruct>,
-    tx: tokio::sync::mpsc::Sender<Vec<String>>, // send out new symbols
+    tx: tokio::sync::mpsc::Sender<TopicChange<T>>,
 ) -> tokio::task::JoinHandle<()> {
-    let num_topics_per_connection = get_num_subscriptions_per_connection(&exchange, market_type);
-    let mut subscribed_symbols = subscribed_symbols;
-    let mut num_subscribed_of_last_client = subscribed_symbols.len() % num_topics_per_connection;
-    let mut hourly = tokio::time::interval(Duration::from_secs(3600));
+    let interval = symbol_discovery_interval();
+    let mut ticker = tokio::time::interval_at(tokio::time::Instant::now() + interval, interval);
     tokio::task::spawn(async move {
         loop {
             tokio::select! {
                 _ = stop_ch_rx.recv() => {
                     break;
                 }
-                _ = hourly.tick() => {
+                _ = ticker.tick() => {
                     let exchange_clone = exchange.to_string();
                     let latest_symbols = tokio::task::block_in_place(move || {
                         fetch_symbols_retry(&exchange_clone, market_type)
                     });
+                    let (listed, delisted) = listings.update(&latest_symbols);
 
-                    let mut new_symbols: Vec<String> = latest_symbols
-                        .iter()
-                        .filter(|s| !subscribed_symbols.contains(s))
-                        .cloned()
-                        .collect();
-
-                    if !new_symbols.is_empty() {
-                        warn!("Found new symbols: {}", new_symbols.join(", "));
-                        if tx.send(new_symbols.clone()).await.is_err() {
+                    if !listed.is_empty() {
+                        warn!("Found new symbols: {}", listed.join(", "));
+                        if tx.send(TopicChange::Add(to_topics(listed))).await.is_err() {
                             break; // break the loop if there is no receiver
                         }
-                        num_subscribed_of_last_client += new_symbols.len();
-                        subscribed_symbols.append(&mut new_symbols);
                     }
-                    if num_subscribed_of_last_client >= num_topics_per_connection {
-                        panic!(
-                            "The last connection has subscribed {num_subscribed_of_last_client} topics, which is more than {num_topics_per_connection}, restarting the process",
-                        ); // pm2 will restart the whole process
+                    if !delisted.is_empty() {
+                        warn!("Found delisted symbols: {}", delisted.join(", "));
+                        if tx.send(TopicChange::Remove(delisted)).await.is_err() {
+                            break; // break the loop if there is no receiver
+                        }
                     }
                 }
             }
@@ -564,58 +627,39 @@ fn create_symbol_discovery_thread(
     })
 }
 
-fn create_new_symbol_receiver_thread(
+// create a thread to convert Sender<Message> Sender<String>
+pub(crate) fn create_conversion_thread(
     exchange: String,
     msg_type: MessageType,
     market_type: MarketType,
-    mut symbols_rx: tokio::sync::mpsc::Receiver<Vec<String>>,
-    ws_client: Arc<dyn WSClient + Send + Sync>,
-) -> tokio::task::JoinHandle<()> {
-    tokio::task::spawn(async move {
-        let exchange_clone = exchange;
-        while let Some(new_symbols) = symbols_rx.recv().await {
-            subscribe_with_lock(
-                exchange_clone.clone(),
-                market_type,
-                msg_type,
-                new_symbols,
-                ws_client.clone(),
-            )
-            .await;
-        }
-    })
-}
-
-fn create_new_symbol_receiver_thread_candlestick(
-    intervals: Vec<usize>,
-    mut rx: tokio::sync::mpsc::Receiver<Vec<String>>,
-    ws_client: Arc<dyn WSClient + Send + Sync>,
-) -> tokio::task::JoinHandle<()> {
-    tokio::task::spawn(async move {
-        while let Some(new_symbols) = rx.recv().await {
-            let new_symbol_interval_list = new_symbols
-                .iter()
-                .flat_map(|symbol| {
-                    intervals.clone().into_iter().map(move |interval| (symbol.clone(), interval))
-                })
-                .collect::<Vec<(String, usize)>>();
-            ws_client.subscribe_candlestick(&new_symbol_interval_list).await;
-        }
-    })
+    tx: Sender<Message>,
+) -> Sender<String> {
+    create_tracking_conversion_thread(exchange, msg_type, market_type, tx, None, Arc::default())
 }
 
-// create a thread to convert Sender<Message> Sender<String>
-pub(crate) fn create_conversion_thread(
+// Also records symbols of messages to `tracker` if any, and sets
+// `receiver_dropped` once the receiver of messages is dropped
+fn create_tracking_conversion_thread(
     exchange: String,
     msg_type: MessageType,
     market_type: MarketType,
     tx: Sender<Message>,
+    tracker: Option<Arc<ActivityTracker>>,
+    receiver_dropped: Arc<AtomicBool>,
 ) -> Sender<String> {
-    let (tx_raw, rx_raw) = std::sync::mpsc::channel();
+    let (tx_raw, rx_raw) = std::sync::mpsc::channel::<String>();
     tokio::task::spawn_blocking(move || {
         for json in rx_raw {
-            let msg = Message::new(exchange.clone(), market_type, msg_type, json);
+            let symbol = extract_symbol(&exchange, market_type, &json);
+            if let (Some(tracker), Some(symbol)) = (tracker.as_ref(), symbol.as_deref()) {
+                if symbol != "ALL" {
+                    tracker.record(symbol, Instant::now());
+                }
+            }
+            let mut msg = Message::new(exchange.clone(), market_type, msg_type, json);
+            msg.symbol = symbol;
             if tx.send(msg).is_err() {
+                receiver_dropped.store(true, Ordering::SeqCst);
                 break; // break the loop if there is no receiver
             }
         }
@@ -666,35 +710,365 @@ fn create_parser_thread(
     tx_raw
 }
 
-async fn crawl_event_one_chunk(
+// A topic subscribed by a websocket connection
+trait Topic: Clone + Send + Sync + 'static {
+    fn symbol(&self) -> &str;
+
+    fn subscribe(
+        exchange: String,
+        market_type: MarketType,
+        msg_type: MessageType,
+        topics: Vec<Self>,
+        ws_client: Arc<dyn WSClient + Send + Sync>,
+    ) -> Pin<Box<dyn Future<Output = Result<(), CrawlError>> + Send>>;
+}
+
+// A symbol
+impl Topic for String {
+    fn symbol(&self) -> &str {
+        self
+    }
+
+    fn subscribe(
+        exchange: String,
+        market_type: MarketType,
+        msg_type: MessageType,
+        topics: Vec<Self>,
+        ws_client: Arc<dyn WSClient + Send + Sync>,
+    ) -> Pin<Box<dyn Future<Output = Result<(), CrawlError>> + Send>> {
+        Box::pin(subscribe_with_lock(exchange, market_type, msg_type, topics, ws_client))
+    }
+}
+
+// A (symbol, interval) pair of candlesticks
+impl Topic for (String, usize) {
+    fn symbol(&self) -> &str {
+        &self.0
+    }
+
+    fn subscribe(
+        _exchange: String,
+        _market_type: MarketType,
+        _msg_type: MessageType,
+        topics: Vec<Self>,
+        ws_client: Arc<dyn WSClient + Send + Sync>,
+    ) -> Pin<Box<dyn Future<Output = Result<(), CrawlError>> + Send>> {
+        Box::pin(async move {
+            subscribe_candlestick(ws_client.as_ref(), &topics).await;
+            Ok(())
+        })
+    }
+}
+
+// Topics of a websocket connection, which are subscribed again after restarts
+struct Chunk<T> {
+    topics: std::sync::Mutex<Vec<T>>,
+    ws_client: std::sync::Mutex<Option<Arc<dyn WSClient + Send + Sync>>>,
+    // set before closing the connection to connect again
+    reconnect: AtomicBool,
+    // set once the receiver of messages is dropped
+    receiver_dropped: Arc<AtomicBool>,
+}
+
+impl<T: Topic> Chunk<T> {
+    fn has_symbol(&self, symbol: &str) -> bool {
+        self.topics.lock().unwrap().iter().any(|topic| topic.symbol().eq_ignore_ascii_case(symbol))
+    }
+}
+
+// Connects again if the watchdog closed the connection, otherwise fails once
+// the connection stops, so that the supervisor restarts it with backoff,
+// unless the receiver of messages was dropped.
+async fn run_chunk<T, C, Fut>(
     exchange: String,
     market_type: MarketType,
     msg_type: MessageType,
-    ws_client: Option<Arc<dyn WSClient + Send + Sync>>,
-    symbols: Vec<String>,
+    chunk: Arc<Chunk<T>>,
+    connect: C,
+) -> Result<(), String>
+where
+    T: Topic,
+    C: Fn() -> Fut,
+    Fut: Future<Output = Result<Arc<dyn WSClient + Send + Sync>, CrawlError>>,
+{
+    loop {
+        let ws_client = connect().await.map_err(|err| err.to_string())?;
+        *chunk.ws_client.lock().unwrap() = Some(ws_client.clone());
+        let topics = chunk.topics.lock().unwrap().clone();
+        // subscribe while running, so that messages are received in the meantime
+        let (subscribed, _) = tokio::join!(
+            T::subscribe(exchange.clone(), market_type, msg_type, topics, ws_client.clone()),
+            ws_client.run()
+        );
+        ws_client.close().await;
+        subscribed.map_err(|err| err.to_string())?;
+        if chunk.receiver_dropped.load(Ordering::SeqCst) {
+            return Ok(());
+        }
+        if !chunk.reconnect.swap(false, Ordering::SeqCst) {
+            return Err("the connection was closed".to_string());
+        }
+    }
+}
+
+// Splits topics into websocket connections, each of which is supervised and
+// restarted independently, so that a failed connection doesn't affect others.
+struct ChunkedCrawl<T> {
+    exchange: String,
+    market_type: MarketType,
+    msg_type: MessageType,
+    num_topics_per_connection: usize,
+    reporter: StatusReporter,
     tx: Sender<Message>,
-) -> tokio::task::JoinHandle<()> {
-    let ws_client = if let Some(ws_client) = ws_client {
-        ws_client
-    } else {
-        let tx_clone = tx.clone();
-        create_ws_client(&exchange, market_type, msg_type, tx_clone).await
-    };
+    chunks: Vec<Arc<Chunk<T>>>,
+    tasks: tokio::task::JoinSet<()>,
+    // watches quiet topics if any
+    tracker: Option<Arc<ActivityTracker>>,
+}
+
+impl<T: Topic> ChunkedCrawl<T> {
+    fn new(
+        exchange: &str,
+        market_type: MarketType,
+        msg_type: MessageType,
+        tx: Sender<Message>,
+    ) -> Self {
+        ChunkedCrawl {
+            exchange: exchange.to_string(),
+            market_type,
+            msg_type,
+            num_topics_per_connection: get_num_subscriptions_per_connection(exchange, market_type),
+            reporter: StatusReporter::new(exchange, market_type, msg_type, status_sender()),
+            tx,
+            chunks: Vec::new(),
+            tasks: tokio::task::JoinSet::new(),
+            tracker: None,
+        }
+    }
 
-    {
-        // fire and forget
-        let exchange_clone = exchange.to_string();
-        let ws_client_clone = ws_client.clone();
-        tokio::task::spawn(async move {
-            subscribe_with_lock(exchange_clone, market_type, msg_type, symbols, ws_client_clone)
-                .await;
+    // Resubscribes topics without messages for a while, or reconnects their
+    // connections
+    fn with_watchdog(mut self) -> Self {
+        self.tracker = Some(Arc::new(ActivityTracker::default()));
+        self
+    }
+
+    fn spawn_chunk(&mut self, topics: Vec<T>) {
+        let chunk = Arc::new(Chunk {
+            topics: std::sync::Mutex::new(topics),
+            ws_client: std::sync::Mutex::new(None),
+            reconnect: AtomicBool::new(false),
+            receiver_dropped: Arc::new(AtomicBool::new(false)),
         });
+        self.chunks.push(chunk.clone());
+        let task = format!("chunk-{}", self.chunks.len() - 1);
+        let exchange = self.exchange.clone();
+        let market_type = self.market_type;
+        let msg_type = self.msg_type;
+        let tx = self.tx.clone();
+        let tracker = self.tracker.clone();
+        let receiver_dropped = chunk.receiver_dropped.clone();
+        let connect = {
+            let exchange = exchange.clone();
+            move || {
+                let exchange = exchange.clone();
+                let tx = tx.clone();
+                let tracker = tracker.clone();
+                let receiver_dropped = receiver_dropped.clone();
+                async move {
+                    create_ws_client(
+                        &exchange,
+                        market_type,
+                        msg_type,
+                        tx,
+                        tracker,
+                        receiver_dropped,
+                    )
+                    .await
+                }
+            }
+        };
+        self.tasks.spawn(supervise(self.reporter.clone(), task, move || {
+            run_chunk(exchange.clone(), market_type, msg_type, chunk.clone(), connect.clone())
+        }));
     }
 
-    tokio::task::spawn(async move {
-        ws_client.run().await;
-        ws_client.close().await;
-    })
+    // Adds topics to the last connection, and opens new connections once it is
+    // full
+    fn add_topics(&mut self, mut topics: Vec<T>) {
+        let num_free = match self.chunks.last() {
+            Some(chunk) => {
+                self.num_topics_per_connection.saturating_sub(chunk.topics.lock().unwrap().len())
+            }
+            None => 0,
+        };
+        let rest = topics.split_off(num_free.min(topics.len()));
+        if !topics.is_empty() {
+            let chunk = self.chunks.last().unwrap();
+            chunk.topics.lock().unwrap().extend(topics.iter().cloned());
+            let ws_client = chunk.ws_client.lock().unwrap().clone();
+            if let Some(ws_client) = ws_client {
+                // fire and forget, a failed connection subscribes again after restart
+                let subscribe = T::subscribe(
+                    self.exchange.clone(),
+                    self.market_type,
+                    self.msg_type,
+                    topics,
+                    ws_client,
+                );
+                tokio::task::spawn(async move {
+                    if let Err(err) = subscribe.await {
+                        error!("{}", err);
+                    }
+                });
+            }
+        }
+        for chunk in rest.chunks(self.num_topics_per_connection) {
+            self.spawn_chunk(chunk.to_vec());
+        }
+        debug!(
+            "{} {} {} {} connections",
+            self.exchange,
+            self.market_type,
+            self.msg_type,
+            self.chunks.len()
+        );
+    }
+
+    fn check_activity(&self, tracker: &ActivityTracker) {
+        let now = Instant::now();
+        let channel = watchdog::get_channel(&self.exchange, self.market_type, self.msg_type);
+        let mut reconnects = Vec::new();
+        for (symbol, remedy) in tracker.check(now, channel.is_some()) {
+            let Some(index) = self.chunks.iter().position(|chunk| chunk.has_symbol(&symbol)) else {
+                continue;
+            };
+            let chunk = &self.chunks[index];
+            let ws_client = chunk.ws_client.lock().unwrap().clone();
+            match (remedy, channel, ws_client) {
+                (Remedy::Resubscribe, Some(channel), Some(ws_client)) => {
+                    warn!(
+                        "{} {} {} {} is quiet, subscribing it again",
+                        self.exchange, self.market_type, self.msg_type, symbol
+                    );
+                    let topics = chunk
+                        .topics
+                        .lock()
+                        .unwrap()
+                        .iter()
+                        .filter(|topic| topic.symbol().eq_ignore_ascii_case(&symbol))
+                        .cloned()
+                        .collect::<Vec<T>>();
+                    let resubscribe = T::subscribe(
+                        self.exchange.clone(),
+                        self.market_type,
+                        self.msg_type,
+                        topics,
+                        ws_client.clone(),
+                    );
+                    let topic = (channel.to_string(), symbol);
+                    tokio::task::spawn(async move {
+                        ws_client.unsubscribe(&[topic]).await;
+                        if let Err(err) = resubscribe.await {
+                            error!("{}", err);
+                        }
+                    });
+                }
+                _ if !reconnects.contains(&index) => {
+                    warn!(
+                        "{} {} {} {} is quiet, reconnecting chunk-{}",
+                        self.exchange, self.market_type, self.msg_type, symbol, index
+                    );
+                    reconnects.push(index);
+                }
+                _ => {}
+            }
+        }
+        for index in reconnects {
+            let chunk = &self.chunks[index];
+            let topics = chunk.topics.lock().unwrap().clone();
+            tracker.reconnected(
+                &topics.iter().map(|topic| topic.symbol()).collect::<Vec<&str>>(),
+                now,
+            );
+            let ws_client = chunk.ws_client.lock().unwrap().clone();
+            if let Some(ws_client) = ws_client {
+                chunk.reconnect.store(true, Ordering::SeqCst);
+                tokio::task::spawn(async move { ws_client.close().await });
+            }
+        }
+    }
+
+    // Removes topics of delisted symbols, and unsubscribes them if the channel
+    // is known
+    fn remove_symbols(&mut self, symbols: Vec<String>) {
+        let channel = watchdog::get_channel(&self.exchange, self.market_type, self.msg_type);
+        for chunk in self.chunks.iter() {
+            let removed = {
+                let mut topics = chunk.topics.lock().unwrap();
+                let (removed, kept) = topics
+                    .drain(..)
+                    .partition::<Vec<T>, _>(|topic| symbols.iter().any(|s| s == topic.symbol()));
+                *topics = kept;
+                removed
+            };
+            if removed.is_empty() {
+                continue;
+            }
+            let ws_client = chunk.ws_client.lock().unwrap().clone();
+            if let (Some(channel), Some(ws_client)) = (channel, ws_client) {
+                let topics = removed
+                    .iter()
+                    .map(|topic| (channel.to_string(), topic.symbol().to_string()))
+                    .collect::<Vec<(String, String)>>();
+                tokio::task::spawn(async move { ws_client.unsubscribe(&topics).await });
+            }
+        }
+        if let Some(tracker) = self.tracker.as_ref() {
+            tracker.remove(&symbols);
+        }
+    }
+
+    // Runs until all connections stop, e.g., the receiver of messages was dropped
+    async fn run(
+        mut self,
+        topics: Vec<T>,
+        mut new_topics_rx: Option<tokio::sync::mpsc::Receiver<TopicChange<T>>>,
+    ) {
+        self.add_topics(topics);
+        let mut check_interval = tokio::time::interval(watchdog::CHECK_INTERVAL);
+        loop {
+            let new_topics = async {
+                match new_topics_rx.as_mut() {
+                    Some(rx) => rx.recv().await,
+                    None => std::future::pending().await,
+                }
+            };
+            let check = async {
+                match self.tracker.as_ref() {
+                    Some(_) => check_interval.tick().await,
+                    None => std::future::pending().await,
+                }
+            };
+            tokio::select! {
+                change = new_topics => match change {
+                    Some(TopicChange::Add(topics)) => self.add_topics(topics),
+                    Some(TopicChange::Remove(symbols)) => self.remove_symbols(symbols),
+                    None => new_topics_rx = None,
+                },
+                _ = check => {
+                    if let Some(tracker) = self.tracker.clone() {
+                        self.check_activity(&tracker);
+                    }
+                },
+                finished = self.tasks.join_next() => {
+                    if finished.is_none() {
+                        break;
+                    }
+                }
+            }
+        }
+    }
 }
 
 pub(crate) async fn crawl_event(
@@ -704,18 +1078,7 @@ pub(crate) async fn crawl_event(
     symbols: Option<&[String]>,
     tx: Sender<Message>,
 ) {
-    let num_topics_per_connection = get_num_subscriptions_per_connection(exchange, market_type);
-    let is_empty = match symbols {
-        Some(list) => {
-            if list.is_empty() {
-                true
-            } else {
-                tokio::task::block_in_place(move || check_args(exchange, market_type, list));
-                false
-            }
-        }
-        None => true,
-    };
+    let is_empty = symbols.is_none_or(|list| list.is_empty());
     let automatic_symbol_discovery = is_empty;
 
     let real_symbols = if is_empty {
@@ -732,12 +1095,13 @@ pub(crate) async fn crawl_event(
     let (stop_ch_tx, stop_ch_rx) = tokio::sync::broadcast::channel::<EmptyStruct>(1);
 
     // create a thread to discover new symbols
-    let (tx_symbols, rx_symbols) = tokio::sync::mpsc::channel::<Vec<String>>(4);
+    let (tx_symbols, rx_symbols) = tokio::sync::mpsc::channel::<TopicChange<String>>(4);
     let symbol_discovery_thread = if automatic_symbol_discovery {
         let thread = create_symbol_discovery_thread(
             exchange.to_string(),
             market_type,
-            real_symbols.clone(),
+            Listings::new(exchange, market_type, msg_type, &real_symbols, symbol_event_sender()),
+            |symbols| symbols,
             stop_ch_rx,
             tx_symbols,
         );
@@ -746,87 +1110,20 @@ pub(crate) async fn crawl_event(
         None
     };
 
-    // create a thread to convert Sender<String> to Sender<Message>
-    if real_symbols.len() <= num_topics_per_connection {
-        let ws_client = create_ws_client(exchange, market_type, msg_type, tx).await;
-        subscribe_with_lock(
-            exchange.to_string(),
-            market_type,
-            msg_type,
-            real_symbols,
-            ws_client.clone(),
-        )
+    ChunkedCrawl::new(exchange, market_type, msg_type, tx)
+        .with_watchdog()
+        .run(real_symbols, symbol_discovery_thread.as_ref().map(|_| rx_symbols))
         .await;
-        if automatic_symbol_discovery {
-            create_new_symbol_receiver_thread(
-                exchange.to_string(),
-                msg_type,
-                market_type,
-                rx_symbols,
-                ws_client.clone(),
-            );
-        }
-        ws_client.run().await;
-        ws_client.close().await;
-    } else {
-        // split to chunks
-        let mut chunks: Vec<Vec<String>> = Vec::new();
-        for i in (0..real_symbols.len()).step_by(num_topics_per_connection) {
-            let chunk = real_symbols
-                [i..(std::cmp::min(i + num_topics_per_connection, real_symbols.len()))]
-                .to_vec();
-            chunks.push(chunk);
-        }
-        debug!("{} {} {}", real_symbols.len(), num_topics_per_connection, chunks.len(),);
-        assert!(chunks.len() > 1);
-
-        let mut last_ws_client = None;
-        let mut handles = Vec::new();
-        {
-            let n = chunks.len();
-            for (i, chunk) in chunks.into_iter().enumerate() {
-                last_ws_client = if i == (n - 1) {
-                    let tx_clone = tx.clone();
-                    Some(create_ws_client(exchange, market_type, msg_type, tx_clone).await)
-                } else {
-                    None
-                };
-                let ret = crawl_event_one_chunk(
-                    exchange.to_string(),
-                    market_type,
-                    msg_type,
-                    last_ws_client.clone(),
-                    chunk,
-                    tx.clone(),
-                );
-                handles.push(ret.await);
-            }
-            drop(tx);
-        }
-        if automatic_symbol_discovery && last_ws_client.is_some() {
-            create_new_symbol_receiver_thread(
-                exchange.to_string(),
-                msg_type,
-                market_type,
-                rx_symbols,
-                last_ws_client.unwrap(),
-            );
-        }
-        for handle in handles {
-            if let Err(err) = handle.await {
-                panic!("{}", err); // TODO: use tokio::task::JoinSet or futures::stream::FuturesUnordered
-            }
-        }
-    };
+
     _ = stop_ch_tx.send(EmptyStruct {});
     if let Some(thread) = symbol_discovery_thread {
         _ = thread.await;
     }
 }
 
-// from 1m to 5m
-fn get_candlestick_intervals(exchange: &str, market_type: MarketType) -> Vec<usize> {
-    match exchange {
+// from 1m to 5m, filtered by intervals the exchange supports
+pub(crate) fn get_candlestick_intervals(exchange: &str, market_type: MarketType) -> Vec<usize> {
+    let preferred = match exchange {
         "binance" => vec![60, 180, 300],
         "bybit" => vec![60, 180, 300],
         "deribit" => vec![60, 180, 300],
@@ -846,35 +1143,25 @@ fn get_candlestick_intervals(exchange: &str, market_type: MarketType) -> Vec<usi
             _ => vec![60, 180, 300],
         },
         _ => vec![60, 300],
-    }
-}
-
-async fn crawl_candlestick_one_chunk(
-    exchange: String,
-    market_type: MarketType,
-    ws_client: Option<Arc<dyn WSClient + Send + Sync>>,
-    symbol_interval_list: Vec<(String, usize)>,
-    tx: Sender<Message>,
-) -> tokio::task::JoinHandle<()> {
-    let ws_client = if let Some(ws_client) = ws_client {
-        ws_client
-    } else {
-        let tx_clone = tx.clone();
-        create_ws_client(&exchange, market_type, MessageType::Candlestick, tx_clone).await
     };
+    let supported = supported_candlestick_intervals(exchange, market_type);
+    preferred
+        .into_iter()
+        .filter(|interval| supported.contains(&CandleInterval::from_secs(*interval)))
+        .collect()
+}
 
-    {
-        // fire and forget
-        let ws_client_clone = ws_client.clone();
-        tokio::task::spawn(async move {
-            ws_client_clone.subscribe_candlestick(&symbol_interval_list).await;
-        });
+async fn subscribe_candlestick(
+    ws_client: &(dyn WSClient + Send + Sync),
+    symbol_interval_list: &[(String, usize)],
+) {
+    let symbol_interval_list = symbol_interval_list
+        .iter()
+        .map(|(symbol, interval)| (symbol.clone(), CandleInterval::from_secs(*interval)))
+        .collect::<Vec<(String, CandleInterval)>>();
+    if let Err(err) = ws_client.subscribe_candlestick(&symbol_interval_list).await {
+        error!("{}", err);
     }
-
-    tokio::task::spawn(async move {
-        ws_client.run().await;
-        ws_client.close().await;
-    })
 }
 
 pub(crate) async fn crawl_candlestick_ext(
@@ -883,31 +1170,22 @@ pub(crate) async fn crawl_candlestick_ext(
     symbol_interval_list: Option<&[(String, usize)]>,
     tx: Sender<Message>,
 ) {
-    let num_topics_per_connection = get_num_subscriptions_per_connection(exchange, market_type);
-    let is_empty = match symbol_interval_list {
-        Some(list) => {
-            if list.is_empty() {
-                true
-            } else {
-                let symbols: Vec<String> = list.iter().map(|t| t.0.clone()).collect();
-                tokio::task::block_in_place(move || check_args(exchange, market_type, &symbols));
-                false
-            }
-        }
-        None => true,
-    };
+    let is_empty = symbol_interval_list.is_none_or(|list| list.is_empty());
     let automatic_symbol_discovery = is_empty;
 
-    let symbol_interval_list: Vec<(String, usize)> = if is_empty {
-        let symbols =
-            tokio::task::block_in_place(move || fetch_symbols_retry(exchange, market_type));
-        let intervals = get_candlestick_intervals(exchange, market_type);
+    let intervals = get_candlestick_intervals(exchange, market_type);
+    let to_topics = move |symbols: Vec<String>| {
         symbols
             .iter()
             .flat_map(|symbol| {
                 intervals.clone().into_iter().map(move |interval| (symbol.clone(), interval))
             })
             .collect::<Vec<(String, usize)>>()
+    };
+    let symbol_interval_list: Vec<(String, usize)> = if is_empty {
+        let symbols =
+            tokio::task::block_in_place(move || fetch_symbols_retry(exchange, market_type));
+        to_topics(symbols)
     } else {
         symbol_interval_list.unwrap().to_vec()
     };
@@ -916,92 +1194,128 @@ pub(crate) async fn crawl_candlestick_ext(
         return;
     }
     let real_symbols: Vec<String> = symbol_interval_list.iter().map(|t| t.0.clone()).collect();
-    let real_intervals: Vec<usize> = symbol_interval_list.iter().map(|t| t.1).collect();
 
     // The stop channel is used by all tokio tasks
     let (stop_ch_tx, stop_ch_rx) = tokio::sync::broadcast::channel::<EmptyStruct>(1);
 
     // create a thread to discover new symbols
-    let (tx_symbols, rx_symbols) = tokio::sync::mpsc::channel::<Vec<String>>(4);
+    let (tx_topics, rx_topics) = tokio::sync::mpsc::channel::<TopicChange<(String, usize)>>(4);
     let symbol_discovery_thread = if automatic_symbol_discovery {
         let thread = create_symbol_discovery_thread(
             exchange.to_string(),
             market_type,
-            real_symbols,
+            Listings::new(
+                exchange,
+                market_type,
+                MessageType::Candlestick,
+                &real_symbols,
+                symbol_event_sender(),
+            ),
+            to_topics,
             stop_ch_rx,
-            tx_symbols,
+            tx_topics,
         );
         Some(thread)
     } else {
         None
     };
 
-    if symbol_interval_list.len() <= num_topics_per_connection {
-        let ws_client = create_ws_client(exchange, market_type, MessageType::Candlestick, tx).await;
-        ws_client.subscribe_candlestick(&symbol_interval_list).await;
-        if automatic_symbol_discovery {
-            create_new_symbol_receiver_thread_candlestick(
-                real_intervals,
-                rx_symbols,
-                ws_client.clone(),
+    ChunkedCrawl::new(exchange, market_type, MessageType::Candlestick, tx)
+        .run(symbol_interval_list, symbol_discovery_thread.as_ref().map(|_| rx_topics))
+        .await;
+
+    _ = stop_ch_tx.send(EmptyStruct {});
+    if let Some(thread) = symbol_discovery_thread {
+        _ = thread.await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use super::*;
+    use crypto_ws_mock::*;
+
+    // Supervises a chunk of Binance spot trades connected to `url`
+    async fn supervise_mock_chunk(url: String, tx: Sender<Message>) {
+        let chunk = Arc::new(Chunk {
+            topics: Mutex::new(vec!["BTCUSDT".to_string()]),
+            ws_client: Mutex::new(None),
+            reconnect: AtomicBool::new(false),
+            receiver_dropped: Arc::new(AtomicBool::new(false)),
+        });
+        let receiver_dropped = chunk.receiver_dropped.clone();
+        let connect = move || {
+            let tx = create_tracking_conversion_thread(
+                "binance".to_string(),
+                MessageType::Trade,
+                MarketType::Spot,
+                tx.clone(),
+                None,
+                receiver_dropped.clone(),
             );
-        }
-        ws_client.run().await;
-        ws_client.close().await;
-    } else {
-        // split to chunks
-        let mut chunks: Vec<Vec<(String, usize)>> = Vec::new();
-        {
-            for i in (0..symbol_interval_list.len()).step_by(num_topics_per_connection) {
-                let chunk: Vec<(String, usize)> = symbol_interval_list
-                    [i..(std::cmp::min(i + num_topics_per_connection, symbol_interval_list.len()))]
-                    .to_vec();
-                chunks.push(chunk);
+            let url = url.clone();
+            async move {
+                let ws_client: Arc<dyn WSClient + Send + Sync> =
+                    Arc::new(BinanceSpotWSClient::new(tx, Some(&url)).await);
+                Ok(ws_client)
             }
+        };
+        let reporter = StatusReporter::new("binance", MarketType::Spot, MessageType::Trade, None);
+        supervise(reporter, "chunk-0".to_string(), move || {
+            run_chunk(
+                "binance".to_string(),
+                MarketType::Spot,
+                MessageType::Trade,
+                chunk.clone(),
+                connect.clone(),
+            )
+        })
+        .await
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_restart_on_disconnect() {
+        let server = MockServer::start(exchanges::binance().then(vec![Frame::Disconnect])).await;
+        let (tx, rx) = std::sync::mpsc::channel();
+        let handle = tokio::task::spawn(supervise_mock_chunk(server.url().to_string(), tx));
+
+        // subscribes again after the restart
+        for _ in 0..2 {
+            let msg = rx.recv_timeout(Duration::from_secs(10)).unwrap();
+            assert_eq!(Some("BTCUSDT".to_string()), msg.symbol);
         }
-        debug!("{} {} {}", symbol_interval_list.len(), num_topics_per_connection, chunks.len(),);
-        assert!(chunks.len() > 1);
-
-        let mut last_ws_client = None;
-        let mut handles = Vec::new();
-        {
-            let n = chunks.len();
-            for (i, chunk) in chunks.into_iter().enumerate() {
-                last_ws_client = if i == (n - 1) {
-                    let tx_clone = tx.clone();
-                    Some(
-                        create_ws_client(exchange, market_type, MessageType::Candlestick, tx_clone)
-                            .await,
-                    )
-                } else {
-                    None
-                };
-                let ret = crawl_candlestick_one_chunk(
-                    exchange.to_string(),
-                    market_type,
-                    last_ws_client.clone(),
-                    chunk,
-                    tx.clone(),
-                );
-                handles.push(ret.await);
-            }
-            drop(tx);
-        }
-        if automatic_symbol_discovery && last_ws_client.is_some() {
-            create_new_symbol_receiver_thread_candlestick(
-                real_intervals,
-                rx_symbols,
-                last_ws_client.unwrap(),
-            );
-        }
-        for handle in handles {
-            if let Err(err) = handle.await {
-                panic!("{}", err); // TODO: use tokio::task::JoinSet or futures::stream::FuturesUnordered
+        assert_eq!(2, server.received().texts.len());
+        assert!(!handle.is_finished());
+        handle.abort();
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_stop_on_receiver_dropped() {
+        let trade = r#"{"stream":"btcusdt@aggTrade","data":{"e":"aggTrade","E":1677000000123,"s":"BTCUSDT","a":2075193823,"p":"24512.35000000","q":"0.00420000","f":2418403615,"l":2418403615,"T":1677000000122,"m":false,"M":true}}"#;
+        // the conversion thread exits on the first message, then the client
+        // fails to send the second one
+        let exchange = MockExchange::new(move |msg| {
+            if msg.contains("SUBSCRIBE") {
+                vec![
+                    Frame::Text(trade.to_string()),
+                    Frame::Sleep(Duration::from_millis(500)),
+                    Frame::Text(trade.to_string()),
+                ]
+            } else {
+                Vec::new()
             }
-        }
-    };
-    _ = stop_ch_tx.send(EmptyStruct {});
-    if let Some(thread) = symbol_discovery_thread {
-        _ = thread.await;
+        });
+        let server = MockServer::start(exchange).await;
+        let (tx, rx) = std::sync::mpsc::channel();
+        drop(rx);
+        // the supervisor returns instead of restarting the chunk
+        tokio::time::timeout(
+            Duration::from_secs(10),
+            supervise_mock_chunk(server.url().to_string(), tx),
+        )
+        .await
+        .unwrap();
     }
 }
@@ -0,0 +1,249 @@
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use crypto_market_type::MarketType;
+use crypto_msg_type::MessageType;
+
+/// How often quiet topics are checked
+pub(super) const CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+// A topic is quiet after QUIET_GAPS average gaps without messages, bounded by
+// MIN_QUIET and MAX_QUIET
+const QUIET_GAPS: u32 = 20;
+const MIN_QUIET: Duration = Duration::from_secs(60);
+const MAX_QUIET: Duration = Duration::from_secs(6 * 3600);
+// Weight of the latest gap in the moving average of gaps
+const ALPHA: f64 = 0.05;
+
+/// What to do with a quiet topic.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(super) enum Remedy {
+    /// Unsubscribe and subscribe the topic again on the same connection.
+    Resubscribe,
+    /// Reconnect the connection of the topic.
+    Reconnect,
+}
+
+struct Activity {
+    last_seen: Instant,
+    // moving average of gaps between messages in seconds
+    avg_gap: Option<f64>,
+    // remedies applied since the last message
+    strikes: u32,
+    last_remedy: Option<Instant>,
+}
+
+impl Activity {
+    // Doubled after each remedy
+    fn threshold(&self) -> Duration {
+        match self.avg_gap {
+            Some(avg_gap) => {
+                Duration::from_secs_f64(avg_gap * QUIET_GAPS as f64).clamp(MIN_QUIET, MAX_QUIET)
+                    * 2_u32.pow(self.strikes)
+            }
+            None => MAX_QUIET,
+        }
+    }
+
+    fn is_quiet(&self, now: Instant) -> bool {
+        let since = self.last_remedy.map_or(self.last_seen, |t| t.max(self.last_seen));
+        now.saturating_duration_since(since) >= self.threshold()
+    }
+}
+
+/// Tracks when each symbol of a crawl last received a message.
+///
+/// Only symbols that have received messages are watched, so symbols without
+/// any activity, or whose messages don't carry a symbol, never get remedies.
+#[derive(Default)]
+pub(super) struct ActivityTracker {
+    symbols: Mutex<HashMap<String, Activity>>,
+}
+
+impl ActivityTracker {
+    pub(super) fn record(&self, symbol: &str, now: Instant) {
+        let mut symbols = self.symbols.lock().unwrap();
+        match symbols.get_mut(symbol) {
+            Some(activity) => {
+                // gaps of quiet periods would inflate the average
+                if activity.strikes == 0 {
+                    let gap = now.saturating_duration_since(activity.last_seen).as_secs_f64();
+                    activity.avg_gap = Some(match activity.avg_gap {
+                        Some(avg_gap) => avg_gap * (1.0 - ALPHA) + gap * ALPHA,
+                        None => gap,
+                    });
+                }
+                activity.last_seen = now;
+                activity.strikes = 0;
+                activity.last_remedy = None;
+            }
+            None => {
+                symbols.insert(
+                    symbol.to_string(),
+                    Activity { last_seen: now, avg_gap: None, strikes: 0, last_remedy: None },
+                );
+            }
+        }
+    }
+
+    /// Returns quiet symbols and their remedies, i.e., resubscribing first if
+    /// `can_resubscribe`, then reconnecting. A symbol still quiet after
+    /// reconnecting is left alone until it receives messages again.
+    pub(super) fn check(&self, now: Instant, can_resubscribe: bool) -> Vec<(String, Remedy)> {
+        let mut symbols = self.symbols.lock().unwrap();
+        let mut quiet = Vec::new();
+        for (symbol, activity) in symbols.iter_mut() {
+            if activity.strikes >= 2 || !activity.is_quiet(now) {
+                continue;
+            }
+            let remedy = if activity.strikes == 0 && can_resubscribe {
+                activity.strikes = 1;
+                Remedy::Resubscribe
+            } else {
+                activity.strikes = 2;
+                Remedy::Reconnect
+            };
+            activity.last_remedy = Some(now);
+            quiet.push((symbol.clone(), remedy));
+        }
+        quiet
+    }
+
+    /// Stops watching delisted symbols.
+    pub(super) fn remove(&self, symbols: &[String]) {
+        let mut activities = self.symbols.lock().unwrap();
+        activities.retain(|symbol, _| !symbols.iter().any(|s| s.eq_ignore_ascii_case(symbol)));
+    }
+
+    /// Gives symbols of a reconnected connection time to receive messages.
+    pub(super) fn reconnected(&self, symbols: &[&str], now: Instant) {
+        let mut activities = self.symbols.lock().unwrap();
+        for (symbol, activity) in activities.iter_mut() {
+            // symbols of messages may differ in case, e.g., btcusdt and BTCUSDT
+            if symbols.iter().any(|s| s.eq_ignore_ascii_case(symbol)) {
+                activity.last_remedy = Some(now);
+            }
+        }
+    }
+}
+
+/// The channel of `WSClient::subscribe()` and `WSClient::unsubscribe()` for
+/// `msg_type`, if known.
+pub(super) fn get_channel(
+    exchange: &str,
+    market_type: MarketType,
+    msg_type: MessageType,
+) -> Option<&'static str> {
+    let channel = match (exchange, msg_type) {
+        ("binance", MessageType::Trade) => {
+            if market_type == MarketType::EuropeanOption {
+                "trade"
+            } else {
+                "aggTrade"
+            }
+        }
+        ("binance", MessageType::L2Event) => "depth@100ms",
+        ("binance", MessageType::L2TopK) => {
+            if market_type == MarketType::EuropeanOption {
+                "depth10"
+            } else {
+                "depth20"
+            }
+        }
+        ("binance", MessageType::BBO) => "bookTicker",
+        ("binance", MessageType::Ticker) => "ticker",
+        ("bitmex", MessageType::Trade) => "trade",
+        ("bitmex", MessageType::BBO) => "quote",
+        ("bitmex", MessageType::L2Event) => "orderBookL2",
+        ("bitmex", MessageType::L2TopK) => "orderBook10",
+        ("bybit", MessageType::Trade) => "trade",
+        ("bybit", MessageType::L2Event) => "orderBookL2_25",
+        ("bybit", MessageType::Ticker) => "instrument_info.100ms",
+        ("deribit", MessageType::Trade) => "trades.SYMBOL.100ms",
+        ("deribit", MessageType::Ticker) => "ticker.SYMBOL.100ms",
+        ("deribit", MessageType::L2Event) => "book.SYMBOL.100ms",
+        ("deribit", MessageType::L2TopK) => "book.SYMBOL.none.20.100ms",
+        ("deribit", MessageType::BBO) => "quote.SYMBOL",
+        ("gate", MessageType::Trade) => "trades",
+        ("gate", MessageType::Ticker) => "tickers",
+        ("gate", MessageType::L2Event) => match market_type {
+            MarketType::InverseFuture | MarketType::LinearFuture => "order_book",
+            _ => "order_book_update",
+        },
+        ("huobi", MessageType::Trade) => "trade.detail",
+        ("huobi", MessageType::L2Event) if market_type == MarketType::Spot => "mbp.20",
+        ("huobi", MessageType::L2TopK) => {
+            if market_type == MarketType::Spot {
+                "depth.step1"
+            } else {
+                "depth.step7"
+            }
+        }
+        ("huobi", MessageType::BBO) => "bbo",
+        ("huobi", MessageType::Ticker) => "detail",
+        ("okx", MessageType::Trade) => "trades",
+        ("okx", MessageType::Ticker) => "tickers",
+        ("okx", MessageType::BBO) => "bbo-tbt",
+        ("okx", MessageType::L2Event) => "books",
+        ("okx", MessageType::L2TopK) => "books5",
+        _ => return None,
+    };
+    Some(channel)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use super::{ActivityTracker, Remedy};
+
+    #[test]
+    fn test_escalation() {
+        let tracker = ActivityTracker::default();
+        let start = Instant::now();
+        // one message per second
+        for i in 0..100 {
+            tracker.record("BTCUSDT", start + Duration::from_secs(i));
+        }
+        let last_seen = start + Duration::from_secs(99);
+        // the threshold is 20 seconds, raised to the minimum of 60 seconds
+        assert!(tracker.check(last_seen + Duration::from_secs(59), true).is_empty());
+        let now = last_seen + Duration::from_secs(60);
+        assert_eq!(vec![("BTCUSDT".to_string(), Remedy::Resubscribe)], tracker.check(now, true));
+        // the threshold is doubled after each remedy
+        assert!(tracker.check(now + Duration::from_secs(119), true).is_empty());
+        let now = now + Duration::from_secs(120);
+        assert_eq!(vec![("BTCUSDT".to_string(), Remedy::Reconnect)], tracker.check(now, true));
+        assert!(tracker.check(now + Duration::from_secs(24 * 3600), true).is_empty());
+
+        // a message starts over
+        tracker.record("BTCUSDT", now);
+        assert_eq!(
+            vec![("BTCUSDT".to_string(), Remedy::Reconnect)],
+            tracker.check(now + Duration::from_secs(60), false)
+        );
+    }
+
+    #[test]
+    fn test_adaptive_threshold() {
+        let tracker = ActivityTracker::default();
+        let start = Instant::now();
+        // one message per second and one per minute
+        for i in 0..100 {
+            tracker.record("BTCUSDT", start + Duration::from_secs(i));
+            tracker.record("DOGEUSDT", start + Duration::from_secs(60 * i));
+        }
+        let now = start + Duration::from_secs(60 * 99);
+        assert_eq!(
+            vec![("BTCUSDT".to_string(), Remedy::Resubscribe)],
+            tracker.check(now + Duration::from_secs(60 * 19), true)
+        );
+        assert_eq!(
+            vec![("DOGEUSDT".to_string(), Remedy::Resubscribe)],
+            tracker.check(now + Duration::from_secs(60 * 20), true)
+        );
+    }
+}
@@ -10,7 +10,7 @@
 //!     let (tx, rx) = std::sync::mpsc::channel();
 //!     tokio::task::spawn(async move {
 //!         // Crawl realtime trades for all symbols of binance inverse_swap markets
-//!         crawl_trade("binance", MarketType::InverseSwap, None, tx).await;
+//!         crawl_trade("binance", MarketType::InverseSwap, None, tx).await.unwrap();
 //!     });
 //!
 //!     let mut messages = Vec::new();
@@ -32,7 +32,7 @@
 //!     let (tx, rx) = std::sync::mpsc::channel();
 //!     tokio::task::spawn(async move {
 //!         // Crawl realtime level2 incremental updates for all symbols of binance inverse_swap markets
-//!         crawl_l2_event("binance", MarketType::InverseSwap, None, tx).await;
+//!         crawl_l2_event("binance", MarketType::InverseSwap, None, tx).await.unwrap();
 //!     });
 //!
 //!     let mut messages = Vec::new();
@@ -52,7 +52,7 @@
 //! let (tx, rx) = std::sync::mpsc::channel();
 //! std::thread::spawn(move || {
 //!     // Crawl level2 full snapshots for all symbols of binance inverse_swap markets
-//!     crawl_l2_snapshot("binance", MarketType::InverseSwap, None, tx);
+//!     crawl_l2_snapshot("binance", MarketType::InverseSwap, None, tx).unwrap();
 //! });
 //!
 //! let mut messages = Vec::new();
@@ -73,7 +73,7 @@
 //!     let (tx, rx) = std::sync::mpsc::channel();
 //!     tokio::task::spawn(async move {
 //!         // Crawl realtime level2 top-k snapshots for all symbols of binance inverse_swap markets
-//!         crawl_l2_topk("binance", MarketType::InverseSwap, None, tx).await;
+//!         crawl_l2_topk("binance", MarketType::InverseSwap, None, tx).await.unwrap();
 //!     });
 //!
 //!     let mut messages = Vec::new();
@@ -95,7 +95,7 @@
 //!     let (tx, rx) = std::sync::mpsc::channel();
 //!     tokio::task::spawn(async move {
 //!         // Crawl realtime level3 updates for all symbols of CoinbasePro spot market
-//!         crawl_l3_event("coinbase_pro", MarketType::Spot, None, tx).await;
+//!         crawl_l3_event("coinbase_pro", MarketType::Spot, None, tx).await.unwrap();
 //!     });
 //!
 //!     let mut messages = Vec::new();
@@ -115,7 +115,7 @@
 //! let (tx, rx) = std::sync::mpsc::channel();
 //! std::thread::spawn(move || {
 //!     // Crawl level3 orderbook full snapshots for all symbols of CoinbasePro spot markets
-//!     crawl_l3_snapshot("coinbase_pro", MarketType::Spot, None, tx);
+//!     crawl_l3_snapshot("coinbase_pro", MarketType::Spot, None, tx).unwrap();
 //! });
 //!
 //! let mut messages = Vec::new();
@@ -136,7 +136,7 @@
 //!     let (tx, rx) = std::sync::mpsc::channel();
 //!     tokio::task::spawn(async move {
 //!         // Crawl realtime best bid and ask messages for all symbols of binance COIN-margined perpetual markets
-//!         crawl_bbo("binance", MarketType::InverseSwap, None, tx).await;
+//!         crawl_bbo("binance", MarketType::InverseSwap, None, tx).await.unwrap();
 //!     });
 //!
 //!     let mut messages = Vec::new();
@@ -158,7 +158,7 @@
 //!     let (tx, rx) = std::sync::mpsc::channel();
 //!     tokio::task::spawn(async move {
 //!         // Crawl 24hr rolling window tickers for all symbols of binance COIN-margined perpetual markets
-//!         crawl_ticker("binance", MarketType::InverseSwap, None, tx).await;
+//!         crawl_ticker("binance", MarketType::InverseSwap, None, tx).await.unwrap();
 //!     });
 //!
 //!     let mut messages = Vec::new();
@@ -180,7 +180,7 @@
 //!     let (tx, rx) = std::sync::mpsc::channel();
 //!     tokio::task::spawn(async move {
 //!         // Crawl candlesticks from 1 minute to 3 minutes for all symbols of binance COIN-margined perpetual markets
-//!         crawl_candlestick("binance", MarketType::InverseSwap, None, tx).await;
+//!         crawl_candlestick("binance", MarketType::InverseSwap, None, tx).await.unwrap();
 //!     });
 //!
 //!     let mut messages = Vec::new();
@@ -202,7 +202,7 @@
 //!     let (tx, rx) = std::sync::mpsc::channel();
 //!     tokio::task::spawn(async move {
 //!         // Crawl funding rates for all symbols of binance COIN-margined perpetual markets
-//!         crawl_funding_rate("binance", MarketType::InverseSwap, None, tx).await;
+//!         crawl_funding_rate("binance", MarketType::InverseSwap, None, tx).await.unwrap();
 //!     });
 //!
 //!     let mut messages = Vec::new();
@@ -213,40 +213,204 @@
 //!     assert!(!messages.is_empty());
 //! }
 //! ```
+//!
+//! ## Maintain BitMEX tables
+//!
+//! BitMEX messages are actions on keyed tables, `BitmexTables` applies them to
+//! local copies, from which snapshots and diffs of `orderBookL2`, `instrument`,
+//! `funding` and other tables can be taken per symbol.
+//!
+//! ## Maintain local level2 orderbooks
+//!
+//! ```rust,no_run
+//! use crypto_crawler::{sync_l2_orderbook, MarketType, OrderBookEvent};
+//!
+//! #[tokio::main(flavor = "multi_thread")]
+//! async fn main() {
+//!     let (tx, rx) = std::sync::mpsc::channel();
+//!     tokio::task::spawn(async move {
+//!         let symbols = vec!["BTCUSDT".to_string()];
+//!         sync_l2_orderbook("binance", MarketType::Spot, &symbols, tx).await.unwrap();
+//!     });
+//!
+//!     for event in rx {
+//!         if let OrderBookEvent::Snapshot(book) = event {
+//!             println!("{:?} {:?}", book.best_bid(), book.best_ask());
+//!             break;
+//!         }
+//!     }
+//! }
+//! ```
+//!
+//! `OrderBookSynchronizer` does the same for messages from other sources,
+//! e.g., recordings.
+//!
+//! ## Capabilities
+//!
+//! `supported_msg_types()` lists message types of a market, `crawl_*`
+//! functions return `CrawlError` for anything else instead of crawling.
+//!
+//! ```rust
+//! use crypto_crawler::{supported_msg_types, MarketType, MessageType};
+//!
+//! let msg_types = supported_msg_types("binance", MarketType::LinearSwap);
+//! assert!(msg_types.contains(&MessageType::FundingRate));
+//! let msg_types = supported_msg_types("binance", MarketType::Spot);
+//! assert!(!msg_types.contains(&MessageType::FundingRate));
+//! ```
+//!
+//! ## Run multiple jobs
+//!
+//! ```rust,no_run
+//! use crypto_crawler::{CrawlJob, Crawler, MarketType, MessageType};
+//!
+//! let (tx, rx) = std::sync::mpsc::channel();
+//! let mut crawler = Crawler::builder()
+//!     .job(CrawlJob::new("binance", MarketType::Spot, MessageType::Trade))
+//!     .job(CrawlJob::new("okx", MarketType::LinearSwap, MessageType::L2Event))
+//!     .build(tx)
+//!     .unwrap();
+//! crawler.start_all();
+//!
+//! for msg in rx {
+//!     println!("{}", msg.json);
+//! }
+//! ```
+//!
+//! `Crawler::stop()` and `Crawler::status()` control jobs one by one.
+//!
+//! ## Write messages to files
+//!
+//! ```rust,no_run
+//! use crypto_crawler::{Compression, FileSink, Message};
+//!
+//! # let (tx, rx) = std::sync::mpsc::channel::<Message>();
+//! // Hourly files such as binance.spot.trade.2023-03-20-08.tsv.zst
+//! let mut sink = FileSink::builder("/data").compression(Compression::Zstd).build();
+//! for msg in rx {
+//!     sink.write(&msg).unwrap();
+//! }
+//! ```
+//!
+//! With the `parquet` feature, `ParquetSink` writes hourly Parquet files with
+//! raw messages, or with trades and level2 updates parsed into typed columns.
+//!
+//! ## Send messages to other processes
+//!
+//! ```rust,no_run
+//! use crypto_crawler::{crawl_trade, spawn_sink, FanoutSink, MarketType, RedisSink, TcpSink};
+//!
+//! #[tokio::main(flavor = "multi_thread")]
+//! async fn main() {
+//!     // Newline-delimited JSON over TCP, and a Redis stream of about 1M entries
+//!     let redis = RedisSink::connect("127.0.0.1:6379", "trades").unwrap();
+//!     let sink = FanoutSink::new()
+//!         .sink(TcpSink::connect("127.0.0.1:9000").unwrap())
+//!         .sink(redis.with_max_len(1_000_000));
+//!     let (tx, _writer) = spawn_sink(sink);
+//!     crawl_trade("binance", MarketType::Spot, None, tx).await.unwrap();
+//! }
+//! ```
+//!
+//! Any type implementing `Sink` can be passed to `spawn_sink()`.
+//!
+//! ## Failures
+//!
+//! Websocket crawlers split symbols into connections, a connection which fails
+//! is restarted with exponential backoff while the others keep running. Run a
+//! crawl in `report_status_to()`, or set `CrawlerBuilder::status_sender()`, to
+//! receive these failures and restarts as `CrawlStatus` events.
+//!
+//! A symbol may also stop receiving messages while its connection stays alive.
+//! Crawlers of `crawl_trade()`, `crawl_l2_event()` and other events watch
+//! each symbol against a threshold learned from its usual message rate, a
+//! quiet symbol is unsubscribed and subscribed again, then its connection is
+//! reconnected if it is still quiet.
+//!
+//! ## Symbol discovery
+//!
+//! Websocket crawls of all symbols, i.e., `symbols` is `None` or empty, fetch
+//! listed symbols hourly, or by `set_symbol_discovery_interval()`. New symbols
+//! are subscribed on new connections once existing ones are full, delisted
+//! symbols are unsubscribed. Run a crawl in `report_symbol_events_to()`, or
+//! set `CrawlerBuilder::symbol_event_sender()`, to receive these changes as
+//! `SymbolEvent`s.
+mod bitmex_tables;
+mod capabilities;
+mod crawler;
 mod crawlers;
 mod msg;
+mod orderbook;
+mod sinks;
 mod utils;
 
 use std::sync::mpsc::Sender;
 
-pub use crawlers::fetch_symbols_retry;
+pub use bitmex_tables::{BitmexTables, TableAction, TableDiff, TableError, TableUpdate};
+pub use capabilities::{supported_msg_types, CrawlError};
+pub use crawler::{CrawlJob, Crawler, CrawlerBuilder, JobStatus};
+pub use crawlers::{
+    fetch_symbols_retry, report_status_to, report_symbol_events_to, CrawlStatus, SymbolChange,
+    SymbolEvent, TaskState,
+};
 pub use crypto_market_type::MarketType;
 pub use crypto_msg_type::MessageType;
 pub use msg::*;
-pub use utils::get_hot_spot_symbols;
+pub use orderbook::{
+    BookDelta, OrderBook, OrderBookEvent, OrderBookSynchronizer, SnapshotSource, SyncError,
+    SyncStatus,
+};
+pub use sinks::{
+    spawn_sink, Compression, FanoutSink, FileFormat, FileSink, FileSinkBuilder, RedisSink, Sink,
+    TcpSink,
+};
+#[cfg(feature = "parquet")]
+pub use sinks::{ParquetMode, ParquetSink, ParquetSinkBuilder};
+#[cfg(unix)]
+pub use sinks::UnixSink;
+pub use utils::{
+    get_hot_spot_symbols, set_data_dir, set_rest_retry_count, set_symbol_discovery_interval,
+};
+
+fn check_args(
+    exchange: &str,
+    market_type: MarketType,
+    msg_type: MessageType,
+    symbols: Option<&[String]>,
+) -> Result<(), CrawlError> {
+    capabilities::check_msg_type(exchange, market_type, msg_type)?;
+    match symbols {
+        // MarketType::Unknown crawls all symbols
+        Some(list) if !list.is_empty() && market_type != MarketType::Unknown => {
+            // fetching symbols blocks
+            tokio::task::block_in_place(|| crawlers::check_symbols(exchange, market_type, list))
+        }
+        _ => Ok(()),
+    }
+}
 
 /// Crawl realtime trades.
 ///
 /// If `symbols` is None or empty, this API will crawl realtime trades for all
 /// symbols in the `market_type` market, and launch a thread to discover new
 /// symbols every hour. And so forth for all other APIs.
+///
+/// Returns `CrawlError` before crawling if arguments are invalid, and so forth
+/// for all other APIs.
 pub async fn crawl_trade(
     exchange: &str,
     market_type: MarketType,
     symbols: Option<&[String]>,
     tx: Sender<Message>,
-) {
+) -> Result<(), CrawlError> {
+    check_args(exchange, market_type, MessageType::Trade, symbols)?;
     match exchange {
         "binance" => crawlers::binance::crawl_trade(market_type, symbols, tx).await,
         "bitmex" => crawlers::bitmex::crawl_trade(market_type, symbols, tx).await,
         "deribit" => crawlers::deribit::crawl_trade(market_type, symbols, tx).await,
-        "bitfinex" | "bitget" | "bithumb" | "bitstamp" | "bitz" | "bybit" | "coinbase_pro"
-        | "dydx" | "ftx" | "gate" | "huobi" | "kraken" | "kucoin" | "mexc" | "okx" | "zb"
-        | "zbg" => {
-            crawlers::crawl_event(exchange, MessageType::Trade, market_type, symbols, tx).await
-        }
-        _ => panic!("{exchange} does NOT have the trade websocket channel"),
+        _ => crawlers::crawl_event(exchange, MessageType::Trade, market_type, symbols, tx).await,
     }
+    Ok(())
 }
 
 /// Crawl level2 orderbook update events.
@@ -255,17 +419,32 @@ pub async fn crawl_l2_event(
     market_type: MarketType,
     symbols: Option<&[String]>,
     tx: Sender<Message>,
-) {
+) -> Result<(), CrawlError> {
+    check_args(exchange, market_type, MessageType::L2Event, symbols)?;
     match exchange {
         "bitmex" => crawlers::bitmex::crawl_l2_event(market_type, symbols, tx).await,
         "huobi" => crawlers::huobi::crawl_l2_event(market_type, symbols, tx).await,
-        "binance" | "bitfinex" | "bitget" | "bithumb" | "bitstamp" | "bitz" | "bybit"
-        | "coinbase_pro" | "deribit" | "dydx" | "ftx" | "gate" | "kraken" | "kucoin" | "mexc"
-        | "okx" | "zb" | "zbg" => {
-            crawlers::crawl_event(exchange, MessageType::L2Event, market_type, symbols, tx).await
-        }
-        _ => panic!("{exchange} does NOT have the incremental level2 websocket channel"),
+        _ => crawlers::crawl_event(exchange, MessageType::L2Event, market_type, symbols, tx).await,
     }
+    Ok(())
+}
+
+/// Maintain local level2 orderbooks from websocket deltas and snapshots.
+///
+/// An `OrderBookEvent::Snapshot` is sent once the book of a symbol is built,
+/// followed by deltas. If a delta is missing, `OrderBookEvent::OutOfSync` is
+/// sent and the book is rebuilt from a new snapshot automatically.
+///
+/// Supports Binance, Bybit, Gate, Huobi Spot, KuCoin and OKX, symbols are
+/// in the websocket format, e.g., `BTCUSDT` of Binance and `btcusdt` of Huobi.
+/// Returns `CrawlError::UnsupportedOrderBookSync` for other markets.
+pub async fn sync_l2_orderbook(
+    exchange: &str,
+    market_type: MarketType,
+    symbols: &[String],
+    tx: Sender<OrderBookEvent>,
+) -> Result<(), CrawlError> {
+    orderbook::sync_l2_orderbook(exchange, market_type, symbols, tx).await
 }
 
 /// Crawl level3 orderbook update events.
@@ -274,13 +453,10 @@ pub async fn crawl_l3_event(
     market_type: MarketType,
     symbols: Option<&[String]>,
     tx: Sender<Message>,
-) {
-    match exchange {
-        "bitfinex" | "bitstamp" | "coinbase_pro" | "kucoin" => {
-            crawlers::crawl_event(exchange, MessageType::L3Event, market_type, symbols, tx).await
-        }
-        _ => panic!("{exchange} does NOT have the incremental level3 websocket channel"),
-    }
+) -> Result<(), CrawlError> {
+    check_args(exchange, market_type, MessageType::L3Event, symbols)?;
+    crawlers::crawl_event(exchange, MessageType::L3Event, market_type, symbols, tx).await;
+    Ok(())
 }
 
 /// Crawl level2 orderbook snapshots through RESTful APIs.
@@ -289,8 +465,9 @@ pub fn crawl_l2_snapshot(
     market_type: MarketType,
     symbols: Option<&[String]>,
     tx: Sender<Message>,
-) {
-    crawlers::crawl_snapshot(exchange, market_type, MessageType::L2Snapshot, symbols, tx);
+) -> Result<(), CrawlError> {
+    check_args(exchange, market_type, MessageType::L2Snapshot, symbols)?;
+    crawlers::crawl_snapshot(exchange, market_type, MessageType::L2Snapshot, symbols, tx)
 }
 
 /// Crawl best bid and ask.
@@ -299,16 +476,15 @@ pub async fn crawl_bbo(
     market_type: MarketType,
     symbols: Option<&[String]>,
     tx: Sender<Message>,
-) {
+) -> Result<(), CrawlError> {
+    check_args(exchange, market_type, MessageType::BBO, symbols)?;
     match exchange {
         "binance" => crawlers::binance::crawl_bbo(market_type, symbols, tx).await,
         "bitmex" => crawlers::bitmex::crawl_bbo(market_type, symbols, tx).await,
         "kucoin" => crawlers::kucoin::crawl_bbo(market_type, symbols, tx).await,
-        "deribit" | "ftx" | "gate" | "huobi" | "kraken" | "okx" => {
-            crawlers::crawl_event(exchange, MessageType::BBO, market_type, symbols, tx).await
-        }
-        _ => panic!("{exchange} does NOT have BBO websocket channel"),
+        _ => crawlers::crawl_event(exchange, MessageType::BBO, market_type, symbols, tx).await,
     }
+    Ok(())
 }
 
 /// Crawl level2 orderbook top-k snapshots through websocket.
@@ -317,15 +493,13 @@ pub async fn crawl_l2_topk(
     market_type: MarketType,
     symbols: Option<&[String]>,
     tx: Sender<Message>,
-) {
+) -> Result<(), CrawlError> {
+    check_args(exchange, market_type, MessageType::L2TopK, symbols)?;
     match exchange {
         "bitmex" => crawlers::bitmex::crawl_l2_topk(market_type, symbols, tx).await,
-        "binance" | "bitget" | "bybit" | "bitstamp" | "deribit" | "gate" | "huobi" | "kucoin"
-        | "mexc" | "okx" | "zb" => {
-            crawlers::crawl_event(exchange, MessageType::L2TopK, market_type, symbols, tx).await
-        }
-        _ => panic!("{exchange} does NOT have the level2 top-k snapshot websocket channel"),
+        _ => crawlers::crawl_event(exchange, MessageType::L2TopK, market_type, symbols, tx).await,
     }
+    Ok(())
 }
 
 /// Crawl level3 orderbook snapshots through RESTful APIs.
@@ -334,7 +508,8 @@ pub fn crawl_l3_snapshot(
     market_type: MarketType,
     symbols: Option<&[String]>,
     tx: Sender<Message>,
-) {
+) -> Result<(), CrawlError> {
+    check_args(exchange, market_type, MessageType::L3Snapshot, symbols)?;
     crawlers::crawl_snapshot(exchange, market_type, MessageType::L3Snapshot, symbols, tx)
 }
 
@@ -347,17 +522,15 @@ pub async fn crawl_ticker(
     market_type: MarketType,
     symbols: Option<&[String]>,
     tx: Sender<Message>,
-) {
+) -> Result<(), CrawlError> {
+    check_args(exchange, market_type, MessageType::Ticker, symbols)?;
     match exchange {
         "binance" => crawlers::binance::crawl_ticker(market_type, symbols, tx).await,
-        "bitfinex" | "bitget" | "bithumb" | "bitz" | "bybit" | "coinbase_pro" | "deribit"
-        | "gate" | "huobi" | "kraken" | "kucoin" | "mexc" | "okx" => {
-            crawlers::crawl_event(exchange, MessageType::Ticker, market_type, symbols, tx).await
-        }
         "zb" => crawlers::zb::crawl_ticker(market_type, symbols, tx).await,
         "zbg" => crawlers::zbg::crawl_ticker(market_type, symbols, tx).await,
-        _ => panic!("{exchange} does NOT have the ticker websocket channel"),
+        _ => crawlers::crawl_event(exchange, MessageType::Ticker, market_type, symbols, tx).await,
     }
+    Ok(())
 }
 
 /// Crawl perpetual swap funding rates.
@@ -366,14 +539,16 @@ pub async fn crawl_funding_rate(
     market_type: MarketType,
     symbols: Option<&[String]>,
     tx: Sender<Message>,
-) {
+) -> Result<(), CrawlError> {
+    check_args(exchange, market_type, MessageType::FundingRate, symbols)?;
     match exchange {
         "binance" => crawlers::binance::crawl_funding_rate(market_type, symbols, tx).await,
         "bitmex" => crawlers::bitmex::crawl_funding_rate(market_type, symbols, tx).await,
         "huobi" => crawlers::huobi::crawl_funding_rate(market_type, symbols, tx).await,
         "okx" => crawlers::okx::crawl_funding_rate(market_type, symbols, tx).await,
-        _ => panic!("{exchange} does NOT have perpetual swap market"),
+        _ => unreachable!("{exchange} has funding rates in the capability table"),
     }
+    Ok(())
 }
 
 /// Crawl candlestick(i.e., OHLCV) data.
@@ -385,22 +560,27 @@ pub async fn crawl_candlestick(
     market_type: MarketType,
     symbol_interval_list: Option<&[(String, usize)]>,
     tx: Sender<Message>,
-) {
+) -> Result<(), CrawlError> {
+    let symbols = symbol_interval_list
+        .map(|list| list.iter().map(|(symbol, _)| symbol.clone()).collect::<Vec<String>>());
+    check_args(exchange, market_type, MessageType::Candlestick, symbols.as_deref())?;
     match exchange {
         "bitmex" => {
             crawlers::bitmex::crawl_candlestick(market_type, symbol_interval_list, tx).await
         }
-        "binance" | "bitfinex" | "bitget" | "bitz" | "bybit" | "deribit" | "gate" | "huobi"
-        | "kraken" | "kucoin" | "mexc" | "okx" | "zb" | "zbg" => {
-            crawlers::crawl_candlestick_ext(exchange, market_type, symbol_interval_list, tx).await
-        }
-        _ => panic!("{exchange} does NOT have the candlestick websocket channel"),
-    };
+        _ => crawlers::crawl_candlestick_ext(exchange, market_type, symbol_interval_list, tx).await,
+    }
+    Ok(())
 }
 
 /// Crawl all open interest.
-pub fn crawl_open_interest(exchange: &str, market_type: MarketType, tx: Sender<Message>) {
-    crawlers::crawl_open_interest(exchange, market_type, tx);
+pub fn crawl_open_interest(
+    exchange: &str,
+    market_type: MarketType,
+    tx: Sender<Message>,
+) -> Result<(), CrawlError> {
+    check_args(exchange, market_type, MessageType::OpenInterest, None)?;
+    crawlers::crawl_open_interest(exchange, market_type, tx)
 }
 
 /// Subscribe to multiple message types of one symbol.
@@ -414,11 +594,16 @@ pub async fn subscribe_symbol(
     symbol: &str,
     msg_types: &[MessageType],
     tx: Sender<String>,
-) {
-    let ws_client = crawlers::create_ws_client_symbol(exchange, market_type, tx).await;
+) -> Result<(), CrawlError> {
     let symbols = vec![symbol.to_string()];
+    for msg_type in msg_types {
+        check_args(exchange, market_type, *msg_type, None)?;
+    }
+    tokio::task::block_in_place(|| crawlers::check_symbols(exchange, market_type, &symbols))?;
+    let ws_client = crawlers::create_ws_client_symbol(exchange, market_type, tx).await?;
     let commands = crypto_msg_type::get_ws_commands(exchange, msg_types, &symbols, true, None);
     ws_client.send(&commands).await;
     ws_client.run().await;
     ws_client.close().await;
+    Ok(())
 }
@@ -8,7 +8,7 @@ use std::{
 };
 
 /// Message represents messages received by crawlers.
-#[derive(Serialize, Deserialize)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Message {
     /// The exchange name, unique for each exchage
     pub exchange: String,
@@ -16,6 +16,8 @@ pub struct Message {
     pub market_type: MarketType,
     /// Message type
     pub msg_type: MessageType,
+    /// The exchange symbol, `ALL` if the message contains multiple symbols,
+    /// or `None` if it has no symbol
     #[serde(skip_serializing_if = "Option::is_none")]
     pub symbol: Option<String>,
     /// Unix timestamp in milliseconds
@@ -0,0 +1,527 @@
+use std::{
+    collections::HashMap,
+    sync::{
+        mpsc::{Receiver, Sender},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use crypto_market_type::MarketType;
+use crypto_rest_client::fetch_l2_snapshot;
+use crypto_ws_client::*;
+use log::*;
+
+use super::{exchanges, OrderBookEvent, OrderBookSynchronizer, SnapshotSource, SyncStatus};
+use crate::{
+    crawlers::{get_cooldown_time_per_request, panic_message},
+    CrawlError,
+};
+
+// Delay before reconnecting, doubled after every failed connection
+const MIN_RECONNECT_DELAY: Duration = Duration::from_secs(1);
+const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(60);
+// A connection lasting longer than this reconnects after MIN_RECONNECT_DELAY
+const HEALTHY_DURATION: Duration = Duration::from_secs(60);
+
+enum Input {
+    WebSocket(String),
+    // (symbol, response of the RESTful API)
+    Snapshot(String, Result<String, String>),
+    // run() of the websocket client returned
+    Disconnected,
+}
+
+// How sync_with_client() returned
+#[derive(Debug, PartialEq, Eq)]
+enum SessionEnd {
+    // The websocket connection was lost, reconnect
+    Disconnected,
+    // The receiver was dropped or the snapshot thread exited
+    Stopped,
+}
+
+async fn create_ws_client(
+    exchange: &str,
+    market_type: MarketType,
+    url: Option<&str>,
+    tx: Sender<String>,
+) -> Arc<dyn WSClient + Send + Sync> {
+    match exchange {
+        "binance" => match market_type {
+            MarketType::Spot => Arc::new(BinanceSpotWSClient::new(tx, url).await),
+            MarketType::InverseFuture | MarketType::InverseSwap => {
+                Arc::new(BinanceInverseWSClient::new(tx, url).await)
+            }
+            _ => Arc::new(BinanceLinearWSClient::new(tx, url).await),
+        },
+        "bybit" => match market_type {
+            MarketType::Spot => Arc::new(BybitV5SpotWSClient::new(tx, url).await),
+            MarketType::InverseFuture | MarketType::InverseSwap => {
+                Arc::new(BybitV5InverseWSClient::new(tx, url).await)
+            }
+            _ => Arc::new(BybitV5LinearWSClient::new(tx, url).await),
+        },
+        "gate" => match market_type {
+            MarketType::Spot => Arc::new(GateSpotWSClient::new(tx, url).await),
+            MarketType::InverseSwap => Arc::new(GateInverseSwapWSClient::new(tx, url).await),
+            _ => Arc::new(GateLinearSwapWSClient::new(tx, url).await),
+        },
+        "huobi" => Arc::new(HuobiSpotWSClient::new(tx, url).await),
+        "kucoin" => match market_type {
+            MarketType::Spot => Arc::new(KuCoinSpotWSClient::new(tx, url).await),
+            _ => Arc::new(KuCoinSwapWSClient::new(tx, url).await),
+        },
+        "okx" => Arc::new(OkxWSClient::new(tx, url).await),
+        _ => panic!("Unknown exchange {exchange}"),
+    }
+}
+
+// Fetches snapshots one by one to respect the rate limit of RESTful APIs
+fn create_snapshot_thread(
+    fetch_snapshot: impl Fn(&str) -> Result<String, String> + Send + 'static,
+    cooldown_time: Duration,
+    input_tx: Sender<Input>,
+) -> Sender<String> {
+    let (tx, rx): (Sender<String>, Receiver<String>) = std::sync::mpsc::channel();
+    std::thread::spawn(move || {
+        for symbol in rx {
+            let resp = fetch_snapshot(&symbol);
+            if input_tx.send(Input::Snapshot(symbol, resp)).is_err() {
+                break; // the processing thread has exited
+            }
+            std::thread::sleep(cooldown_time);
+        }
+    });
+    tx
+}
+
+struct Resyncer {
+    exchange: String,
+    source: SnapshotSource,
+    ws_client: Arc<dyn WSClient + Send + Sync>,
+    snapshot_tx: Sender<String>,
+    handle: tokio::runtime::Handle,
+}
+
+impl Resyncer {
+    // Returns false if the snapshot thread has exited
+    fn request_snapshot(&self, symbol: &str) -> bool {
+        match self.source {
+            SnapshotSource::Rest => {
+                if self.snapshot_tx.send(symbol.to_string()).is_err() {
+                    error!("The snapshot thread of {} has exited", self.exchange);
+                    return false;
+                }
+            }
+            SnapshotSource::Subscription => {
+                // a new subscription starts with a snapshot
+                let channel = if self.exchange == "okx" { "books" } else { "orderbook.50" };
+                let topics = [(channel.to_string(), symbol.to_string())];
+                self.handle.block_on(async {
+                    self.ws_client.unsubscribe(&topics).await;
+                    self.ws_client.subscribe(&topics).await;
+                });
+            }
+            SnapshotSource::Request => {
+                let command = format!(r#"{{"req":"market.{symbol}.mbp.20","id":"{symbol}"}}"#);
+                self.handle.block_on(self.ws_client.send(&[command]));
+            }
+        }
+        true
+    }
+}
+
+/// Maintains local level2 orderbooks of `symbols` and sends them to `tx`.
+pub(crate) async fn sync_l2_orderbook(
+    exchange: &str,
+    market_type: MarketType,
+    symbols: &[String],
+    tx: Sender<OrderBookEvent>,
+) -> Result<(), CrawlError> {
+    let fetch_snapshot = {
+        let exchange = exchange.to_string();
+        move |symbol: &str| {
+            fetch_l2_snapshot(&exchange, market_type, symbol, Some(3))
+                .map_err(|err| err.to_string())
+        }
+    };
+    sync_with_url(exchange, market_type, symbols, None, fetch_snapshot, tx).await
+}
+
+// Connects to `url`, or the default URL of the exchange, and reconnects
+// until the receiver is dropped. Books start over from new snapshots after
+// every reconnection.
+async fn sync_with_url(
+    exchange: &str,
+    market_type: MarketType,
+    symbols: &[String],
+    url: Option<&str>,
+    fetch_snapshot: impl Fn(&str) -> Result<String, String> + Clone + Send + 'static,
+    tx: Sender<OrderBookEvent>,
+) -> Result<(), CrawlError> {
+    let unsupported =
+        || CrawlError::UnsupportedOrderBookSync { exchange: exchange.to_string(), market_type };
+    if exchanges::snapshot_source(exchange, market_type).is_none() {
+        return Err(unsupported());
+    }
+    let new_synchronizers = || -> Result<HashMap<String, OrderBookSynchronizer>, CrawlError> {
+        let mut synchronizers = HashMap::new();
+        for symbol in symbols {
+            let synchronizer = OrderBookSynchronizer::new(exchange, market_type, symbol)
+                .map_err(|_| unsupported())?;
+            synchronizers.insert(symbol.clone(), synchronizer);
+        }
+        Ok(synchronizers)
+    };
+    let mut synchronizers = new_synchronizers()?;
+
+    let mut delay = MIN_RECONNECT_DELAY;
+    loop {
+        let (ws_tx, ws_rx) = std::sync::mpsc::channel::<String>();
+        // clients panic if they fail to connect
+        let connected = {
+            let (exchange, url) = (exchange.to_string(), url.map(|x| x.to_string()));
+            tokio::task::spawn(async move {
+                create_ws_client(&exchange, market_type, url.as_deref(), ws_tx).await
+            })
+            .await
+        };
+        match connected {
+            Ok(ws_client) => {
+                let started_at = Instant::now();
+                let end = sync_with_client(
+                    exchange,
+                    market_type,
+                    synchronizers,
+                    ws_client,
+                    ws_rx,
+                    fetch_snapshot.clone(),
+                    tx.clone(),
+                )
+                .await;
+                if end == SessionEnd::Stopped {
+                    return Ok(());
+                }
+                if started_at.elapsed() >= HEALTHY_DURATION {
+                    delay = MIN_RECONNECT_DELAY;
+                }
+                warn!(
+                    "{} {} orderbook connection lost, reconnecting in {:?}",
+                    exchange, market_type, delay
+                );
+            }
+            Err(err) => {
+                error!(
+                    "Failed to connect to {} {}, reconnecting in {:?}, {}",
+                    exchange,
+                    market_type,
+                    delay,
+                    panic_message(err.into_panic())
+                );
+            }
+        }
+        synchronizers = new_synchronizers()?;
+        tokio::time::sleep(delay).await;
+        delay = (delay * 2).min(MAX_RECONNECT_DELAY);
+    }
+}
+
+// `ws_client` sends messages to `ws_rx`, and `fetch_snapshot` fetches a
+// snapshot from the RESTful API. Once the connection is lost, OutOfSync is
+// sent for every synced book.
+async fn sync_with_client(
+    exchange: &str,
+    market_type: MarketType,
+    mut synchronizers: HashMap<String, OrderBookSynchronizer>,
+    ws_client: Arc<dyn WSClient + Send + Sync>,
+    ws_rx: Receiver<String>,
+    fetch_snapshot: impl Fn(&str) -> Result<String, String> + Send + 'static,
+    tx: Sender<OrderBookEvent>,
+) -> SessionEnd {
+    let Some(source) = synchronizers.values().next().map(|s| s.snapshot_source()) else {
+        return SessionEnd::Stopped; // no symbols
+    };
+    let symbols = synchronizers.keys().cloned().collect::<Vec<String>>();
+    let (input_tx, input_rx) = std::sync::mpsc::channel::<Input>();
+    {
+        let input_tx = input_tx.clone();
+        // ws_rx disconnects once run() returns and drops its sender
+        std::thread::spawn(move || {
+            for msg in ws_rx {
+                if input_tx.send(Input::WebSocket(msg)).is_err() {
+                    return;
+                }
+            }
+            _ = input_tx.send(Input::Disconnected);
+        });
+    }
+    ws_client.subscribe_orderbook(&symbols).await;
+    {
+        let ws_client = ws_client.clone();
+        tokio::task::spawn(async move { ws_client.run().await });
+    }
+
+    let resyncer = Resyncer {
+        exchange: exchange.to_string(),
+        source,
+        ws_client: ws_client.clone(),
+        snapshot_tx: create_snapshot_thread(
+            fetch_snapshot,
+            get_cooldown_time_per_request(exchange, market_type),
+            input_tx,
+        ),
+        handle: tokio::runtime::Handle::current(),
+    };
+    let exchange = exchange.to_string();
+    tokio::task::spawn_blocking(move || {
+        // deltas are buffered until snapshots arrive
+        if source != SnapshotSource::Subscription
+            && !symbols.iter().all(|symbol| resyncer.request_snapshot(symbol))
+        {
+            resyncer.handle.block_on(resyncer.ws_client.close());
+            return SessionEnd::Stopped;
+        }
+        let end = loop {
+            // the forwarding thread sends Disconnected before it exits
+            let Ok(input) = input_rx.recv() else { break SessionEnd::Disconnected };
+            let (symbol, status) = match input {
+                Input::WebSocket(msg) => {
+                    match exchanges::parse_update(&exchange, market_type, &msg) {
+                        Ok(Some(update)) => match synchronizers.get_mut(&update.symbol) {
+                            Some(synchronizer) => {
+                                (update.symbol.clone(), synchronizer.process(update))
+                            }
+                            None => continue,
+                        },
+                        Ok(None) => continue,
+                        Err(err) => {
+                            warn!("{}", err);
+                            continue;
+                        }
+                    }
+                }
+                Input::Snapshot(symbol, resp) => {
+                    let synchronizer = synchronizers.get_mut(&symbol).unwrap();
+                    match resp.map(|msg| synchronizer.on_snapshot(&msg)) {
+                        Ok(Ok(status)) => (symbol, status),
+                        Ok(Err(err)) => {
+                            error!("{}, fetching again", err);
+                            if !resyncer.request_snapshot(&symbol) {
+                                break SessionEnd::Stopped;
+                            }
+                            continue;
+                        }
+                        Err(err) => {
+                            error!(
+                                "Failed to fetch the snapshot of {} {} {}, {}, fetching again",
+                                exchange, market_type, symbol, err
+                            );
+                            if !resyncer.request_snapshot(&symbol) {
+                                break SessionEnd::Stopped;
+                            }
+                            continue;
+                        }
+                    }
+                }
+                Input::Disconnected => {
+                    let mut synced = synchronizers
+                        .iter()
+                        .filter(|(_, synchronizer)| synchronizer.is_synced())
+                        .map(|(symbol, _)| symbol.clone())
+                        .collect::<Vec<String>>();
+                    synced.sort();
+                    if synced
+                        .into_iter()
+                        .all(|symbol| tx.send(OrderBookEvent::OutOfSync(symbol)).is_ok())
+                    {
+                        break SessionEnd::Disconnected;
+                    }
+                    break SessionEnd::Stopped;
+                }
+            };
+            let event = match status {
+                SyncStatus::Synced => {
+                    OrderBookEvent::Snapshot(synchronizers[&symbol].book().unwrap().clone())
+                }
+                SyncStatus::Applied(delta) => OrderBookEvent::Delta(delta),
+                SyncStatus::Resync { .. } => {
+                    if !resyncer.request_snapshot(&symbol) {
+                        break SessionEnd::Stopped;
+                    }
+                    OrderBookEvent::OutOfSync(symbol)
+                }
+                SyncStatus::Ignored | SyncStatus::Buffered => continue,
+            };
+            if tx.send(event).is_err() {
+                break SessionEnd::Stopped; // the receiver has been dropped
+            }
+        };
+        resyncer.handle.block_on(resyncer.ws_client.close());
+        end
+    })
+    .await
+    .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::VecDeque,
+        sync::{mpsc::RecvTimeoutError, Mutex},
+    };
+
+    use super::*;
+    use crypto_ws_mock::*;
+
+    fn binance_delta(first_id: u64, last_id: u64) -> Frame {
+        Frame::Text(format!(
+            r#"{{"stream":"btcusdt@depth@100ms","data":{{"e":"depthUpdate","E":1677000000100,"s":"BTCUSDT","U":{first_id},"u":{last_id},"b":[["99.0","{last_id}"]],"a":[]}}}}"#
+        ))
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_binance_resync() {
+        let exchange = MockExchange::new(|msg| {
+            if !msg.contains("SUBSCRIBE") {
+                return Vec::new();
+            }
+            vec![
+                Frame::Text(r#"{"result":null,"id":9527}"#.to_string()),
+                // buffered until the first snapshot
+                binance_delta(100, 102),
+                Frame::Sleep(Duration::from_secs(1)),
+                binance_delta(103, 104),
+                // 105 is missing
+                binance_delta(106, 107),
+                // buffered until the second snapshot
+                binance_delta(108, 109),
+                Frame::Sleep(Duration::from_secs(2)),
+                binance_delta(110, 111),
+            ]
+        });
+        let server = MockServer::start(exchange).await;
+
+        let (ws_tx, ws_rx) = std::sync::mpsc::channel();
+        let ws_client = Arc::new(BinanceSpotWSClient::new(ws_tx, Some(server.url())).await);
+        let snapshots = Mutex::new(VecDeque::from([
+            r#"{"lastUpdateId":101,"bids":[["100.0","1"]],"asks":[["101.0","1"]]}"#,
+            r#"{"lastUpdateId":108,"bids":[["100.0","1"]],"asks":[["101.0","1"]]}"#,
+        ]));
+        let fetch_snapshot = move |symbol: &str| {
+            assert_eq!("BTCUSDT", symbol);
+            // arrives after buffered deltas
+            std::thread::sleep(Duration::from_millis(500));
+            Ok(snapshots.lock().unwrap().pop_front().unwrap().to_string())
+        };
+        let (tx, rx) = std::sync::mpsc::channel();
+        let synchronizer =
+            OrderBookSynchronizer::new("binance", MarketType::Spot, "BTCUSDT").unwrap();
+        let synchronizers = HashMap::from([("BTCUSDT".to_string(), synchronizer)]);
+        let handle = tokio::task::spawn(async move {
+            sync_with_client(
+                "binance",
+                MarketType::Spot,
+                synchronizers,
+                ws_client,
+                ws_rx,
+                fetch_snapshot,
+                tx,
+            )
+            .await
+        });
+
+        let recv = || rx.recv_timeout(Duration::from_secs(10));
+        match recv().unwrap() {
+            OrderBookEvent::Snapshot(book) => assert_eq!(102, book.seq_id),
+            event => panic!("{event:?}"),
+        }
+        match recv().unwrap() {
+            OrderBookEvent::Delta(delta) => assert_eq!(vec![(99.0, 104.0)], delta.bids),
+            event => panic!("{event:?}"),
+        }
+        assert_eq!(OrderBookEvent::OutOfSync("BTCUSDT".to_string()), recv().unwrap());
+        match recv().unwrap() {
+            OrderBookEvent::Snapshot(book) => {
+                assert_eq!(109, book.seq_id);
+                assert_eq!(vec![(100.0, 1.0), (99.0, 109.0)], book.bids().collect::<Vec<_>>());
+            }
+            event => panic!("{event:?}"),
+        }
+        assert_eq!(Err(RecvTimeoutError::Timeout), rx.recv_timeout(Duration::from_millis(100)));
+
+        // returns on the next delta once the receiver is dropped
+        drop(rx);
+        let end = tokio::time::timeout(Duration::from_secs(10), handle).await.unwrap().unwrap();
+        assert_eq!(SessionEnd::Stopped, end);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_reconnect() {
+        let exchange = MockExchange::new(|msg| {
+            if !msg.contains("SUBSCRIBE") {
+                return Vec::new();
+            }
+            vec![
+                Frame::Text(r#"{"result":null,"id":9527}"#.to_string()),
+                binance_delta(100, 102),
+                Frame::Sleep(Duration::from_secs(1)),
+                binance_delta(103, 104),
+                // the connection drops mid-stream
+                Frame::Disconnect,
+            ]
+        });
+        let server = MockServer::start(exchange).await;
+
+        let fetch_snapshot = |_symbol: &str| {
+            std::thread::sleep(Duration::from_millis(500));
+            Ok(r#"{"lastUpdateId":101,"bids":[["100.0","1"]],"asks":[["101.0","1"]]}"#.to_string())
+        };
+        let (tx, rx) = std::sync::mpsc::channel();
+        let url = server.url().to_string();
+        let handle = tokio::task::spawn(async move {
+            sync_with_url(
+                "binance",
+                MarketType::Spot,
+                &["BTCUSDT".to_string()],
+                Some(&url),
+                fetch_snapshot,
+                tx,
+            )
+            .await
+        });
+
+        let recv = || rx.recv_timeout(Duration::from_secs(10));
+        // the book is rebuilt on a new connection
+        for _ in 0..2 {
+            match recv().unwrap() {
+                OrderBookEvent::Snapshot(book) => assert_eq!(102, book.seq_id),
+                event => panic!("{event:?}"),
+            }
+            match recv().unwrap() {
+                OrderBookEvent::Delta(delta) => assert_eq!(vec![(99.0, 104.0)], delta.bids),
+                event => panic!("{event:?}"),
+            }
+            assert_eq!(OrderBookEvent::OutOfSync("BTCUSDT".to_string()), recv().unwrap());
+        }
+        let subscriptions =
+            server.received().texts.iter().filter(|txt| txt.contains("SUBSCRIBE")).count();
+        assert!(subscriptions >= 2);
+
+        drop(rx);
+        let result = tokio::time::timeout(Duration::from_secs(10), handle).await.unwrap().unwrap();
+        assert_eq!(Ok(()), result);
+    }
+
+    #[tokio::test]
+    async fn test_unsupported() {
+        let (tx, _rx) = std::sync::mpsc::channel();
+        assert_eq!(
+            Err(CrawlError::UnsupportedOrderBookSync {
+                exchange: "kraken".to_string(),
+                market_type: MarketType::Spot
+            }),
+            sync_l2_orderbook("kraken", MarketType::Spot, &["XBT/USD".to_string()], tx).await
+        );
+    }
+}
@@ -0,0 +1,432 @@
+//! Exchange-specific parsers of level2 orderbook messages.
+//!
+//! Every exchange numbers its orderbook updates differently, these parsers
+//! normalize them into `RawUpdate`, see `OrderBookSynchronizer` for how the
+//! numbers are checked.
+
+use crypto_market_type::MarketType;
+use serde_json::Value;
+
+use super::{SnapshotSource, SyncError};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(super) enum UpdateKind {
+    Delta,
+    /// Pushed by the subscription, the next delta follows it immediately
+    Snapshot,
+    /// Fetched separately, buffered deltas may overlap it
+    FetchedSnapshot,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub(super) struct Level {
+    pub price: f64,
+    pub quantity: f64,
+    /// Update ID of this level, only KuCoin Spot has it
+    pub seq_id: Option<u64>,
+}
+
+/// A level2 message normalized from all exchanges.
+///
+/// A delta covers update IDs from `first_id` to `last_id`. If `prev_id` is
+/// present, it is the `last_id` of the previous delta, otherwise the previous
+/// delta ends at `first_id - 1`.
+#[derive(Clone, Debug, PartialEq)]
+pub(super) struct RawUpdate {
+    pub kind: UpdateKind,
+    pub symbol: String,
+    pub first_id: u64,
+    pub last_id: u64,
+    pub prev_id: Option<u64>,
+    pub timestamp: i64,
+    pub bids: Vec<Level>,
+    pub asks: Vec<Level>,
+}
+
+pub(super) fn snapshot_source(exchange: &str, market_type: MarketType) -> Option<SnapshotSource> {
+    match exchange {
+        "binance" => match market_type {
+            MarketType::Spot
+            | MarketType::InverseFuture
+            | MarketType::InverseSwap
+            | MarketType::LinearFuture
+            | MarketType::LinearSwap => Some(SnapshotSource::Rest),
+            _ => None,
+        },
+        "bybit" => match market_type {
+            MarketType::Spot
+            | MarketType::InverseFuture
+            | MarketType::InverseSwap
+            | MarketType::LinearFuture
+            | MarketType::LinearSwap => Some(SnapshotSource::Subscription),
+            _ => None,
+        },
+        "gate" => match market_type {
+            MarketType::Spot | MarketType::InverseSwap | MarketType::LinearSwap => {
+                Some(SnapshotSource::Rest)
+            }
+            _ => None,
+        },
+        "huobi" => match market_type {
+            MarketType::Spot => Some(SnapshotSource::Request),
+            _ => None,
+        },
+        "kucoin" => match market_type {
+            MarketType::Spot
+            | MarketType::InverseSwap
+            | MarketType::LinearSwap
+            | MarketType::InverseFuture => Some(SnapshotSource::Rest),
+            _ => None,
+        },
+        "okx" => match market_type {
+            MarketType::Spot
+            | MarketType::InverseFuture
+            | MarketType::InverseSwap
+            | MarketType::LinearFuture
+            | MarketType::LinearSwap => Some(SnapshotSource::Subscription),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Parses a websocket message, returns None if it is not a level2 message.
+pub(super) fn parse_update(
+    exchange: &str,
+    market_type: MarketType,
+    msg: &str,
+) -> Result<Option<RawUpdate>, SyncError> {
+    let obj = serde_json::from_str::<Value>(msg).map_err(|_| invalid(msg))?;
+    let update = match exchange {
+        "binance" => parse_binance_update(&obj),
+        "bybit" => parse_bybit_update(&obj),
+        "gate" => parse_gate_update(&obj),
+        "huobi" => parse_huobi_update(&obj),
+        "kucoin" => {
+            if market_type == MarketType::Spot {
+                parse_kucoin_spot_update(&obj)
+            } else {
+                parse_kucoin_swap_update(&obj)
+            }
+        }
+        "okx" => parse_okx_update(&obj),
+        _ => return Err(SyncError::Unsupported(format!("{exchange} {market_type}"))),
+    };
+    update.ok_or_else(|| invalid(msg))
+}
+
+/// Parses a snapshot from the RESTful API.
+pub(super) fn parse_snapshot(
+    exchange: &str,
+    market_type: MarketType,
+    symbol: &str,
+    msg: &str,
+) -> Result<RawUpdate, SyncError> {
+    let obj = serde_json::from_str::<Value>(msg).map_err(|_| invalid(msg))?;
+    let snapshot = match exchange {
+        "binance" => parse_binance_snapshot(&obj),
+        "gate" => parse_gate_snapshot(&obj),
+        "kucoin" => parse_kucoin_snapshot(&obj),
+        _ => return Err(SyncError::Unsupported(format!("{exchange} {market_type} RESTful API"))),
+    };
+    let mut snapshot = snapshot.ok_or_else(|| invalid(msg))?;
+    snapshot.symbol = symbol.to_string();
+    Ok(snapshot)
+}
+
+fn invalid(msg: &str) -> SyncError {
+    SyncError::Invalid(msg.to_string())
+}
+
+// Numbers are strings in most exchanges
+fn to_f64(value: &Value) -> Option<f64> {
+    match value {
+        Value::String(s) => s.parse::<f64>().ok(),
+        _ => value.as_f64(),
+    }
+}
+
+fn to_u64(value: &Value) -> Option<u64> {
+    match value {
+        Value::String(s) => s.parse::<u64>().ok(),
+        _ => value.as_u64(),
+    }
+}
+
+fn to_i64(value: &Value) -> Option<i64> {
+    match value {
+        Value::String(s) => s.parse::<i64>().ok(),
+        _ => value.as_i64().or_else(|| value.as_f64().map(|x| x as i64)),
+    }
+}
+
+// Levels are either arrays like [price, quantity, ...] or objects like
+// {"p": price, "s": size}, quantities are never negative.
+fn parse_levels(value: Option<&Value>) -> Option<Vec<Level>> {
+    let levels = match value {
+        Some(value) => value.as_array()?,
+        None => return Some(Vec::new()),
+    };
+    levels
+        .iter()
+        .map(|level| {
+            let (price, quantity) = if level.is_object() {
+                (level.get("p")?, level.get("s")?)
+            } else {
+                (level.get(0)?, level.get(1)?)
+            };
+            Some(Level { price: to_f64(price)?, quantity: to_f64(quantity)?.abs(), seq_id: None })
+        })
+        .collect()
+}
+
+// https://binance-docs.github.io/apidocs/spot/en/#how-to-manage-a-local-order-book-correctly
+// https://binance-docs.github.io/apidocs/futures/en/#how-to-manage-a-local-order-book-correctly
+fn parse_binance_update(obj: &Value) -> Option<Option<RawUpdate>> {
+    // combined streams wrap the event in data
+    let data = obj.get("data").unwrap_or(obj);
+    if data.get("e").and_then(|x| x.as_str()) != Some("depthUpdate") {
+        return Some(None);
+    }
+    Some(Some(RawUpdate {
+        kind: UpdateKind::Delta,
+        symbol: data.get("s")?.as_str()?.to_string(),
+        first_id: to_u64(data.get("U")?)?,
+        last_id: to_u64(data.get("u")?)?,
+        // futures only
+        prev_id: data.get("pu").and_then(to_u64),
+        timestamp: data.get("E").and_then(to_i64).unwrap_or_default(),
+        bids: parse_levels(data.get("b"))?,
+        asks: parse_levels(data.get("a"))?,
+    }))
+}
+
+fn parse_binance_snapshot(obj: &Value) -> Option<RawUpdate> {
+    let last_update_id = to_u64(obj.get("lastUpdateId")?)?;
+    Some(RawUpdate {
+        kind: UpdateKind::FetchedSnapshot,
+        symbol: String::new(),
+        first_id: last_update_id,
+        last_id: last_update_id,
+        prev_id: None,
+        timestamp: obj.get("E").and_then(to_i64).unwrap_or_default(),
+        bids: parse_levels(obj.get("bids"))?,
+        asks: parse_levels(obj.get("asks"))?,
+    })
+}
+
+// https://bybit-exchange.github.io/docs/v5/websocket/public/orderbook
+fn parse_bybit_update(obj: &Value) -> Option<Option<RawUpdate>> {
+    let topic = obj.get("topic").and_then(|x| x.as_str()).unwrap_or_default();
+    if !topic.starts_with("orderbook.") {
+        return Some(None);
+    }
+    let kind = match obj.get("type")?.as_str()? {
+        "snapshot" => UpdateKind::Snapshot,
+        "delta" => UpdateKind::Delta,
+        _ => return None,
+    };
+    let data = obj.get("data")?;
+    let update_id = to_u64(data.get("u")?)?;
+    Some(Some(RawUpdate {
+        kind,
+        symbol: data.get("s")?.as_str()?.to_string(),
+        first_id: update_id,
+        last_id: update_id,
+        prev_id: None,
+        timestamp: obj.get("ts").and_then(to_i64).unwrap_or_default(),
+        bids: parse_levels(data.get("b"))?,
+        asks: parse_levels(data.get("a"))?,
+    }))
+}
+
+// https://www.gate.io/docs/developers/apiv4/ws/en/#changed-order-book-levels
+// https://www.gate.io/docs/developers/futures/ws/en/#order-book-update-subscription
+fn parse_gate_update(obj: &Value) -> Option<Option<RawUpdate>> {
+    let channel = obj.get("channel").and_then(|x| x.as_str()).unwrap_or_default();
+    if !channel.ends_with(".order_book_update")
+        || obj.get("event").and_then(|x| x.as_str()) != Some("update")
+    {
+        return Some(None);
+    }
+    let result = obj.get("result")?;
+    Some(Some(RawUpdate {
+        kind: UpdateKind::Delta,
+        symbol: result.get("s")?.as_str()?.to_string(),
+        first_id: to_u64(result.get("U")?)?,
+        last_id: to_u64(result.get("u")?)?,
+        prev_id: None,
+        timestamp: result.get("t").and_then(to_i64).unwrap_or_default(),
+        bids: parse_levels(result.get("b"))?,
+        asks: parse_levels(result.get("a"))?,
+    }))
+}
+
+fn parse_gate_snapshot(obj: &Value) -> Option<RawUpdate> {
+    // requires with_id=true
+    let id = to_u64(obj.get("id")?)?;
+    // milliseconds in Spot, seconds in Swap
+    let current = obj.get("current").and_then(to_f64).unwrap_or_default();
+    Some(RawUpdate {
+        kind: UpdateKind::FetchedSnapshot,
+        symbol: String::new(),
+        first_id: id,
+        last_id: id,
+        prev_id: None,
+        timestamp: if current < 1e11 { (current * 1000.0) as i64 } else { current as i64 },
+        bids: parse_levels(obj.get("bids"))?,
+        asks: parse_levels(obj.get("asks"))?,
+    })
+}
+
+// Updates are pushed by the mbp channel, while the snapshot is the response of
+// a req command, see https://huobiapi.github.io/docs/spot/v1/en/#market-by-price-incremental-update
+fn parse_huobi_update(obj: &Value) -> Option<Option<RawUpdate>> {
+    let (topic, data, kind) = if let Some(ch) = obj.get("ch") {
+        (ch.as_str()?, obj.get("tick")?, UpdateKind::Delta)
+    } else if let Some(rep) = obj.get("rep") {
+        (rep.as_str()?, obj.get("data")?, UpdateKind::FetchedSnapshot)
+    } else {
+        return Some(None);
+    };
+    // market.$symbol.mbp.$levels
+    let parts = topic.split('.').collect::<Vec<&str>>();
+    if parts.len() != 4 || parts[2] != "mbp" {
+        return Some(None);
+    }
+    let seq_num = to_u64(data.get("seqNum")?)?;
+    Some(Some(RawUpdate {
+        kind,
+        symbol: parts[1].to_string(),
+        first_id: seq_num,
+        last_id: seq_num,
+        prev_id: if kind == UpdateKind::Delta {
+            Some(to_u64(data.get("prevSeqNum")?)?)
+        } else {
+            None
+        },
+        timestamp: obj.get("ts").and_then(to_i64).unwrap_or_default(),
+        bids: parse_levels(data.get("bids"))?,
+        asks: parse_levels(data.get("asks"))?,
+    }))
+}
+
+// https://docs.kucoin.com/#level-2-market-data
+fn parse_kucoin_spot_update(obj: &Value) -> Option<Option<RawUpdate>> {
+    if obj.get("subject").and_then(|x| x.as_str()) != Some("trade.l2update") {
+        return Some(None);
+    }
+    let data = obj.get("data")?;
+    let changes = data.get("changes")?;
+    // [price, size, sequence]
+    let parse_changes = |value: Option<&Value>| -> Option<Vec<Level>> {
+        let mut levels = parse_levels(value)?;
+        for (level, raw) in levels.iter_mut().zip(value?.as_array()?.iter()) {
+            level.seq_id = Some(to_u64(raw.get(2)?)?);
+        }
+        Some(levels)
+    };
+    Some(Some(RawUpdate {
+        kind: UpdateKind::Delta,
+        symbol: data.get("symbol")?.as_str()?.to_string(),
+        first_id: to_u64(data.get("sequenceStart")?)?,
+        last_id: to_u64(data.get("sequenceEnd")?)?,
+        prev_id: None,
+        timestamp: data.get("time").and_then(to_i64).unwrap_or_default(),
+        bids: parse_changes(changes.get("bids"))?,
+        asks: parse_changes(changes.get("asks"))?,
+    }))
+}
+
+// https://docs.kucoin.com/futures/#level-2-market-data
+fn parse_kucoin_swap_update(obj: &Value) -> Option<Option<RawUpdate>> {
+    let topic = obj.get("topic").and_then(|x| x.as_str()).unwrap_or_default();
+    if !topic.starts_with("/contractMarket/level2:") {
+        return Some(None);
+    }
+    let data = obj.get("data")?;
+    let sequence = to_u64(data.get("sequence")?)?;
+    // price,side,size
+    let change = data.get("change")?.as_str()?.split(',').collect::<Vec<&str>>();
+    if change.len() != 3 {
+        return None;
+    }
+    let level = Level {
+        price: change[0].parse::<f64>().ok()?,
+        quantity: change[2].parse::<f64>().ok()?,
+        seq_id: None,
+    };
+    let (bids, asks) = match change[1] {
+        "buy" => (vec![level], Vec::new()),
+        "sell" => (Vec::new(), vec![level]),
+        _ => return None,
+    };
+    Some(Some(RawUpdate {
+        kind: UpdateKind::Delta,
+        symbol: topic.split(':').nth(1)?.to_string(),
+        first_id: sequence,
+        last_id: sequence,
+        prev_id: None,
+        timestamp: data.get("timestamp").and_then(to_i64).unwrap_or_default(),
+        bids,
+        asks,
+    }))
+}
+
+fn parse_kucoin_snapshot(obj: &Value) -> Option<RawUpdate> {
+    if obj.get("code")?.as_str()? != "200000" {
+        return None;
+    }
+    let data = obj.get("data")?;
+    let sequence = to_u64(data.get("sequence")?)?;
+    Some(RawUpdate {
+        kind: UpdateKind::FetchedSnapshot,
+        symbol: String::new(),
+        first_id: sequence,
+        last_id: sequence,
+        prev_id: None,
+        // Spot has time in milliseconds, while Swap has ts in nanoseconds
+        timestamp: data
+            .get("time")
+            .and_then(to_i64)
+            .or_else(|| data.get("ts").and_then(to_i64).map(|ts| ts / 1_000_000))
+            .unwrap_or_default(),
+        bids: parse_levels(data.get("bids"))?,
+        asks: parse_levels(data.get("asks"))?,
+    })
+}
+
+// https://www.okx.com/docs-v5/en/#order-book-trading-market-data-ws-order-book-channel
+fn parse_okx_update(obj: &Value) -> Option<Option<RawUpdate>> {
+    let channel = obj.get("arg").and_then(|arg| arg.get("channel")).and_then(|x| x.as_str());
+    if !matches!(channel, Some("books" | "books-l2-tbt" | "books50-l2-tbt")) {
+        return Some(None);
+    }
+    let symbol = obj.get("arg")?.get("instId")?.as_str()?;
+    let kind = match obj.get("action")?.as_str()? {
+        "snapshot" => UpdateKind::Snapshot,
+        "update" => UpdateKind::Delta,
+        _ => return None,
+    };
+    let data = obj.get("data")?.as_array()?;
+    if data.len() != 1 {
+        return None;
+    }
+    let data = &data[0];
+    let seq_id = to_u64(data.get("seqId")?)?;
+    Some(Some(RawUpdate {
+        kind,
+        symbol: symbol.to_string(),
+        first_id: seq_id,
+        last_id: seq_id,
+        // -1 in snapshots
+        prev_id: if kind == UpdateKind::Delta {
+            Some(to_u64(data.get("prevSeqId")?)?)
+        } else {
+            None
+        },
+        timestamp: data.get("ts").and_then(to_i64).unwrap_or_default(),
+        bids: parse_levels(data.get("bids"))?,
+        asks: parse_levels(data.get("asks"))?,
+    }))
+}
@@ -0,0 +1,558 @@
+//! Local level2 orderbooks rebuilt from websocket deltas and snapshots.
+//!
+//! Exchanges push deltas of level2 orderbooks via websocket, a local
+//! orderbook starts from a snapshot, then applies deltas in sequence. Missing
+//! a single delta silently corrupts the book, so every delta is checked
+//! against the update ID of the book, and the book is rebuilt from a new
+//! snapshot once a gap is detected.
+
+mod driver;
+mod exchanges;
+
+use std::{
+    cmp::Ordering,
+    collections::{BTreeMap, VecDeque},
+    fmt,
+};
+
+use crypto_market_type::MarketType;
+use log::*;
+
+use exchanges::{RawUpdate, UpdateKind};
+
+pub(crate) use driver::sync_l2_orderbook;
+
+// Deltas buffered while waiting for a snapshot, older ones are dropped
+const MAX_BUFFERED_UPDATES: usize = 1024;
+
+/// Where snapshots of an exchange come from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SnapshotSource {
+    /// The RESTful API, pass it to `OrderBookSynchronizer::on_snapshot()`,
+    /// used by Binance, Gate and KuCoin.
+    Rest,
+    /// Pushed by the orderbook subscription before the first delta, used by
+    /// Bybit and OKX. Unsubscribe and subscribe again to get a new one.
+    Subscription,
+    /// The response of a websocket request, used by Huobi, e.g.,
+    /// `{"req":"market.btcusdt.mbp.20","id":"btcusdt"}`.
+    Request,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SyncError {
+    /// The exchange or market type is not supported.
+    Unsupported(String),
+    /// Not a valid orderbook message or snapshot.
+    Invalid(String),
+}
+
+impl fmt::Display for SyncError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SyncError::Unsupported(what) => write!(f, "Orderbook sync of {what} is not supported"),
+            SyncError::Invalid(msg) => write!(f, "Invalid orderbook message {msg}"),
+        }
+    }
+}
+
+impl std::error::Error for SyncError {}
+
+// f64 is not Ord, prices are never NaN
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Price(f64);
+
+impl Eq for Price {}
+
+impl PartialOrd for Price {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Price {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.total_cmp(&other.0)
+    }
+}
+
+/// A consistent level2 orderbook.
+#[derive(Clone, Debug, PartialEq)]
+pub struct OrderBook {
+    pub exchange: String,
+    pub market_type: MarketType,
+    pub symbol: String,
+    /// Update ID of the last applied snapshot or delta
+    pub seq_id: u64,
+    /// Milliseconds of the last applied snapshot or delta, 0 if unknown
+    pub timestamp: i64,
+    bids: BTreeMap<Price, f64>,
+    asks: BTreeMap<Price, f64>,
+}
+
+impl OrderBook {
+    /// Bids as (price, quantity), from the highest price.
+    pub fn bids(&self) -> impl Iterator<Item = (f64, f64)> + '_ {
+        self.bids.iter().rev().map(|(price, quantity)| (price.0, *quantity))
+    }
+
+    /// Asks as (price, quantity), from the lowest price.
+    pub fn asks(&self) -> impl Iterator<Item = (f64, f64)> + '_ {
+        self.asks.iter().map(|(price, quantity)| (price.0, *quantity))
+    }
+
+    pub fn best_bid(&self) -> Option<(f64, f64)> {
+        self.bids().next()
+    }
+
+    pub fn best_ask(&self) -> Option<(f64, f64)> {
+        self.asks().next()
+    }
+
+    // Applies levels not older than the book, returns the applied ones
+    fn apply(&mut self, update: &RawUpdate) -> BookDelta {
+        let mut delta = BookDelta {
+            symbol: self.symbol.clone(),
+            seq_id: update.last_id,
+            timestamp: update.timestamp,
+            bids: Vec::new(),
+            asks: Vec::new(),
+        };
+        for (levels, side, changes) in [
+            (&update.bids, &mut self.bids, &mut delta.bids),
+            (&update.asks, &mut self.asks, &mut delta.asks),
+        ] {
+            for level in levels.iter() {
+                if level.seq_id.map(|seq_id| seq_id <= self.seq_id).unwrap_or(false) {
+                    continue;
+                }
+                if level.quantity == 0.0 {
+                    side.remove(&Price(level.price));
+                } else {
+                    side.insert(Price(level.price), level.quantity);
+                }
+                changes.push((level.price, level.quantity));
+            }
+        }
+        self.seq_id = update.last_id;
+        if update.timestamp > 0 {
+            self.timestamp = update.timestamp;
+        }
+        delta
+    }
+}
+
+/// Changed levels of an orderbook.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BookDelta {
+    pub symbol: String,
+    /// Update ID of the book after this delta
+    pub seq_id: u64,
+    /// Milliseconds, 0 if unknown
+    pub timestamp: i64,
+    /// (price, quantity), quantity 0 means the level is removed
+    pub bids: Vec<(f64, f64)>,
+    pub asks: Vec<(f64, f64)>,
+}
+
+/// An event emitted by `sync_l2_orderbook()`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum OrderBookEvent {
+    /// The book of a symbol was built or rebuilt from a snapshot.
+    Snapshot(OrderBook),
+    /// A delta applied on top of the last `Snapshot` of its symbol.
+    Delta(BookDelta),
+    /// A gap was detected, the book of this symbol is discarded until the
+    /// next `Snapshot`.
+    OutOfSync(String),
+}
+
+/// The outcome of a message passed to `OrderBookSynchronizer`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum SyncStatus {
+    /// Not an orderbook message of this symbol, or a delta older than the book.
+    Ignored,
+    /// A delta was buffered, the book is waiting for a snapshot.
+    Buffered,
+    /// The book was rebuilt from a snapshot and buffered deltas, read it by
+    /// `book()`.
+    Synced,
+    /// A delta was applied to the book.
+    Applied(BookDelta),
+    /// Deltas are missing between `expected` and `got`, or the snapshot is
+    /// older than the buffered deltas. The book is discarded until the next
+    /// snapshot.
+    Resync { expected: u64, got: u64 },
+}
+
+/// Rebuilds a level2 orderbook of one symbol from websocket deltas and
+/// snapshots, supports Binance, Bybit, Gate, Huobi Spot, KuCoin and OKX.
+///
+/// Pass all websocket messages of the orderbook channel to `on_message()`.
+/// Deltas are buffered until a snapshot arrives, then the book is built and
+/// kept in sync, see `SnapshotSource` for where snapshots come from. If an
+/// update ID is skipped, `SyncStatus::Resync` is returned and a new snapshot
+/// is needed.
+///
+/// ```
+/// use crypto_crawler::{MarketType, OrderBookSynchronizer, SyncStatus};
+///
+/// let mut synchronizer =
+///     OrderBookSynchronizer::new("binance", MarketType::Spot, "BTCUSDT").unwrap();
+/// let delta = r#"{"stream":"btcusdt@depth@100ms","data":{"e":"depthUpdate","E":1677000000100,"s":"BTCUSDT","U":101,"u":102,"b":[["23999.9","0.5"]],"a":[]}}"#;
+/// assert_eq!(SyncStatus::Buffered, synchronizer.on_message(delta).unwrap());
+///
+/// let snapshot = r#"{"lastUpdateId":100,"bids":[["23999.9","1.0"]],"asks":[["24000.1","2.0"]]}"#;
+/// assert_eq!(SyncStatus::Synced, synchronizer.on_snapshot(snapshot).unwrap());
+/// let book = synchronizer.book().unwrap();
+/// assert_eq!(102, book.seq_id);
+/// assert_eq!(Some((23999.9, 0.5)), book.best_bid());
+/// ```
+pub struct OrderBookSynchronizer {
+    exchange: String,
+    market_type: MarketType,
+    symbol: String,
+    source: SnapshotSource,
+    book: Option<OrderBook>,
+    // Whether the book has been linked with a delta, only then update IDs must
+    // be contiguous
+    linked: bool,
+    buffer: VecDeque<RawUpdate>,
+}
+
+impl OrderBookSynchronizer {
+    pub fn new(exchange: &str, market_type: MarketType, symbol: &str) -> Result<Self, SyncError> {
+        let source = exchanges::snapshot_source(exchange, market_type)
+            .ok_or_else(|| SyncError::Unsupported(format!("{exchange} {market_type}")))?;
+        Ok(OrderBookSynchronizer {
+            exchange: exchange.to_string(),
+            market_type,
+            symbol: symbol.to_string(),
+            source,
+            book: None,
+            linked: false,
+            buffer: VecDeque::new(),
+        })
+    }
+
+    /// Where snapshots of this exchange come from.
+    pub fn snapshot_source(&self) -> SnapshotSource {
+        self.source
+    }
+
+    /// The book, None until the first snapshot or after a gap.
+    pub fn book(&self) -> Option<&OrderBook> {
+        self.book.as_ref()
+    }
+
+    pub fn is_synced(&self) -> bool {
+        self.book.is_some()
+    }
+
+    /// Processes a websocket message, which can be a delta, or a snapshot of
+    /// Bybit, Huobi and OKX.
+    pub fn on_message(&mut self, msg: &str) -> Result<SyncStatus, SyncError> {
+        match exchanges::parse_update(&self.exchange, self.market_type, msg)? {
+            Some(update) if update.symbol == self.symbol => Ok(self.process(update)),
+            _ => Ok(SyncStatus::Ignored),
+        }
+    }
+
+    /// Processes a snapshot from the RESTful API.
+    pub fn on_snapshot(&mut self, msg: &str) -> Result<SyncStatus, SyncError> {
+        let snapshot =
+            exchanges::parse_snapshot(&self.exchange, self.market_type, &self.symbol, msg)?;
+        Ok(self.process(snapshot))
+    }
+
+    fn process(&mut self, update: RawUpdate) -> SyncStatus {
+        if update.kind != UpdateKind::Delta {
+            return self.load_snapshot(update);
+        }
+        if self.book.is_none() {
+            if self.buffer.len() >= MAX_BUFFERED_UPDATES {
+                self.buffer.pop_front();
+            }
+            self.buffer.push_back(update);
+            return SyncStatus::Buffered;
+        }
+        self.apply(update)
+    }
+
+    fn load_snapshot(&mut self, snapshot: RawUpdate) -> SyncStatus {
+        let mut book = OrderBook {
+            exchange: self.exchange.clone(),
+            market_type: self.market_type,
+            symbol: self.symbol.clone(),
+            seq_id: 0,
+            timestamp: 0,
+            bids: BTreeMap::new(),
+            asks: BTreeMap::new(),
+        };
+        book.apply(&snapshot);
+        self.book = Some(book);
+        // A pushed snapshot is followed by the next delta immediately, while
+        // buffered deltas may overlap a fetched one
+        self.linked = snapshot.kind == UpdateKind::Snapshot;
+
+        let buffer = std::mem::take(&mut self.buffer);
+        for update in buffer {
+            if let SyncStatus::Resync { expected, got } = self.apply(update) {
+                return SyncStatus::Resync { expected, got };
+            }
+        }
+        SyncStatus::Synced
+    }
+
+    fn apply(&mut self, update: RawUpdate) -> SyncStatus {
+        let book = self.book.as_mut().unwrap();
+        if update.last_id <= book.seq_id {
+            return SyncStatus::Ignored;
+        }
+        // Deltas either point to the previous one by prev_id, or continue from
+        // first_id. The first delta after a fetched snapshot only needs to
+        // cover the snapshot.
+        let (expected, got) = match update.prev_id {
+            Some(prev_id) => (book.seq_id, prev_id),
+            None => (book.seq_id + 1, update.first_id),
+        };
+        let contiguous = if self.linked { got == expected } else { got <= expected };
+        if !contiguous {
+            warn!(
+                "{} {} {} expected update ID {}, got {}, resyncing",
+                self.exchange, self.market_type, self.symbol, expected, got
+            );
+            self.book = None;
+            self.linked = false;
+            self.buffer.clear();
+            return SyncStatus::Resync { expected, got };
+        }
+        self.linked = true;
+        SyncStatus::Applied(book.apply(&update))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{OrderBookSynchronizer, SyncStatus};
+    use crypto_market_type::MarketType;
+
+    fn binance_delta(first_id: u64, last_id: u64, bid: &str) -> String {
+        format!(
+            r#"{{"stream":"btcusdt@depth@100ms","data":{{"e":"depthUpdate","E":1677000000100,"s":"BTCUSDT","U":{first_id},"u":{last_id},"b":[{bid}],"a":[]}}}}"#
+        )
+    }
+
+    #[test]
+    fn test_binance_spot() {
+        let mut synchronizer =
+            OrderBookSynchronizer::new("binance", MarketType::Spot, "BTCUSDT").unwrap();
+        // older than the snapshot
+        synchronizer.on_message(&binance_delta(95, 99, r#"["100.0","9"]"#)).unwrap();
+        synchronizer.on_message(&binance_delta(100, 102, r#"["99.0","1"]"#)).unwrap();
+        let snapshot = r#"{"lastUpdateId":101,"bids":[["100.0","1"]],"asks":[["101.0","1"]]}"#;
+        assert_eq!(SyncStatus::Synced, synchronizer.on_snapshot(snapshot).unwrap());
+        let book = synchronizer.book().unwrap();
+        assert_eq!(102, book.seq_id);
+        assert_eq!(vec![(100.0, 1.0), (99.0, 1.0)], book.bids().collect::<Vec<(f64, f64)>>());
+
+        match synchronizer.on_message(&binance_delta(103, 104, r#"["100.0","0"]"#)).unwrap() {
+            SyncStatus::Applied(delta) => assert_eq!(vec![(100.0, 0.0)], delta.bids),
+            status => panic!("{status:?}"),
+        }
+        assert_eq!(Some((99.0, 1.0)), synchronizer.book().unwrap().best_bid());
+
+        // 105 is missing
+        assert_eq!(
+            SyncStatus::Resync { expected: 105, got: 106 },
+            synchronizer.on_message(&binance_delta(106, 107, r#"["98.0","1"]"#)).unwrap()
+        );
+        assert!(!synchronizer.is_synced());
+        assert_eq!(
+            SyncStatus::Buffered,
+            synchronizer.on_message(&binance_delta(108, 109, r#"["98.0","1"]"#)).unwrap()
+        );
+        // the snapshot is older than the buffered deltas
+        let snapshot = r#"{"lastUpdateId":105,"bids":[],"asks":[]}"#;
+        assert_eq!(
+            SyncStatus::Resync { expected: 106, got: 108 },
+            synchronizer.on_snapshot(snapshot).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_binance_futures() {
+        let mut synchronizer =
+            OrderBookSynchronizer::new("binance", MarketType::LinearSwap, "BTCUSDT").unwrap();
+        let snapshot = r#"{"lastUpdateId":100,"E":1677000000000,"T":1677000000000,"bids":[["100.0","1"]],"asks":[["101.0","1"]]}"#;
+        assert_eq!(SyncStatus::Synced, synchronizer.on_snapshot(snapshot).unwrap());
+        let delta = |first_id: u64, last_id: u64, prev_id: u64| {
+            format!(
+                r#"{{"stream":"btcusdt@depth@100ms","data":{{"e":"depthUpdate","E":1677000000100,"T":1677000000100,"s":"BTCUSDT","U":{first_id},"u":{last_id},"pu":{prev_id},"b":[],"a":[["101.0","2"]]}}}}"#
+            )
+        };
+        // update IDs are not contiguous in futures, pu links deltas
+        assert!(matches!(
+            synchronizer.on_message(&delta(95, 105, 90)).unwrap(),
+            SyncStatus::Applied(_)
+        ));
+        assert!(matches!(
+            synchronizer.on_message(&delta(120, 130, 105)).unwrap(),
+            SyncStatus::Applied(_)
+        ));
+        assert_eq!(
+            SyncStatus::Resync { expected: 130, got: 140 },
+            synchronizer.on_message(&delta(150, 160, 140)).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_okx() {
+        let mut synchronizer =
+            OrderBookSynchronizer::new("okx", MarketType::Spot, "BTC-USDT").unwrap();
+        let snapshot = r#"{"arg":{"channel":"books","instId":"BTC-USDT"},"action":"snapshot","data":[{"asks":[["3366.8","9","0","3"]],"bids":[["3366.1","7","0","3"]],"ts":"1597026383085","checksum":-1881014294,"prevSeqId":-1,"seqId":123}]}"#;
+        assert_eq!(SyncStatus::Synced, synchronizer.on_message(snapshot).unwrap());
+        let update = r#"{"arg":{"channel":"books","instId":"BTC-USDT"},"action":"update","data":[{"asks":[],"bids":[["3366.1","0","0","0"]],"ts":"1597026383086","checksum":0,"prevSeqId":123,"seqId":130}]}"#;
+        assert!(matches!(synchronizer.on_message(update).unwrap(), SyncStatus::Applied(_)));
+        assert_eq!(None, synchronizer.book().unwrap().best_bid());
+        // no change, seqId is the same as prevSeqId
+        let heartbeat = r#"{"arg":{"channel":"books","instId":"BTC-USDT"},"action":"update","data":[{"asks":[],"bids":[],"ts":"1597026383087","checksum":0,"prevSeqId":130,"seqId":130}]}"#;
+        assert_eq!(SyncStatus::Ignored, synchronizer.on_message(heartbeat).unwrap());
+        let gap = r#"{"arg":{"channel":"books","instId":"BTC-USDT"},"action":"update","data":[{"asks":[],"bids":[],"ts":"1597026383088","checksum":0,"prevSeqId":131,"seqId":135}]}"#;
+        assert_eq!(
+            SyncStatus::Resync { expected: 130, got: 131 },
+            synchronizer.on_message(gap).unwrap()
+        );
+        // other symbols
+        let other = r#"{"arg":{"channel":"books","instId":"ETH-USDT"},"action":"snapshot","data":[{"asks":[],"bids":[],"ts":"1597026383085","checksum":0,"prevSeqId":-1,"seqId":1}]}"#;
+        assert_eq!(SyncStatus::Ignored, synchronizer.on_message(other).unwrap());
+    }
+
+    #[test]
+    fn test_kucoin_spot() {
+        let mut synchronizer =
+            OrderBookSynchronizer::new("kucoin", MarketType::Spot, "BTC-USDT").unwrap();
+        let delta = r#"{"type":"message","topic":"/market/level2:BTC-USDT","subject":"trade.l2update","data":{"changes":{"asks":[["18906","0.00331","14103845"]],"bids":[["18900","1","14103844"]]},"sequenceEnd":14103845,"sequenceStart":14103844,"symbol":"BTC-USDT","time":1663747970273}}"#;
+        assert_eq!(SyncStatus::Buffered, synchronizer.on_message(delta).unwrap());
+        let snapshot = r#"{"code":"200000","data":{"time":1663747970000,"sequence":"14103844","bids":[["18900","2"]],"asks":[["18906","1"]]}}"#;
+        assert_eq!(SyncStatus::Synced, synchronizer.on_snapshot(snapshot).unwrap());
+        let book = synchronizer.book().unwrap();
+        // the bid at 14103844 is already in the snapshot
+        assert_eq!(Some((18900.0, 2.0)), book.best_bid());
+        assert_eq!(Some((18906.0, 0.00331)), book.best_ask());
+        assert_eq!(14103845, book.seq_id);
+    }
+
+    #[test]
+    fn test_bybit() {
+        let mut synchronizer =
+            OrderBookSynchronizer::new("bybit", MarketType::LinearSwap, "BTCUSDT").unwrap();
+        let snapshot = r#"{"topic":"orderbook.50.BTCUSDT","type":"snapshot","ts":1672304484978,"data":{"s":"BTCUSDT","b":[["16493.50","0.006"]],"a":[["16611.00","0.029"]],"u":18521288,"seq":7961638724},"cts":1672304484976}"#;
+        assert_eq!(SyncStatus::Synced, synchronizer.on_message(snapshot).unwrap());
+        assert_eq!(18521288, synchronizer.book().unwrap().seq_id);
+        let delta = |u: u64| {
+            format!(
+                r#"{{"topic":"orderbook.50.BTCUSDT","type":"delta","ts":1672304484978,"data":{{"s":"BTCUSDT","b":[["16493.50","0"]],"a":[],"u":{u},"seq":7961638724}},"cts":1672304484976}}"#
+            )
+        };
+        match synchronizer.on_message(&delta(18521289)).unwrap() {
+            SyncStatus::Applied(delta) => assert_eq!(vec![(16493.5, 0.0)], delta.bids),
+            status => panic!("{status:?}"),
+        }
+        assert_eq!(None, synchronizer.book().unwrap().best_bid());
+        assert_eq!(
+            SyncStatus::Resync { expected: 18521290, got: 18521291 },
+            synchronizer.on_message(&delta(18521291)).unwrap()
+        );
+        // trades are not orderbook messages
+        let trade =
+            r#"{"topic":"publicTrade.BTCUSDT","type":"snapshot","ts":1672304486868,"data":[]}"#;
+        assert_eq!(SyncStatus::Ignored, synchronizer.on_message(trade).unwrap());
+    }
+
+    #[test]
+    fn test_gate_spot() {
+        let mut synchronizer =
+            OrderBookSynchronizer::new("gate", MarketType::Spot, "BTC_USDT").unwrap();
+        let delta = |first_id: u64, last_id: u64| {
+            format!(
+                r#"{{"time":1606294781,"time_ms":1606294781236,"channel":"spot.order_book_update","event":"update","result":{{"t":1606294781123,"e":"depthUpdate","E":1606294781,"s":"BTC_USDT","U":{first_id},"u":{last_id},"b":[["19137.74","0.0001"]],"a":[["19137.75","0.6135"]]}}}}"#
+            )
+        };
+        assert_eq!(
+            SyncStatus::Buffered,
+            synchronizer.on_message(&delta(48776301, 48776306)).unwrap()
+        );
+        let snapshot = r#"{"id":48776303,"current":1606294781200,"update":1606294781000,"asks":[["19137.75","0.5"]],"bids":[["19137.74","1"]]}"#;
+        assert_eq!(SyncStatus::Synced, synchronizer.on_snapshot(snapshot).unwrap());
+        let book = synchronizer.book().unwrap();
+        assert_eq!(48776306, book.seq_id);
+        assert_eq!(Some((19137.74, 0.0001)), book.best_bid());
+        assert_eq!(Some((19137.75, 0.6135)), book.best_ask());
+
+        assert!(matches!(
+            synchronizer.on_message(&delta(48776307, 48776310)).unwrap(),
+            SyncStatus::Applied(_)
+        ));
+        assert_eq!(
+            SyncStatus::Resync { expected: 48776311, got: 48776312 },
+            synchronizer.on_message(&delta(48776312, 48776315)).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_gate_swap() {
+        let mut synchronizer =
+            OrderBookSynchronizer::new("gate", MarketType::LinearSwap, "BTC_USDT").unwrap();
+        // levels are objects, sizes are numbers of contracts, negative for asks
+        let snapshot = r#"{"id":100,"current":1669799999.5,"update":1669799999.1,"asks":[{"p":"16494","s":5}],"bids":[{"p":"16493.5","s":10}]}"#;
+        assert_eq!(SyncStatus::Synced, synchronizer.on_snapshot(snapshot).unwrap());
+        let book = synchronizer.book().unwrap();
+        assert_eq!(100, book.seq_id);
+        assert_eq!(Some((16494.0, 5.0)), book.best_ask());
+        let delta = r#"{"time":1669800000,"time_ms":1669800000123,"channel":"futures.order_book_update","event":"update","result":{"t":1669800000123,"s":"BTC_USDT","U":99,"u":101,"b":[],"a":[{"p":"16494","s":-7}]}}"#;
+        match synchronizer.on_message(delta).unwrap() {
+            SyncStatus::Applied(delta) => assert_eq!(vec![(16494.0, 7.0)], delta.asks),
+            status => panic!("{status:?}"),
+        }
+    }
+
+    #[test]
+    fn test_huobi() {
+        let mut synchronizer =
+            OrderBookSynchronizer::new("huobi", MarketType::Spot, "btcusdt").unwrap();
+        let delta = |seq_num: u64, prev_seq_num: u64| {
+            format!(
+                r#"{{"ch":"market.btcusdt.mbp.20","ts":1573199608679,"tick":{{"seqNum":{seq_num},"prevSeqNum":{prev_seq_num},"bids":[["618.37","71.594"]],"asks":[]}}}}"#
+            )
+        };
+        assert_eq!(
+            SyncStatus::Buffered,
+            synchronizer.on_message(&delta(100020146795, 100020146794)).unwrap()
+        );
+        // the response of {"req":"market.btcusdt.mbp.20","id":"btcusdt"}
+        let snapshot = r#"{"id":"btcusdt","rep":"market.btcusdt.mbp.20","status":"ok","data":{"seqNum":100020146795,"bids":[["618.37","60.0"]],"asks":[["618.38","1"]]}}"#;
+        assert_eq!(SyncStatus::Synced, synchronizer.on_message(snapshot).unwrap());
+        // the buffered delta is included in the snapshot
+        assert_eq!(Some((618.37, 60.0)), synchronizer.book().unwrap().best_bid());
+
+        assert!(matches!(
+            synchronizer.on_message(&delta(100020146796, 100020146795)).unwrap(),
+            SyncStatus::Applied(_)
+        ));
+        assert_eq!(Some((618.37, 71.594)), synchronizer.book().unwrap().best_bid());
+        assert_eq!(
+            SyncStatus::Resync { expected: 100020146796, got: 100020146797 },
+            synchronizer.on_message(&delta(100020146798, 100020146797)).unwrap()
+        );
+        // other channels
+        let trade = r#"{"ch":"market.btcusdt.trade.detail","ts":1630994963175,"tick":{"id":137005445109,"ts":1630994963173,"data":[]}}"#;
+        assert_eq!(SyncStatus::Ignored, synchronizer.on_message(trade).unwrap());
+    }
+
+    #[test]
+    fn test_unsupported() {
+        assert!(OrderBookSynchronizer::new("huobi", MarketType::LinearSwap, "BTC-USDT").is_err());
+        assert!(OrderBookSynchronizer::new("bitmex", MarketType::InverseSwap, "XBTUSD").is_err());
+    }
+}
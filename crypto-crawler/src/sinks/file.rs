@@ -0,0 +1,415 @@
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use log::*;
+use serde::{Deserialize, Serialize};
+
+use super::HOUR_MS;
+use crate::Message;
+
+/// Line format of data files.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FileFormat {
+    /// `Message::to_tsv_string()` per line
+    #[default]
+    Tsv,
+    /// One JSON message per line
+    JsonLines,
+}
+
+/// Compression of data files.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Compression {
+    #[default]
+    None,
+    Gzip,
+    Zstd,
+}
+
+/// Builds a `FileSink`.
+pub struct FileSinkBuilder {
+    dir: PathBuf,
+    format: FileFormat,
+    compression: Compression,
+    rotate_interval: Duration,
+    max_size: Option<u64>,
+}
+
+impl FileSinkBuilder {
+    pub fn format(mut self, format: FileFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    pub fn compression(mut self, compression: Compression) -> Self {
+        self.compression = compression;
+        self
+    }
+
+    /// Starts new files every `interval`, one hour by default.
+    ///
+    /// Panics if `interval` is shorter than one minute.
+    pub fn rotate_interval(mut self, interval: Duration) -> Self {
+        assert!(interval >= Duration::from_secs(60), "rotate interval is shorter than 1 minute");
+        self.rotate_interval = interval;
+        self
+    }
+
+    /// Starts a new file after writing `max_size` uncompressed bytes.
+    pub fn max_size(mut self, max_size: u64) -> Self {
+        self.max_size = Some(max_size);
+        self
+    }
+
+    pub fn build(self) -> FileSink {
+        FileSink {
+            dir: self.dir,
+            format: self.format,
+            compression: self.compression,
+            interval_ms: self.rotate_interval.as_millis() as u64,
+            max_size: self.max_size,
+            latest_period: 0,
+            files: HashMap::new(),
+        }
+    }
+}
+
+enum Encoder {
+    Plain(BufWriter<File>),
+    Gzip(flate2::write::GzEncoder<BufWriter<File>>),
+    Zstd(zstd::Encoder<'static, BufWriter<File>>),
+}
+
+impl Encoder {
+    fn new(file: File, compression: Compression) -> io::Result<Self> {
+        let writer = BufWriter::new(file);
+        let encoder = match compression {
+            Compression::None => Encoder::Plain(writer),
+            Compression::Gzip => {
+                Encoder::Gzip(flate2::write::GzEncoder::new(writer, flate2::Compression::default()))
+            }
+            Compression::Zstd => Encoder::Zstd(zstd::Encoder::new(writer, 0)?),
+        };
+        Ok(encoder)
+    }
+
+    fn writer(&mut self) -> &mut dyn Write {
+        match self {
+            Encoder::Plain(writer) => writer,
+            Encoder::Gzip(writer) => writer,
+            Encoder::Zstd(writer) => writer,
+        }
+    }
+
+    // Writes the compression trailer, returns the underlying file
+    fn finish(self) -> io::Result<File> {
+        let writer = match self {
+            Encoder::Plain(writer) => writer,
+            Encoder::Gzip(writer) => writer.finish()?,
+            Encoder::Zstd(writer) => writer.finish()?,
+        };
+        writer.into_inner().map_err(|err| err.into_error())
+    }
+}
+
+// A file being written
+struct DataFile {
+    period: u64,
+    size: u64,
+    path: PathBuf,
+    tmp_path: PathBuf,
+    encoder: Encoder,
+}
+
+impl DataFile {
+    // Renames the file to its final name after it is on disk
+    fn close(self) -> io::Result<()> {
+        let file = self.encoder.finish()?;
+        super::commit(file, &self.tmp_path, &self.path)?;
+        debug!("Closed {}", self.path.display());
+        Ok(())
+    }
+}
+
+/// Writes messages to rotating files in a directory.
+///
+/// Messages of a market and message type go to
+/// `{exchange}.{market_type}.{msg_type}.{YYYY-MM-DD-HH}.{tsv|json}` in UTC by
+/// their `received_at`, with a `.gz` or `.zst` suffix if compressed, and a
+/// `-MM` minute suffix if the rotate interval is not whole hours. A file
+/// exceeding the maximum size is continued in `{...}.{YYYY-MM-DD-HH}.1.tsv`,
+/// `.2.tsv` and so on.
+///
+/// Files are written as `{name}.tmp`, then synced and renamed when a new
+/// period starts, either by `received_at` of a message or by the clock in
+/// `flush()`, so files without the `.tmp` suffix are complete. Messages
+/// received before the latest period, e.g., delayed RESTful responses, are
+/// written to the current files.
+pub struct FileSink {
+    dir: PathBuf,
+    format: FileFormat,
+    compression: Compression,
+    interval_ms: u64,
+    max_size: Option<u64>,
+    // start of the latest period in milliseconds
+    latest_period: u64,
+    // {exchange}.{market_type}.{msg_type} -> file
+    files: HashMap<String, DataFile>,
+}
+
+impl FileSink {
+    /// Writes hourly TSV files to `dir` without compression by default.
+    pub fn builder(dir: impl AsRef<Path>) -> FileSinkBuilder {
+        FileSinkBuilder {
+            dir: dir.as_ref().to_path_buf(),
+            format: FileFormat::default(),
+            compression: Compression::default(),
+            rotate_interval: Duration::from_millis(HOUR_MS),
+            max_size: None,
+        }
+    }
+
+    pub fn write(&mut self, msg: &Message) -> io::Result<()> {
+        self.advance(msg.received_at)?;
+        let period = self.latest_period;
+
+        let prefix = format!("{}.{}.{}", msg.exchange, msg.market_type, msg.msg_type);
+        if let Some(file) = self.files.get(&prefix) {
+            let full = self.max_size.is_some_and(|max_size| file.size >= max_size);
+            if file.period < period || full {
+                self.files.remove(&prefix).unwrap().close()?;
+            }
+        }
+        let file = match self.files.get_mut(&prefix) {
+            Some(file) => file,
+            None => {
+                let file = self.open(&prefix, period)?;
+                self.files.entry(prefix).or_insert(file)
+            }
+        };
+
+        let line = match self.format {
+            FileFormat::Tsv => msg.to_tsv_string(),
+            FileFormat::JsonLines => msg.to_string(),
+        };
+        let writer = file.encoder.writer();
+        writer.write_all(line.as_bytes())?;
+        writer.write_all(b"\n")?;
+        file.size += line.len() as u64 + 1;
+        Ok(())
+    }
+
+    /// Flushes buffered lines to the `.tmp` files, and closes files of past
+    /// periods by the current time, so a quiet stream doesn't keep them open.
+    pub fn flush(&mut self) -> io::Result<()> {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_millis() as u64;
+        self.advance(now)?;
+        for file in self.files.values_mut() {
+            file.encoder.writer().flush()?;
+        }
+        Ok(())
+    }
+
+    /// Closes all files, later messages go to new files.
+    pub fn close(&mut self) -> io::Result<()> {
+        self.close_before(u64::MAX)
+    }
+
+    // Starts the period of `timestamp` if it is later than the latest period
+    fn advance(&mut self, timestamp: u64) -> io::Result<()> {
+        let period = timestamp / self.interval_ms * self.interval_ms;
+        if period > self.latest_period {
+            self.latest_period = period;
+            self.close_before(period)?;
+        }
+        Ok(())
+    }
+
+    fn close_before(&mut self, period: u64) -> io::Result<()> {
+        let expired = self
+            .files
+            .iter()
+            .filter(|(_, file)| file.period < period)
+            .map(|(prefix, _)| prefix.clone())
+            .collect::<Vec<String>>();
+        for prefix in expired {
+            self.files.remove(&prefix).unwrap().close()?;
+        }
+        Ok(())
+    }
+
+    fn open(&self, prefix: &str, period: u64) -> io::Result<DataFile> {
+        let extension = match self.format {
+            FileFormat::Tsv => "tsv",
+            FileFormat::JsonLines => "json",
+        };
+        let extension = match self.compression {
+            Compression::None => extension.to_string(),
+            Compression::Gzip => format!("{extension}.gz"),
+            Compression::Zstd => format!("{extension}.zst"),
+        };
+        let stem = format!("{prefix}.{}", super::period_name(period, self.interval_ms));
+        let (file, path, tmp_path) = super::create_tmp_file(&self.dir, &stem, &extension)?;
+        Ok(DataFile {
+            period,
+            size: 0,
+            path,
+            tmp_path,
+            encoder: Encoder::new(file, self.compression)?,
+        })
+    }
+}
+
+impl Drop for FileSink {
+    fn drop(&mut self) {
+        if let Err(err) = self.close() {
+            error!("Failed to close files in {}, {}", self.dir.display(), err);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{io::Read, path::PathBuf, time::Duration};
+
+    use crypto_market_type::MarketType;
+    use crypto_msg_type::MessageType;
+
+    use super::{Compression, FileFormat, FileSink};
+    use crate::Message;
+
+    // 2023-03-20 08:00:00 UTC
+    const START: u64 = 1679299200000;
+
+    fn message(msg_type: MessageType, received_at: u64) -> Message {
+        let mut msg = Message::new_with_symbol(
+            "binance".to_string(),
+            MarketType::Spot,
+            msg_type,
+            "BTCUSDT".to_string(),
+            r#"{"e":"trade"}"#.to_string(),
+        );
+        msg.received_at = received_at;
+        msg
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("file_sink_{name}_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir
+    }
+
+    fn file_names(dir: &PathBuf) -> Vec<String> {
+        let mut names = std::fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect::<Vec<String>>();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn test_rotate() {
+        let dir = temp_dir("rotate");
+        let mut sink = FileSink::builder(&dir).max_size(100).build();
+        sink.write(&message(MessageType::Trade, START)).unwrap();
+        sink.write(&message(MessageType::L2Event, START + 1)).unwrap();
+        assert_eq!(
+            vec![
+                "binance.spot.l2_event.2023-03-20-08.tsv.tmp",
+                "binance.spot.trade.2023-03-20-08.tsv.tmp"
+            ],
+            file_names(&dir)
+        );
+
+        // the next hour closes files of the previous hour
+        sink.write(&message(MessageType::Trade, START + 3600 * 1000)).unwrap();
+        // a delayed message goes to the current file
+        sink.write(&message(MessageType::L2Event, START + 2)).unwrap();
+        assert_eq!(
+            vec![
+                "binance.spot.l2_event.2023-03-20-08.tsv",
+                "binance.spot.l2_event.2023-03-20-09.tsv.tmp",
+                "binance.spot.trade.2023-03-20-08.tsv",
+                "binance.spot.trade.2023-03-20-09.tsv.tmp"
+            ],
+            file_names(&dir)
+        );
+
+        // each line is 36 bytes, so the fourth line goes to a new file
+        for _ in 0..3 {
+            sink.write(&message(MessageType::Trade, START + 3600 * 1000)).unwrap();
+        }
+        drop(sink);
+        assert_eq!(
+            vec![
+                "binance.spot.l2_event.2023-03-20-08.tsv",
+                "binance.spot.l2_event.2023-03-20-09.tsv",
+                "binance.spot.trade.2023-03-20-08.tsv",
+                "binance.spot.trade.2023-03-20-09.1.tsv",
+                "binance.spot.trade.2023-03-20-09.tsv"
+            ],
+            file_names(&dir)
+        );
+        assert_eq!(
+            "1679302800000\tBTCUSDT\t{\"e\":\"trade\"}\n".repeat(3),
+            std::fs::read_to_string(dir.join("binance.spot.trade.2023-03-20-09.tsv")).unwrap()
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_flush_closes_expired() {
+        let dir = temp_dir("flush");
+        let mut sink = FileSink::builder(&dir).build();
+        sink.write(&message(MessageType::Trade, START)).unwrap();
+        // the clock is past 2023-03-20-08, so flush() closes the file
+        sink.flush().unwrap();
+        assert_eq!(vec!["binance.spot.trade.2023-03-20-08.tsv"], file_names(&dir));
+
+        // a delayed message goes to a file of the current period
+        sink.write(&message(MessageType::Trade, START)).unwrap();
+        let names = file_names(&dir);
+        assert_eq!(2, names.len());
+        assert!(names[1].ends_with(".tsv.tmp") && names[1] > names[0]);
+        drop(sink);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_compression() {
+        let dir = temp_dir("compression");
+        let msg = message(MessageType::Trade, START);
+        for compression in [Compression::Gzip, Compression::Zstd] {
+            let mut sink = FileSink::builder(&dir)
+                .format(FileFormat::JsonLines)
+                .compression(compression)
+                .rotate_interval(Duration::from_secs(60))
+                .build();
+            sink.write(&msg).unwrap();
+            sink.close().unwrap();
+        }
+        assert_eq!(
+            vec![
+                "binance.spot.trade.2023-03-20-08-00.json.gz",
+                "binance.spot.trade.2023-03-20-08-00.json.zst"
+            ],
+            file_names(&dir)
+        );
+
+        let mut text = String::new();
+        let file = std::fs::File::open(dir.join(&file_names(&dir)[0])).unwrap();
+        flate2::read::GzDecoder::new(file).read_to_string(&mut text).unwrap();
+        assert_eq!(format!("{msg}\n"), text);
+        let file = std::fs::File::open(dir.join(&file_names(&dir)[1])).unwrap();
+        assert_eq!(format!("{msg}\n").into_bytes(), zstd::decode_all(file).unwrap());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
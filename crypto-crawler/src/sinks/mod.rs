@@ -0,0 +1,202 @@
+mod file;
+#[cfg(feature = "parquet")]
+mod parquet;
+mod redis;
+mod socket;
+
+use std::{
+    fs::{self, File, OpenOptions},
+    io,
+    net::{TcpStream, ToSocketAddrs},
+    path::{Path, PathBuf},
+    sync::mpsc::{RecvTimeoutError, Sender},
+    thread::JoinHandle,
+    time::Duration,
+};
+
+use chrono::{TimeZone, Utc};
+use log::*;
+
+use crate::Message;
+
+pub use file::{Compression, FileFormat, FileSink, FileSinkBuilder};
+#[cfg(feature = "parquet")]
+pub use parquet::{ParquetMode, ParquetSink, ParquetSinkBuilder};
+pub use redis::RedisSink;
+pub use socket::TcpSink;
+#[cfg(unix)]
+pub use socket::UnixSink;
+
+/// A destination of crawled messages.
+///
+/// `crawl_*` functions send messages to a `Sender<Message>`, `spawn_sink()`
+/// connects such a sender to any sink.
+pub trait Sink: Send {
+    fn write(&mut self, msg: &Message) -> io::Result<()>;
+
+    /// Called after a batch of messages, sinks may buffer messages until then.
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Sink for Sender<Message> {
+    fn write(&mut self, msg: &Message) -> io::Result<()> {
+        self.send(msg.clone())
+            .map_err(|_| io::Error::new(io::ErrorKind::BrokenPipe, "The receiver was dropped"))
+    }
+}
+
+impl<S: Sink + ?Sized> Sink for Box<S> {
+    fn write(&mut self, msg: &Message) -> io::Result<()> {
+        (**self).write(msg)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        (**self).flush()
+    }
+}
+
+impl Sink for FileSink {
+    fn write(&mut self, msg: &Message) -> io::Result<()> {
+        FileSink::write(self, msg)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        FileSink::flush(self)
+    }
+}
+
+#[cfg(feature = "parquet")]
+impl Sink for ParquetSink {
+    fn write(&mut self, msg: &Message) -> io::Result<()> {
+        ParquetSink::write(self, msg)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        ParquetSink::flush(self)
+    }
+}
+
+/// Writes every message to all sinks.
+#[derive(Default)]
+pub struct FanoutSink {
+    sinks: Vec<Box<dyn Sink>>,
+}
+
+impl FanoutSink {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn sink(mut self, sink: impl Sink + 'static) -> Self {
+        self.sinks.push(Box::new(sink));
+        self
+    }
+}
+
+impl Sink for FanoutSink {
+    /// Writes to all sinks even if some of them fail, returns the first error.
+    fn write(&mut self, msg: &Message) -> io::Result<()> {
+        self.sinks.iter_mut().map(|sink| sink.write(msg)).fold(Ok(()), Result::and)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.sinks.iter_mut().map(|sink| sink.flush()).fold(Ok(()), Result::and)
+    }
+}
+
+/// Writes messages sent to the returned sender to `sink` in a new thread.
+///
+/// Errors are logged and the thread keeps going, it flushes `sink` after each
+/// batch of received messages, or every second without messages, and exits
+/// after all senders are dropped.
+pub fn spawn_sink(mut sink: impl Sink + 'static) -> (Sender<Message>, JoinHandle<()>) {
+    let (tx, rx) = std::sync::mpsc::channel::<Message>();
+    let handle = std::thread::spawn(move || loop {
+        let result = match rx.recv_timeout(Duration::from_secs(1)) {
+            Ok(msg) => std::iter::once(msg)
+                .chain(rx.try_iter())
+                .map(|msg| sink.write(&msg))
+                .fold(Ok(()), Result::and)
+                .and(sink.flush()),
+            Err(RecvTimeoutError::Timeout) => sink.flush(),
+            Err(RecvTimeoutError::Disconnected) => break,
+        };
+        if let Err(err) = result {
+            error!("Failed to write messages, {}", err);
+        }
+    });
+    (tx, handle)
+}
+
+const HOUR_MS: u64 = 3600 * 1000;
+
+// Default timeout of connecting to, reading from and writing to a server
+const SOCKET_TIMEOUT: Duration = Duration::from_secs(10);
+
+// Connects to a TCP server with timeouts, so that an unresponsive server fails
+// the sink, which reconnects on its next write, instead of blocking it forever
+fn connect_tcp(addr: &str, timeout: Duration) -> io::Result<TcpStream> {
+    let mut last_err = None;
+    for socket_addr in addr.to_socket_addrs()? {
+        match TcpStream::connect_timeout(&socket_addr, timeout) {
+            Ok(stream) => {
+                stream.set_read_timeout(Some(timeout))?;
+                stream.set_write_timeout(Some(timeout))?;
+                return Ok(stream);
+            }
+            Err(err) => last_err = Some(err),
+        }
+    }
+    Err(last_err.unwrap_or_else(|| {
+        io::Error::new(io::ErrorKind::InvalidInput, format!("{addr} has no address"))
+    }))
+}
+
+// A period in UTC, e.g., 2023-03-20-08, with minutes if periods are not whole
+// hours
+fn period_name(period: u64, interval_ms: u64) -> String {
+    let time = Utc.timestamp_millis_opt(period as i64).unwrap();
+    if interval_ms.is_multiple_of(HOUR_MS) {
+        time.format("%Y-%m-%d-%H").to_string()
+    } else {
+        time.format("%Y-%m-%d-%H-%M").to_string()
+    }
+}
+
+// Creates `{stem}.{extension}.tmp`, or `{stem}.{seq}.{extension}.tmp` if
+// files of previous runs exist, returns the file, its final path and its
+// temporary path
+fn create_tmp_file(
+    dir: &Path,
+    stem: &str,
+    extension: &str,
+) -> io::Result<(File, PathBuf, PathBuf)> {
+    fs::create_dir_all(dir)?;
+    let (path, tmp_path) = (0..)
+        .map(|seq| {
+            let name = if seq == 0 {
+                format!("{stem}.{extension}")
+            } else {
+                format!("{stem}.{seq}.{extension}")
+            };
+            (dir.join(&name), dir.join(format!("{name}.tmp")))
+        })
+        .find(|(path, tmp_path)| !path.exists() && !tmp_path.exists())
+        .unwrap();
+    let file = OpenOptions::new().write(true).create_new(true).open(&tmp_path)?;
+    Ok((file, path, tmp_path))
+}
+
+// Renames a temporary file to its final path after it is on disk
+fn commit(file: File, tmp_path: &Path, path: &Path) -> io::Result<()> {
+    file.sync_all()?;
+    drop(file);
+    fs::rename(tmp_path, path)?;
+    #[cfg(unix)]
+    if let Some(dir) = path.parent() {
+        File::open(dir)?.sync_all()?;
+    }
+    Ok(())
+}
@@ -0,0 +1,559 @@
+use std::{
+    collections::HashMap,
+    fmt::Display,
+    fs::File,
+    io,
+    panic::UnwindSafe,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use arrow_array::{
+    ArrayRef, BooleanArray, Float64Array, RecordBatch, StringArray, TimestampMillisecondArray,
+    UInt64Array,
+};
+use arrow_schema::{DataType, Field, Schema, TimeUnit};
+use crypto_message::TradeSide;
+use crypto_msg_type::MessageType;
+use log::*;
+use parquet::{
+    arrow::ArrowWriter,
+    basic::{Compression, ZstdLevel},
+    file::properties::WriterProperties,
+};
+use serde::{Deserialize, Serialize};
+
+use super::HOUR_MS;
+use crate::{crawlers::panic_message, Message};
+
+/// Columns of Parquet files.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ParquetMode {
+    /// `exchange`, `market_type`, `msg_type`, `symbol`, `received_at` and the
+    /// original `json` of each message
+    #[default]
+    Raw,
+    /// Trades and level2 updates parsed by crypto-msg-parser, one row per
+    /// trade or price level, other message types are stored as `Raw`
+    Typed,
+}
+
+/// Builds a `ParquetSink`.
+pub struct ParquetSinkBuilder {
+    dir: PathBuf,
+    mode: ParquetMode,
+    row_group_size: usize,
+}
+
+impl ParquetSinkBuilder {
+    pub fn mode(mut self, mode: ParquetMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Rows buffered in memory before they are written as a row group,
+    /// 100,000 by default.
+    pub fn row_group_size(mut self, row_group_size: usize) -> Self {
+        assert!(row_group_size > 0);
+        self.row_group_size = row_group_size;
+        self
+    }
+
+    pub fn build(self) -> ParquetSink {
+        ParquetSink {
+            dir: self.dir,
+            mode: self.mode,
+            row_group_size: self.row_group_size,
+            latest_period: 0,
+            files: HashMap::new(),
+        }
+    }
+}
+
+struct RawRow {
+    symbol: Option<String>,
+    received_at: i64,
+    json: String,
+}
+
+struct TradeRow {
+    symbol: String,
+    pair: String,
+    received_at: i64,
+    timestamp: i64,
+    side: &'static str,
+    price: f64,
+    quantity_base: f64,
+    quantity_quote: f64,
+    quantity_contract: Option<f64>,
+    trade_id: String,
+}
+
+// A price level of a level2 update
+struct L2Row {
+    symbol: String,
+    pair: String,
+    received_at: i64,
+    timestamp: i64,
+    snapshot: bool,
+    side: &'static str,
+    price: f64,
+    quantity_base: f64,
+    quantity_quote: f64,
+    quantity_contract: Option<f64>,
+    seq_id: Option<u64>,
+}
+
+// Rows of the next row group
+enum Rows {
+    Raw(Vec<RawRow>),
+    Trade(Vec<TradeRow>),
+    L2(Vec<L2Row>),
+}
+
+fn strings<'a>(values: impl Iterator<Item = &'a str>) -> ArrayRef {
+    Arc::new(StringArray::from_iter_values(values))
+}
+
+fn timestamps(values: impl Iterator<Item = i64>) -> ArrayRef {
+    Arc::new(TimestampMillisecondArray::from_iter_values(values).with_timezone("UTC"))
+}
+
+fn floats(values: impl Iterator<Item = f64>) -> ArrayRef {
+    Arc::new(Float64Array::from_iter_values(values))
+}
+
+fn timestamp_field(name: &str) -> Field {
+    Field::new(name, DataType::Timestamp(TimeUnit::Millisecond, Some("UTC".into())), false)
+}
+
+// Messages of crypto-msg-parser, nothing if the parser fails or panics, so that
+// one malformed message doesn't stop the sink
+fn parse_or_skip<T, E: Display>(
+    msg: &Message,
+    parse: impl FnOnce() -> Result<Vec<T>, E> + UnwindSafe,
+) -> Vec<T> {
+    let error = match std::panic::catch_unwind(parse) {
+        Ok(Ok(parsed)) => return parsed,
+        Ok(Err(err)) => err.to_string(),
+        Err(panic) => panic_message(panic),
+    };
+    warn!("Skipped {} {} {}, {}", msg.exchange, msg.market_type, msg.json, error);
+    Vec::new()
+}
+
+impl Rows {
+    fn new(mode: ParquetMode, msg_type: MessageType) -> Self {
+        match (mode, msg_type) {
+            (ParquetMode::Typed, MessageType::Trade) => Rows::Trade(Vec::new()),
+            (ParquetMode::Typed, MessageType::L2Event) => Rows::L2(Vec::new()),
+            _ => Rows::Raw(Vec::new()),
+        }
+    }
+
+    fn len(&self) -> usize {
+        match self {
+            Rows::Raw(rows) => rows.len(),
+            Rows::Trade(rows) => rows.len(),
+            Rows::L2(rows) => rows.len(),
+        }
+    }
+
+    fn append(&mut self, msg: &Message) {
+        let received_at = msg.received_at as i64;
+        match self {
+            Rows::Raw(rows) => rows.push(RawRow {
+                symbol: msg.symbol.clone(),
+                received_at,
+                json: msg.json.clone(),
+            }),
+            Rows::Trade(rows) => {
+                let trades = parse_or_skip(msg, || {
+                    crypto_msg_parser::parse_trade(&msg.exchange, msg.market_type, &msg.json)
+                });
+                rows.extend(trades.into_iter().map(|trade| TradeRow {
+                    symbol: trade.symbol,
+                    pair: trade.pair,
+                    received_at,
+                    timestamp: trade.timestamp,
+                    side: match trade.side {
+                        TradeSide::Buy => "buy",
+                        TradeSide::Sell => "sell",
+                    },
+                    price: trade.price,
+                    quantity_base: trade.quantity_base,
+                    quantity_quote: trade.quantity_quote,
+                    quantity_contract: trade.quantity_contract,
+                    trade_id: trade.trade_id,
+                }));
+            }
+            Rows::L2(rows) => {
+                let orderbooks = parse_or_skip(msg, || {
+                    crypto_msg_parser::parse_l2(
+                        &msg.exchange,
+                        msg.market_type,
+                        &msg.json,
+                        Some(received_at),
+                    )
+                });
+                for orderbook in orderbooks {
+                    let levels = orderbook
+                        .asks
+                        .iter()
+                        .map(|order| ("ask", order))
+                        .chain(orderbook.bids.iter().map(|order| ("bid", order)));
+                    rows.extend(levels.map(|(side, order)| L2Row {
+                        symbol: orderbook.symbol.clone(),
+                        pair: orderbook.pair.clone(),
+                        received_at,
+                        timestamp: orderbook.timestamp,
+                        snapshot: orderbook.snapshot,
+                        side,
+                        price: order.price,
+                        quantity_base: order.quantity_base,
+                        quantity_quote: order.quantity_quote,
+                        quantity_contract: order.quantity_contract,
+                        seq_id: orderbook.seq_id,
+                    }));
+                }
+            }
+        }
+    }
+
+    fn schema(&self) -> Schema {
+        let mut fields = vec![
+            Field::new("exchange", DataType::Utf8, false),
+            Field::new("market_type", DataType::Utf8, false),
+            Field::new("msg_type", DataType::Utf8, false),
+            Field::new("symbol", DataType::Utf8, !matches!(self, Rows::Trade(_) | Rows::L2(_))),
+            timestamp_field("received_at"),
+        ];
+        match self {
+            Rows::Raw(_) => fields.push(Field::new("json", DataType::Utf8, false)),
+            Rows::Trade(_) => fields.extend([
+                Field::new("pair", DataType::Utf8, false),
+                timestamp_field("timestamp"),
+                Field::new("side", DataType::Utf8, false),
+                Field::new("price", DataType::Float64, false),
+                Field::new("quantity_base", DataType::Float64, false),
+                Field::new("quantity_quote", DataType::Float64, false),
+                Field::new("quantity_contract", DataType::Float64, true),
+                Field::new("trade_id", DataType::Utf8, false),
+            ]),
+            Rows::L2(_) => fields.extend([
+                Field::new("pair", DataType::Utf8, false),
+                timestamp_field("timestamp"),
+                Field::new("snapshot", DataType::Boolean, false),
+                Field::new("side", DataType::Utf8, false),
+                Field::new("price", DataType::Float64, false),
+                Field::new("quantity_base", DataType::Float64, false),
+                Field::new("quantity_quote", DataType::Float64, false),
+                Field::new("quantity_contract", DataType::Float64, true),
+                Field::new("seq_id", DataType::UInt64, true),
+            ]),
+        }
+        Schema::new(fields)
+    }
+
+    // Moves rows to a record batch
+    fn take_batch(&mut self, exchange: &str, market_type: &str, msg_type: &str) -> RecordBatch {
+        let len = self.len();
+        let mut columns = vec![
+            strings(std::iter::repeat_n(exchange, len)),
+            strings(std::iter::repeat_n(market_type, len)),
+            strings(std::iter::repeat_n(msg_type, len)),
+        ];
+        match self {
+            Rows::Raw(rows) => columns.extend([
+                Arc::new(StringArray::from_iter(rows.iter().map(|row| row.symbol.as_deref())))
+                    as ArrayRef,
+                timestamps(rows.iter().map(|row| row.received_at)),
+                strings(rows.iter().map(|row| row.json.as_str())),
+            ]),
+            Rows::Trade(rows) => columns.extend([
+                strings(rows.iter().map(|row| row.symbol.as_str())),
+                timestamps(rows.iter().map(|row| row.received_at)),
+                strings(rows.iter().map(|row| row.pair.as_str())),
+                timestamps(rows.iter().map(|row| row.timestamp)),
+                strings(rows.iter().map(|row| row.side)),
+                floats(rows.iter().map(|row| row.price)),
+                floats(rows.iter().map(|row| row.quantity_base)),
+                floats(rows.iter().map(|row| row.quantity_quote)),
+                Arc::new(Float64Array::from_iter(rows.iter().map(|row| row.quantity_contract))),
+                strings(rows.iter().map(|row| row.trade_id.as_str())),
+            ]),
+            Rows::L2(rows) => columns.extend([
+                strings(rows.iter().map(|row| row.symbol.as_str())),
+                timestamps(rows.iter().map(|row| row.received_at)),
+                strings(rows.iter().map(|row| row.pair.as_str())),
+                timestamps(rows.iter().map(|row| row.timestamp)),
+                Arc::new(BooleanArray::from_iter(rows.iter().map(|row| Some(row.snapshot)))),
+                strings(rows.iter().map(|row| row.side)),
+                floats(rows.iter().map(|row| row.price)),
+                floats(rows.iter().map(|row| row.quantity_base)),
+                floats(rows.iter().map(|row| row.quantity_quote)),
+                Arc::new(Float64Array::from_iter(rows.iter().map(|row| row.quantity_contract))),
+                Arc::new(UInt64Array::from_iter(rows.iter().map(|row| row.seq_id))),
+            ]),
+        }
+        let batch = RecordBatch::try_new(Arc::new(self.schema()), columns).unwrap();
+        match self {
+            Rows::Raw(rows) => rows.clear(),
+            Rows::Trade(rows) => rows.clear(),
+            Rows::L2(rows) => rows.clear(),
+        }
+        batch
+    }
+}
+
+// A file being written
+struct ParquetFile {
+    period: u64,
+    path: PathBuf,
+    tmp_path: PathBuf,
+    exchange: String,
+    market_type: String,
+    msg_type: String,
+    rows: Rows,
+    writer: ArrowWriter<File>,
+}
+
+impl ParquetFile {
+    // Writes buffered rows as a row group
+    fn write_row_group(&mut self) -> io::Result<()> {
+        if self.rows.len() == 0 {
+            return Ok(());
+        }
+        let batch = self.rows.take_batch(&self.exchange, &self.market_type, &self.msg_type);
+        self.writer.write(&batch).map_err(io::Error::other)?;
+        self.writer.flush().map_err(io::Error::other)
+    }
+
+    fn close(mut self) -> io::Result<()> {
+        self.write_row_group()?;
+        let file = self.writer.into_inner().map_err(io::Error::other)?;
+        super::commit(file, &self.tmp_path, &self.path)?;
+        debug!("Closed {}", self.path.display());
+        Ok(())
+    }
+}
+
+/// Writes messages to hourly Parquet files in a directory.
+///
+/// Files are named like `FileSink` files with the `.parquet` extension, e.g.,
+/// `binance.spot.trade.2023-03-20-08.parquet`, and compressed by zstd. Each
+/// file has at least one row group, so files of a quiet market may have
+/// small row groups.
+pub struct ParquetSink {
+    dir: PathBuf,
+    mode: ParquetMode,
+    row_group_size: usize,
+    // start of the latest hour in milliseconds
+    latest_period: u64,
+    // {exchange}.{market_type}.{msg_type} -> file
+    files: HashMap<String, ParquetFile>,
+}
+
+impl ParquetSink {
+    /// Writes `Raw` columns to `dir` by default.
+    pub fn builder(dir: impl AsRef<Path>) -> ParquetSinkBuilder {
+        ParquetSinkBuilder {
+            dir: dir.as_ref().to_path_buf(),
+            mode: ParquetMode::default(),
+            row_group_size: 100_000,
+        }
+    }
+
+    /// Appends a message to the next row group.
+    ///
+    /// In the `Typed` mode, a message which can't be parsed is skipped with a
+    /// warning.
+    pub fn write(&mut self, msg: &Message) -> io::Result<()> {
+        self.advance(msg.received_at)?;
+        let period = self.latest_period;
+
+        let prefix = format!("{}.{}.{}", msg.exchange, msg.market_type, msg.msg_type);
+        let file = match self.files.get_mut(&prefix) {
+            Some(file) => file,
+            None => {
+                let file = self.open(&prefix, period, msg)?;
+                self.files.entry(prefix).or_insert(file)
+            }
+        };
+        file.rows.append(msg);
+        if file.rows.len() >= self.row_group_size {
+            file.write_row_group()?;
+        }
+        Ok(())
+    }
+
+    /// Closes files of past hours by the current time, so a quiet stream
+    /// doesn't keep them open. Buffered rows of the current hour are kept for
+    /// the next row group.
+    pub fn flush(&mut self) -> io::Result<()> {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_millis() as u64;
+        self.advance(now)
+    }
+
+    /// Closes all files, later messages go to new files.
+    pub fn close(&mut self) -> io::Result<()> {
+        self.close_before(u64::MAX)
+    }
+
+    // Starts the hour of `timestamp` if it is later than the latest hour
+    fn advance(&mut self, timestamp: u64) -> io::Result<()> {
+        let period = timestamp / HOUR_MS * HOUR_MS;
+        if period > self.latest_period {
+            self.latest_period = period;
+            self.close_before(period)?;
+        }
+        Ok(())
+    }
+
+    fn close_before(&mut self, period: u64) -> io::Result<()> {
+        let expired = self
+            .files
+            .iter()
+            .filter(|(_, file)| file.period < period)
+            .map(|(prefix, _)| prefix.clone())
+            .collect::<Vec<String>>();
+        for prefix in expired {
+            self.files.remove(&prefix).unwrap().close()?;
+        }
+        Ok(())
+    }
+
+    fn open(&self, prefix: &str, period: u64, msg: &Message) -> io::Result<ParquetFile> {
+        let stem = format!("{prefix}.{}", super::period_name(period, HOUR_MS));
+        let (file, path, tmp_path) = super::create_tmp_file(&self.dir, &stem, "parquet")?;
+        let rows = Rows::new(self.mode, msg.msg_type);
+        let props = WriterProperties::builder()
+            .set_compression(Compression::ZSTD(ZstdLevel::default()))
+            .set_max_row_group_size(self.row_group_size)
+            .build();
+        let writer = ArrowWriter::try_new(file, Arc::new(rows.schema()), Some(props))
+            .map_err(io::Error::other)?;
+        Ok(ParquetFile {
+            period,
+            path,
+            tmp_path,
+            exchange: msg.exchange.clone(),
+            market_type: msg.market_type.to_string(),
+            msg_type: msg.msg_type.to_string(),
+            rows,
+            writer,
+        })
+    }
+}
+
+impl Drop for ParquetSink {
+    fn drop(&mut self) {
+        if let Err(err) = self.close() {
+            error!("Failed to close files in {}, {}", self.dir.display(), err);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use arrow_array::{cast::AsArray, types::Float64Type, RecordBatch};
+    use crypto_market_type::MarketType;
+    use crypto_msg_type::MessageType;
+    use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+
+    use super::{ParquetMode, ParquetSink};
+    use crate::Message;
+
+    // 2021-03-19 18:00:00 UTC
+    const START: u64 = 1616176800000;
+
+    const TRADE: &str = r#"{"stream":"btcusdt@trade","data":{"e":"trade","E":1616176861895,"s":"BTCUSDT","t":714174749,"p":"58589.37000000","q":"0.00137000","b":5285380560,"a":5285380434,"T":1616176861893,"m":false,"M":true}}"#;
+
+    fn message(received_at: u64) -> Message {
+        let mut msg = Message::new(
+            "binance".to_string(),
+            MarketType::Spot,
+            MessageType::Trade,
+            TRADE.to_string(),
+        );
+        msg.received_at = received_at;
+        msg
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("parquet_sink_{name}_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir
+    }
+
+    // Returns row group sizes and all rows
+    fn read(path: PathBuf) -> (Vec<i64>, RecordBatch) {
+        let file = std::fs::File::open(path).unwrap();
+        let builder = ParquetRecordBatchReaderBuilder::try_new(file).unwrap();
+        let row_groups =
+            builder.metadata().row_groups().iter().map(|group| group.num_rows()).collect();
+        let mut reader = builder.with_batch_size(1024 * 1024).build().unwrap();
+        (row_groups, reader.next().unwrap().unwrap())
+    }
+
+    #[test]
+    fn test_raw() {
+        let dir = temp_dir("raw");
+        let mut sink = ParquetSink::builder(&dir).row_group_size(2).build();
+        for i in 0..3 {
+            sink.write(&message(START + i)).unwrap();
+        }
+        sink.write(&message(START + 3600 * 1000)).unwrap();
+        drop(sink);
+
+        let (row_groups, batch) = read(dir.join("binance.spot.trade.2021-03-19-18.parquet"));
+        assert_eq!(vec![2, 1], row_groups);
+        assert_eq!(
+            "binance",
+            batch.column_by_name("exchange").unwrap().as_string::<i32>().value(0)
+        );
+        assert_eq!(TRADE, batch.column_by_name("json").unwrap().as_string::<i32>().value(1));
+        assert!(batch.column_by_name("symbol").unwrap().is_null(0));
+        assert_eq!(vec![1], read(dir.join("binance.spot.trade.2021-03-19-19.parquet")).0);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_typed() {
+        let dir = temp_dir("typed");
+        let mut sink = ParquetSink::builder(&dir).mode(ParquetMode::Typed).build();
+        sink.write(&message(START)).unwrap();
+        // skipped
+        let mut invalid = message(START);
+        invalid.json = "{}".to_string();
+        sink.write(&invalid).unwrap();
+        drop(sink);
+
+        let (_, batch) = read(dir.join("binance.spot.trade.2021-03-19-18.parquet"));
+        assert_eq!(1, batch.num_rows());
+        assert_eq!("BTCUSDT", batch.column_by_name("symbol").unwrap().as_string::<i32>().value(0));
+        assert_eq!("buy", batch.column_by_name("side").unwrap().as_string::<i32>().value(0));
+        assert_eq!(
+            58589.37,
+            batch.column_by_name("price").unwrap().as_primitive::<Float64Type>().value(0)
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_flush() {
+        let dir = temp_dir("flush");
+        let mut sink = ParquetSink::builder(&dir).build();
+        sink.write(&message(START)).unwrap();
+        assert!(!dir.join("binance.spot.trade.2021-03-19-18.parquet").exists());
+        // the hour has passed
+        sink.flush().unwrap();
+        assert_eq!(vec![1], read(dir.join("binance.spot.trade.2021-03-19-18.parquet")).0);
+        drop(sink);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
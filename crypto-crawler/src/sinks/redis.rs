@@ -0,0 +1,344 @@
+use std::{
+    io::{self, BufRead, BufReader, BufWriter, Read, Write},
+    net::TcpStream,
+    time::Duration,
+};
+
+use log::*;
+
+use super::{connect_tcp, Sink, SOCKET_TIMEOUT};
+use crate::Message;
+
+// Replies are read after this many commands at most
+const MAX_PENDING: usize = 256;
+
+struct Connection {
+    reader: BufReader<TcpStream>,
+    writer: BufWriter<TcpStream>,
+}
+
+impl Connection {
+    fn open(addr: &str, timeout: Duration) -> io::Result<Self> {
+        let stream = connect_tcp(addr, timeout)?;
+        Ok(Connection {
+            reader: BufReader::new(stream.try_clone()?),
+            writer: BufWriter::new(stream),
+        })
+    }
+
+    // Writes a command as a RESP array of bulk strings
+    fn send(&mut self, args: &[&str]) -> io::Result<()> {
+        write!(self.writer, "*{}\r\n", args.len())?;
+        for arg in args {
+            write!(self.writer, "${}\r\n{}\r\n", arg.len(), arg)?;
+        }
+        Ok(())
+    }
+
+    // Reads a reply, returns the error message of an error reply
+    fn receive(&mut self) -> io::Result<Option<String>> {
+        let mut line = String::new();
+        if self.reader.read_line(&mut line)? == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "Redis closed the connection",
+            ));
+        }
+        let line = line.trim_end();
+        match line.chars().next() {
+            Some('-') => Ok(Some(line[1..].to_string())),
+            Some('+' | ':') => Ok(None),
+            Some('$') => {
+                let len = line[1..].parse::<i64>().map_err(|_| invalid_reply(line))?;
+                if len >= 0 {
+                    // the entry ID and CRLF
+                    let mut buf = vec![0; len as usize + 2];
+                    self.reader.read_exact(&mut buf)?;
+                }
+                Ok(None)
+            }
+            _ => Err(invalid_reply(line)),
+        }
+    }
+}
+
+fn invalid_reply(line: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("Invalid Redis reply {line}"))
+}
+
+/// Appends messages to a Redis stream by `XADD`.
+///
+/// Each entry has `exchange`, `market_type`, `msg_type`, `symbol` if any,
+/// `received_at` and `json` fields. Commands are pipelined and their replies
+/// are read by `flush()`, which also happens every 256 messages. The
+/// connection is re-established on the next write after a failure, including
+/// a timeout.
+pub struct RedisSink {
+    addr: String,
+    key: String,
+    max_len: Option<usize>,
+    timeout: Duration,
+    connection: Option<Connection>,
+    // commands without replies read
+    pending: usize,
+}
+
+impl RedisSink {
+    /// Connects to a Redis server at `addr`, e.g., `127.0.0.1:6379`, and
+    /// appends to the stream `key`.
+    pub fn connect(addr: &str, key: &str) -> io::Result<Self> {
+        let connection = Connection::open(addr, SOCKET_TIMEOUT)?;
+        Ok(RedisSink {
+            addr: addr.to_string(),
+            key: key.to_string(),
+            max_len: None,
+            timeout: SOCKET_TIMEOUT,
+            connection: Some(connection),
+            pending: 0,
+        })
+    }
+
+    /// Trims the stream to about `max_len` entries by `MAXLEN ~`.
+    pub fn with_max_len(mut self, max_len: usize) -> Self {
+        self.max_len = Some(max_len);
+        self
+    }
+
+    /// Sets the timeout of connecting, reading replies and writing commands,
+    /// 10 seconds by default.
+    pub fn with_timeout(mut self, timeout: Duration) -> io::Result<Self> {
+        if let Some(connection) = self.connection.as_ref() {
+            // the reader shares the socket and its timeouts
+            let stream = connection.writer.get_ref();
+            stream.set_read_timeout(Some(timeout))?;
+            stream.set_write_timeout(Some(timeout))?;
+        }
+        self.timeout = timeout;
+        Ok(self)
+    }
+
+    fn xadd(&mut self, msg: &Message) -> io::Result<()> {
+        if self.connection.is_none() {
+            self.connection = Some(Connection::open(&self.addr, self.timeout)?);
+            info!("Reconnected to Redis {}", self.addr);
+        }
+        let max_len = self.max_len.map(|max_len| max_len.to_string());
+        let market_type = msg.market_type.to_string();
+        let msg_type = msg.msg_type.to_string();
+        let received_at = msg.received_at.to_string();
+
+        let mut args = vec!["XADD", self.key.as_str()];
+        if let Some(max_len) = max_len.as_deref() {
+            args.extend(["MAXLEN", "~", max_len]);
+        }
+        args.extend(["*", "exchange", &msg.exchange, "market_type", &market_type]);
+        args.extend(["msg_type", &msg_type]);
+        if let Some(symbol) = msg.symbol.as_deref() {
+            args.extend(["symbol", symbol]);
+        }
+        args.extend(["received_at", &received_at, "json", &msg.json]);
+        self.connection.as_mut().unwrap().send(&args)?;
+        self.pending += 1;
+        Ok(())
+    }
+
+    fn read_replies(&mut self) -> io::Result<()> {
+        let connection = match self.connection.as_mut() {
+            Some(connection) => connection,
+            None => return Ok(()),
+        };
+        connection.writer.flush()?;
+        let mut first_error = None;
+        while self.pending > 0 {
+            if let Some(err) = connection.receive()? {
+                first_error.get_or_insert(err);
+            }
+            self.pending -= 1;
+        }
+        match first_error {
+            Some(err) => Err(io::Error::other(format!("Redis XADD failed, {err}"))),
+            None => Ok(()),
+        }
+    }
+
+    // Drops the connection after IO errors and timeouts, whose commands may be
+    // lost
+    fn check(&mut self, result: io::Result<()>) -> io::Result<()> {
+        if let Err(err) = result.as_ref() {
+            if err.kind() != io::ErrorKind::Other {
+                self.connection = None;
+                self.pending = 0;
+            }
+        }
+        result
+    }
+}
+
+impl Sink for RedisSink {
+    fn write(&mut self, msg: &Message) -> io::Result<()> {
+        let result = self.xadd(msg);
+        self.check(result)?;
+        if self.pending >= MAX_PENDING {
+            self.flush()?;
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        let result = self.read_replies();
+        self.check(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{BufRead, BufReader, Write},
+        time::Duration,
+    };
+
+    use crypto_market_type::MarketType;
+    use crypto_msg_type::MessageType;
+
+    use super::{Connection, RedisSink, SOCKET_TIMEOUT};
+    use crate::{sinks::Sink, Message};
+
+    fn trade() -> Message {
+        let mut msg = Message::new_with_symbol(
+            "binance".to_string(),
+            MarketType::Spot,
+            MessageType::Trade,
+            "BTCUSDT".to_string(),
+            r#"{"e":"trade"}"#.to_string(),
+        );
+        msg.received_at = 1679299200000;
+        msg
+    }
+
+    // Reads a command of the RESP protocol
+    fn read_command(reader: &mut impl BufRead) -> Vec<String> {
+        let mut line = String::new();
+        reader.read_line(&mut line).unwrap();
+        let n = line.trim_end()[1..].parse::<usize>().unwrap();
+        (0..n)
+            .map(|_| {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                let len = line.trim_end()[1..].parse::<usize>().unwrap();
+                let mut buf = vec![0; len + 2];
+                reader.read_exact(&mut buf).unwrap();
+                String::from_utf8(buf[..len].to_vec()).unwrap()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_xadd() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        let server = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let first = read_command(&mut reader);
+            let second = read_command(&mut reader);
+            stream.write_all(b"$15\r\n1679299200000-0\r\n-ERR wrong type\r\n").unwrap();
+            (first, second)
+        });
+
+        let msg = trade();
+        let mut sink = RedisSink::connect(&addr, "trades").unwrap().with_max_len(1000);
+        sink.write(&msg).unwrap();
+        sink.write(&msg).unwrap();
+        assert!(sink.flush().unwrap_err().to_string().contains("ERR wrong type"));
+
+        let (first, second) = server.join().unwrap();
+        assert_eq!(
+            vec![
+                "XADD",
+                "trades",
+                "MAXLEN",
+                "~",
+                "1000",
+                "*",
+                "exchange",
+                "binance",
+                "market_type",
+                "spot",
+                "msg_type",
+                "trade",
+                "symbol",
+                "BTCUSDT",
+                "received_at",
+                "1679299200000",
+                "json",
+                r#"{"e":"trade"}"#
+            ],
+            first
+        );
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn test_timeout() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        let server = std::thread::spawn(move || {
+            // never replies to the first connection
+            let (idle, _) = listener.accept().unwrap();
+            read_command(&mut BufReader::new(&idle));
+            let (mut stream, _) = listener.accept().unwrap();
+            read_command(&mut BufReader::new(stream.try_clone().unwrap()));
+            stream.write_all(b"$15\r\n1679299200000-0\r\n").unwrap();
+        });
+
+        let msg = trade();
+        let mut sink = RedisSink::connect(&addr, "trades")
+            .unwrap()
+            .with_timeout(Duration::from_millis(200))
+            .unwrap();
+        sink.write(&msg).unwrap();
+        let err = sink.flush().unwrap_err();
+        assert!(matches!(
+            err.kind(),
+            std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut
+        ));
+        assert!(sink.connection.is_none());
+
+        sink.write(&msg).unwrap();
+        sink.flush().unwrap();
+        server.join().unwrap();
+    }
+
+    // Sends a command and returns the first line of its reply
+    fn query(addr: &str, args: &[&str]) -> String {
+        let mut connection = Connection::open(addr, SOCKET_TIMEOUT).unwrap();
+        connection.send(args).unwrap();
+        connection.writer.flush().unwrap();
+        let mut line = String::new();
+        connection.reader.read_line(&mut line).unwrap();
+        line.trim_end().to_string()
+    }
+
+    // REDIS_ADDR=127.0.0.1:6379 cargo test -p crypto-crawler test_redis -- --ignored
+    #[test]
+    #[ignore]
+    fn test_redis() {
+        let addr = std::env::var("REDIS_ADDR").expect("REDIS_ADDR is not set");
+        let key = format!("crypto_crawler_test_{}", std::process::id());
+        query(&addr, &["DEL", &key]);
+
+        let msg = trade();
+        let mut sink = RedisSink::connect(&addr, &key).unwrap().with_max_len(100);
+        for _ in 0..1000 {
+            sink.write(&msg).unwrap();
+        }
+        sink.flush().unwrap();
+
+        // MAXLEN ~ removes whole macro nodes, so a few more entries are kept
+        let len = query(&addr, &["XLEN", &key])[1..].parse::<usize>().unwrap();
+        assert!((100..1000).contains(&len), "XLEN is {len}");
+        let entry = query(&addr, &["XRANGE", &key, "-", "+", "COUNT", "1"]);
+        assert_eq!("*1", entry);
+        assert_eq!(":1", query(&addr, &["DEL", &key]));
+    }
+}
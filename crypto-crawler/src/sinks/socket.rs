@@ -0,0 +1,165 @@
+use std::{
+    io::{self, BufWriter, Write},
+    net::TcpStream,
+};
+
+use log::*;
+
+use super::{connect_tcp, Sink, SOCKET_TIMEOUT};
+use crate::Message;
+
+// Writes newline-delimited JSON to a stream, reconnects on the next write
+// after a failure
+struct LineWriter<S: Write> {
+    peer: String,
+    connect: Box<dyn Fn() -> io::Result<S> + Send>,
+    writer: Option<BufWriter<S>>,
+}
+
+impl<S: Write> LineWriter<S> {
+    fn new(peer: String, connect: Box<dyn Fn() -> io::Result<S> + Send>) -> io::Result<Self> {
+        let stream = connect()?;
+        Ok(LineWriter { peer, connect, writer: Some(BufWriter::new(stream)) })
+    }
+
+    fn writer(&mut self) -> io::Result<&mut BufWriter<S>> {
+        if self.writer.is_none() {
+            let stream = (self.connect)()?;
+            info!("Reconnected to {}", self.peer);
+            self.writer = Some(BufWriter::new(stream));
+        }
+        Ok(self.writer.as_mut().unwrap())
+    }
+
+    fn write(&mut self, msg: &Message) -> io::Result<()> {
+        let result = self.writer().and_then(|writer| writeln!(writer, "{msg}"));
+        if result.is_err() {
+            // lines buffered since the last flush are lost
+            self.writer = None;
+        }
+        result
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        let result = match self.writer.as_mut() {
+            Some(writer) => writer.flush(),
+            None => Ok(()),
+        };
+        if result.is_err() {
+            self.writer = None;
+        }
+        result
+    }
+}
+
+/// Writes one JSON message per line to a TCP server.
+///
+/// Connecting and writing time out after 10 seconds. The connection is
+/// re-established on the next write after a failure, including a timeout.
+pub struct TcpSink(LineWriter<TcpStream>);
+
+impl TcpSink {
+    /// Connects to `addr`, e.g., `127.0.0.1:9000`.
+    pub fn connect(addr: &str) -> io::Result<Self> {
+        let peer = addr.to_string();
+        let connect = move || connect_tcp(&peer, SOCKET_TIMEOUT);
+        LineWriter::new(addr.to_string(), Box::new(connect)).map(TcpSink)
+    }
+}
+
+impl Sink for TcpSink {
+    fn write(&mut self, msg: &Message) -> io::Result<()> {
+        self.0.write(msg)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.0.flush()
+    }
+}
+
+/// Writes one JSON message per line to a Unix domain socket.
+///
+/// Writing times out after 10 seconds. The connection is re-established on
+/// the next write after a failure, including a timeout.
+#[cfg(unix)]
+pub struct UnixSink(LineWriter<std::os::unix::net::UnixStream>);
+
+#[cfg(unix)]
+impl UnixSink {
+    pub fn connect(path: impl AsRef<std::path::Path>) -> io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let peer = path.display().to_string();
+        let connect = move || {
+            let stream = std::os::unix::net::UnixStream::connect(&path)?;
+            stream.set_write_timeout(Some(SOCKET_TIMEOUT))?;
+            Ok(stream)
+        };
+        LineWriter::new(peer, Box::new(connect)).map(UnixSink)
+    }
+}
+
+#[cfg(unix)]
+impl Sink for UnixSink {
+    fn write(&mut self, msg: &Message) -> io::Result<()> {
+        self.0.write(msg)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.0.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{BufRead, BufReader};
+
+    use crypto_market_type::MarketType;
+    use crypto_msg_type::MessageType;
+
+    use super::TcpSink;
+    use crate::{sinks::Sink, Message};
+
+    #[test]
+    fn test_tcp() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        let msg = Message::new(
+            "binance".to_string(),
+            MarketType::Spot,
+            MessageType::Trade,
+            r#"{"e":"trade"}"#.to_string(),
+        );
+
+        let mut sink = TcpSink::connect(&addr).unwrap();
+        let (stream, _) = listener.accept().unwrap();
+        sink.write(&msg).unwrap();
+        sink.write(&msg).unwrap();
+        sink.flush().unwrap();
+
+        let lines = BufReader::new(stream).lines().take(2).map(|line| line.unwrap());
+        assert_eq!(vec![msg.to_string(), msg.to_string()], lines.collect::<Vec<String>>());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_unix() {
+        let path = std::env::temp_dir().join(format!("unix_sink_{}.sock", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let listener = std::os::unix::net::UnixListener::bind(&path).unwrap();
+        let msg = Message::new(
+            "okx".to_string(),
+            MarketType::LinearSwap,
+            MessageType::L2Event,
+            r#"{"arg":{}}"#.to_string(),
+        );
+
+        let mut sink = super::UnixSink::connect(&path).unwrap();
+        let (stream, _) = listener.accept().unwrap();
+        sink.write(&msg).unwrap();
+        sink.flush().unwrap();
+
+        let line = BufReader::new(stream).lines().next().unwrap().unwrap();
+        assert_eq!(msg.to_string(), line);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
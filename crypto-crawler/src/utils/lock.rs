@@ -122,11 +122,7 @@ fn get_lock_file_name(exchange: &str, market_type: MarketType, prefix: &str) ->
 }
 
 fn create_lock_file(filename: &str) -> LockFile {
-    let dir = if std::env::var("DATA_DIR").is_ok() {
-        std::path::Path::new(std::env::var("DATA_DIR").unwrap().as_str()).join("locks")
-    } else {
-        std::env::temp_dir().join("locks")
-    };
+    let dir = super::data_dir().unwrap_or_else(std::env::temp_dir).join("locks");
     let _ = std::fs::create_dir_all(&dir);
     let file_path = dir.join(filename);
     LockFile::open(file_path.as_path())
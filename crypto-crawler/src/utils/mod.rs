@@ -1,6 +1,9 @@
 pub(crate) mod cmc_rank;
 mod lock;
+mod settings;
 pub(crate) mod spot_symbols;
 
 pub(crate) use lock::{REST_LOCKS, WS_LOCKS};
+pub(crate) use settings::{data_dir, rest_retry_count, symbol_discovery_interval};
+pub use settings::{set_data_dir, set_rest_retry_count, set_symbol_discovery_interval};
 pub use spot_symbols::get_hot_spot_symbols;
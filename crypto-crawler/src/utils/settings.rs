@@ -0,0 +1,46 @@
+use std::{path::PathBuf, sync::Mutex, time::Duration};
+
+static REST_RETRY_COUNT: Mutex<Option<u32>> = Mutex::new(None);
+static DATA_DIR: Mutex<Option<PathBuf>> = Mutex::new(None);
+static SYMBOL_DISCOVERY_INTERVAL: Mutex<Duration> = Mutex::new(Duration::from_secs(3600));
+
+/// Sets how many times RESTful requests are tried, which overrides the
+/// `REST_RETRY_COUNT` environment variable.
+pub fn set_rest_retry_count(count: u32) {
+    *REST_RETRY_COUNT.lock().unwrap() = Some(count);
+}
+
+/// Sets the directory of lock files, which overrides the `DATA_DIR`
+/// environment variable.
+///
+/// Lock files are created by the first crawl, so call it before crawling.
+pub fn set_data_dir(dir: PathBuf) {
+    *DATA_DIR.lock().unwrap() = Some(dir);
+}
+
+/// Sets how often crawls of all symbols check listed and delisted symbols,
+/// hourly by default.
+///
+/// It applies to crawls started afterwards.
+pub fn set_symbol_discovery_interval(interval: Duration) {
+    assert!(!interval.is_zero(), "The symbol discovery interval must be positive");
+    *SYMBOL_DISCOVERY_INTERVAL.lock().unwrap() = interval;
+}
+
+pub(crate) fn symbol_discovery_interval() -> Duration {
+    *SYMBOL_DISCOVERY_INTERVAL.lock().unwrap()
+}
+
+pub(crate) fn rest_retry_count() -> u32 {
+    if let Some(count) = *REST_RETRY_COUNT.lock().unwrap() {
+        return count;
+    }
+    std::env::var("REST_RETRY_COUNT").map(|count| count.parse::<u32>().unwrap()).unwrap_or(5)
+}
+
+pub(crate) fn data_dir() -> Option<PathBuf> {
+    if let Some(dir) = DATA_DIR.lock().unwrap().clone() {
+        return Some(dir);
+    }
+    std::env::var("DATA_DIR").ok().map(PathBuf::from)
+}
@@ -15,24 +15,24 @@ async fn crawl_all(msg_type: MessageType) {
     tokio::task::spawn(async move {
         match msg_type {
             MessageType::Trade => {
-                crawl_trade(EXCHANGE_NAME, MarketType::Unknown, None, tx).await;
+                crawl_trade(EXCHANGE_NAME, MarketType::Unknown, None, tx).await.unwrap();
             }
             MessageType::L2Event => {
-                crawl_l2_event(EXCHANGE_NAME, MarketType::Unknown, None, tx).await;
+                crawl_l2_event(EXCHANGE_NAME, MarketType::Unknown, None, tx).await.unwrap();
             }
             MessageType::L2Snapshot => {
                 tokio::task::block_in_place(move || {
-                    crawl_l2_snapshot(EXCHANGE_NAME, MarketType::Unknown, None, tx);
+                    crawl_l2_snapshot(EXCHANGE_NAME, MarketType::Unknown, None, tx).unwrap();
                 });
             }
             MessageType::BBO => {
-                crawl_bbo(EXCHANGE_NAME, MarketType::Unknown, None, tx).await;
+                crawl_bbo(EXCHANGE_NAME, MarketType::Unknown, None, tx).await.unwrap();
             }
             MessageType::L2TopK => {
-                crawl_l2_topk(EXCHANGE_NAME, MarketType::Unknown, None, tx).await;
+                crawl_l2_topk(EXCHANGE_NAME, MarketType::Unknown, None, tx).await.unwrap();
             }
             MessageType::FundingRate => {
-                crawl_funding_rate(EXCHANGE_NAME, MarketType::Unknown, None, tx).await;
+                crawl_funding_rate(EXCHANGE_NAME, MarketType::Unknown, None, tx).await.unwrap();
             }
             _ => panic!("unsupported message type {msg_type}"),
         };
@@ -79,7 +79,7 @@ async fn test_crawl_funding_rate_all() {
 async fn test_crawl_candlestick_rate_all() {
     let (tx, rx) = std::sync::mpsc::channel();
     tokio::task::spawn(async move {
-        crawl_candlestick(EXCHANGE_NAME, MarketType::Unknown, None, tx).await;
+        crawl_candlestick(EXCHANGE_NAME, MarketType::Unknown, None, tx).await.unwrap();
     });
 
     let msg = rx.recv().unwrap();
@@ -106,7 +106,7 @@ async fn test_crawl_funding_rate(market_type: MarketType, symbol: &str) {
 fn test_crawl_open_interest(market_type: MarketType) {
     let (tx, rx) = std::sync::mpsc::channel();
     std::thread::spawn(move || {
-        crawl_open_interest(EXCHANGE_NAME, market_type, tx);
+        crawl_open_interest(EXCHANGE_NAME, market_type, tx).unwrap();
     });
 
     let msg = rx.recv().unwrap();
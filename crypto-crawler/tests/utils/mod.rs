@@ -41,7 +41,7 @@ macro_rules! test_one_symbol {
         let (tx, rx) = std::sync::mpsc::channel();
         let symbols = vec![$symbol.to_string()];
         tokio::task::spawn(async move {
-            $crawl_func($exchange, $market_type, Some(&symbols), tx).await;
+            $crawl_func($exchange, $market_type, Some(&symbols), tx).await.unwrap();
         });
 
         let msg = rx.recv().unwrap();
@@ -59,7 +59,7 @@ macro_rules! test_all_symbols {
     ($crawl_func:ident, $exchange:expr, $market_type:expr, $msg_type:expr) => {{
         let (tx, rx) = std::sync::mpsc::channel();
         tokio::task::spawn(async move {
-            $crawl_func($exchange, $market_type, None, tx).await;
+            $crawl_func($exchange, $market_type, None, tx).await.unwrap();
         });
 
         let msg = rx.recv().unwrap();
@@ -78,7 +78,7 @@ macro_rules! test_crawl_restful {
         let (tx, rx) = std::sync::mpsc::channel();
         let symbols = vec![$symbol.to_string()];
         std::thread::spawn(move || {
-            $crawl_func($exchange, $market_type, Some(&symbols), tx);
+            $crawl_func($exchange, $market_type, Some(&symbols), tx).unwrap();
         });
 
         let msg = rx.recv().unwrap();
@@ -96,7 +96,7 @@ macro_rules! test_crawl_restful_all_symbols {
     ($crawl_func:ident, $exchange:expr, $market_type:expr, $msg_type:expr) => {{
         let (tx, rx) = std::sync::mpsc::channel();
         std::thread::spawn(move || {
-            $crawl_func($exchange, $market_type, None, tx);
+            $crawl_func($exchange, $market_type, None, tx).unwrap();
         });
 
         let msg = rx.recv().unwrap();
@@ -114,7 +114,7 @@ macro_rules! gen_test_crawl_candlestick {
     ($exchange:expr, $market_type:expr) => {{
         let (tx, rx) = std::sync::mpsc::channel();
         tokio::task::spawn(async move {
-            crawl_candlestick($exchange, $market_type, None, tx).await;
+            crawl_candlestick($exchange, $market_type, None, tx).await.unwrap();
         });
 
         let msg = rx.recv().unwrap();
@@ -133,7 +133,7 @@ macro_rules! gen_test_subscribe_symbol {
         let (tx, rx) = std::sync::mpsc::channel();
         tokio::task::spawn(async move {
             let msg_types = vec![MessageType::Trade, MessageType::L2Event];
-            subscribe_symbol($exchange, $market_type, $symbol, &msg_types, tx).await;
+            subscribe_symbol($exchange, $market_type, $symbol, &msg_types, tx).await.unwrap();
         });
 
         let mut messages = Vec::new();
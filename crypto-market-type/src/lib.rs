@@ -81,7 +81,14 @@ pub fn get_market_types(exchange: &str) -> Vec<MarketType> {
         ],
         "bitstamp" => vec![MarketType::Spot],
         "bitz" => vec![MarketType::Spot, MarketType::InverseSwap, MarketType::LinearSwap],
-        "bybit" => vec![MarketType::InverseSwap, MarketType::LinearSwap, MarketType::InverseFuture],
+        "bybit" => vec![
+            MarketType::Spot,
+            MarketType::InverseSwap,
+            MarketType::LinearSwap,
+            MarketType::InverseFuture,
+            MarketType::EuropeanOption,
+        ],
+        "coinbase" => vec![MarketType::Spot],
         "coinbase_pro" => vec![MarketType::Spot],
         // Deribit only accepts Bitcoin as funds to deposit.
         "deribit" => vec![
@@ -1,7 +1,10 @@
 use std::collections::HashMap;
 
 use super::utils::http_get;
-use crate::{error::Result, Fees, Market, MarketType, Precision, QuantityLimit};
+use crate::{
+    error::{Error, Result},
+    Fees, Market, MarketType, Precision, QuantityLimit,
+};
 
 use chrono::{prelude::*, DateTime};
 use serde::{Deserialize, Serialize};
@@ -12,6 +15,8 @@ pub(crate) fn fetch_symbols(market_type: MarketType) -> Result<Vec<String>> {
         MarketType::InverseSwap => fetch_inverse_swap_symbols(),
         MarketType::LinearSwap => fetch_linear_swap_symbols(),
         MarketType::InverseFuture => fetch_inverse_future_symbols(),
+        MarketType::Spot => fetch_spot_symbols(),
+        MarketType::EuropeanOption => fetch_option_symbols(),
         _ => panic!("Unsupported market_type: {market_type}"),
     }
 }
@@ -21,6 +26,8 @@ pub(crate) fn fetch_markets(market_type: MarketType) -> Result<Vec<Market>> {
         MarketType::InverseSwap => fetch_inverse_swap_markets(),
         MarketType::LinearSwap => fetch_linear_swap_markets(),
         MarketType::InverseFuture => fetch_inverse_future_markets(),
+        MarketType::Spot => fetch_spot_markets(),
+        MarketType::EuropeanOption => fetch_option_markets(),
         _ => panic!("Unsupported market_type: {market_type}"),
     }
 }
@@ -214,3 +221,210 @@ fn fetch_inverse_future_markets() -> Result<Vec<Market>> {
         .collect::<Vec<Market>>();
     Ok(markets)
 }
+
+// Bybit v5 instruments, see https://bybit-exchange.github.io/docs/v5/market/instrument
+
+#[derive(Serialize, Deserialize)]
+#[allow(non_snake_case)]
+struct V5PriceFilter {
+    tickSize: String,
+    #[serde(flatten)]
+    extra: HashMap<String, Value>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[allow(non_snake_case)]
+struct V5SpotLotSizeFilter {
+    basePrecision: String,
+    minOrderQty: String,
+    maxOrderQty: String,
+    minOrderAmt: String,
+    maxOrderAmt: String,
+    #[serde(flatten)]
+    extra: HashMap<String, Value>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[allow(non_snake_case)]
+struct V5SpotMarket {
+    symbol: String,
+    baseCoin: String,
+    quoteCoin: String,
+    status: String,
+    marginTrading: String,
+    lotSizeFilter: V5SpotLotSizeFilter,
+    priceFilter: V5PriceFilter,
+    #[serde(flatten)]
+    extra: HashMap<String, Value>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[allow(non_snake_case)]
+struct V5OptionLotSizeFilter {
+    minOrderQty: String,
+    maxOrderQty: String,
+    qtyStep: String,
+    #[serde(flatten)]
+    extra: HashMap<String, Value>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[allow(non_snake_case)]
+struct V5OptionMarket {
+    symbol: String,
+    baseCoin: String,
+    quoteCoin: String,
+    settleCoin: String,
+    status: String,
+    optionsType: String,
+    deliveryTime: String,
+    lotSizeFilter: V5OptionLotSizeFilter,
+    priceFilter: V5PriceFilter,
+    #[serde(flatten)]
+    extra: HashMap<String, Value>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[allow(non_snake_case)]
+struct V5Result<T> {
+    category: String,
+    list: Vec<T>,
+    #[serde(default)]
+    nextPageCursor: String,
+}
+
+#[derive(Serialize, Deserialize)]
+#[allow(non_snake_case)]
+struct V5Response<T> {
+    retCode: i64,
+    retMsg: String,
+    result: V5Result<T>,
+}
+
+// Options are listed per base coin, BTC is returned if baseCoin is absent
+const V5_OPTION_BASE_COINS: [&str; 3] = ["BTC", "ETH", "SOL"];
+
+fn fetch_v5_instruments<T: serde::de::DeserializeOwned>(
+    category: &str,
+    base_coin: Option<&str>,
+) -> Result<Vec<T>> {
+    let mut instruments = Vec::<T>::new();
+    let mut cursor = String::new();
+    loop {
+        let mut params = HashMap::from([
+            ("category".to_string(), category.to_string()),
+            ("limit".to_string(), "1000".to_string()),
+        ]);
+        if let Some(base_coin) = base_coin {
+            params.insert("baseCoin".to_string(), base_coin.to_string());
+        }
+        if !cursor.is_empty() {
+            params.insert("cursor".to_string(), cursor.clone());
+        }
+        let txt = http_get("https://api.bybit.com/v5/market/instruments-info", Some(&params))?;
+        let resp = serde_json::from_str::<V5Response<T>>(&txt)?;
+        if resp.retCode != 0 {
+            return Err(Error(txt));
+        }
+        instruments.extend(resp.result.list);
+        if resp.result.nextPageCursor.is_empty() {
+            break;
+        }
+        cursor = resp.result.nextPageCursor;
+    }
+    Ok(instruments)
+}
+
+fn fetch_spot_markets_raw() -> Result<Vec<V5SpotMarket>> {
+    let markets = fetch_v5_instruments::<V5SpotMarket>("spot", None)?;
+    Ok(markets.into_iter().filter(|m| m.status == "Trading").collect())
+}
+
+fn fetch_option_markets_raw() -> Result<Vec<V5OptionMarket>> {
+    let mut markets = Vec::<V5OptionMarket>::new();
+    for base_coin in V5_OPTION_BASE_COINS {
+        markets.extend(fetch_v5_instruments::<V5OptionMarket>("option", Some(base_coin))?);
+    }
+    Ok(markets.into_iter().filter(|m| m.status == "Trading").collect())
+}
+
+fn fetch_spot_symbols() -> Result<Vec<String>> {
+    let symbols = fetch_spot_markets_raw()?.into_iter().map(|m| m.symbol).collect::<Vec<String>>();
+    Ok(symbols)
+}
+
+fn fetch_option_symbols() -> Result<Vec<String>> {
+    let symbols =
+        fetch_option_markets_raw()?.into_iter().map(|m| m.symbol).collect::<Vec<String>>();
+    Ok(symbols)
+}
+
+fn fetch_spot_markets() -> Result<Vec<Market>> {
+    let markets = fetch_spot_markets_raw()?
+        .into_iter()
+        .map(|m| Market {
+            exchange: "bybit".to_string(),
+            market_type: MarketType::Spot,
+            symbol: m.symbol.clone(),
+            base_id: m.baseCoin.clone(),
+            quote_id: m.quoteCoin.clone(),
+            settle_id: None,
+            base: crypto_pair::normalize_currency(&m.baseCoin, "bybit"),
+            quote: crypto_pair::normalize_currency(&m.quoteCoin, "bybit"),
+            settle: None,
+            active: m.status == "Trading",
+            margin: m.marginTrading != "none",
+            // see https://www.bybit.com/en/help-center/article/Trading-Fee-Structure
+            fees: Fees { maker: 0.001, taker: 0.001 },
+            precision: Precision {
+                tick_size: m.priceFilter.tickSize.parse::<f64>().unwrap(),
+                lot_size: m.lotSizeFilter.basePrecision.parse::<f64>().unwrap(),
+            },
+            quantity_limit: Some(QuantityLimit {
+                min: m.lotSizeFilter.minOrderQty.parse::<f64>().ok(),
+                max: m.lotSizeFilter.maxOrderQty.parse::<f64>().ok(),
+                notional_min: m.lotSizeFilter.minOrderAmt.parse::<f64>().ok(),
+                notional_max: m.lotSizeFilter.maxOrderAmt.parse::<f64>().ok(),
+            }),
+            contract_value: None,
+            delivery_date: None,
+            info: serde_json::to_value(&m).unwrap().as_object().unwrap().clone(),
+        })
+        .collect::<Vec<Market>>();
+    Ok(markets)
+}
+
+fn fetch_option_markets() -> Result<Vec<Market>> {
+    let markets = fetch_option_markets_raw()?
+        .into_iter()
+        .map(|m| Market {
+            exchange: "bybit".to_string(),
+            market_type: MarketType::EuropeanOption,
+            symbol: m.symbol.clone(),
+            base_id: m.baseCoin.clone(),
+            quote_id: m.quoteCoin.clone(),
+            settle_id: Some(m.settleCoin.clone()),
+            base: crypto_pair::normalize_currency(&m.baseCoin, "bybit"),
+            quote: crypto_pair::normalize_currency(&m.quoteCoin, "bybit"),
+            settle: Some(crypto_pair::normalize_currency(&m.settleCoin, "bybit")),
+            active: m.status == "Trading",
+            margin: true,
+            // see https://www.bybit.com/en/help-center/article/Trading-Fee-Structure
+            fees: Fees { maker: 0.0002, taker: 0.0003 },
+            precision: Precision {
+                tick_size: m.priceFilter.tickSize.parse::<f64>().unwrap(),
+                lot_size: m.lotSizeFilter.qtyStep.parse::<f64>().unwrap(),
+            },
+            quantity_limit: Some(QuantityLimit {
+                min: m.lotSizeFilter.minOrderQty.parse::<f64>().ok(),
+                max: m.lotSizeFilter.maxOrderQty.parse::<f64>().ok(),
+                notional_min: None,
+                notional_max: None,
+            }),
+            contract_value: Some(1.0),
+            delivery_date: m.deliveryTime.parse::<u64>().ok(),
+            info: serde_json::to_value(&m).unwrap().as_object().unwrap().clone(),
+        })
+        .collect::<Vec<Market>>();
+    Ok(markets)
+}
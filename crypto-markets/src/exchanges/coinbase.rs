@@ -0,0 +1,112 @@
+use std::collections::HashMap;
+
+use super::utils::http_get;
+use crate::{error::Result, Fees, Market, MarketType, Precision, QuantityLimit};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+pub(crate) fn fetch_symbols(market_type: MarketType) -> Result<Vec<String>> {
+    match market_type {
+        MarketType::Spot => fetch_spot_symbols(),
+        _ => panic!("Unsupported market_type: {market_type}"),
+    }
+}
+
+pub(crate) fn fetch_markets(market_type: MarketType) -> Result<Vec<Market>> {
+    match market_type {
+        MarketType::Spot => fetch_spot_markets(),
+        _ => panic!("Unsupported market_type: {market_type}"),
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct SpotMarket {
+    product_id: String,
+    base_currency_id: String,
+    quote_currency_id: String,
+    base_increment: String,
+    quote_increment: String,
+    price_increment: String,
+    base_min_size: String,
+    base_max_size: String,
+    quote_min_size: String,
+    quote_max_size: String,
+    status: String,
+    cancel_only: bool,
+    trading_disabled: bool,
+    product_type: String,
+    #[serde(flatten)]
+    extra: HashMap<String, Value>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ProductsResponse {
+    products: Vec<SpotMarket>,
+    #[serde(flatten)]
+    extra: HashMap<String, Value>,
+}
+
+impl SpotMarket {
+    fn is_active(&self) -> bool {
+        !self.trading_disabled && self.status == "online" && !self.cancel_only
+    }
+}
+
+// see <https://docs.cdp.coinbase.com/advanced-trade/reference/retailbrokerageapi_getpublicproducts>
+fn fetch_spot_markets_raw() -> Result<Vec<SpotMarket>> {
+    let txt = http_get(
+        "https://api.coinbase.com/api/v3/brokerage/market/products?product_type=SPOT",
+        None,
+    )?;
+    let resp = serde_json::from_str::<ProductsResponse>(&txt)?;
+    Ok(resp.products.into_iter().filter(|m| m.product_type == "SPOT").collect())
+}
+
+fn fetch_spot_symbols() -> Result<Vec<String>> {
+    let symbols = fetch_spot_markets_raw()?
+        .into_iter()
+        .filter(|m| m.is_active())
+        .map(|m| m.product_id)
+        .collect::<Vec<String>>();
+    Ok(symbols)
+}
+
+fn fetch_spot_markets() -> Result<Vec<Market>> {
+    let markets = fetch_spot_markets_raw()?
+        .into_iter()
+        .map(|m| {
+            let info = serde_json::to_value(&m).unwrap().as_object().unwrap().clone();
+            let active = m.is_active();
+            Market {
+                exchange: "coinbase".to_string(),
+                market_type: MarketType::Spot,
+                symbol: m.product_id,
+                base: m.base_currency_id.to_uppercase(),
+                quote: m.quote_currency_id.to_uppercase(),
+                base_id: m.base_currency_id,
+                quote_id: m.quote_currency_id,
+                settle_id: None,
+                settle: None,
+                active,
+                margin: false,
+                // see https://help.coinbase.com/en/coinbase/trading-and-funding/advanced-trade/advanced-trade-fees
+                fees: Fees { maker: 0.006, taker: 0.008 },
+                precision: Precision {
+                    tick_size: m.price_increment.parse::<f64>().unwrap(),
+                    lot_size: m.base_increment.parse::<f64>().unwrap(),
+                },
+                quantity_limit: Some(QuantityLimit {
+                    min: m.base_min_size.parse::<f64>().ok(),
+                    max: m.base_max_size.parse::<f64>().ok(),
+                    notional_min: m.quote_min_size.parse::<f64>().ok(),
+                    notional_max: m.quote_max_size.parse::<f64>().ok(),
+                }),
+                contract_value: None,
+                delivery_date: None,
+                info,
+            }
+        })
+        .collect::<Vec<Market>>();
+    Ok(markets)
+}
@@ -7,20 +7,18 @@ use crypto_market_type::MarketType;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
-const BASE_URL: &str = "https://api.dydx.exchange";
+const BASE_URL: &str = "https://indexer.dydx.trade";
 
 #[derive(Clone, Serialize, Deserialize)]
 #[allow(non_snake_case)]
 struct PerpetualMarket {
-    market: String,
+    clobPairId: String,
+    ticker: String,
     status: String,
-    baseAsset: String,
-    quoteAsset: String,
-    stepSize: String,
     tickSize: String,
-    minOrderSize: String,
-    #[serde(rename = "type")]
-    type_: String,
+    stepSize: String,
+    initialMarginFraction: String,
+    maintenanceMarginFraction: String,
     #[serde(flatten)]
     extra: HashMap<String, Value>,
 }
@@ -31,21 +29,24 @@ struct MarketsResponse {
     markets: HashMap<String, PerpetualMarket>,
 }
 
-// See https://docs.dydx.exchange/#get-markets
+// See https://docs.dydx.exchange/api_integration-indexer/indexer_api#listperpetualmarkets
+//
+// `DYDX_INDEXER_URL` overrides the public indexer, e.g., a self-hosted one.
 fn fetch_markets_raw() -> Result<Vec<PerpetualMarket>> {
-    let txt = http_get(format!("{BASE_URL}/v3/markets").as_str(), None)?;
+    let base_url = std::env::var("DYDX_INDEXER_URL").unwrap_or_else(|_| BASE_URL.to_string());
+    let txt = http_get(format!("{base_url}/v4/perpetualMarkets").as_str(), None)?;
     let resp = serde_json::from_str::<MarketsResponse>(&txt)?;
     Ok(resp
         .markets
         .values()
         .cloned()
-        .filter(|x| x.status == "ONLINE")
+        .filter(|x| x.status == "ACTIVE")
         .collect::<Vec<PerpetualMarket>>())
 }
 
 pub(super) fn fetch_linear_swap_symbols() -> Result<Vec<String>> {
     let markets = fetch_markets_raw()?;
-    let symbols = markets.into_iter().map(|m| m.market).collect::<Vec<String>>();
+    let symbols = markets.into_iter().map(|m| m.ticker).collect::<Vec<String>>();
     Ok(symbols)
 }
 
@@ -54,31 +55,40 @@ pub(super) fn fetch_linear_swap_markets() -> Result<Vec<Market>> {
         .into_iter()
         .map(|m| {
             let info = serde_json::to_value(&m).unwrap().as_object().unwrap().clone();
-            let pair = crypto_pair::normalize_pair(&m.market, "dydx").unwrap();
+            let pair = crypto_pair::normalize_pair(&m.ticker, "dydx").unwrap();
             let (base, quote) = {
                 let v: Vec<&str> = pair.split('/').collect();
                 (v[0].to_string(), v[1].to_string())
             };
+            let (base_id, quote_id) = {
+                let v: Vec<&str> = m.ticker.split('-').collect();
+                (v[0].to_string(), v[1].to_string())
+            };
+            let step_size = m.stepSize.parse::<f64>().unwrap();
             Market {
                 exchange: "dydx".to_string(),
                 market_type: MarketType::LinearSwap,
-                symbol: m.market,
-                base_id: m.baseAsset,
-                quote_id: m.quoteAsset,
-                settle_id: Some(quote.clone()),
+                symbol: m.ticker,
+                base_id,
+                quote_id,
+                // All perpetual markets are margined and settled in USDC
+                settle_id: Some("USDC".to_string()),
                 base,
-                quote: quote.clone(),
-                settle: Some(quote),
-                active: m.status == "ONLINE",
+                quote,
+                settle: Some("USDC".to_string()),
+                active: m.status == "ACTIVE",
                 margin: true,
-                // see https://trade.dydx.exchange/portfolio/fees
-                fees: Fees { maker: 0.0005, taker: 0.0001 },
+                // see https://dydx.trade/portfolio/fees
+                fees: Fees { maker: 0.0001, taker: 0.0005 },
                 precision: Precision {
                     tick_size: m.tickSize.parse::<f64>().unwrap(),
-                    lot_size: m.stepSize.parse::<f64>().unwrap(),
+                    lot_size: step_size,
                 },
+                // v4 dropped the `minOrderSize` field of v3, the protocol
+                // only requires a quantity in multiples of `stepBaseQuantums`,
+                // so the smallest order is one step
                 quantity_limit: Some(QuantityLimit {
-                    min: m.minOrderSize.parse::<f64>().ok(),
+                    min: Some(step_size),
                     max: None,
                     notional_min: None,
                     notional_max: None,
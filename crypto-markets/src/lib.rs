@@ -32,6 +32,7 @@ pub fn fetch_symbols(exchange: &str, market_type: MarketType) -> Result<Vec<Stri
         "bitstamp" => exchanges::bitstamp::fetch_symbols(market_type),
         "bitz" => exchanges::bitz::fetch_symbols(market_type),
         "bybit" => exchanges::bybit::fetch_symbols(market_type),
+        "coinbase" => exchanges::coinbase::fetch_symbols(market_type),
         "coinbase_pro" => exchanges::coinbase_pro::fetch_symbols(market_type),
         "deribit" => exchanges::deribit::fetch_symbols(market_type),
         "dydx" => exchanges::dydx::fetch_symbols(market_type),
@@ -74,6 +75,7 @@ pub fn fetch_markets(exchange: &str, market_type: MarketType) -> Result<Vec<Mark
         "bitstamp" => exchanges::bitstamp::fetch_markets(market_type),
         "bitz" => exchanges::bitz::fetch_markets(market_type),
         "bybit" => exchanges::bybit::fetch_markets(market_type),
+        "coinbase" => exchanges::coinbase::fetch_markets(market_type),
         "coinbase_pro" => exchanges::coinbase_pro::fetch_markets(market_type),
         "deribit" => exchanges::deribit::fetch_markets(market_type),
         "dydx" => exchanges::dydx::fetch_markets(market_type),
@@ -44,6 +44,48 @@ fn fetch_inverse_future_symbols() {
     }
 }
 
+#[test]
+fn fetch_spot_symbols() {
+    let symbols = fetch_symbols(EXCHANGE_NAME, MarketType::Spot).unwrap();
+    assert!(!symbols.is_empty());
+    assert!(symbols.contains(&"BTCUSDT".to_string()));
+    for symbol in symbols.iter() {
+        assert!(!symbol.contains('-'));
+    }
+}
+
+#[test]
+fn fetch_option_symbols() {
+    let symbols = fetch_symbols(EXCHANGE_NAME, MarketType::EuropeanOption).unwrap();
+    assert!(!symbols.is_empty());
+    for symbol in symbols.iter() {
+        assert!(symbol.ends_with("-C") || symbol.ends_with("-P"));
+    }
+}
+
+#[test]
+fn fetch_spot_markets() {
+    let markets = fetch_markets(EXCHANGE_NAME, MarketType::Spot).unwrap();
+    assert!(!markets.is_empty());
+
+    let btcusdt = markets.iter().find(|m| m.symbol == "BTCUSDT").unwrap().clone();
+    assert_eq!(btcusdt.base, "BTC");
+    assert_eq!(btcusdt.quote, "USDT");
+    assert!(btcusdt.settle.is_none());
+    assert!(btcusdt.contract_value.is_none());
+    assert_eq!(btcusdt.precision.tick_size, 0.01);
+}
+
+#[test]
+fn fetch_option_markets() {
+    let markets = fetch_markets(EXCHANGE_NAME, MarketType::EuropeanOption).unwrap();
+    assert!(!markets.is_empty());
+    for market in markets.iter() {
+        assert!(market.delivery_date.is_some());
+        assert_eq!(market.settle, Some("USDC".to_string()));
+    }
+}
+
 #[test]
 fn fetch_inverse_swap_markets() {
     let markets = fetch_markets(EXCHANGE_NAME, MarketType::InverseSwap).unwrap();
@@ -33,7 +33,8 @@ fn fetch_linear_swap_markets() {
     assert_eq!(btcusd.precision.tick_size, 1.0);
     assert_eq!(btcusd.precision.lot_size, 0.0001);
     let quantity_limit = btcusd.quantity_limit.unwrap();
-    assert_eq!(quantity_limit.min.unwrap(), 0.001);
+    // the minimum order of v4 is one step, v3 had a separate minOrderSize of 0.001
+    assert_eq!(quantity_limit.min.unwrap(), 0.0001);
     assert_eq!(quantity_limit.max, None);
 }
 
@@ -0,0 +1,42 @@
+use super::utils::http_get;
+use crate::error::Result;
+use std::collections::BTreeMap;
+
+const BASE_URL: &str = "https://api.coinbase.com/api/v3/brokerage/market";
+
+/// The REST client for Coinbase Advanced Trade, which replaces CoinbasePro.
+///
+/// Coinbase Advanced Trade has only Spot market.
+///
+///   * REST API doc: <https://docs.cdp.coinbase.com/advanced-trade/reference>
+///   * Trading at: <https://www.coinbase.com/advanced-trade>
+///   * Rate Limits: <https://docs.cdp.coinbase.com/advanced-trade/docs/rest-api-rate-limits>
+///   * Public endpoints are limited to 10 requests per second per IP
+pub struct CoinbaseRestClient {
+    _api_key: Option<String>,
+    _api_secret: Option<String>,
+}
+
+impl CoinbaseRestClient {
+    pub fn new(api_key: Option<String>, api_secret: Option<String>) -> Self {
+        CoinbaseRestClient { _api_key: api_key, _api_secret: api_secret }
+    }
+
+    /// List the latest trades for a product.
+    ///
+    /// `/products/{symbol}/ticker`
+    ///
+    /// For example: <https://api.coinbase.com/api/v3/brokerage/market/products/BTC-USD/ticker?limit=100>
+    pub fn fetch_trades(symbol: &str) -> Result<String> {
+        gen_api!(format!("/products/{symbol}/ticker?limit=100"))
+    }
+
+    /// Get the latest Level2 orderbook snapshot.
+    ///
+    /// Top 1000 bids and asks (aggregated) are returned.
+    ///
+    /// For example: <https://api.coinbase.com/api/v3/brokerage/market/product_book?product_id=BTC-USD&limit=1000>
+    pub fn fetch_l2_snapshot(symbol: &str) -> Result<String> {
+        gen_api!(format!("/product_book?product_id={symbol}&limit=1000"))
+    }
+}
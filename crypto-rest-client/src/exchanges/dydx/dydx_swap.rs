@@ -2,13 +2,21 @@ use super::super::utils::http_get;
 use crate::error::Result;
 use std::collections::BTreeMap;
 
-const BASE_URL: &str = "https://api.dydx.exchange";
+const BASE_URL: &str = "https://indexer.dydx.trade";
 
-/// dYdX perpetual RESTful client.
+/// The indexer endpoint, `DYDX_INDEXER_URL` overrides the public indexer so
+/// that a self-hosted indexer can be used.
+fn indexer_url() -> String {
+    std::env::var("DYDX_INDEXER_URL").unwrap_or_else(|_| BASE_URL.to_string())
+}
+
+/// dYdX v4 perpetual RESTful client.
+///
+/// Market data is served by the indexer.
 ///
-/// * REST API doc: <https://docs.dydx.exchange/>
-/// * Trading at: <https://trade.dydx.exchange/trade>
-/// * Rate Limits: <https://docs.dydx.exchange/#rate-limits>
+/// * REST API doc: <https://docs.dydx.exchange/api_integration-indexer/indexer_api>
+/// * Trading at: <https://dydx.trade/trade/BTC-USD>
+/// * Rate Limits: <https://docs.dydx.exchange/api_integration-indexer/indexer_api#rate-limiting>
 ///   * 100 requests per 10 seconds
 pub struct DydxSwapRestClient {
     _api_key: Option<String>,
@@ -20,19 +28,33 @@ impl DydxSwapRestClient {
         DydxSwapRestClient { _api_key: api_key, _api_secret: api_secret }
     }
 
+    /// Get the latest trades.
+    ///
+    /// For example: <https://indexer.dydx.trade/v4/trades/perpetualMarket/BTC-USD>
+    pub fn fetch_trades(symbol: &str) -> Result<String> {
+        gen_api!(format!("{}/v4/trades/perpetualMarket/{symbol}", indexer_url()))
+    }
+
     /// Get a Level2 orderbook snapshot.
     ///
     /// All price levels are returned.
     ///
-    /// For example: <https://api.dydx.exchange/v3/orderbook/BTC-USD>
+    /// For example: <https://indexer.dydx.trade/v4/orderbooks/perpetualMarket/BTC-USD>
     pub fn fetch_l2_snapshot(symbol: &str) -> Result<String> {
-        gen_api!(format!("/v3/orderbook/{symbol}"))
+        gen_api!(format!("{}/v4/orderbooks/perpetualMarket/{symbol}", indexer_url()))
     }
 
     /// Get open interest.
     ///
-    /// For example: <https://api.dydx.exchange/v3/markets>
+    /// For example: <https://indexer.dydx.trade/v4/perpetualMarkets>
     pub fn fetch_open_interest() -> Result<String> {
-        gen_api!("/v3/markets")
+        gen_api!(format!("{}/v4/perpetualMarkets", indexer_url()))
+    }
+
+    /// Get historical funding rates.
+    ///
+    /// For example: <https://indexer.dydx.trade/v4/historicalFunding/BTC-USD>
+    pub fn fetch_historical_funding(symbol: &str) -> Result<String> {
+        gen_api!(format!("{}/v4/historicalFunding/{symbol}", indexer_url()))
     }
 }
@@ -22,10 +22,11 @@ impl GateSpotRestClient {
 
     /// Get the latest Level2 snapshot of orderbook.
     ///
-    /// Top 1000 asks and bids are returned.
+    /// Top 1000 asks and bids are returned, `id` is the ID of the last update
+    /// applied, which aligns with `U` and `u` of websocket `order_book_update`.
     ///
-    /// For example: <https://api.gateio.ws/api/v4/spot/order_book?currency_pair=BTC_USDT&limit=1000>,
+    /// For example: <https://api.gateio.ws/api/v4/spot/order_book?currency_pair=BTC_USDT&limit=1000&with_id=true>,
     pub fn fetch_l2_snapshot(symbol: &str) -> Result<String> {
-        gen_api!(format!("/spot/order_book?currency_pair={symbol}&limit=1000"))
+        gen_api!(format!("/spot/order_book?currency_pair={symbol}&limit=1000&with_id=true"))
     }
 }
@@ -22,12 +22,13 @@ impl GateSwapRestClient {
 
     /// Get the latest Level2 snapshot of orderbook.
     ///
-    /// Top 200 asks and bids are returned.
+    /// Top 200 asks and bids are returned, `id` is the ID of the last update
+    /// applied, which aligns with `U` and `u` of websocket `order_book_update`.
     ///
     /// For example:
     ///
-    /// - <https://api.gateio.ws/api/v4/futures/btc/order_book?contract=BTC_USD&limit=200>
-    /// - <https://api.gateio.ws/api/v4/futures/usdt/order_book?contract=BTC_USDT&limit=200>
+    /// - <https://api.gateio.ws/api/v4/futures/btc/order_book?contract=BTC_USD&limit=200&with_id=true>
+    /// - <https://api.gateio.ws/api/v4/futures/usdt/order_book?contract=BTC_USDT&limit=200&with_id=true>
     pub fn fetch_l2_snapshot(symbol: &str) -> Result<String> {
         let settle = if symbol.ends_with("_USD") {
             "btc"
@@ -36,7 +37,7 @@ impl GateSwapRestClient {
         } else {
             panic!("Unknown symbol {symbol}");
         };
-        gen_api!(format!("/futures/{settle}/order_book?contract={symbol}&limit=200"))
+        gen_api!(format!("/futures/{settle}/order_book?contract={symbol}&limit=200&with_id=true"))
     }
 
     /// Get open interest.
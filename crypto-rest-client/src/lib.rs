@@ -14,6 +14,7 @@ pub use exchanges::{
     bitstamp::BitstampRestClient,
     bitz::*,
     bybit::BybitRestClient,
+    coinbase::CoinbaseRestClient,
     coinbase_pro::CoinbaseProRestClient,
     deribit::DeribitRestClient,
     dydx::dydx_swap::DydxSwapRestClient,
@@ -51,6 +52,7 @@ fn fetch_l2_snapshot_internal(
         "bitstamp" => exchanges::bitstamp::BitstampRestClient::fetch_l2_snapshot(symbol),
         "bitz" => exchanges::bitz::fetch_l2_snapshot(market_type, symbol),
         "bybit" => exchanges::bybit::BybitRestClient::fetch_l2_snapshot(symbol),
+        "coinbase" => exchanges::coinbase::CoinbaseRestClient::fetch_l2_snapshot(symbol),
         "coinbase_pro" => exchanges::coinbase_pro::CoinbaseProRestClient::fetch_l2_snapshot(symbol),
         "deribit" => exchanges::deribit::DeribitRestClient::fetch_l2_snapshot(symbol),
         "dydx" => exchanges::dydx::fetch_l2_snapshot(market_type, symbol),
@@ -0,0 +1,14 @@
+use crypto_market_type::MarketType;
+use crypto_rest_client::{fetch_l2_snapshot, CoinbaseRestClient};
+
+#[test]
+fn test_trades() {
+    let text = CoinbaseRestClient::fetch_trades("BTC-USD").unwrap();
+    assert!(text.starts_with("{\"trades\":[{"));
+}
+
+#[test]
+fn test_l2_snapshot() {
+    let text = fetch_l2_snapshot("coinbase", MarketType::Spot, "BTC-USD", Some(3)).unwrap();
+    assert!(text.starts_with("{\"pricebook\":"));
+}
@@ -1,5 +1,5 @@
 use crypto_market_type::MarketType;
-use crypto_rest_client::{fetch_l2_snapshot, fetch_open_interest};
+use crypto_rest_client::{fetch_l2_snapshot, fetch_open_interest, DydxSwapRestClient};
 use serde_json::Value;
 use std::collections::HashMap;
 use test_case::test_case;
@@ -22,3 +22,17 @@ fn test_open_interest(market_type: MarketType) {
     let obj = serde_json::from_str::<HashMap<String, Value>>(&text).unwrap();
     assert!(obj.contains_key("markets"));
 }
+
+#[test]
+fn test_trades() {
+    let text = DydxSwapRestClient::fetch_trades("BTC-USD").unwrap();
+    let obj = serde_json::from_str::<HashMap<String, Value>>(&text).unwrap();
+    assert!(!obj.get("trades").unwrap().as_array().unwrap().is_empty());
+}
+
+#[test]
+fn test_historical_funding() {
+    let text = DydxSwapRestClient::fetch_historical_funding("BTC-USD").unwrap();
+    let obj = serde_json::from_str::<HashMap<String, Value>>(&text).unwrap();
+    assert!(!obj.get("historicalFunding").unwrap().as_array().unwrap().is_empty());
+}
@@ -0,0 +1,11 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+// Each line of the input is a message received from the exchange.
+fuzz_target!(|data: &[u8]| {
+    if let Ok(txt) = std::str::from_utf8(data) {
+        let msgs = txt.split('\n').collect::<Vec<&str>>();
+        crypto_ws_client::fuzz_message_handler("bithumb", &msgs);
+    }
+});
@@ -5,12 +5,13 @@ use tokio_tungstenite::tungstenite::Message;
 
 use crate::{
     common::{
+        candle_interval::check_candlestick_intervals,
         command_translator::CommandTranslator,
         message_handler::{MessageHandler, MiscMessage},
         utils::ensure_frame_size,
         ws_client_internal::WSClientInternal,
     },
-    WSClient,
+    CandleInterval, Error, WSClient,
 };
 use log::*;
 use serde_json::Value;
@@ -35,6 +36,28 @@ const WS_FRAME_SIZE: usize = 4096;
 const UPLINK_LIMIT: (NonZeroU32, std::time::Duration) =
     (nonzero!(5u32), std::time::Duration::from_secs(1));
 
+// Binance doesn't document the maximum length of URLs, stay below 8KB, which
+// is the default limit of request lines of most web servers
+const MAX_URL_LENGTH: usize = 8192;
+
+pub(super) const CANDLESTICK_INTERVALS: &[CandleInterval] = &[
+    CandleInterval::MIN1,
+    CandleInterval::MIN3,
+    CandleInterval::MIN5,
+    CandleInterval::MIN15,
+    CandleInterval::MIN30,
+    CandleInterval::HOUR1,
+    CandleInterval::HOUR2,
+    CandleInterval::HOUR4,
+    CandleInterval::HOUR6,
+    CandleInterval::HOUR8,
+    CandleInterval::HOUR12,
+    CandleInterval::DAY1,
+    CandleInterval::DAY3,
+    CandleInterval::WEEK1,
+    CandleInterval::MONTH1,
+];
+
 // Internal unified client
 pub struct BinanceWSClient<const MARKET_TYPE: char> {
     client: WSClientInternal<BinanceMessageHandler>,
@@ -63,17 +86,7 @@ impl<const MARKET_TYPE: char> BinanceWSClient<MARKET_TYPE> {
     pub async fn new(tx: std::sync::mpsc::Sender<String>, url: Option<&str>) -> Self {
         let real_url = match url {
             Some(endpoint) => endpoint,
-            None => {
-                if MARKET_TYPE == 'S' {
-                    SPOT_WEBSOCKET_URL
-                } else if MARKET_TYPE == 'I' {
-                    INVERSE_WEBSOCKET_URL
-                } else if MARKET_TYPE == 'L' {
-                    LINEAR_WEBSOCKET_URL
-                } else {
-                    panic!("Unknown market type {MARKET_TYPE}");
-                }
-            }
+            None => Self::default_url(),
         };
         BinanceWSClient {
             client: WSClientInternal::connect(
@@ -87,6 +100,61 @@ impl<const MARKET_TYPE: char> BinanceWSClient<MARKET_TYPE> {
             translator: BinanceCommandTranslator { market_type: MARKET_TYPE },
         }
     }
+
+    /// Creates clients with topics subscribed in URLs, e.g.,
+    /// `/stream?streams=btcusdt@aggTrade/btcusdt@ticker`.
+    ///
+    /// Unlike `subscribe()`, topics in URLs are not throttled by the limit of 5
+    /// messages per second, which makes it much faster to subscribe to
+    /// thousands of topics. Topics are split into multiple URLs by the number
+    /// of streams per connection and the URL length, each URL gets its own
+    /// client. `subscribe()` and `unsubscribe()` still work on every client.
+    ///
+    /// # Arguments
+    ///
+    /// * `tx` - The sending part of a channel, shared by all clients
+    /// * `topics` - Topics to subscribe, in the same format as `subscribe()`
+    pub async fn new_with_topics(
+        tx: std::sync::mpsc::Sender<String>,
+        topics: &[(String, String)],
+    ) -> Vec<Self> {
+        let mut clients = Vec::new();
+        for url in Self::topics_to_urls(topics) {
+            clients.push(Self::new(tx.clone(), Some(&url)).await);
+        }
+        clients
+    }
+
+    /// Splits topics into combined stream URLs, see `new_with_topics()`.
+    pub fn topics_to_urls(topics: &[(String, String)]) -> Vec<String> {
+        ensure_frame_size(
+            topics,
+            true,
+            Self::topics_to_url,
+            MAX_URL_LENGTH,
+            Some(BinanceCommandTranslator::max_num_topics(MARKET_TYPE)),
+        )
+    }
+
+    fn topics_to_url(topics: &[(String, String)], _subscribe: bool) -> String {
+        let raw_topics = topics
+            .iter()
+            .map(|(topic, symbol)| BinanceCommandTranslator::to_raw_topic(topic, symbol))
+            .collect::<Vec<String>>();
+        format!("{}?streams={}", Self::default_url(), raw_topics.join("/"))
+    }
+
+    fn default_url() -> &'static str {
+        if MARKET_TYPE == 'S' {
+            SPOT_WEBSOCKET_URL
+        } else if MARKET_TYPE == 'I' {
+            INVERSE_WEBSOCKET_URL
+        } else if MARKET_TYPE == 'L' {
+            LINEAR_WEBSOCKET_URL
+        } else {
+            panic!("Unknown market type {MARKET_TYPE}");
+        }
+    }
 }
 
 #[async_trait]
@@ -135,13 +203,23 @@ impl<const URL: char> WSClient for BinanceWSClient<URL> {
         self.subscribe(&topics).await;
     }
 
-    async fn subscribe_candlestick(&self, symbol_interval_list: &[(String, usize)]) {
+    async fn subscribe_candlestick(
+        &self,
+        symbol_interval_list: &[(String, CandleInterval)],
+    ) -> Result<(), Error> {
+        let symbol_interval_list = check_candlestick_intervals(
+            EXCHANGE_NAME,
+            CANDLESTICK_INTERVALS,
+            symbol_interval_list,
+        )?;
         let commands =
-            self.translator.translate_to_candlestick_commands(true, symbol_interval_list);
+            self.translator.translate_to_candlestick_commands(true, &symbol_interval_list);
         self.client.send(&commands).await;
+        Ok(())
     }
 
     async fn subscribe(&self, topics: &[(String, String)]) {
+        self.client.add_channels(topics);
         let commands = self.translator.translate_to_commands(true, topics);
         self.client.send(&commands).await;
     }
@@ -170,10 +248,26 @@ struct BinanceCommandTranslator {
 }
 
 impl BinanceCommandTranslator {
+    fn to_raw_topic(topic: &str, symbol: &str) -> String {
+        format!("{}@{}", symbol.to_lowercase(), topic)
+    }
+
+    // Maximum number of streams of a single connection
+    fn max_num_topics(market_type: char) -> usize {
+        if market_type == 'S' {
+            // https://binance-docs.github.io/apidocs/spot/en/#websocket-limits
+            1024
+        } else {
+            // https://binance-docs.github.io/apidocs/futures/en/#websocket-market-streams
+            // https://binance-docs.github.io/apidocs/delivery/en/#websocket-market-streams
+            200
+        }
+    }
+
     fn topics_to_command(topics: &[(String, String)], subscribe: bool) -> String {
         let raw_topics = topics
             .iter()
-            .map(|(topic, symbol)| format!("{}@{}", symbol.to_lowercase(), topic))
+            .map(|(topic, symbol)| Self::to_raw_topic(topic, symbol))
             .collect::<Vec<String>>();
         format!(
             r#"{{"id":9527,"method":"{}","params":{}}}"#,
@@ -216,13 +310,13 @@ impl MessageHandler for BinanceMessageHandler {
         let obj = resp.unwrap();
 
         if obj.contains_key("error") {
-            panic!("Received {msg} from {EXCHANGE_NAME}");
+            MiscMessage::Error(format!("Received {msg} from {EXCHANGE_NAME}"))
         } else if obj.contains_key("stream") && obj.contains_key("data") {
             MiscMessage::Normal
         } else {
             if let Some(result) = obj.get("result") {
                 if serde_json::Value::Null != *result {
-                    panic!("Received {msg} from {EXCHANGE_NAME}");
+                    return MiscMessage::Error(format!("Received {msg} from {EXCHANGE_NAME}"));
                 } else {
                     info!("Received {} from {}", msg, EXCHANGE_NAME);
                 }
@@ -245,22 +339,17 @@ impl MessageHandler for BinanceMessageHandler {
     }
 }
 
+impl_fuzz_message_handler!(BinanceMessageHandler {});
+impl_new_message_handler!(BinanceMessageHandler {});
+
 impl CommandTranslator for BinanceCommandTranslator {
     fn translate_to_commands(&self, subscribe: bool, topics: &[(String, String)]) -> Vec<String> {
-        let max_num_topics = if self.market_type == 'S' {
-            // https://binance-docs.github.io/apidocs/spot/en/#websocket-limits
-            1024
-        } else {
-            // https://binance-docs.github.io/apidocs/futures/en/#websocket-market-streams
-            // https://binance-docs.github.io/apidocs/delivery/en/#websocket-market-streams
-            200
-        };
         ensure_frame_size(
             topics,
             subscribe,
             Self::topics_to_command,
             WS_FRAME_SIZE,
-            Some(max_num_topics),
+            Some(Self::max_num_topics(self.market_type)),
         )
     }
 
@@ -314,4 +403,30 @@ mod tests {
             commands[0]
         );
     }
+
+    #[test]
+    fn test_topics_to_urls() {
+        let urls = super::BinanceLinearWSClient::topics_to_urls(&[
+            ("aggTrade".to_string(), "BTCUSDT".to_string()),
+            ("ticker".to_string(), "BTCUSDT".to_string()),
+        ]);
+        assert_eq!(
+            vec!["wss://fstream.binance.com/stream?streams=btcusdt@aggTrade/btcusdt@ticker"],
+            urls
+        );
+
+        let topics = (0..1500)
+            .map(|i| ("aggTrade".to_string(), format!("SYMBOL{i}USDT")))
+            .collect::<Vec<(String, String)>>();
+        let urls = super::BinanceSpotWSClient::topics_to_urls(&topics);
+        assert!(urls.len() > 1);
+        let mut num_streams = 0;
+        for url in urls.iter() {
+            assert!(url.len() <= super::MAX_URL_LENGTH);
+            let streams = url.split_once("?streams=").unwrap().1.split('/').count();
+            assert!(streams <= 1024);
+            num_streams += streams;
+        }
+        assert_eq!(topics.len(), num_streams);
+    }
 }
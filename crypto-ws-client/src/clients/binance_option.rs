@@ -11,7 +11,7 @@ use crate::{
         message_handler::{MessageHandler, MiscMessage},
         ws_client_internal::WSClientInternal,
     },
-    WSClient,
+    CandleInterval, WSClient,
 };
 use log::*;
 use serde_json::Value;
@@ -20,6 +20,17 @@ pub(crate) const EXCHANGE_NAME: &str = "binance";
 
 pub(super) const WEBSOCKET_URL: &str = "wss://stream.opsnest.com/stream";
 
+pub(super) const CANDLESTICK_INTERVALS: &[CandleInterval] = &[
+    CandleInterval::MIN1,
+    CandleInterval::MIN5,
+    CandleInterval::MIN15,
+    CandleInterval::MIN30,
+    CandleInterval::HOUR1,
+    CandleInterval::HOUR4,
+    CandleInterval::DAY1,
+    CandleInterval::WEEK1,
+];
+
 /// Binance Option market
 ///
 ///   * WebSocket API doc: <https://binance-docs.github.io/apidocs/voptions/en/>
@@ -98,7 +109,7 @@ impl MessageHandler for BinanceOptionMessageHandler {
         let obj = resp.unwrap();
 
         if obj.contains_key("code") {
-            panic!("Received {msg} from {EXCHANGE_NAME}");
+            return MiscMessage::Error(format!("Received {msg} from {EXCHANGE_NAME}"));
         }
 
         if let Some(result) = obj.get("result") {
@@ -125,6 +136,9 @@ impl MessageHandler for BinanceOptionMessageHandler {
     }
 }
 
+impl_fuzz_message_handler!(BinanceOptionMessageHandler {});
+impl_new_message_handler!(BinanceOptionMessageHandler {});
+
 impl CommandTranslator for BinanceOptionCommandTranslator {
     fn translate_to_commands(&self, subscribe: bool, topics: &[(String, String)]) -> Vec<String> {
         let command = Self::topics_to_command(topics, subscribe);
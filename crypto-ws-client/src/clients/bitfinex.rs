@@ -1,6 +1,6 @@
 use async_trait::async_trait;
 use std::{
-    collections::{BTreeMap, HashMap},
+    collections::{BTreeMap, HashMap, HashSet},
     time::Duration,
 };
 use tokio_tungstenite::tungstenite::Message;
@@ -10,11 +10,12 @@ use crate::{
         Candlestick, Level3OrderBook, OrderBook, OrderBookTopK, Ticker, Trade, BBO,
     },
     common::{
+        checksum::{crc32, interleave_levels, ChecksumConfig, ChecksumValidator, L2Book},
         command_translator::CommandTranslator,
         message_handler::{MessageHandler, MiscMessage},
         ws_client_internal::WSClientInternal,
     },
-    WSClient,
+    CandleInterval, WSClient,
 };
 
 use log::*;
@@ -24,6 +25,21 @@ pub(super) const EXCHANGE_NAME: &str = "bitfinex";
 
 const WEBSOCKET_URL: &str = "wss://api-pub.bitfinex.com/ws/2";
 
+pub(super) const CANDLESTICK_INTERVALS: &[CandleInterval] = &[
+    CandleInterval::MIN1,
+    CandleInterval::MIN5,
+    CandleInterval::MIN15,
+    CandleInterval::MIN30,
+    CandleInterval::HOUR1,
+    CandleInterval::HOUR3,
+    CandleInterval::HOUR6,
+    CandleInterval::HOUR12,
+    CandleInterval::DAY1,
+    CandleInterval::WEEK1,
+    CandleInterval::WEEK2,
+    CandleInterval::MONTH1,
+];
+
 /// The WebSocket client for Bitfinex, including all markets.
 ///
 /// * WebSocket API doc: <https://docs.bitfinex.com/docs/ws-general>
@@ -35,28 +51,111 @@ pub struct BitfinexWSClient {
     translator: BitfinexCommandTranslator, // used by close() and run()
 }
 
-impl_new_constructor!(
-    BitfinexWSClient,
-    EXCHANGE_NAME,
-    WEBSOCKET_URL,
-    BitfinexMessageHandler { channel_id_meta: HashMap::new() },
-    BitfinexCommandTranslator {}
-);
+/// Flags of the `conf` event, which is sent right after connecting.
+///
+/// See <https://docs.bitfinex.com/docs/ws-general#configuration>
+#[derive(Clone, Copy, Debug, Default)]
+pub struct BitfinexConf {
+    /// `TIMESTAMP`, appends a millisecond timestamp to every event
+    pub timestamp: bool,
+    /// `SEQ_ALL`, appends a sequence number to every event, a gap causes a
+    /// reconnect
+    pub seq_all: bool,
+    /// `OB_CHECKSUM`, sends a checksum after every book update
+    pub ob_checksum: bool,
+    /// `BULK_UPDATES`, sends book updates in bulk arrays
+    pub bulk_updates: bool,
+}
 
-impl_trait!(Trade, BitfinexWSClient, subscribe_trade, "trades");
-impl_trait!(Ticker, BitfinexWSClient, subscribe_ticker, "ticker");
-impl_candlestick!(BitfinexWSClient);
+impl BitfinexConf {
+    fn flags(&self) -> u32 {
+        let mut flags = 0;
+        if self.timestamp {
+            flags |= 32768;
+        }
+        if self.seq_all {
+            flags |= 65536;
+        }
+        if self.ob_checksum {
+            flags |= 131072;
+        }
+        if self.bulk_updates {
+            flags |= 536870912;
+        }
+        flags
+    }
+}
 
-panic_bbo!(BitfinexWSClient);
-panic_l2_topk!(BitfinexWSClient);
+impl BitfinexWSClient {
+    pub async fn new(tx: std::sync::mpsc::Sender<String>, url: Option<&str>) -> Self {
+        Self::new_with_conf(tx, url, BitfinexConf::default(), None).await
+    }
 
-#[async_trait]
-impl OrderBook for BitfinexWSClient {
-    async fn subscribe_orderbook(&self, symbols: &[String]) {
+    /// Creates a client which enables the `OB_CHECKSUM` flag and validates
+    /// `book` channels with their checksums.
+    ///
+    /// Only price aggregated books, i.e., `prec` from `P0` to `P4`, are
+    /// validated.
+    pub async fn new_with_checksum(
+        tx: std::sync::mpsc::Sender<String>,
+        url: Option<&str>,
+        config: ChecksumConfig,
+    ) -> Self {
+        Self::new_with_conf(tx, url, BitfinexConf::default(), Some(config)).await
+    }
+
+    /// Creates a client which enables the given `conf` flags on connect.
+    ///
+    /// `OB_CHECKSUM` is always enabled if `checksum` is set.
+    pub async fn new_with_conf(
+        tx: std::sync::mpsc::Sender<String>,
+        url: Option<&str>,
+        conf: BitfinexConf,
+        checksum: Option<ChecksumConfig>,
+    ) -> Self {
+        let real_url = match url {
+            Some(endpoint) => endpoint,
+            None => WEBSOCKET_URL,
+        };
+        let conf = BitfinexConf { ob_checksum: conf.ob_checksum || checksum.is_some(), ..conf };
+        BitfinexWSClient {
+            client: WSClientInternal::connect(
+                EXCHANGE_NAME,
+                real_url,
+                BitfinexMessageHandler {
+                    channel_id_meta: HashMap::new(),
+                    conf,
+                    last_seq: None,
+                    book_symbols: HashMap::new(),
+                    pending_snapshots: HashSet::new(),
+                    checksum: checksum.map(|config| ChecksumValidator::new(EXCHANGE_NAME, config)),
+                },
+                None,
+                tx,
+            )
+            .await,
+            translator: BitfinexCommandTranslator {},
+        }
+    }
+
+    /// Subscribes to `book` channels with the given precision, frequency and
+    /// number of price levels.
+    ///
+    /// * `prec` `P0` to `P4` for price aggregated books, `R0` for raw books
+    /// * `freq` `F0` for realtime, `F1` for every 2 seconds
+    /// * `len` 1, 25, 100 or 250
+    pub async fn subscribe_orderbook_with_params(
+        &self,
+        symbols: &[String],
+        prec: &str,
+        freq: &str,
+        len: u32,
+    ) {
         let commands = symbols
             .iter()
             .map(|symbol| {
-                format!(r#"{{"event": "subscribe","channel": "book","symbol": "{symbol}","prec": "P0","frec": "F0","len":25}}"#,
+                format!(
+                    r#"{{"event": "subscribe","channel": "book","symbol": "{symbol}","prec": "{prec}","freq": "{freq}","len": {len}}}"#,
                 )
             })
             .collect::<Vec<String>>();
@@ -65,18 +164,25 @@ impl OrderBook for BitfinexWSClient {
     }
 }
 
+impl_trait!(Trade, BitfinexWSClient, subscribe_trade, "trades");
+impl_trait!(Ticker, BitfinexWSClient, subscribe_ticker, "ticker");
+impl_candlestick!(BitfinexWSClient);
+
+panic_bbo!(BitfinexWSClient);
+panic_l2_topk!(BitfinexWSClient);
+
+#[async_trait]
+impl OrderBook for BitfinexWSClient {
+    async fn subscribe_orderbook(&self, symbols: &[String]) {
+        self.subscribe_orderbook_with_params(symbols, "P0", "F0", 25).await;
+    }
+}
+
 #[async_trait]
 impl Level3OrderBook for BitfinexWSClient {
     async fn subscribe_l3_orderbook(&self, symbols: &[String]) {
-        let commands = symbols
-            .iter()
-            .map(|symbol| {
-                format!(r#"{{"event": "subscribe","channel": "book","symbol": "{symbol}","prec": "R0","len": 250}}"#,
-                )
-            })
-            .collect::<Vec<String>>();
-
-        self.send(&commands).await;
+        // raw books contain individual orders
+        self.subscribe_orderbook_with_params(symbols, "R0", "F0", 250).await;
     }
 }
 
@@ -84,6 +190,13 @@ impl_ws_client_trait!(BitfinexWSClient);
 
 struct BitfinexMessageHandler {
     channel_id_meta: HashMap<i64, String>, // CHANNEL_ID information
+    conf: BitfinexConf,
+    last_seq: Option<i64>, // the last sequence number if SEQ_ALL is enabled
+    // CHANNEL_ID -> (PRECISION, SYMBOL) of books to validate
+    book_symbols: HashMap<i64, (String, String)>,
+    // Books waiting for snapshots, which can't be told apart from bulk updates
+    pending_snapshots: HashSet<i64>,
+    checksum: Option<ChecksumValidator>,
 }
 struct BitfinexCommandTranslator {}
 
@@ -122,14 +235,109 @@ impl BitfinexCommandTranslator {
     }
 }
 
+impl BitfinexMessageHandler {
+    // SEQ_ALL appends a sequence number after the original fields, which
+    // increases by one for every event on the connection.
+    //
+    // Returns false if there is a gap.
+    fn check_sequence(&mut self, arr: &[Value]) -> bool {
+        let num_fields = match arr.get(1).and_then(|x| x.as_str()) {
+            Some("hb") => 2,
+            Some(_) => 3, // te, tu, cs
+            None => 2,
+        };
+        if let Some(seq) = arr.get(num_fields).and_then(|x| x.as_i64()) {
+            if let Some(last_seq) = self.last_seq {
+                if seq != last_seq + 1 {
+                    error!(
+                        "Sequence gap detected, expected {} but got {}, {}",
+                        last_seq + 1,
+                        seq,
+                        EXCHANGE_NAME
+                    );
+                    self.last_seq = None;
+                    return false;
+                }
+            }
+            self.last_seq = Some(seq);
+        }
+        true
+    }
+
+    // [PRICE, COUNT, AMOUNT], AMOUNT is negative for asks
+    fn apply_level(book: &mut L2Book, level: &[Value]) {
+        let (price, count, amount) = match level {
+            [price, count, amount, ..] => match (count.as_i64(), amount.as_f64()) {
+                (Some(count), Some(amount)) => (price.to_string(), count, amount),
+                _ => return,
+            },
+            _ => return,
+        };
+        if count == 0 {
+            book.remove(amount > 0.0, &price);
+        } else {
+            book.update(amount > 0.0, &price, &level[2].to_string());
+        }
+    }
+
+    // see https://docs.bitfinex.com/docs/ws-websocket-checksum
+    //
+    // Returns resubscribe commands if the checksum mismatches and resubscription is
+    // enabled.
+    fn validate_checksum(&mut self, channel_id: i64, arr: &[Value]) -> Option<Vec<String>> {
+        let validator = self.checksum.as_mut()?;
+        let (prec, symbol) = self.book_symbols.get(&channel_id)?;
+
+        if arr.get(1).and_then(|x| x.as_str()) == Some("cs") {
+            let expected = arr.get(2).and_then(|x| x.as_i64())? as i32 as u32;
+            let book = validator.book(prec, symbol)?;
+            let actual = crc32(&interleave_levels(book, 25));
+            if !validator.verify(prec, symbol, expected, actual) && validator.resubscribe() {
+                let mut subscribe = serde_json::from_str::<BTreeMap<String, Value>>(
+                    self.channel_id_meta.get(&channel_id)?,
+                )
+                .ok()?;
+                subscribe.insert("event".to_string(), Value::String("subscribe".to_string()));
+                return Some(vec![
+                    format!(r#"{{"event":"unsubscribe","chanId":{channel_id}}}"#),
+                    serde_json::to_string(&subscribe).unwrap(),
+                ]);
+            }
+        } else if let Some(levels) = arr.get(1).and_then(|x| x.as_array()) {
+            if self.pending_snapshots.remove(&channel_id) {
+                let book = validator.reset(prec, symbol);
+                for level in levels.iter().filter_map(|x| x.as_array()) {
+                    Self::apply_level(book, level);
+                }
+            } else if let Some(book) = validator.book(prec, symbol) {
+                if levels.first().map(|x| x.is_array()).unwrap_or(false) {
+                    // bulk updates
+                    for level in levels.iter().filter_map(|x| x.as_array()) {
+                        Self::apply_level(book, level);
+                    }
+                } else if !levels.is_empty() {
+                    Self::apply_level(book, levels);
+                }
+            }
+        }
+        None
+    }
+}
+
 impl MessageHandler for BitfinexMessageHandler {
     fn handle_message(&mut self, txt: &str) -> MiscMessage {
         if txt.starts_with('{') {
-            let obj = serde_json::from_str::<HashMap<String, Value>>(txt).unwrap();
-            let event = obj.get("event").unwrap().as_str().unwrap();
+            let obj = match serde_json::from_str::<HashMap<String, Value>>(txt) {
+                Ok(obj) => obj,
+                Err(_) => {
+                    error!("{} is not a JSON string, {}", txt, EXCHANGE_NAME);
+                    return MiscMessage::Other;
+                }
+            };
+            let event = obj.get("event").and_then(|x| x.as_str()).unwrap_or_default();
             match event {
                 "error" => {
-                    let code = obj.get("code").unwrap().as_i64().unwrap();
+                    let code = obj.get("code").and_then(|x| x.as_i64()).unwrap_or_default();
                     match code {
                         10301 | 10401 => {
                             // 10301: Already subscribed
@@ -142,8 +350,7 @@ impl MessageHandler for BitfinexMessageHandler {
                             // 10302: Unknown channel
                             // 10001: Unknown pair
                             // 10305: Reached limit of open channels
-                            error!("{} from {}", txt, EXCHANGE_NAME);
-                            panic!("{txt} from {EXCHANGE_NAME}");
+                            return MiscMessage::Error(format!("{txt} from {EXCHANGE_NAME}"));
                         }
                         _ => warn!("{} from {}", txt, EXCHANGE_NAME),
                     }
@@ -154,27 +361,29 @@ impl MessageHandler for BitfinexMessageHandler {
                         // 1 for operative, 0 for maintenance
                         let status = obj
                             .get("platform")
-                            .unwrap()
-                            .as_object()
-                            .unwrap()
-                            .get("status")
-                            .unwrap()
-                            .as_i64()
-                            .unwrap();
+                            .and_then(|x| x.get("status"))
+                            .and_then(|x| x.as_i64())
+                            .unwrap_or(1);
                         if status == 0 {
                             std::thread::sleep(Duration::from_secs(15));
                             MiscMessage::Reconnect
+                        } else if self.conf.flags() != 0 {
+                            MiscMessage::WebSocket(Message::Text(format!(
+                                r#"{{"event":"conf","flags":{}}}"#,
+                                self.conf.flags()
+                            )))
                         } else {
                             MiscMessage::Other
                         }
                     } else {
-                        let code = obj.get("code").unwrap().as_i64().unwrap();
+                        let code = obj.get("code").and_then(|x| x.as_i64()).unwrap_or_default();
                         match code {
                             20051 => {
                                 // Stop/Restart Websocket Server (please reconnect)
                                 // self.reconnect();
                                 error!("Stop/Restart Websocket Server, exiting now...");
-                                MiscMessage::Reconnect // fail fast, pm2 will restart
+                                MiscMessage::Reconnect // fail fast, pm2 will
+                                                       // restart
                             }
                             20060 => {
                                 // Entering in Maintenance mode. Please pause any activity and
@@ -205,11 +414,28 @@ impl MessageHandler for BitfinexMessageHandler {
                     MiscMessage::Other
                 }
                 "subscribed" => {
+                    let chan_id = match obj.get("chanId").and_then(|x| x.as_i64()) {
+                        Some(chan_id) => chan_id,
+                        None => {
+                            warn!("{} from {}", txt, EXCHANGE_NAME);
+                            return MiscMessage::Other;
+                        }
+                    };
                     let mut obj_sorted = BTreeMap::<String, Value>::new();
                     for (key, value) in obj.iter() {
                         obj_sorted.insert(key.to_string(), value.clone());
                     }
-                    let chan_id = obj.get("chanId").unwrap().as_i64().unwrap();
+                    let get_str = |key: &str| obj.get(key).and_then(|x| x.as_str());
+                    if self.checksum.is_some() && get_str("channel") == Some("book") {
+                        match (get_str("prec"), get_str("symbol")) {
+                            (Some(prec), Some(symbol)) if prec.starts_with('P') => {
+                                self.book_symbols
+                                    .insert(chan_id, (prec.to_string(), symbol.to_string()));
+                                self.pending_snapshots.insert(chan_id);
+                            }
+                            _ => (),
+                        }
+                    }
                     obj_sorted.remove("event");
                     obj_sorted.remove("chanId");
                     obj_sorted.remove("pair");
@@ -218,32 +444,55 @@ impl MessageHandler for BitfinexMessageHandler {
                     MiscMessage::Other
                 }
                 "unsubscribed" => {
-                    let chan_id = obj.get("chanId").unwrap().as_i64().unwrap();
-                    self.channel_id_meta.remove(&chan_id);
+                    if let Some(chan_id) = obj.get("chanId").and_then(|x| x.as_i64()) {
+                        self.channel_id_meta.remove(&chan_id);
+                        self.pending_snapshots.remove(&chan_id);
+                        if let Some((prec, symbol)) = self.book_symbols.remove(&chan_id) {
+                            if let Some(validator) = self.checksum.as_mut() {
+                                validator.remove(&prec, &symbol);
+                            }
+                        }
+                    }
                     MiscMessage::Other
                 }
                 _ => MiscMessage::Other,
             }
         } else {
-            debug_assert!(txt.starts_with('['));
-            let arr = serde_json::from_str::<Vec<Value>>(txt).unwrap();
-            if arr.is_empty() {
-                MiscMessage::Other // ignore empty array
-            } else if arr.len() == 2 && arr[1].as_str().unwrap_or("null") == "hb" {
+            let arr = match serde_json::from_str::<Vec<Value>>(txt) {
+                Ok(arr) => arr,
+                Err(_) => {
+                    error!("{} is not a JSON array, {}", txt, EXCHANGE_NAME);
+                    return MiscMessage::Other;
+                }
+            };
+            if arr.len() < 2 {
+                return MiscMessage::Other; // ignore empty array
+            }
+            if self.conf.seq_all && !self.check_sequence(&arr) {
+                return MiscMessage::Reconnect;
+            }
+            if arr[1].as_str() == Some("hb") {
                 // If there is no activity in the channel for 15 seconds, the Websocket server
                 // will send you a heartbeat message in this format.
                 // see <https://docs.bitfinex.com/docs/ws-general#heartbeating>
                 MiscMessage::WebSocket(Message::Text(r#"{"event":"ping"}"#.to_string()))
-            } else {
-                // replace CHANNEL_ID with meta info
-                let i = txt.find(',').unwrap(); // first comma, for example, te, tu, see https://blog.bitfinex.com/api/websocket-api-update/
-                let channel_id = (txt[1..i]).parse::<i64>().unwrap();
-                if let Some(channel_info) = self.channel_id_meta.get(&channel_id) {
+            } else if let Some(channel_id) = arr[0].as_i64() {
+                if let Some(commands) = self.validate_checksum(channel_id, &arr) {
+                    return MiscMessage::Resubscribe(commands);
+                }
+                // replace CHANNEL_ID with meta info, the first comma follows CHANNEL_ID,
+                // for example, te, tu, see https://blog.bitfinex.com/api/websocket-api-update/
+                if let (Some(channel_info), Some(i)) =
+                    (self.channel_id_meta.get(&channel_id), txt.find(','))
+                {
                     let new_txt = format!("[{}{}", channel_info, &txt[i..]);
                     MiscMessage::Mutated(new_txt)
                 } else {
                     MiscMessage::Other
                 }
+            } else {
+                warn!("{} from {}", txt, EXCHANGE_NAME);
+                MiscMessage::Other
             }
         }
     }
@@ -255,6 +504,26 @@ impl MessageHandler for BitfinexMessageHandler {
     }
 }
 
+impl_fuzz_message_handler!(BitfinexMessageHandler {
+    channel_id_meta: HashMap::new(),
+    conf: BitfinexConf { seq_all: true, ob_checksum: true, ..Default::default() },
+    last_seq: None,
+    book_symbols: HashMap::new(),
+    pending_snapshots: HashSet::new(),
+    checksum: Some(ChecksumValidator::new(
+        EXCHANGE_NAME,
+        ChecksumConfig { resubscribe: true, ..Default::default() }
+    )),
+});
+impl_new_message_handler!(BitfinexMessageHandler {
+    channel_id_meta: HashMap::new(),
+    conf: BitfinexConf::default(),
+    last_seq: None,
+    book_symbols: HashMap::new(),
+    pending_snapshots: HashSet::new(),
+    checksum: None,
+});
+
 impl CommandTranslator for BitfinexCommandTranslator {
     fn translate_to_commands(&self, subscribe: bool, topics: &[(String, String)]) -> Vec<String> {
         topics
@@ -304,4 +573,121 @@ mod tests {
             commands[0]
         );
     }
+
+    #[test]
+    fn test_checksum() {
+        use crate::common::{
+            checksum::{ChecksumConfig, ChecksumValidator},
+            message_handler::{MessageHandler, MiscMessage},
+        };
+        use std::collections::{HashMap, HashSet};
+
+        let mut handler = super::BitfinexMessageHandler {
+            channel_id_meta: HashMap::new(),
+            conf: super::BitfinexConf { ob_checksum: true, ..Default::default() },
+            last_seq: None,
+            book_symbols: HashMap::new(),
+            pending_snapshots: HashSet::new(),
+            checksum: Some(ChecksumValidator::new(
+                super::EXCHANGE_NAME,
+                ChecksumConfig { tx: None, resubscribe: true },
+            )),
+        };
+
+        let subscribed = r#"{"event":"subscribed","channel":"book","chanId":17,"symbol":"tBTCUSD","prec":"P0","freq":"F0","len":"25","pair":"BTCUSD"}"#;
+        assert!(matches!(handler.handle_message(subscribed), MiscMessage::Other));
+        let snapshot = r#"[17,[[7000,1,0.5],[6999,2,1],[7001,1,-0.3]]]"#;
+        assert!(matches!(handler.handle_message(snapshot), MiscMessage::Mutated(_)));
+        assert!(matches!(
+            handler.handle_message(r#"[17,"cs",788042065]"#),
+            MiscMessage::Mutated(_)
+        ));
+
+        // the P1 book of the same symbol doesn't affect the P0 book
+        let subscribed = r#"{"event":"subscribed","channel":"book","chanId":18,"symbol":"tBTCUSD","prec":"P1","freq":"F0","len":"25","pair":"BTCUSD"}"#;
+        assert!(matches!(handler.handle_message(subscribed), MiscMessage::Other));
+        assert!(matches!(handler.handle_message(r#"[18,[[7000,3,2]]]"#), MiscMessage::Mutated(_)));
+        assert!(matches!(
+            handler.handle_message(r#"[17,"cs",788042065]"#),
+            MiscMessage::Mutated(_)
+        ));
+
+        // removes 6999 from bids
+        assert!(matches!(handler.handle_message(r#"[17,[6999,0,1]]"#), MiscMessage::Mutated(_)));
+        match handler.handle_message(r#"[17,"cs",788042065]"#) {
+            MiscMessage::Resubscribe(commands) => {
+                assert_eq!(
+                    vec![
+                        r#"{"event":"unsubscribe","chanId":17}"#,
+                        r#"{"channel":"book","event":"subscribe","freq":"F0","len":"25","prec":"P0","symbol":"tBTCUSD"}"#,
+                    ],
+                    commands
+                );
+            }
+            other => panic!("Unexpected {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_bulk_updates() {
+        use crate::common::{
+            checksum::{ChecksumConfig, ChecksumValidator},
+            message_handler::{MessageHandler, MiscMessage},
+        };
+        use std::collections::{HashMap, HashSet};
+
+        let mut handler = super::BitfinexMessageHandler {
+            channel_id_meta: HashMap::new(),
+            conf: super::BitfinexConf {
+                ob_checksum: true,
+                bulk_updates: true,
+                ..Default::default()
+            },
+            last_seq: None,
+            book_symbols: HashMap::new(),
+            pending_snapshots: HashSet::new(),
+            checksum: Some(ChecksumValidator::new(
+                super::EXCHANGE_NAME,
+                ChecksumConfig { tx: None, resubscribe: true },
+            )),
+        };
+
+        let subscribed = r#"{"event":"subscribed","channel":"book","chanId":17,"symbol":"tBTCUSD","prec":"P0","freq":"F0","len":"25","pair":"BTCUSD"}"#;
+        assert!(matches!(handler.handle_message(subscribed), MiscMessage::Other));
+        let snapshot = r#"[17,[[7000,1,0.5],[7001,1,-0.3]]]"#;
+        assert!(matches!(handler.handle_message(snapshot), MiscMessage::Mutated(_)));
+        // adds 6999 to bids and removes it again
+        let bulk = r#"[17,[[6999,2,1],[6999,0,1]]]"#;
+        assert!(matches!(handler.handle_message(bulk), MiscMessage::Mutated(_)));
+        assert!(matches!(
+            handler.handle_message(r#"[17,"cs",-649841233]"#),
+            MiscMessage::Mutated(_)
+        ));
+    }
+
+    #[test]
+    fn test_sequence_gap() {
+        use crate::common::message_handler::{MessageHandler, MiscMessage};
+        use std::collections::{HashMap, HashSet};
+
+        let mut handler = super::BitfinexMessageHandler {
+            channel_id_meta: HashMap::new(),
+            conf: super::BitfinexConf { seq_all: true, timestamp: true, ..Default::default() },
+            last_seq: None,
+            book_symbols: HashMap::new(),
+            pending_snapshots: HashSet::new(),
+            checksum: None,
+        };
+
+        let subscribed = r#"{"event":"subscribed","channel":"trades","chanId":18,"symbol":"tBTCUSD","pair":"BTCUSD"}"#;
+        assert!(matches!(handler.handle_message(subscribed), MiscMessage::Other));
+        let trade = r#"[18,"te",[401597395,1574694478808,0.005,7245.3],1,1574694478811]"#;
+        assert!(matches!(handler.handle_message(trade), MiscMessage::Mutated(_)));
+        assert!(matches!(
+            handler.handle_message(r#"[18,"hb",2,1574694478812]"#),
+            MiscMessage::WebSocket(_)
+        ));
+        let trade = r#"[18,"te",[401597396,1574694478813,0.005,7245.3],4,1574694478814]"#;
+        assert!(matches!(handler.handle_message(trade), MiscMessage::Reconnect));
+    }
 }
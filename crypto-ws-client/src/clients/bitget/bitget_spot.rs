@@ -9,7 +9,7 @@ use crate::{
 };
 
 use super::{
-    utils::{BitgetCommandTranslator, BitgetMessageHandler, UPLINK_LIMIT},
+    utils::{BitgetCommandTranslator, BitgetMessageHandler, CANDLESTICK_INTERVALS, UPLINK_LIMIT},
     EXCHANGE_NAME,
 };
 
@@ -1,5 +1,5 @@
 use super::{
-    utils::{BitgetCommandTranslator, BitgetMessageHandler, UPLINK_LIMIT},
+    utils::{BitgetCommandTranslator, BitgetMessageHandler, CANDLESTICK_INTERVALS, UPLINK_LIMIT},
     EXCHANGE_NAME,
 };
 use crate::{
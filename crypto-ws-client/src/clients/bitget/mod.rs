@@ -1,6 +1,6 @@
 mod bitget_spot;
 mod bitget_swap;
-mod utils;
+pub(super) mod utils;
 pub use bitget_spot::BitgetSpotWSClient;
 pub use bitget_swap::BitgetSwapWSClient;
 // pub use message_models::*;
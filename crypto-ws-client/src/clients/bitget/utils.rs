@@ -6,15 +6,30 @@ use std::{
 use tokio_tungstenite::tungstenite::Message;
 
 use log::*;
-use serde_json::{json, Number, Value};
-
-use crate::common::{
-    command_translator::CommandTranslator,
-    message_handler::{MessageHandler, MiscMessage},
-    utils::ensure_frame_size,
+use serde_json::Value;
+
+use crate::{
+    common::{
+        command_translator::CommandTranslator,
+        message_handler::{MessageHandler, MiscMessage},
+        utils::ensure_frame_size,
+    },
+    CandleInterval,
 };
 pub(crate) const EXCHANGE_NAME: &str = "bitget";
 
+pub(crate) const CANDLESTICK_INTERVALS: &[CandleInterval] = &[
+    CandleInterval::MIN1,
+    CandleInterval::MIN5,
+    CandleInterval::MIN15,
+    CandleInterval::MIN30,
+    CandleInterval::HOUR1,
+    CandleInterval::HOUR4,
+    CandleInterval::HOUR12,
+    CandleInterval::DAY1,
+    CandleInterval::WEEK1,
+];
+
 // The total length of multiple channel can not exceeds 4096 bytes, see:
 // * https://bitgetlimited.github.io/apidoc/en/mix/#subscribe
 // * https://bitgetlimited.github.io/apidoc/en/spot/#subscribe
@@ -97,16 +112,17 @@ impl MessageHandler for BitgetMessageHandler {
             return MiscMessage::Other;
         }
         let obj = resp.unwrap();
-        let code = obj.get("code").unwrap_or(&json!(0)).as_i64().unwrap();
+        let code = obj.get("code").and_then(|x| x.as_i64()).unwrap_or_default();
         if let Some(event) = obj.get("event") {
-            match event.as_str().unwrap() {
+            match event.as_str().unwrap_or_default() {
                 "error" => {
-                    match code {
-                        30030 | 30012 | 30015 | 30004 | 30011 | 30013 => panic!(
-                            "Ivalid API credentials. Received {} from {}",
-                            msg, EXCHANGE_NAME
+                    return match code {
+                        30030 | 30012 | 30015 | 30004 | 30011 | 30013 => MiscMessage::Error(
+                            format!("Ivalid API credentials. Received {msg} from {EXCHANGE_NAME}"),
                         ),
-                        _ => panic!("Unexpected error. Received {} from {}", msg, EXCHANGE_NAME),
+                        _ => MiscMessage::Error(format!(
+                            "Unexpected error. Received {msg} from {EXCHANGE_NAME}"
+                        )),
                     };
                 }
                 "subscribe" => info!("Received {} from {}", msg, EXCHANGE_NAME),
@@ -116,7 +132,11 @@ impl MessageHandler for BitgetMessageHandler {
                         info!("Success authorized");
                         self.authorized = true;
                     }
-                    _ => panic!("Unexpected error. Received {} from {}", msg, EXCHANGE_NAME),
+                    _ => {
+                        return MiscMessage::Error(format!(
+                            "Unexpected error. Received {msg} from {EXCHANGE_NAME}"
+                        ))
+                    }
                 },
                 _ => warn!("Received {} from {}", msg, EXCHANGE_NAME),
             }
@@ -135,6 +155,9 @@ impl MessageHandler for BitgetMessageHandler {
     }
 }
 
+impl_fuzz_message_handler!(BitgetMessageHandler { authorized: false });
+impl_new_message_handler!(BitgetMessageHandler { authorized: false });
+
 impl<const MARKET_TYPE: char> CommandTranslator for BitgetCommandTranslator<MARKET_TYPE> {
     fn translate_to_commands(&self, subscribe: bool, topics: &[(String, String)]) -> Vec<String> {
         ensure_frame_size(topics, subscribe, Self::topics_to_command, WS_FRAME_SIZE, None)
@@ -59,14 +59,25 @@ struct BithumbCommandTranslator {}
 
 impl MessageHandler for BithumbMessageHandler {
     fn handle_message(&mut self, msg: &str) -> MiscMessage {
-        let obj = serde_json::from_str::<HashMap<String, Value>>(msg).unwrap();
-        let code = obj.get("code").unwrap().as_str().unwrap();
-        let code = code.parse::<i64>().unwrap();
+        let resp = serde_json::from_str::<HashMap<String, Value>>(msg);
+        if resp.is_err() {
+            error!("{} is not a JSON string, {}", msg, EXCHANGE_NAME);
+            return MiscMessage::Other;
+        }
+        let obj = resp.unwrap();
+        let code =
+            match obj.get("code").and_then(|x| x.as_str()).and_then(|x| x.parse::<i64>().ok()) {
+                Some(code) => code,
+                None => {
+                    warn!("Received {} from {}", msg, EXCHANGE_NAME);
+                    return MiscMessage::Other;
+                }
+            };
         if code < 10000 {
             match code {
                 0 => MiscMessage::Pong,
                 6 => {
-                    let arr = obj.get("data").unwrap().as_array();
+                    let arr = obj.get("data").and_then(|x| x.as_array());
                     if arr.is_some() && arr.unwrap().is_empty() {
                         // ignore empty data
                         MiscMessage::Other
@@ -81,7 +92,7 @@ impl MessageHandler for BithumbMessageHandler {
                 }
             }
         } else {
-            panic!("Received {msg} from {EXCHANGE_NAME}");
+            MiscMessage::Error(format!("Received {msg} from {EXCHANGE_NAME}"))
         }
     }
 
@@ -90,6 +101,9 @@ impl MessageHandler for BithumbMessageHandler {
     }
 }
 
+impl_fuzz_message_handler!(BithumbMessageHandler {});
+impl_new_message_handler!(BithumbMessageHandler {});
+
 impl BithumbCommandTranslator {
     fn topics_to_command(topics: &[(String, String)], subscribe: bool) -> String {
         let raw_channels: Vec<String> =
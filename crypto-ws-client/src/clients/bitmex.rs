@@ -11,7 +11,7 @@ use crate::{
         message_handler::{MessageHandler, MiscMessage},
         ws_client_internal::WSClientInternal,
     },
-    WSClient,
+    CandleInterval, WSClient,
 };
 use log::*;
 use serde_json::Value;
@@ -23,6 +23,9 @@ const WEBSOCKET_URL: &str = "wss://www.bitmex.com/realtime";
 // Too many args sent. Max length is 20
 const MAX_CHANNELS_PER_COMMAND: usize = 20;
 
+pub(super) const CANDLESTICK_INTERVALS: &[CandleInterval] =
+    &[CandleInterval::MIN1, CandleInterval::MIN5, CandleInterval::HOUR1, CandleInterval::DAY1];
+
 /// The WebSocket client for BitMEX.
 ///
 /// BitMEX has Swap and Future markets.
@@ -95,9 +98,9 @@ impl MessageHandler for BitmexMessageHandler {
         }
         let obj = resp.unwrap();
 
-        if obj.contains_key("error") {
-            let error_msg = obj.get("error").unwrap().as_str().unwrap();
-            let code = obj.get("status").unwrap().as_i64().unwrap();
+        if let Some(error_msg) = obj.get("error") {
+            let error_msg = error_msg.as_str().unwrap_or_default();
+            let code = obj.get("status").and_then(|x| x.as_i64()).unwrap_or_default();
 
             match code {
                 // Rate limit exceeded
@@ -107,7 +110,7 @@ impl MessageHandler for BitmexMessageHandler {
                 }
                 400 => {
                     if error_msg.starts_with("Unknown") {
-                        panic!("Received {msg} from {EXCHANGE_NAME}");
+                        return MiscMessage::Error(format!("Received {msg} from {EXCHANGE_NAME}"));
                     } else if error_msg.starts_with("You are already subscribed to this topic") {
                         info!("Received {} from {}", msg, EXCHANGE_NAME)
                     } else {
@@ -136,6 +139,9 @@ impl MessageHandler for BitmexMessageHandler {
     }
 }
 
+impl_fuzz_message_handler!(BitmexMessageHandler {});
+impl_new_message_handler!(BitmexMessageHandler {});
+
 impl CommandTranslator for BitmexCommandTranslator {
     fn translate_to_commands(&self, subscribe: bool, topics: &[(String, String)]) -> Vec<String> {
         let mut commands: Vec<String> = Vec::new();
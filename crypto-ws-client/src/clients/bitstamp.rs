@@ -48,7 +48,7 @@ impl_trait!(OrderBookTopK, BitstampWSClient, subscribe_orderbook_topk, "order_bo
 impl_trait!(Level3OrderBook, BitstampWSClient, subscribe_l3_orderbook, "live_orders");
 
 panic_bbo!(BitstampWSClient);
-impl_candlestick!(BitstampWSClient);
+panic_candlestick!(BitstampWSClient);
 panic_ticker!(BitstampWSClient);
 
 impl_ws_client_trait!(BitstampWSClient);
@@ -65,16 +65,19 @@ impl MessageHandler for BitstampMessageHandler {
         }
         let obj = resp.unwrap();
 
-        let event = obj.get("event").unwrap().as_str().unwrap();
+        let event = match obj.get("event").and_then(|x| x.as_str()) {
+            Some(event) => event,
+            None => {
+                warn!("Received {} from {}", msg, EXCHANGE_NAME);
+                return MiscMessage::Other;
+            }
+        };
         match event {
             "bts:subscription_succeeded" | "bts:unsubscription_succeeded" | "bts:heartbeat" => {
                 debug!("Received {} from {}", msg, EXCHANGE_NAME);
                 MiscMessage::Other
             }
-            "bts:error" => {
-                error!("Received {} from {}", msg, EXCHANGE_NAME);
-                panic!("Received {msg} from {EXCHANGE_NAME}");
-            }
+            "bts:error" => MiscMessage::Error(format!("Received {msg} from {EXCHANGE_NAME}")),
             "bts:request_reconnect" => {
                 warn!("Received {}, which means Bitstamp is under maintenance", msg);
                 std::thread::sleep(std::time::Duration::from_secs(20));
@@ -90,6 +93,9 @@ impl MessageHandler for BitstampMessageHandler {
     }
 }
 
+impl_fuzz_message_handler!(BitstampMessageHandler {});
+impl_new_message_handler!(BitstampMessageHandler {});
+
 impl CommandTranslator for BitstampCommandTranslator {
     fn translate_to_commands(&self, subscribe: bool, topics: &[(String, String)]) -> Vec<String> {
         topics
@@ -14,7 +14,7 @@ use crate::{
         message_handler::{MessageHandler, MiscMessage},
         ws_client_internal::WSClientInternal,
     },
-    WSClient,
+    CandleInterval, WSClient,
 };
 
 use log::*;
@@ -24,6 +24,19 @@ use super::EXCHANGE_NAME;
 
 const WEBSOCKET_URL: &str = "wss://wsapi.bitz.plus/";
 
+pub(crate) const CANDLESTICK_INTERVALS: &[CandleInterval] = &[
+    CandleInterval::MIN1,
+    CandleInterval::MIN5,
+    CandleInterval::MIN15,
+    CandleInterval::MIN30,
+    CandleInterval::HOUR1,
+    CandleInterval::HOUR4,
+    CandleInterval::DAY1,
+    CandleInterval::DAY5,
+    CandleInterval::WEEK1,
+    CandleInterval::MONTH1,
+];
+
 /// The WebSocket client for Bitz spot market.
 ///
 /// * WebSocket API doc: <https://apidocv2.bitz.plus/en/#websocket-url>
@@ -63,31 +76,33 @@ impl MessageHandler for BitzMessageHandler {
         if msg == "pong" {
             return MiscMessage::Pong;
         }
-        let obj = serde_json::from_str::<HashMap<String, Value>>(msg).unwrap();
+        let resp = serde_json::from_str::<HashMap<String, Value>>(msg);
+        if resp.is_err() {
+            error!("{} is not a JSON string, {}", msg, EXCHANGE_NAME);
+            return MiscMessage::Other;
+        }
+        let obj = resp.unwrap();
 
-        if obj.contains_key("action")
-            && obj.get("action").unwrap().as_str().unwrap().starts_with("Pushdata.")
+        if obj
+            .get("action")
+            .and_then(|x| x.as_str())
+            .map(|x| x.starts_with("Pushdata."))
+            .unwrap_or(false)
         {
             MiscMessage::Normal
-        } else if obj.contains_key("status") {
-            let status = obj.get("status").unwrap().as_i64().unwrap();
+        } else if let Some(status) = obj.get("status").and_then(|x| x.as_i64()) {
             // see https://apidocv2.bitz.plus/en/#error
             match status {
-                -101001 => {
-                    error!("Subscription type parameter error: {}", msg);
-                    panic!("Subscription type parameter error: {msg}");
-                }
-                -101002 => {
-                    error!("Fail to get subscribed symbol of trading pair: {}", msg);
-                    panic!("Fail to get subscribed symbol of trading pair: {msg}");
+                -101001 => MiscMessage::Error(format!("Subscription type parameter error: {msg}")),
+                -101002 => MiscMessage::Error(format!(
+                    "Fail to get subscribed symbol of trading pair: {msg}"
+                )),
+                -101003 => MiscMessage::Error(format!("k-line scale resolution error: {msg}")),
+                _ => {
+                    warn!("Received {} from {}", msg, EXCHANGE_NAME);
+                    MiscMessage::Other
                 }
-                -101003 => {
-                    error!("k-line scale resolution error: {}", msg);
-                    panic!("k-line scale resolution error: {msg}");
-                }
-                _ => warn!("Received {} from {}", msg, EXCHANGE_NAME),
             }
-            MiscMessage::Other
         } else {
             warn!("Received {} from {}", msg, EXCHANGE_NAME);
             MiscMessage::Other
@@ -100,6 +115,9 @@ impl MessageHandler for BitzMessageHandler {
     }
 }
 
+impl_fuzz_message_handler!(BitzMessageHandler {});
+impl_new_message_handler!(BitzMessageHandler {});
+
 impl BitzCommandTranslator {
     fn symbol_channels_to_command(pair: &str, channels: &[String], subscribe: bool) -> String {
         format!(
@@ -1,4 +1,4 @@
-mod bitz_spot;
+pub(super) mod bitz_spot;
 // mod bitz_swap;
 
 pub use bitz_spot::BitzSpotWSClient;
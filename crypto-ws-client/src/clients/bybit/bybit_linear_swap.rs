@@ -5,13 +5,28 @@ use crate::{
         Candlestick, Level3OrderBook, OrderBook, OrderBookTopK, Ticker, Trade, BBO,
     },
     common::{command_translator::CommandTranslator, ws_client_internal::WSClientInternal},
-    WSClient,
+    CandleInterval, WSClient,
 };
 
 use super::utils::{BybitMessageHandler, EXCHANGE_NAME};
 
 const WEBSOCKET_URL: &str = "wss://stream.bybit.com/realtime_public";
 
+pub(crate) const CANDLESTICK_INTERVALS: &[CandleInterval] = &[
+    CandleInterval::MIN1,
+    CandleInterval::MIN3,
+    CandleInterval::MIN5,
+    CandleInterval::MIN15,
+    CandleInterval::MIN30,
+    CandleInterval::HOUR1,
+    CandleInterval::HOUR2,
+    CandleInterval::HOUR4,
+    CandleInterval::HOUR6,
+    CandleInterval::DAY1,
+    CandleInterval::WEEK1,
+    CandleInterval::MONTH1,
+];
+
 /// Bybit LinearSwap market.
 ///
 /// * WebSocket API doc: <https://bybit-exchange.github.io/docs/inverse/#t-websocket>
@@ -0,0 +1,412 @@
+use async_trait::async_trait;
+use std::{
+    collections::{HashMap, HashSet},
+    sync::atomic::{AtomicU64, Ordering},
+};
+use tokio_tungstenite::tungstenite::Message;
+
+use log::*;
+use serde_json::Value;
+
+use crate::{
+    clients::common_traits::{
+        Candlestick, Level3OrderBook, OrderBook, OrderBookTopK, Ticker, Trade, BBO,
+    },
+    common::{
+        command_translator::CommandTranslator,
+        message_handler::{MessageHandler, MiscMessage},
+        utils::ensure_frame_size,
+        ws_client_internal::WSClientInternal,
+    },
+    CandleInterval, WSClient,
+};
+
+use super::utils::EXCHANGE_NAME;
+
+const SPOT_WEBSOCKET_URL: &str = "wss://stream.bybit.com/v5/public/spot";
+const LINEAR_WEBSOCKET_URL: &str = "wss://stream.bybit.com/v5/public/linear";
+const INVERSE_WEBSOCKET_URL: &str = "wss://stream.bybit.com/v5/public/inverse";
+const OPTION_WEBSOCKET_URL: &str = "wss://stream.bybit.com/v5/public/option";
+
+// The total length of args in one request cannot exceed 21,000 characters,
+// see https://bybit-exchange.github.io/docs/v5/ws/connect#how-to-subscribe-to-topics
+const WS_FRAME_SIZE: usize = 21000;
+// Spot can input up to 10 args for each subscription request
+const SPOT_MAX_TOPICS_PER_COMMAND: usize = 10;
+
+pub(crate) const CANDLESTICK_INTERVALS: &[CandleInterval] = &[
+    CandleInterval::MIN1,
+    CandleInterval::MIN3,
+    CandleInterval::MIN5,
+    CandleInterval::MIN15,
+    CandleInterval::MIN30,
+    CandleInterval::HOUR1,
+    CandleInterval::HOUR2,
+    CandleInterval::HOUR4,
+    CandleInterval::HOUR6,
+    CandleInterval::HOUR12,
+    CandleInterval::DAY1,
+    CandleInterval::WEEK1,
+    CandleInterval::MONTH1,
+];
+
+/// Bybit v5 Spot market.
+///
+/// * WebSocket API doc: <https://bybit-exchange.github.io/docs/v5/ws/connect>
+/// * Trading at: <https://www.bybit.com/en/trade/spot/BTC/USDT>
+pub struct BybitV5SpotWSClient {
+    client: WSClientInternal<BybitV5MessageHandler>,
+    translator: BybitV5CommandTranslator,
+}
+
+/// Bybit v5 Linear markets, including USDT and USDC perpetuals and futures.
+///
+/// * WebSocket API doc: <https://bybit-exchange.github.io/docs/v5/ws/connect>
+/// * Trading at: <https://www.bybit.com/trade/usdt/BTCUSDT>
+pub struct BybitV5LinearWSClient {
+    client: WSClientInternal<BybitV5MessageHandler>,
+    translator: BybitV5CommandTranslator,
+}
+
+/// Bybit v5 Inverse markets, including inverse perpetuals and futures.
+///
+/// * WebSocket API doc: <https://bybit-exchange.github.io/docs/v5/ws/connect>
+/// * Trading at: <https://www.bybit.com/trade/inverse/BTCUSD>
+pub struct BybitV5InverseWSClient {
+    client: WSClientInternal<BybitV5MessageHandler>,
+    translator: BybitV5CommandTranslator,
+}
+
+/// Bybit v5 Option market.
+///
+/// Option trades are pushed per base coin, so `subscribe_trade()` accepts
+/// base coins such as `BTC` and `ETH`, while other channels accept option
+/// symbols such as `BTC-29DEC23-40000-C`.
+///
+/// * WebSocket API doc: <https://bybit-exchange.github.io/docs/v5/ws/connect>
+/// * Trading at: <https://www.bybit.com/trade/option/usdc/BTC>
+pub struct BybitV5OptionWSClient {
+    client: WSClientInternal<BybitV5MessageHandler>,
+    translator: BybitV5CommandTranslator,
+}
+
+impl_new_constructor!(
+    BybitV5SpotWSClient,
+    EXCHANGE_NAME,
+    SPOT_WEBSOCKET_URL,
+    BybitV5MessageHandler::default(),
+    BybitV5CommandTranslator::new(Some(SPOT_MAX_TOPICS_PER_COMMAND))
+);
+impl_new_constructor!(
+    BybitV5LinearWSClient,
+    EXCHANGE_NAME,
+    LINEAR_WEBSOCKET_URL,
+    BybitV5MessageHandler::default(),
+    BybitV5CommandTranslator::new(None)
+);
+impl_new_constructor!(
+    BybitV5InverseWSClient,
+    EXCHANGE_NAME,
+    INVERSE_WEBSOCKET_URL,
+    BybitV5MessageHandler::default(),
+    BybitV5CommandTranslator::new(None)
+);
+impl_new_constructor!(
+    BybitV5OptionWSClient,
+    EXCHANGE_NAME,
+    OPTION_WEBSOCKET_URL,
+    BybitV5MessageHandler::default(),
+    BybitV5CommandTranslator::new(None)
+);
+
+impl_trait!(Trade, BybitV5SpotWSClient, subscribe_trade, "publicTrade");
+impl_trait!(OrderBook, BybitV5SpotWSClient, subscribe_orderbook, "orderbook.50");
+impl_trait!(BBO, BybitV5SpotWSClient, subscribe_bbo, "orderbook.1");
+impl_trait!(Ticker, BybitV5SpotWSClient, subscribe_ticker, "tickers");
+impl_candlestick!(BybitV5SpotWSClient);
+panic_l2_topk!(BybitV5SpotWSClient);
+panic_l3_orderbook!(BybitV5SpotWSClient);
+impl_ws_client_trait!(BybitV5SpotWSClient);
+
+impl_trait!(Trade, BybitV5LinearWSClient, subscribe_trade, "publicTrade");
+impl_trait!(OrderBook, BybitV5LinearWSClient, subscribe_orderbook, "orderbook.50");
+impl_trait!(BBO, BybitV5LinearWSClient, subscribe_bbo, "orderbook.1");
+impl_trait!(Ticker, BybitV5LinearWSClient, subscribe_ticker, "tickers");
+impl_candlestick!(BybitV5LinearWSClient);
+panic_l2_topk!(BybitV5LinearWSClient);
+panic_l3_orderbook!(BybitV5LinearWSClient);
+impl_ws_client_trait!(BybitV5LinearWSClient);
+
+impl_trait!(Trade, BybitV5InverseWSClient, subscribe_trade, "publicTrade");
+impl_trait!(OrderBook, BybitV5InverseWSClient, subscribe_orderbook, "orderbook.50");
+impl_trait!(BBO, BybitV5InverseWSClient, subscribe_bbo, "orderbook.1");
+impl_trait!(Ticker, BybitV5InverseWSClient, subscribe_ticker, "tickers");
+impl_candlestick!(BybitV5InverseWSClient);
+panic_l2_topk!(BybitV5InverseWSClient);
+panic_l3_orderbook!(BybitV5InverseWSClient);
+impl_ws_client_trait!(BybitV5InverseWSClient);
+
+impl_trait!(Trade, BybitV5OptionWSClient, subscribe_trade, "publicTrade");
+impl_trait!(OrderBook, BybitV5OptionWSClient, subscribe_orderbook, "orderbook.25");
+impl_trait!(Ticker, BybitV5OptionWSClient, subscribe_ticker, "tickers");
+panic_bbo!(BybitV5OptionWSClient);
+panic_candlestick!(BybitV5OptionWSClient);
+panic_l2_topk!(BybitV5OptionWSClient);
+panic_l3_orderbook!(BybitV5OptionWSClient);
+impl_ws_client_trait!(BybitV5OptionWSClient);
+
+impl BybitV5LinearWSClient {
+    /// Subscribes to liquidation channels.
+    pub async fn subscribe_liquidation(&self, symbols: &[String]) {
+        let topics = symbols
+            .iter()
+            .map(|symbol| ("liquidation".to_string(), symbol.to_string()))
+            .collect::<Vec<(String, String)>>();
+        self.subscribe(&topics).await;
+    }
+}
+
+impl BybitV5InverseWSClient {
+    /// Subscribes to liquidation channels.
+    pub async fn subscribe_liquidation(&self, symbols: &[String]) {
+        let topics = symbols
+            .iter()
+            .map(|symbol| ("liquidation".to_string(), symbol.to_string()))
+            .collect::<Vec<(String, String)>>();
+        self.subscribe(&topics).await;
+    }
+}
+
+#[derive(Default)]
+struct BybitV5MessageHandler {
+    // orderbook topics which have received a snapshot
+    snapshot_topics: HashSet<String>,
+}
+
+struct BybitV5CommandTranslator {
+    max_topics_per_command: Option<usize>,
+    req_id: AtomicU64, // every request gets a unique req_id
+}
+
+impl MessageHandler for BybitV5MessageHandler {
+    fn handle_message(&mut self, msg: &str) -> MiscMessage {
+        let resp = serde_json::from_str::<HashMap<String, Value>>(msg);
+        if resp.is_err() {
+            error!("{} is not a JSON object, {}", msg, EXCHANGE_NAME);
+            return MiscMessage::Other;
+        }
+        let obj = resp.unwrap();
+
+        if let Some(topic) = obj.get("topic").and_then(|x| x.as_str()) {
+            if !obj.contains_key("data") {
+                warn!("Received {} from {}", msg, EXCHANGE_NAME);
+                return MiscMessage::Other;
+            }
+            if topic.starts_with("orderbook.") {
+                // The first message of an orderbook topic is a snapshot, the following
+                // messages are deltas. A new snapshot resets the orderbook, e.g., after
+                // a service restart, see https://bybit-exchange.github.io/docs/v5/websocket/public/orderbook
+                match obj.get("type").and_then(|x| x.as_str()) {
+                    Some("snapshot") => {
+                        self.snapshot_topics.insert(topic.to_string());
+                    }
+                    Some("delta") if !self.snapshot_topics.contains(topic) => {
+                        warn!("Dropped a delta before snapshot, {} from {}", msg, EXCHANGE_NAME);
+                        return MiscMessage::Other;
+                    }
+                    _ => (),
+                }
+            }
+            MiscMessage::Normal
+        } else if let Some(op) = obj.get("op").and_then(|x| x.as_str()) {
+            let req_id = obj.get("req_id").and_then(|x| x.as_str()).unwrap_or_default();
+            let success = obj.get("success").and_then(|x| x.as_bool()).unwrap_or(true);
+            let ret_msg = obj.get("ret_msg").and_then(|x| x.as_str()).unwrap_or_default();
+            match op {
+                // Option replies {"op":"pong"}, others reply {"op":"ping","ret_msg":"pong"}
+                "pong" => MiscMessage::Pong,
+                "ping" if ret_msg == "pong" => MiscMessage::Pong,
+                "subscribe" | "unsubscribe" => {
+                    if success {
+                        info!("Request {} succeeded, {} from {}", req_id, msg, EXCHANGE_NAME);
+                    } else if ret_msg.starts_with("Invalid symbol") {
+                        // Sometimes symbols returned from RESTful API don't exist in WebSocket yet
+                        error!("Request {} failed, {} from {}", req_id, msg, EXCHANGE_NAME);
+                    } else {
+                        return MiscMessage::Error(format!(
+                            "Request {req_id} failed, {msg} from {EXCHANGE_NAME}"
+                        ));
+                    }
+                    MiscMessage::Other
+                }
+                _ => {
+                    warn!("Received {} from {}", msg, EXCHANGE_NAME);
+                    MiscMessage::Other
+                }
+            }
+        } else {
+            warn!("Received {} from {}", msg, EXCHANGE_NAME);
+            MiscMessage::Other
+        }
+    }
+
+    fn get_ping_msg_and_interval(&self) -> Option<(Message, u64)> {
+        // Send the ping heartbeat packet every 20 seconds to maintain the connection,
+        // see https://bybit-exchange.github.io/docs/v5/ws/connect#how-to-send-the-heartbeat-packet
+        Some((Message::Text(r#"{"req_id":"ping","op":"ping"}"#.to_string()), 20))
+    }
+}
+
+impl_fuzz_message_handler!(BybitV5MessageHandler { snapshot_topics: HashSet::new() });
+impl_new_message_handler!(BybitV5MessageHandler { snapshot_topics: HashSet::new() });
+
+impl BybitV5CommandTranslator {
+    fn new(max_topics_per_command: Option<usize>) -> Self {
+        BybitV5CommandTranslator { max_topics_per_command, req_id: AtomicU64::new(1) }
+    }
+
+    fn topics_to_command(topics: &[(String, String)], subscribe: bool) -> String {
+        let raw_channels = topics
+            .iter()
+            .map(|(channel, symbol)| format!("{channel}.{symbol}"))
+            .collect::<Vec<String>>();
+        format!(
+            r#"{{"op":"{}","args":{}}}"#,
+            if subscribe { "subscribe" } else { "unsubscribe" },
+            serde_json::to_string(&raw_channels).unwrap()
+        )
+    }
+
+    // https://bybit-exchange.github.io/docs/v5/websocket/public/kline
+    fn to_candlestick_raw_channel(interval: usize) -> String {
+        let interval_str = match interval {
+            60 => "1",
+            180 => "3",
+            300 => "5",
+            900 => "15",
+            1800 => "30",
+            3600 => "60",
+            7200 => "120",
+            14400 => "240",
+            21600 => "360",
+            43200 => "720",
+            86400 => "D",
+            604800 => "W",
+            2592000 => "M",
+            _ => panic!(
+                "Bybit v5 has intervals 1min,3min,5min,15min,30min,1hour,2hour,4hour,6hour,12hour,1day,1week,1mon"
+            ),
+        };
+        format!("kline.{interval_str}")
+    }
+}
+
+impl CommandTranslator for BybitV5CommandTranslator {
+    fn translate_to_commands(&self, subscribe: bool, topics: &[(String, String)]) -> Vec<String> {
+        ensure_frame_size(
+            topics,
+            subscribe,
+            Self::topics_to_command,
+            WS_FRAME_SIZE,
+            self.max_topics_per_command,
+        )
+        .into_iter()
+        .map(|command| {
+            // prepend a req_id which will be echoed back in the acknowledgement
+            let req_id = self.req_id.fetch_add(1, Ordering::SeqCst);
+            format!(r#"{{"req_id":"{}",{}"#, req_id, &command[1..])
+        })
+        .collect()
+    }
+
+    fn translate_to_candlestick_commands(
+        &self,
+        subscribe: bool,
+        symbol_interval_list: &[(String, usize)],
+    ) -> Vec<String> {
+        let topics = symbol_interval_list
+            .iter()
+            .map(|(symbol, interval)| {
+                let channel = Self::to_candlestick_raw_channel(*interval);
+                (channel, symbol.to_string())
+            })
+            .collect::<Vec<(String, String)>>();
+        self.translate_to_commands(subscribe, &topics)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::common::{
+        command_translator::CommandTranslator,
+        message_handler::{MessageHandler, MiscMessage},
+    };
+
+    #[test]
+    fn test_multiple_channels() {
+        let translator = super::BybitV5CommandTranslator::new(None);
+        let commands = translator.translate_to_commands(
+            true,
+            &[
+                ("publicTrade".to_string(), "BTCUSDT".to_string()),
+                ("orderbook.50".to_string(), "BTCUSDT".to_string()),
+            ],
+        );
+
+        assert_eq!(1, commands.len());
+        assert_eq!(
+            r#"{"req_id":"1","op":"subscribe","args":["publicTrade.BTCUSDT","orderbook.50.BTCUSDT"]}"#,
+            commands[0]
+        );
+    }
+
+    #[test]
+    fn test_spot_max_topics() {
+        let translator =
+            super::BybitV5CommandTranslator::new(Some(super::SPOT_MAX_TOPICS_PER_COMMAND));
+        let topics = (0..15)
+            .map(|i| ("publicTrade".to_string(), format!("COIN{i}USDT")))
+            .collect::<Vec<(String, String)>>();
+        let commands = translator.translate_to_commands(true, &topics);
+
+        assert_eq!(2, commands.len());
+        assert!(commands[0].starts_with(r#"{"req_id":"1","op":"subscribe""#));
+        assert!(commands[1].starts_with(r#"{"req_id":"2","op":"subscribe""#));
+    }
+
+    #[test]
+    fn test_candlestick() {
+        let translator = super::BybitV5CommandTranslator::new(None);
+        let commands =
+            translator.translate_to_candlestick_commands(false, &[("BTCUSD".to_string(), 43200)]);
+
+        assert_eq!(1, commands.len());
+        assert_eq!(r#"{"req_id":"1","op":"unsubscribe","args":["kline.720.BTCUSD"]}"#, commands[0]);
+    }
+
+    #[test]
+    fn test_delta_before_snapshot() {
+        let mut handler = super::BybitV5MessageHandler::default();
+        let delta = r#"{"topic":"orderbook.50.BTCUSDT","type":"delta","ts":1687940967466,"data":{"s":"BTCUSDT","b":[],"a":[],"u":177400507,"seq":66544703342}}"#;
+        let snapshot = r#"{"topic":"orderbook.50.BTCUSDT","type":"snapshot","ts":1687940967466,"data":{"s":"BTCUSDT","b":[],"a":[],"u":177400506,"seq":66544703341}}"#;
+
+        assert!(matches!(handler.handle_message(delta), MiscMessage::Other));
+        assert!(matches!(handler.handle_message(snapshot), MiscMessage::Normal));
+        assert!(matches!(handler.handle_message(delta), MiscMessage::Normal));
+    }
+
+    #[test]
+    fn test_pong() {
+        let mut handler = super::BybitV5MessageHandler::default();
+        assert!(matches!(
+            handler.handle_message(
+                r#"{"success":true,"ret_msg":"pong","conn_id":"0970e817","req_id":"ping","op":"ping"}"#
+            ),
+            MiscMessage::Pong
+        ));
+        assert!(matches!(
+            handler.handle_message(r#"{"args":["1672916271846"],"op":"pong"}"#),
+            MiscMessage::Pong
+        ));
+    }
+}
@@ -1,6 +1,10 @@
-mod bybit_inverse;
-mod bybit_linear_swap;
-mod utils;
+pub(super) mod bybit_inverse;
+pub(super) mod bybit_linear_swap;
+pub(super) mod bybit_v5;
+pub(super) mod utils;
 
 pub use bybit_inverse::BybitInverseWSClient;
 pub use bybit_linear_swap::BybitLinearSwapWSClient;
+pub use bybit_v5::{
+    BybitV5InverseWSClient, BybitV5LinearWSClient, BybitV5OptionWSClient, BybitV5SpotWSClient,
+};
@@ -24,22 +24,24 @@ pub(super) struct BybitMessageHandler {}
 
 impl MessageHandler for BybitMessageHandler {
     fn handle_message(&mut self, msg: &str) -> MiscMessage {
-        let obj = serde_json::from_str::<HashMap<String, Value>>(msg).unwrap();
+        let resp = serde_json::from_str::<HashMap<String, Value>>(msg);
+        if resp.is_err() {
+            error!("{} is not a JSON string, {}", msg, EXCHANGE_NAME);
+            return MiscMessage::Other;
+        }
+        let obj = resp.unwrap();
 
         if obj.contains_key("topic") && obj.contains_key("data") {
             MiscMessage::Normal
         } else {
-            if obj.contains_key("success") {
-                if obj.get("success").unwrap().as_bool().unwrap() {
+            if let Some(success) = obj.get("success") {
+                if success.as_bool().unwrap_or_default() {
                     info!("Received {} from {}", msg, EXCHANGE_NAME);
-                    if obj.contains_key("ret_msg")
-                        && obj.get("ret_msg").unwrap().as_str().unwrap() == "pong"
-                    {
+                    if obj.get("ret_msg").and_then(|x| x.as_str()) == Some("pong") {
                         return MiscMessage::Pong;
                     }
                 } else {
-                    error!("Received {} from {}", msg, EXCHANGE_NAME);
-                    panic!("Received {msg} from {EXCHANGE_NAME}");
+                    return MiscMessage::Error(format!("Received {msg} from {EXCHANGE_NAME}"));
                 }
             } else {
                 warn!("Received {} from {}", msg, EXCHANGE_NAME);
@@ -56,6 +58,9 @@ impl MessageHandler for BybitMessageHandler {
     }
 }
 
+impl_fuzz_message_handler!(BybitMessageHandler {});
+impl_new_message_handler!(BybitMessageHandler {});
+
 #[cfg(test)]
 mod tests {
     #[test]
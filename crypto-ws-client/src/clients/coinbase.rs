@@ -0,0 +1,285 @@
+use async_trait::async_trait;
+use nonzero_ext::nonzero;
+use std::{
+    collections::{BTreeMap, HashMap},
+    num::NonZeroU32,
+};
+use tokio_tungstenite::tungstenite::Message;
+
+use crate::{
+    clients::common_traits::{
+        Candlestick, Level3OrderBook, OrderBook, OrderBookTopK, Ticker, Trade, BBO,
+    },
+    common::{
+        command_translator::CommandTranslator,
+        message_handler::{MessageHandler, MiscMessage},
+        ws_client_internal::WSClientInternal,
+    },
+    CandleInterval, WSClient,
+};
+use log::*;
+use serde_json::Value;
+
+pub(super) const EXCHANGE_NAME: &str = "coinbase";
+
+const WEBSOCKET_URL: &str = "wss://advanced-trade-ws.coinbase.com";
+
+// Unauthenticated connections can send at most 8 messages per second,
+// see https://docs.cdp.coinbase.com/advanced-trade/docs/ws-rate-limits
+const UPLINK_LIMIT: (NonZeroU32, std::time::Duration) =
+    (nonzero!(8u32), std::time::Duration::from_secs(1));
+
+pub(super) const CANDLESTICK_INTERVALS: &[CandleInterval] = &[CandleInterval::MIN5];
+
+/// The WebSocket client for Coinbase Advanced Trade, which replaces
+/// CoinbasePro.
+///
+/// Coinbase Advanced Trade has only Spot market.
+///
+///   * WebSocket API doc: <https://docs.cdp.coinbase.com/advanced-trade/docs/ws-overview>
+///   * Trading at: <https://www.coinbase.com/advanced-trade>
+pub struct CoinbaseWSClient {
+    client: WSClientInternal<CoinbaseMessageHandler>,
+    translator: CoinbaseCommandTranslator,
+}
+
+impl CoinbaseWSClient {
+    pub async fn new(tx: std::sync::mpsc::Sender<String>, url: Option<&str>) -> Self {
+        let real_url = match url {
+            Some(endpoint) => endpoint,
+            None => WEBSOCKET_URL,
+        };
+        CoinbaseWSClient {
+            client: WSClientInternal::connect(
+                EXCHANGE_NAME,
+                real_url,
+                CoinbaseMessageHandler { last_sequence_num: None },
+                Some(UPLINK_LIMIT),
+                tx,
+            )
+            .await,
+            translator: CoinbaseCommandTranslator {},
+        }
+    }
+}
+
+impl_trait!(Trade, CoinbaseWSClient, subscribe_trade, "market_trades");
+impl_trait!(Ticker, CoinbaseWSClient, subscribe_ticker, "ticker");
+#[rustfmt::skip]
+impl_trait!(OrderBook, CoinbaseWSClient, subscribe_orderbook, "level2");
+impl_candlestick!(CoinbaseWSClient);
+
+panic_bbo!(CoinbaseWSClient);
+panic_l2_topk!(CoinbaseWSClient);
+panic_l3_orderbook!(CoinbaseWSClient);
+
+impl_ws_client_trait!(CoinbaseWSClient);
+
+struct CoinbaseMessageHandler {
+    // sequence_num increases by one for every message on the same connection
+    last_sequence_num: Option<u64>,
+}
+struct CoinbaseCommandTranslator {}
+
+impl MessageHandler for CoinbaseMessageHandler {
+    fn handle_message(&mut self, msg: &str) -> MiscMessage {
+        let resp = serde_json::from_str::<HashMap<String, Value>>(msg);
+        if resp.is_err() {
+            error!("{} is not a JSON string, {}", msg, EXCHANGE_NAME);
+            return MiscMessage::Other;
+        }
+        let obj = resp.unwrap();
+
+        if let Some(msg_type) = obj.get("type").and_then(|x| x.as_str()) {
+            if msg_type == "error" {
+                error!("Received {} from {}", msg, EXCHANGE_NAME);
+                if obj
+                    .get("message")
+                    .and_then(|x| x.as_str())
+                    .unwrap_or_default()
+                    .contains("failure to subscribe")
+                {
+                    return MiscMessage::Error(format!("Received {msg} from {EXCHANGE_NAME}"));
+                }
+            } else {
+                warn!("Received {} from {}", msg, EXCHANGE_NAME);
+            }
+            return MiscMessage::Other;
+        }
+
+        if let Some(sequence_num) = obj.get("sequence_num").and_then(|x| x.as_u64()) {
+            if let Some(last_sequence_num) = self.last_sequence_num {
+                if sequence_num != last_sequence_num + 1 {
+                    // Messages were dropped, level2 books built from this connection are no
+                    // longer consistent, so reconnect and receive fresh snapshots
+                    error!(
+                        "Sequence gap detected, expected {} but got {}, {}",
+                        last_sequence_num + 1,
+                        sequence_num,
+                        EXCHANGE_NAME
+                    );
+                    self.last_sequence_num = None;
+                    return MiscMessage::Reconnect;
+                }
+            }
+            self.last_sequence_num = Some(sequence_num);
+        }
+
+        match obj.get("channel").and_then(|x| x.as_str()) {
+            Some("subscriptions") => {
+                info!("Received {} from {}", msg, EXCHANGE_NAME);
+                MiscMessage::Other
+            }
+            Some("heartbeats") => {
+                debug!("Received {} from {}", msg, EXCHANGE_NAME);
+                MiscMessage::Other
+            }
+            Some(_) => MiscMessage::Normal,
+            None => {
+                warn!("Received {} from {}", msg, EXCHANGE_NAME);
+                MiscMessage::Other
+            }
+        }
+    }
+
+    fn get_ping_msg_and_interval(&self) -> Option<(Message, u64)> {
+        // The heartbeats channel keeps the connection alive, see
+        // translate_to_commands()
+        None
+    }
+}
+
+impl_fuzz_message_handler!(CoinbaseMessageHandler { last_sequence_num: None });
+impl_new_message_handler!(CoinbaseMessageHandler { last_sequence_num: None });
+
+impl CoinbaseCommandTranslator {
+    fn channel_to_command(channel: &str, symbols: &[String], subscribe: bool) -> String {
+        format!(
+            r#"{{"type":"{}","product_ids":{},"channel":"{}"}}"#,
+            if subscribe { "subscribe" } else { "unsubscribe" },
+            serde_json::to_string(symbols).unwrap(),
+            channel,
+        )
+    }
+}
+
+impl CommandTranslator for CoinbaseCommandTranslator {
+    fn translate_to_commands(&self, subscribe: bool, topics: &[(String, String)]) -> Vec<String> {
+        let mut channel_symbols = BTreeMap::<String, Vec<String>>::new();
+        for (channel, symbol) in topics {
+            match channel_symbols.get_mut(channel) {
+                Some(symbols) => symbols.push(symbol.to_string()),
+                None => {
+                    channel_symbols.insert(channel.to_string(), vec![symbol.to_string()]);
+                }
+            }
+        }
+
+        // Each command can carry only one channel
+        let mut commands: Vec<String> = channel_symbols
+            .iter()
+            .map(|(channel, symbols)| Self::channel_to_command(channel, symbols, subscribe))
+            .collect();
+
+        // Subscriptions with sparse updates are closed after 60-90 seconds
+        // unless the heartbeats channel is subscribed on the same connection
+        if subscribe && !commands.is_empty() && !channel_symbols.contains_key("heartbeats") {
+            commands.push(r#"{"type":"subscribe","channel":"heartbeats"}"#.to_string());
+        }
+
+        commands
+    }
+
+    fn translate_to_candlestick_commands(
+        &self,
+        subscribe: bool,
+        symbol_interval_list: &[(String, usize)],
+    ) -> Vec<String> {
+        let topics = symbol_interval_list
+            .iter()
+            .map(|(symbol, interval)| {
+                if *interval != 300 {
+                    panic!("Coinbase has only 5m candlesticks");
+                }
+                ("candles".to_string(), symbol.to_string())
+            })
+            .collect::<Vec<(String, String)>>();
+        self.translate_to_commands(subscribe, &topics)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::common::{
+        command_translator::CommandTranslator,
+        message_handler::{MessageHandler, MiscMessage},
+    };
+
+    #[test]
+    fn test_two_symbols() {
+        let translator = super::CoinbaseCommandTranslator {};
+        let commands = translator.translate_to_commands(
+            true,
+            &[
+                ("market_trades".to_string(), "BTC-USD".to_string()),
+                ("market_trades".to_string(), "ETH-USD".to_string()),
+            ],
+        );
+
+        assert_eq!(2, commands.len());
+        assert_eq!(
+            r#"{"type":"subscribe","product_ids":["BTC-USD","ETH-USD"],"channel":"market_trades"}"#,
+            commands[0]
+        );
+        assert_eq!(r#"{"type":"subscribe","channel":"heartbeats"}"#, commands[1]);
+    }
+
+    #[test]
+    fn test_two_channels() {
+        let translator = super::CoinbaseCommandTranslator {};
+        let commands = translator.translate_to_commands(
+            false,
+            &[
+                ("market_trades".to_string(), "BTC-USD".to_string()),
+                ("level2".to_string(), "BTC-USD".to_string()),
+            ],
+        );
+
+        assert_eq!(2, commands.len());
+        assert_eq!(
+            r#"{"type":"unsubscribe","product_ids":["BTC-USD"],"channel":"level2"}"#,
+            commands[0]
+        );
+        assert_eq!(
+            r#"{"type":"unsubscribe","product_ids":["BTC-USD"],"channel":"market_trades"}"#,
+            commands[1]
+        );
+    }
+
+    #[test]
+    fn test_candlestick() {
+        let translator = super::CoinbaseCommandTranslator {};
+        let commands = translator.translate_to_candlestick_commands(
+            true,
+            &[("BTC-USD".to_string(), 300), ("ETH-USD".to_string(), 300)],
+        );
+
+        assert_eq!(2, commands.len());
+        assert_eq!(
+            r#"{"type":"subscribe","product_ids":["BTC-USD","ETH-USD"],"channel":"candles"}"#,
+            commands[0]
+        );
+    }
+
+    #[test]
+    fn test_sequence_gap() {
+        let mut handler = super::CoinbaseMessageHandler { last_sequence_num: None };
+        let heartbeat = r#"{"channel":"heartbeats","client_id":"","timestamp":"2023-06-23T20:31:26.122969572Z","sequence_num":0,"events":[{"current_time":"2023-06-23 20:31:26.121961769 +0000 UTC m=+91717.525857105","heartbeat_counter":3049}]}"#;
+        let trade = r#"{"channel":"market_trades","client_id":"","timestamp":"2023-02-09T20:19:35.39625135Z","sequence_num":1,"events":[{"type":"snapshot","trades":[{"trade_id":"000000000","product_id":"ETH-USD","price":"1260.01","size":"0.3","side":"BUY","time":"2019-08-14T20:42:27.265Z"}]}]}"#;
+        let gap = r#"{"channel":"market_trades","client_id":"","timestamp":"2023-02-09T20:19:35.39625135Z","sequence_num":3,"events":[]}"#;
+
+        assert!(matches!(handler.handle_message(heartbeat), MiscMessage::Other));
+        assert!(matches!(handler.handle_message(trade), MiscMessage::Normal));
+        assert!(matches!(handler.handle_message(gap), MiscMessage::Reconnect));
+    }
+}
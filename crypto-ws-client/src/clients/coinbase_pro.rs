@@ -64,31 +64,33 @@ impl MessageHandler for CoinbaseProMessageHandler {
         }
         let obj = resp.unwrap();
 
-        match obj.get("type").unwrap().as_str().unwrap() {
-            "error" => {
+        match obj.get("type").and_then(|x| x.as_str()) {
+            Some("error") => {
                 error!("Received {} from {}", msg, EXCHANGE_NAME);
-                if obj.contains_key("reason")
-                    && obj
-                        .get("reason")
-                        .unwrap()
-                        .as_str()
-                        .unwrap()
-                        .contains("is not a valid product")
+                if obj
+                    .get("reason")
+                    .and_then(|x| x.as_str())
+                    .unwrap_or_default()
+                    .contains("is not a valid product")
                 {
-                    panic!("Received {msg} from {EXCHANGE_NAME}");
+                    MiscMessage::Error(format!("Received {msg} from {EXCHANGE_NAME}"))
                 } else {
                     MiscMessage::Other
                 }
             }
-            "subscriptions" => {
+            Some("subscriptions") => {
                 info!("Received {} from {}", msg, EXCHANGE_NAME);
                 MiscMessage::Other
             }
-            "heartbeat" => {
+            Some("heartbeat") => {
                 debug!("Received {} from {}", msg, EXCHANGE_NAME);
                 MiscMessage::Other
             }
-            _ => MiscMessage::Normal,
+            Some(_) => MiscMessage::Normal,
+            None => {
+                warn!("Received {} from {}", msg, EXCHANGE_NAME);
+                MiscMessage::Other
+            }
         }
     }
 
@@ -97,6 +99,9 @@ impl MessageHandler for CoinbaseProMessageHandler {
     }
 }
 
+impl_fuzz_message_handler!(CoinbaseProMessageHandler {});
+impl_new_message_handler!(CoinbaseProMessageHandler {});
+
 impl CommandTranslator for CoinbaseProCommandTranslator {
     fn translate_to_commands(&self, subscribe: bool, topics: &[(String, String)]) -> Vec<String> {
         let mut commands: Vec<String> = Vec::new();
@@ -1,5 +1,7 @@
 use async_trait::async_trait;
 
+use crate::{CandleInterval, Error};
+
 // tick-by-tick trade
 #[async_trait]
 pub(super) trait Trade {
@@ -46,8 +48,11 @@ pub(super) trait Candlestick {
     /// Subscribes to candlestick channels which send OHLCV messages.
     ///
     /// `symbol_interval_list` is a list of symbols and intervals of
-    /// candlesticks in seconds.
-    async fn subscribe_candlestick(&self, symbol_interval_list: &[(String, usize)]);
+    /// candlesticks, nothing is subscribed if any interval is unsupported.
+    async fn subscribe_candlestick(
+        &self,
+        symbol_interval_list: &[(String, CandleInterval)],
+    ) -> Result<(), Error>;
 }
 
 macro_rules! impl_trait {
@@ -65,14 +70,26 @@ macro_rules! impl_trait {
     };
 }
 
+/// Implement the Candlestick trait, intervals are checked against the
+/// `CANDLESTICK_INTERVALS` constant in scope.
 macro_rules! impl_candlestick {
     ($struct_name:ident) => {
         #[async_trait]
         impl Candlestick for $struct_name {
-            async fn subscribe_candlestick(&self, symbol_interval_list: &[(String, usize)]) {
+            async fn subscribe_candlestick(
+                &self,
+                symbol_interval_list: &[(String, crate::CandleInterval)],
+            ) -> Result<(), crate::Error> {
+                let symbol_interval_list =
+                    crate::common::candle_interval::check_candlestick_intervals(
+                        EXCHANGE_NAME,
+                        CANDLESTICK_INTERVALS,
+                        symbol_interval_list,
+                    )?;
                 let commands =
-                    self.translator.translate_to_candlestick_commands(true, symbol_interval_list);
+                    self.translator.translate_to_candlestick_commands(true, &symbol_interval_list);
                 self.client.send(&commands).await;
+                Ok(())
             }
         }
     };
@@ -140,8 +157,14 @@ macro_rules! panic_candlestick {
     ($struct_name:ident) => {
         #[async_trait]
         impl Candlestick for $struct_name {
-            async fn subscribe_candlestick(&self, _symbol_interval_list: &[(String, usize)]) {
-                panic!("{} does NOT have the candlestick websocket channel", EXCHANGE_NAME);
+            async fn subscribe_candlestick(
+                &self,
+                _symbol_interval_list: &[(String, crate::CandleInterval)],
+            ) -> Result<(), crate::Error> {
+                Err(crate::Error(format!(
+                    "{} does NOT have the candlestick websocket channel",
+                    EXCHANGE_NAME
+                )))
             }
         }
     };
@@ -201,12 +224,16 @@ macro_rules! impl_ws_client_trait {
                 <$struct_name as BBO>::subscribe_bbo(self, symbols).await
             }
 
-            async fn subscribe_candlestick(&self, symbol_interval_list: &[(String, usize)]) {
+            async fn subscribe_candlestick(
+                &self,
+                symbol_interval_list: &[(String, crate::CandleInterval)],
+            ) -> Result<(), crate::Error> {
                 <$struct_name as Candlestick>::subscribe_candlestick(self, symbol_interval_list)
                     .await
             }
 
             async fn subscribe(&self, topics: &[(String, String)]) {
+                self.client.add_channels(topics);
                 let commands = self.translator.translate_to_commands(true, topics);
                 self.client.send(&commands).await;
             }
@@ -230,3 +257,30 @@ macro_rules! impl_ws_client_trait {
         }
     };
 }
+
+/// Feed messages to a message handler, which is used by fuzz targets.
+macro_rules! impl_fuzz_message_handler {
+    ($handler:expr) => {
+        #[cfg(any(test, feature = "fuzzing"))]
+        pub(crate) fn fuzz_message_handler(msgs: &[&str]) {
+            use crate::common::message_handler::MessageHandler;
+            let mut handler = $handler;
+            for msg in msgs {
+                _ = handler.handle_message(msg);
+            }
+        }
+    };
+}
+
+/// Create a message handler configured as by `new()` of the client, which is
+/// used by `ReplayWSClient`.
+macro_rules! impl_new_message_handler {
+    ($handler:expr) => {
+        impl_new_message_handler!(new_message_handler, $handler);
+    };
+    ($fn_name:ident, $handler:expr) => {
+        pub(crate) fn $fn_name() -> crate::common::message_handler::BoxedMessageHandler {
+            Box::new($handler)
+        }
+    };
+}
@@ -12,7 +12,7 @@ use crate::{
         utils::{ensure_frame_size, topic_to_raw_channel},
         ws_client_internal::WSClientInternal,
     },
-    WSClient,
+    CandleInterval, WSClient,
 };
 
 use log::*;
@@ -26,6 +26,21 @@ const WEBSOCKET_URL: &str = "wss://www.deribit.com/ws/api/v2/";
 /// single frame in websocket connection frame exceeds the limit (32 kB)
 const WS_FRAME_SIZE: usize = 32 * 1024;
 
+pub(super) const CANDLESTICK_INTERVALS: &[CandleInterval] = &[
+    CandleInterval::MIN1,
+    CandleInterval::MIN3,
+    CandleInterval::MIN5,
+    CandleInterval::MIN10,
+    CandleInterval::MIN15,
+    CandleInterval::MIN30,
+    CandleInterval::HOUR1,
+    CandleInterval::HOUR2,
+    CandleInterval::HOUR3,
+    CandleInterval::HOUR6,
+    CandleInterval::HOUR12,
+    CandleInterval::DAY1,
+];
+
 /// The WebSocket client for Deribit.
 ///
 /// Deribit has InverseFuture, InverseSwap and Option markets.
@@ -98,26 +113,27 @@ impl DeribitCommandTranslator {
 
 impl MessageHandler for DeribitMessageHandler {
     fn handle_message(&mut self, msg: &str) -> MiscMessage {
-        let obj = serde_json::from_str::<HashMap<String, Value>>(msg).unwrap();
+        let resp = serde_json::from_str::<HashMap<String, Value>>(msg);
+        if resp.is_err() {
+            error!("{} is not a JSON string, {}", msg, EXCHANGE_NAME);
+            return MiscMessage::Other;
+        }
+        let obj = resp.unwrap();
 
         if obj.contains_key("error") {
-            panic!("Received {msg} from {EXCHANGE_NAME}");
+            MiscMessage::Error(format!("Received {msg} from {EXCHANGE_NAME}"))
         } else if obj.contains_key("result") {
             info!("Received {} from {}", msg, EXCHANGE_NAME);
             MiscMessage::Other
         } else if obj.contains_key("method") && obj.contains_key("params") {
-            match obj.get("method").unwrap().as_str().unwrap() {
+            match obj.get("method").and_then(|x| x.as_str()).unwrap_or_default() {
                 "subscription" => MiscMessage::Normal,
                 "heartbeat" => {
                     let param_type = obj
                         .get("params")
-                        .unwrap()
-                        .as_object()
-                        .unwrap()
-                        .get("type")
-                        .unwrap()
-                        .as_str()
-                        .unwrap();
+                        .and_then(|x| x.get("type"))
+                        .and_then(|x| x.as_str())
+                        .unwrap_or_default();
                     if param_type == "test_request" {
                         let ws_msg = Message::Text(r#"{"method": "public/test"}"#.to_string());
                         MiscMessage::WebSocket(ws_msg)
@@ -142,6 +158,9 @@ impl MessageHandler for DeribitMessageHandler {
     }
 }
 
+impl_fuzz_message_handler!(DeribitMessageHandler {});
+impl_new_message_handler!(DeribitMessageHandler {});
+
 impl CommandTranslator for DeribitCommandTranslator {
     fn translate_to_commands(&self, subscribe: bool, topics: &[(String, String)]) -> Vec<String> {
         let mut all_commands: Vec<String> =
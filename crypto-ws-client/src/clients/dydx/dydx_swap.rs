@@ -11,41 +11,93 @@ use crate::{
         message_handler::{MessageHandler, MiscMessage},
         ws_client_internal::WSClientInternal,
     },
-    WSClient,
+    CandleInterval, WSClient,
 };
 
 use super::EXCHANGE_NAME;
 use log::*;
 use serde_json::Value;
 
-const WEBSOCKET_URL: &str = "wss://api.dydx.exchange/v3/ws";
+const WEBSOCKET_URL: &str = "wss://indexer.dydx.trade/v4/ws";
 
-/// The WebSocket client for dYdX perpetual markets.
+pub(crate) const CANDLESTICK_INTERVALS: &[CandleInterval] = &[
+    CandleInterval::MIN1,
+    CandleInterval::MIN5,
+    CandleInterval::MIN15,
+    CandleInterval::MIN30,
+    CandleInterval::HOUR1,
+    CandleInterval::HOUR4,
+    CandleInterval::DAY1,
+];
+
+/// Converts the HTTP base URL of an indexer to its websocket endpoint.
+fn indexer_ws_url(base_url: &str) -> String {
+    let base_url = base_url.trim_end_matches('/');
+    let ws_url = if let Some(rest) = base_url.strip_prefix("https://") {
+        format!("wss://{rest}")
+    } else if let Some(rest) = base_url.strip_prefix("http://") {
+        format!("ws://{rest}")
+    } else {
+        base_url.to_string()
+    };
+    format!("{ws_url}/v4/ws")
+}
+
+/// The WebSocket client for dYdX v4 perpetual markets.
+///
+/// Data comes from the indexer. The endpoint is chosen from the `url`
+/// argument, then the `DYDX_INDEXER_URL` environment variable, so that a
+/// self-hosted indexer can be used, and falls back to the public indexer.
+///
+/// `DYDX_INDEXER_URL` is the HTTP base URL of the indexer, the same variable
+/// read by crypto-rest-client and crypto-markets, e.g.,
+/// `https://indexer.example.com`, its websocket endpoint is
+/// `wss://indexer.example.com/v4/ws`.
 ///
-/// * WebSocket API doc: <https://docs.dydx.exchange/#v3-websocket-api>
-/// * Trading at: <https://trade.dydx.exchange/trade>
+/// * WebSocket API doc: <https://docs.dydx.exchange/api_integration-indexer/indexer_websocket>
+/// * Trading at: <https://dydx.trade/trade/BTC-USD>
 pub struct DydxSwapWSClient {
     client: WSClientInternal<DydxMessageHandler>,
     translator: DydxCommandTranslator,
 }
 
-impl_new_constructor!(
-    DydxSwapWSClient,
-    EXCHANGE_NAME,
-    WEBSOCKET_URL,
-    DydxMessageHandler {},
-    DydxCommandTranslator {}
-);
+impl DydxSwapWSClient {
+    pub async fn new(tx: std::sync::mpsc::Sender<String>, url: Option<&str>) -> Self {
+        let real_url = match url {
+            Some(endpoint) => endpoint.to_string(),
+            None => std::env::var("DYDX_INDEXER_URL")
+                .map(|base_url| indexer_ws_url(&base_url))
+                .unwrap_or_else(|_| WEBSOCKET_URL.to_string()),
+        };
+        DydxSwapWSClient {
+            client: WSClientInternal::connect(
+                EXCHANGE_NAME,
+                &real_url,
+                DydxMessageHandler {},
+                None,
+                tx,
+            )
+            .await,
+            translator: DydxCommandTranslator {},
+        }
+    }
+
+    /// Subscribes to the `v4_markets` channel, which sends oracle prices and
+    /// trading statistics of all perpetual markets.
+    pub async fn subscribe_markets(&self) {
+        self.subscribe(&[("v4_markets".to_string(), "".to_string())]).await;
+    }
+}
 
-impl_trait!(Trade, DydxSwapWSClient, subscribe_trade, "v3_trades");
+impl_trait!(Trade, DydxSwapWSClient, subscribe_trade, "v4_trades");
 #[rustfmt::skip]
-impl_trait!(OrderBook, DydxSwapWSClient, subscribe_orderbook, "v3_orderbook");
+impl_trait!(OrderBook, DydxSwapWSClient, subscribe_orderbook, "v4_orderbook");
+impl_candlestick!(DydxSwapWSClient);
 
 panic_ticker!(DydxSwapWSClient);
 panic_bbo!(DydxSwapWSClient);
 panic_l2_topk!(DydxSwapWSClient);
 panic_l3_orderbook!(DydxSwapWSClient);
-panic_candlestick!(DydxSwapWSClient);
 
 impl_ws_client_trait!(DydxSwapWSClient);
 
@@ -54,29 +106,31 @@ struct DydxCommandTranslator {}
 
 impl MessageHandler for DydxMessageHandler {
     fn handle_message(&mut self, msg: &str) -> MiscMessage {
-        let obj = serde_json::from_str::<HashMap<String, Value>>(msg).unwrap();
+        let resp = serde_json::from_str::<HashMap<String, Value>>(msg);
+        if resp.is_err() {
+            error!("{} is not a JSON string, {}", msg, EXCHANGE_NAME);
+            return MiscMessage::Other;
+        }
+        let obj = resp.unwrap();
 
-        match obj.get("type").unwrap().as_str().unwrap() {
+        match obj.get("type").and_then(|x| x.as_str()).unwrap_or_default() {
             "error" => {
                 error!("Received {} from {}", msg, EXCHANGE_NAME);
-                if obj.contains_key("message")
-                    && obj
-                        .get("message")
-                        .unwrap()
-                        .as_str()
-                        .unwrap()
-                        .starts_with("Invalid subscription id for channel")
+                let message = obj.get("message").and_then(|x| x.as_str()).unwrap_or_default();
+                if message.starts_with("Invalid subscribe message")
+                    || message.contains("could not fetch data for subscription")
                 {
-                    panic!("Received {msg} from {EXCHANGE_NAME}");
+                    MiscMessage::Error(format!("Received {msg} from {EXCHANGE_NAME}"))
                 } else {
                     MiscMessage::Other
                 }
             }
-            "connected" | "pong" => {
+            "connected" | "unsubscribed" => {
                 debug!("Received {} from {}", msg, EXCHANGE_NAME);
                 MiscMessage::Other
             }
-            "channel_data" | "subscribed" => MiscMessage::Normal,
+            // subscribed messages carry the initial snapshot
+            "channel_data" | "channel_batch_data" | "subscribed" => MiscMessage::Normal,
             _ => {
                 warn!("Received {} from {}", msg, EXCHANGE_NAME);
                 MiscMessage::Other
@@ -85,21 +139,48 @@ impl MessageHandler for DydxMessageHandler {
     }
 
     fn get_ping_msg_and_interval(&self) -> Option<(Message, u64)> {
-        // https://docs.dydx.exchange/#v3-websocket-api
-        // The server will send pings every 30s and expects a pong within 10s.
-        // The server does not expect pings, but will respond with a pong if sent one.
-        Some((Message::Text(r#"{"type":"ping"}"#.to_string()), 30))
+        // The indexer sends a ping frame every 30 seconds and disconnects
+        // clients which don't respond with a pong frame within 10 seconds.
+        // Pong frames are sent by tungstenite automatically.
+        None
     }
 }
 
+impl_fuzz_message_handler!(DydxMessageHandler {});
+impl_new_message_handler!(DydxMessageHandler {});
+
 impl DydxCommandTranslator {
     fn topic_to_command(topic: &(String, String), subscribe: bool) -> String {
-        format!(
-            r#"{{"type": "{}", "channel": "{}", "id": "{}"}}"#,
-            if subscribe { "subscribe" } else { "unsubscribe" },
-            topic.0,
-            topic.1,
-        )
+        let (channel, id) = topic;
+        if id.is_empty() {
+            // v4_markets covers all markets and takes no id
+            format!(
+                r#"{{"type": "{}", "channel": "{}"}}"#,
+                if subscribe { "subscribe" } else { "unsubscribe" },
+                channel,
+            )
+        } else {
+            format!(
+                r#"{{"type": "{}", "channel": "{}", "id": "{}"}}"#,
+                if subscribe { "subscribe" } else { "unsubscribe" },
+                channel,
+                id,
+            )
+        }
+    }
+
+    fn to_candlestick_id(symbol: &str, interval: usize) -> String {
+        let resolution = match interval {
+            60 => "1MIN",
+            300 => "5MINS",
+            900 => "15MINS",
+            1800 => "30MINS",
+            3600 => "1HOUR",
+            14400 => "4HOURS",
+            86400 => "1DAY",
+            _ => panic!("dYdX available intervals 1MIN,5MINS,15MINS,30MINS,1HOUR,4HOURS,1DAY"),
+        };
+        format!("{symbol}/{resolution}")
     }
 }
 
@@ -110,10 +191,16 @@ impl CommandTranslator for DydxCommandTranslator {
 
     fn translate_to_candlestick_commands(
         &self,
-        _subscribe: bool,
-        _symbol_interval_list: &[(String, usize)],
+        subscribe: bool,
+        symbol_interval_list: &[(String, usize)],
     ) -> Vec<String> {
-        panic!("dYdX does NOT have candlestick channel");
+        let topics = symbol_interval_list
+            .iter()
+            .map(|(symbol, interval)| {
+                ("v4_candles".to_string(), Self::to_candlestick_id(symbol, *interval))
+            })
+            .collect::<Vec<(String, String)>>();
+        self.translate_to_commands(subscribe, &topics)
     }
 }
 
@@ -125,11 +212,11 @@ mod tests {
     fn test_one_topic() {
         let translator = super::DydxCommandTranslator {};
         let commands = translator
-            .translate_to_commands(true, &[("v3_trades".to_string(), "BTC-USD".to_string())]);
+            .translate_to_commands(true, &[("v4_trades".to_string(), "BTC-USD".to_string())]);
 
         assert_eq!(1, commands.len());
         assert_eq!(
-            r#"{"type": "subscribe", "channel": "v3_trades", "id": "BTC-USD"}"#,
+            r#"{"type": "subscribe", "channel": "v4_trades", "id": "BTC-USD"}"#,
             commands[0]
         );
     }
@@ -140,19 +227,57 @@ mod tests {
         let commands = translator.translate_to_commands(
             true,
             &[
-                ("v3_trades".to_string(), "BTC-USD".to_string()),
-                ("v3_orderbook".to_string(), "BTC-USD".to_string()),
+                ("v4_trades".to_string(), "BTC-USD".to_string()),
+                ("v4_orderbook".to_string(), "BTC-USD".to_string()),
             ],
         );
 
         assert_eq!(2, commands.len());
         assert_eq!(
-            r#"{"type": "subscribe", "channel": "v3_trades", "id": "BTC-USD"}"#,
+            r#"{"type": "subscribe", "channel": "v4_trades", "id": "BTC-USD"}"#,
+            commands[0]
+        );
+        assert_eq!(
+            r#"{"type": "subscribe", "channel": "v4_orderbook", "id": "BTC-USD"}"#,
+            commands[1]
+        );
+    }
+
+    #[test]
+    fn test_markets() {
+        let translator = super::DydxCommandTranslator {};
+        let commands =
+            translator.translate_to_commands(true, &[("v4_markets".to_string(), "".to_string())]);
+
+        assert_eq!(1, commands.len());
+        assert_eq!(r#"{"type": "subscribe", "channel": "v4_markets"}"#, commands[0]);
+    }
+
+    #[test]
+    fn test_candlestick() {
+        let translator = super::DydxCommandTranslator {};
+        let commands = translator.translate_to_candlestick_commands(
+            true,
+            &[("BTC-USD".to_string(), 60), ("ETH-USD".to_string(), 14400)],
+        );
+
+        assert_eq!(2, commands.len());
+        assert_eq!(
+            r#"{"type": "subscribe", "channel": "v4_candles", "id": "BTC-USD/1MIN"}"#,
             commands[0]
         );
         assert_eq!(
-            r#"{"type": "subscribe", "channel": "v3_orderbook", "id": "BTC-USD"}"#,
+            r#"{"type": "subscribe", "channel": "v4_candles", "id": "ETH-USD/4HOURS"}"#,
             commands[1]
         );
     }
+
+    #[test]
+    fn test_indexer_ws_url() {
+        assert_eq!(
+            "wss://indexer.dydx.trade/v4/ws",
+            super::indexer_ws_url("https://indexer.dydx.trade")
+        );
+        assert_eq!("ws://localhost:3002/v4/ws", super::indexer_ws_url("http://localhost:3002/"));
+    }
 }
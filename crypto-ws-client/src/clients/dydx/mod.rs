@@ -1,4 +1,4 @@
-mod dydx_swap;
+pub(super) mod dydx_swap;
 
 pub use dydx_swap::DydxSwapWSClient;
 
@@ -56,8 +56,13 @@ struct FtxCommandTranslator {}
 
 impl MessageHandler for FtxMessageHandler {
     fn handle_message(&mut self, msg: &str) -> MiscMessage {
-        let obj = serde_json::from_str::<HashMap<String, Value>>(msg).unwrap();
-        let msg_type = obj.get("type").unwrap().as_str().unwrap();
+        let resp = serde_json::from_str::<HashMap<String, Value>>(msg);
+        if resp.is_err() {
+            error!("{} is not a JSON string, {}", msg, EXCHANGE_NAME);
+            return MiscMessage::Other;
+        }
+        let obj = resp.unwrap();
+        let msg_type = obj.get("type").and_then(|x| x.as_str()).unwrap_or_default();
 
         match msg_type {
             // see https://docs.ftx.com/#response-format
@@ -68,13 +73,13 @@ impl MessageHandler for FtxMessageHandler {
             }
             "partial" | "update" => MiscMessage::Normal,
             "error" => {
-                let code = obj.get("code").unwrap().as_i64().unwrap();
+                let code = obj.get("code").and_then(|x| x.as_i64()).unwrap_or_default();
                 match code {
                     400 => {
                         // Already subscribed
                         warn!("Received {} from {}", msg, EXCHANGE_NAME);
                     }
-                    _ => panic!("Received {msg} from {EXCHANGE_NAME}"),
+                    _ => return MiscMessage::Error(format!("Received {msg} from {EXCHANGE_NAME}")),
                 }
                 MiscMessage::Other
             }
@@ -92,6 +97,9 @@ impl MessageHandler for FtxMessageHandler {
     }
 }
 
+impl_fuzz_message_handler!(FtxMessageHandler {});
+impl_new_message_handler!(FtxMessageHandler {});
+
 impl CommandTranslator for FtxCommandTranslator {
     fn translate_to_commands(&self, subscribe: bool, topics: &[(String, String)]) -> Vec<String> {
         topics
@@ -1,11 +1,17 @@
 use async_trait::async_trait;
 
-use super::utils::{GateCommandTranslator, GateMessageHandler, EXCHANGE_NAME};
+use super::utils::{
+    GateCommandTranslator, GateMessageHandler, CANDLESTICK_INTERVALS, EXCHANGE_NAME,
+};
 use crate::{
     clients::common_traits::{
         Candlestick, Level3OrderBook, OrderBook, OrderBookTopK, Ticker, Trade, BBO,
     },
-    common::{command_translator::CommandTranslator, ws_client_internal::WSClientInternal},
+    common::{
+        checksum::{ChecksumConfig, ChecksumValidator},
+        command_translator::CommandTranslator,
+        ws_client_internal::WSClientInternal,
+    },
     WSClient,
 };
 
@@ -30,21 +36,51 @@ pub struct GateLinearFutureWSClient {
     translator: GateCommandTranslator<'F'>,
 }
 
-impl_new_constructor!(
-    GateInverseFutureWSClient,
-    EXCHANGE_NAME,
-    INVERSE_FUTURE_WEBSOCKET_URL,
-    GateMessageHandler::<'F'> {},
-    GateCommandTranslator::<'F'> {}
-);
+// Like impl_new_constructor!, plus new_with_checksum()
+macro_rules! impl_future_constructors {
+    ($struct_name:ident, $default_url:expr) => {
+        impl $struct_name {
+            pub async fn new(tx: std::sync::mpsc::Sender<String>, url: Option<&str>) -> Self {
+                Self::connect(tx, url, None).await
+            }
+
+            /// Creates a client which validates `order_book` messages with
+            /// their checksums.
+            pub async fn new_with_checksum(
+                tx: std::sync::mpsc::Sender<String>,
+                url: Option<&str>,
+                config: ChecksumConfig,
+            ) -> Self {
+                Self::connect(tx, url, Some(ChecksumValidator::new(EXCHANGE_NAME, config))).await
+            }
+
+            async fn connect(
+                tx: std::sync::mpsc::Sender<String>,
+                url: Option<&str>,
+                checksum: Option<ChecksumValidator>,
+            ) -> Self {
+                let real_url = match url {
+                    Some(endpoint) => endpoint,
+                    None => $default_url,
+                };
+                $struct_name {
+                    client: WSClientInternal::connect(
+                        EXCHANGE_NAME,
+                        real_url,
+                        GateMessageHandler::<'F'> { checksum },
+                        None,
+                        tx,
+                    )
+                    .await,
+                    translator: GateCommandTranslator::<'F'> {},
+                }
+            }
+        }
+    };
+}
 
-impl_new_constructor!(
-    GateLinearFutureWSClient,
-    EXCHANGE_NAME,
-    LINEAR_FUTURE_WEBSOCKET_URL,
-    GateMessageHandler::<'F'> {},
-    GateCommandTranslator::<'F'> {}
-);
+impl_future_constructors!(GateInverseFutureWSClient, INVERSE_FUTURE_WEBSOCKET_URL);
+impl_future_constructors!(GateLinearFutureWSClient, LINEAR_FUTURE_WEBSOCKET_URL);
 
 impl_trait!(Trade, GateInverseFutureWSClient, subscribe_trade, "trades");
 #[rustfmt::skip]
@@ -1,6 +1,8 @@
 use async_trait::async_trait;
 
-use super::utils::{GateCommandTranslator, GateMessageHandler, EXCHANGE_NAME};
+use super::utils::{
+    GateCommandTranslator, GateMessageHandler, CANDLESTICK_INTERVALS, EXCHANGE_NAME,
+};
 use crate::{
     clients::common_traits::{
         Candlestick, Level3OrderBook, OrderBook, OrderBookTopK, Ticker, Trade, BBO,
@@ -24,7 +26,7 @@ impl_new_constructor!(
     GateSpotWSClient,
     EXCHANGE_NAME,
     WEBSOCKET_URL,
-    GateMessageHandler::<'S'> {},
+    GateMessageHandler::<'S'> { checksum: None },
     GateCommandTranslator::<'S'> {}
 );
 
@@ -1,6 +1,8 @@
 use async_trait::async_trait;
 
-use super::utils::{GateCommandTranslator, GateMessageHandler, EXCHANGE_NAME};
+use super::utils::{
+    GateCommandTranslator, GateMessageHandler, CANDLESTICK_INTERVALS, EXCHANGE_NAME,
+};
 use crate::{
     clients::common_traits::{
         Candlestick, Level3OrderBook, OrderBook, OrderBookTopK, Ticker, Trade, BBO,
@@ -34,7 +36,7 @@ impl_new_constructor!(
     GateInverseSwapWSClient,
     EXCHANGE_NAME,
     INVERSE_SWAP_WEBSOCKET_URL,
-    GateMessageHandler::<'F'> {},
+    GateMessageHandler::<'F'> { checksum: None },
     GateCommandTranslator::<'F'> {}
 );
 
@@ -42,7 +44,7 @@ impl_new_constructor!(
     GateLinearSwapWSClient,
     EXCHANGE_NAME,
     LINEAR_SWAP_WEBSOCKET_URL,
-    GateMessageHandler::<'F'> {},
+    GateMessageHandler::<'F'> { checksum: None },
     GateCommandTranslator::<'F'> {}
 );
 
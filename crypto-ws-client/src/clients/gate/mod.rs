@@ -1,7 +1,7 @@
 mod gate_future;
 mod gate_spot;
 mod gate_swap;
-mod utils;
+pub(super) mod utils;
 
 pub use gate_future::{GateInverseFutureWSClient, GateLinearFutureWSClient};
 pub use gate_spot::GateSpotWSClient;
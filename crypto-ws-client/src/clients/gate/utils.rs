@@ -4,20 +4,133 @@ use log::*;
 use serde_json::Value;
 use tokio_tungstenite::tungstenite::Message;
 
-use crate::common::{
-    command_translator::CommandTranslator,
-    message_handler::{MessageHandler, MiscMessage},
+use crate::{
+    common::{
+        checksum::{crc32, interleave_levels, ChecksumValidator},
+        command_translator::CommandTranslator,
+        message_handler::{MessageHandler, MiscMessage},
+    },
+    CandleInterval,
 };
 
 pub(super) const EXCHANGE_NAME: &str = "gate";
 
+pub(crate) const CANDLESTICK_INTERVALS: &[CandleInterval] = &[
+    CandleInterval::from_secs(10),
+    CandleInterval::MIN1,
+    CandleInterval::MIN5,
+    CandleInterval::MIN15,
+    CandleInterval::MIN30,
+    CandleInterval::HOUR1,
+    CandleInterval::HOUR4,
+    CandleInterval::HOUR8,
+    CandleInterval::DAY1,
+    CandleInterval::WEEK1,
+];
+
 // MARKET_TYPE: 'S' for spot, 'F' for futures
-pub(super) struct GateMessageHandler<const MARKET_TYPE: char> {}
+pub(super) struct GateMessageHandler<const MARKET_TYPE: char> {
+    pub(super) checksum: Option<ChecksumValidator>,
+}
 pub(super) struct GateCommandTranslator<const MARKET_TYPE: char> {}
 
+// The depth of futures.order_book subscriptions
+const ORDER_BOOK_DEPTH: usize = 20;
+
+impl<const MARKET_TYPE: char> GateMessageHandler<MARKET_TYPE> {
+    // Prices are strings, sizes are numbers of contracts
+    fn to_string(value: &Value) -> Option<String> {
+        match value {
+            Value::String(s) => Some(s.clone()),
+            Value::Number(n) => Some(n.to_string()),
+            _ => None,
+        }
+    }
+
+    // The `all` event is a snapshot with {"p","s"} levels, `update` events are
+    // arrays of {"p","s","c","id"}, positive sizes are bids, negative sizes are
+    // asks and zero removes the price level.
+    //
+    // The checksum is CRC32 of `bid_price:bid_size:ask_price:ask_size...` of
+    // the top levels, the same as OKX.
+    //
+    // Returns resubscribe commands if the checksum mismatches and resubscription is
+    // enabled.
+    fn validate_checksum(
+        validator: &mut ChecksumValidator,
+        obj: &HashMap<String, Value>,
+    ) -> Option<Vec<String>> {
+        let channel = obj.get("channel").and_then(|x| x.as_str())?;
+        if channel != "futures.order_book" {
+            return None;
+        }
+        let event = obj.get("event").and_then(|x| x.as_str())?;
+        let result = obj.get("result")?;
+
+        let symbol = if event == "all" {
+            let symbol = result["contract"].as_str()?;
+            let book = validator.reset(channel, symbol);
+            for (side, is_bid) in [("bids", true), ("asks", false)] {
+                for level in result[side].as_array().into_iter().flatten() {
+                    if let (Some(price), Some(size)) =
+                        (Self::to_string(&level["p"]), Self::to_string(&level["s"]))
+                    {
+                        book.update(is_bid, &price, &size);
+                    }
+                }
+            }
+            symbol
+        } else if event == "update" {
+            let levels = result.as_array()?;
+            let symbol = levels.first()?["c"].as_str()?;
+            let book = validator.book(channel, symbol)?; // no snapshot yet
+            for level in levels {
+                let (price, size) = match (Self::to_string(&level["p"]), level["s"].as_f64()) {
+                    (Some(price), Some(size)) => (price, size),
+                    _ => continue,
+                };
+                if size == 0.0 {
+                    book.remove(true, &price);
+                    book.remove(false, &price);
+                } else {
+                    let size_str = Self::to_string(&level["s"])?;
+                    book.update(size > 0.0, &price, size_str.trim_start_matches('-'));
+                }
+            }
+            symbol
+        } else {
+            return None;
+        };
+
+        let expected = obj.get("checksum").and_then(|x| x.as_i64())? as i32 as u32;
+        let book = validator.book(channel, symbol)?;
+        let actual = crc32(&interleave_levels(book, ORDER_BOOK_DEPTH));
+        if !validator.verify(channel, symbol, expected, actual) && validator.resubscribe() {
+            let symbols = [symbol.to_string()];
+            let mut commands = GateCommandTranslator::<MARKET_TYPE>::channel_symbols_to_command(
+                "order_book",
+                &symbols,
+                false,
+            );
+            commands.extend(GateCommandTranslator::<MARKET_TYPE>::channel_symbols_to_command(
+                "order_book",
+                &symbols,
+                true,
+            ));
+            return Some(commands);
+        }
+        None
+    }
+}
+
 impl<const MARKET_TYPE: char> MessageHandler for GateMessageHandler<MARKET_TYPE> {
     fn handle_message(&mut self, msg: &str) -> MiscMessage {
-        let obj = serde_json::from_str::<HashMap<String, Value>>(msg).unwrap();
+        let resp = serde_json::from_str::<HashMap<String, Value>>(msg);
+        if resp.is_err() {
+            error!("{} is not a JSON string, {}", msg, EXCHANGE_NAME);
+            return MiscMessage::Other;
+        }
+        let obj = resp.unwrap();
 
         // https://www.gate.io/docs/apiv4/ws/en/#server-response
         // Null if the server accepts the client request; otherwise, the detailed reason
@@ -33,23 +146,28 @@ impl<const MARKET_TYPE: char> MessageHandler for GateMessageHandler<MARKET_TYPE>
             }
         };
         if !error.is_null() {
-            let err = error.as_object().unwrap();
             // https://www.gate.io/docs/apiv4/ws/en/#schema_error
             // https://www.gate.io/docs/futures/ws/en/#error
-            let code = err.get("code").unwrap().as_i64().unwrap();
+            let code = error.get("code").and_then(|x| x.as_i64()).unwrap_or_default();
             match code {
-                1 | 2 => panic!("Received {msg} from {EXCHANGE_NAME}"), // client side errors
+                // client side errors
+                1 | 2 => return MiscMessage::Error(format!("Received {msg} from {EXCHANGE_NAME}")),
                 _ => error!("Received {} from {}", msg, EXCHANGE_NAME), // server side errors
             }
             return MiscMessage::Other;
         }
 
-        let channel = obj.get("channel").unwrap().as_str().unwrap();
-        let event = obj.get("event").unwrap().as_str().unwrap();
+        let channel = obj.get("channel").and_then(|x| x.as_str()).unwrap_or_default();
+        let event = obj.get("event").and_then(|x| x.as_str()).unwrap_or_default();
 
         if channel == "spot.pong" || channel == "futures.pong" {
             MiscMessage::Pong
         } else if event == "update" || event == "all" {
+            if let Some(validator) = self.checksum.as_mut() {
+                if let Some(commands) = Self::validate_checksum(validator, &obj) {
+                    return MiscMessage::Resubscribe(commands);
+                }
+            }
             MiscMessage::Normal
         } else if event == "subscribe" || event == "unsubscribe" {
             debug!("Received {} from {}", msg, EXCHANGE_NAME);
@@ -72,6 +190,13 @@ impl<const MARKET_TYPE: char> MessageHandler for GateMessageHandler<MARKET_TYPE>
     }
 }
 
+impl_fuzz_message_handler!(GateMessageHandler::<'S'> { checksum: None });
+impl_new_message_handler!(new_spot_message_handler, GateMessageHandler::<'S'> { checksum: None });
+impl_new_message_handler!(
+    new_futures_message_handler,
+    GateMessageHandler::<'F'> { checksum: None }
+);
+
 impl<const MARKET_TYPE: char> GateCommandTranslator<MARKET_TYPE> {
     fn channel_symbols_to_command(
         channel: &str,
@@ -285,4 +410,45 @@ mod tests {
             commands[1]
         );
     }
+
+    #[test]
+    fn test_futures_checksum() {
+        use crate::common::{
+            checksum::{ChecksumConfig, ChecksumValidator},
+            message_handler::{MessageHandler, MiscMessage},
+        };
+
+        let (tx, rx) = std::sync::mpsc::channel();
+        let mut handler = super::GateMessageHandler::<'F'> {
+            checksum: Some(ChecksumValidator::new(
+                super::EXCHANGE_NAME,
+                ChecksumConfig { tx: Some(tx), resubscribe: true },
+            )),
+        };
+
+        let snapshot = r#"{"time":1677000000,"channel":"futures.order_book","event":"all","error":null,"result":{"t":1677000000123,"contract":"BTC_USD_20230331","id":1,"asks":[{"p":"7001","s":3}],"bids":[{"p":"7000","s":10},{"p":"6999","s":5}]},"checksum":2140761891}"#;
+        assert!(matches!(handler.handle_message(snapshot), MiscMessage::Normal));
+        // removes 6999
+        let update = r#"{"time":1677000001,"channel":"futures.order_book","event":"update","error":null,"result":[{"p":"6999","s":0,"c":"BTC_USD_20230331","id":2}],"checksum":477769599}"#;
+        assert!(matches!(handler.handle_message(update), MiscMessage::Normal));
+        assert!(rx.try_recv().is_err());
+
+        // changes the size of 7001 with a stale checksum
+        let update = r#"{"time":1677000002,"channel":"futures.order_book","event":"update","error":null,"result":[{"p":"7001","s":-4,"c":"BTC_USD_20230331","id":3}],"checksum":477769599}"#;
+        match handler.handle_message(update) {
+            MiscMessage::Resubscribe(commands) => {
+                assert_eq!(
+                    vec![
+                        r#"{"channel":"futures.order_book", "event":"unsubscribe", "payload":["BTC_USD_20230331","20","0"]}"#,
+                        r#"{"channel":"futures.order_book", "event":"subscribe", "payload":["BTC_USD_20230331","20","0"]}"#,
+                    ],
+                    commands
+                );
+            }
+            other => panic!("Unexpected {other:?}"),
+        }
+        let mismatch = rx.try_recv().unwrap();
+        assert_eq!("futures.order_book", mismatch.channel);
+        assert_eq!("BTC_USD_20230331", mismatch.symbol);
+    }
 }
@@ -7,11 +7,12 @@ use tokio_tungstenite::tungstenite::Message;
 
 use crate::{
     common::{
+        candle_interval::check_candlestick_intervals,
         command_translator::CommandTranslator,
         message_handler::{MessageHandler, MiscMessage},
         ws_client_internal::WSClientInternal,
     },
-    WSClient,
+    CandleInterval, Error, WSClient,
 };
 
 pub(crate) const EXCHANGE_NAME: &str = "huobi";
@@ -27,6 +28,18 @@ const COIN_SWAP_WEBSOCKET_URL: &str = "wss://futures.huobi.com/swap-ws";
 const USDT_SWAP_WEBSOCKET_URL: &str = "wss://futures.huobi.com/linear-swap-ws";
 const OPTION_WEBSOCKET_URL: &str = "wss://futures.huobi.com/option-ws";
 
+pub(super) const CANDLESTICK_INTERVALS: &[CandleInterval] = &[
+    CandleInterval::MIN1,
+    CandleInterval::MIN5,
+    CandleInterval::MIN15,
+    CandleInterval::MIN30,
+    CandleInterval::HOUR1,
+    CandleInterval::HOUR4,
+    CandleInterval::DAY1,
+    CandleInterval::WEEK1,
+    CandleInterval::MONTH1,
+];
+
 // Internal unified client
 pub struct HuobiWSClient<const URL: char> {
     client: WSClientInternal<HuobiMessageHandler>,
@@ -157,13 +170,23 @@ impl<const URL: char> WSClient for HuobiWSClient<URL> {
         self.subscribe(&topics).await;
     }
 
-    async fn subscribe_candlestick(&self, symbol_interval_list: &[(String, usize)]) {
+    async fn subscribe_candlestick(
+        &self,
+        symbol_interval_list: &[(String, CandleInterval)],
+    ) -> Result<(), Error> {
+        let symbol_interval_list = check_candlestick_intervals(
+            EXCHANGE_NAME,
+            CANDLESTICK_INTERVALS,
+            symbol_interval_list,
+        )?;
         let commands =
-            self.translator.translate_to_candlestick_commands(true, symbol_interval_list);
+            self.translator.translate_to_candlestick_commands(true, &symbol_interval_list);
         self.client.send(&commands).await;
+        Ok(())
     }
 
     async fn subscribe(&self, topics: &[(String, String)]) {
+        self.client.add_channels(topics);
         let commands = self.translator.translate_to_commands(true, topics);
         self.client.send(&commands).await;
     }
@@ -239,7 +262,7 @@ impl MessageHandler for HuobiMessageHandler {
         }
         // Order Push Heartbeat
         // https://huobiapi.github.io/docs/usdt_swap/v1/en/#market-heartbeat
-        if obj.contains_key("op") && obj.get("op").unwrap().as_str().unwrap() == "ping" {
+        if obj.get("op").and_then(|x| x.as_str()) == Some("ping") {
             debug!("Received {} from {}", msg, EXCHANGE_NAME);
             let mut pong_msg = obj;
             pong_msg.insert("op".to_string(), serde_json::from_str("\"pong\"").unwrap()); // change ping to pong
@@ -252,21 +275,28 @@ impl MessageHandler for HuobiMessageHandler {
             && (obj.contains_key("tick") || obj.contains_key("data"))
         {
             MiscMessage::Normal
+        } else if obj.contains_key("rep") && obj.contains_key("data") {
+            // Response of a req command sent by send(), e.g., the MBP snapshot, see
+            // https://huobiapi.github.io/docs/spot/v1/en/#market-by-price-incremental-update
+            MiscMessage::Normal
         } else {
             if let Some(status) = obj.get("status") {
-                match status.as_str().unwrap() {
+                match status.as_str().unwrap_or_default() {
                     "ok" => info!("Received {} from {}", msg, EXCHANGE_NAME),
                     "error" => {
                         error!("Received {} from {}", msg, EXCHANGE_NAME);
-                        let err_msg = obj.get("err-msg").unwrap().as_str().unwrap();
+                        let err_msg =
+                            obj.get("err-msg").and_then(|x| x.as_str()).unwrap_or_default();
                         if err_msg.starts_with("invalid") {
-                            panic!("Received {msg} from {EXCHANGE_NAME}");
+                            return MiscMessage::Error(format!(
+                                "Received {msg} from {EXCHANGE_NAME}"
+                            ));
                         }
                     }
                     _ => warn!("Received {} from {}", msg, EXCHANGE_NAME),
                 }
             } else if let Some(op) = obj.get("op") {
-                match op.as_str().unwrap() {
+                match op.as_str().unwrap_or_default() {
                     "sub" | "unsub" => MiscMessage::Other,
                     "notify" => MiscMessage::Normal,
                     _ => {
@@ -292,6 +322,9 @@ impl MessageHandler for HuobiMessageHandler {
     }
 }
 
+impl_fuzz_message_handler!(HuobiMessageHandler {});
+impl_new_message_handler!(HuobiMessageHandler {});
+
 impl CommandTranslator for HuobiCommandTranslator {
     fn translate_to_commands(&self, subscribe: bool, topics: &[(String, String)]) -> Vec<String> {
         topics
@@ -347,4 +380,15 @@ mod tests {
         assert_eq!(r#"{"sub":"market.btcusdt.trade.detail","id":"crypto-ws-client"}"#, commands[0]);
         assert_eq!(r#"{"sub":"market.btcusdt.bbo","id":"crypto-ws-client"}"#, commands[1]);
     }
+
+    #[test]
+    fn test_req_response() {
+        use crate::common::message_handler::{MessageHandler, MiscMessage};
+
+        let mut handler = super::HuobiMessageHandler {};
+        let rep = r#"{"id":"btcusdt","rep":"market.btcusdt.mbp.20","status":"ok","data":{"seqNum":100,"bids":[[9999.3,0.1]],"asks":[[10000.1,0.2]]}}"#;
+        assert!(matches!(handler.handle_message(rep), MiscMessage::Normal));
+        let sub = r#"{"id":"crypto-ws-client","status":"ok","subbed":"market.btcusdt.mbp.20","ts":1489474081631}"#;
+        assert!(matches!(handler.handle_message(sub), MiscMessage::Other));
+    }
 }
@@ -68,12 +68,16 @@ impl KrakenCommandTranslator {
 
 impl MessageHandler for KrakenMessageHandler {
     fn handle_message(&mut self, msg: &str) -> MiscMessage {
-        let obj = serde_json::from_str::<HashMap<String, Value>>(msg).unwrap();
+        let resp = serde_json::from_str::<HashMap<String, Value>>(msg);
+        if resp.is_err() {
+            error!("{} is not a JSON string, {}", msg, EXCHANGE_NAME);
+            return MiscMessage::Other;
+        }
+        let obj = resp.unwrap();
 
-        if obj.contains_key("event") {
-            let event = obj.get("event").unwrap().as_str().unwrap();
-            match event {
-                "error" => panic!("Received {msg} from {EXCHANGE_NAME}"),
+        if let Some(event) = obj.get("event") {
+            match event.as_str().unwrap_or_default() {
+                "error" => MiscMessage::Error(format!("Received {msg} from {EXCHANGE_NAME}")),
                 "info" | "subscribed" | "unsubscribed" => {
                     info!("Received {} from {}", msg, EXCHANGE_NAME);
                     MiscMessage::Other
@@ -83,9 +87,8 @@ impl MessageHandler for KrakenMessageHandler {
                     MiscMessage::Other
                 }
             }
-        } else if obj.contains_key("feed") {
-            let feed = obj.get("feed").unwrap().as_str().unwrap();
-            if feed == "heartbeat" {
+        } else if let Some(feed) = obj.get("feed") {
+            if feed.as_str() == Some("heartbeat") {
                 debug!("Received {} from {}", msg, EXCHANGE_NAME);
                 MiscMessage::WebSocket(Message::Ping(Vec::new()))
             } else if obj.contains_key("product_id") {
@@ -106,6 +109,9 @@ impl MessageHandler for KrakenMessageHandler {
     }
 }
 
+impl_fuzz_message_handler!(KrakenMessageHandler {});
+impl_new_message_handler!(KrakenMessageHandler {});
+
 impl CommandTranslator for KrakenCommandTranslator {
     fn translate_to_commands(&self, subscribe: bool, topics: &[(String, String)]) -> Vec<String> {
         let mut commands: Vec<String> = Vec::new();
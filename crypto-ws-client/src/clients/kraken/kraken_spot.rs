@@ -2,12 +2,13 @@ use async_trait::async_trait;
 use std::collections::HashMap;
 use tokio_tungstenite::tungstenite::Message;
 
-use super::EXCHANGE_NAME;
+use super::{CANDLESTICK_INTERVALS, EXCHANGE_NAME};
 use crate::{
     clients::common_traits::{
         Candlestick, Level3OrderBook, OrderBook, OrderBookTopK, Ticker, Trade, BBO,
     },
     common::{
+        checksum::{crc32, ChecksumConfig, ChecksumValidator, L2Book},
         command_translator::CommandTranslator,
         message_handler::{MessageHandler, MiscMessage},
         ws_client_internal::WSClientInternal,
@@ -30,13 +31,42 @@ pub struct KrakenSpotWSClient {
     translator: KrakenCommandTranslator,
 }
 
-impl_new_constructor!(
-    KrakenSpotWSClient,
-    EXCHANGE_NAME,
-    WEBSOCKET_URL,
-    KrakenMessageHandler {},
-    KrakenCommandTranslator {}
-);
+impl KrakenSpotWSClient {
+    pub async fn new(tx: std::sync::mpsc::Sender<String>, url: Option<&str>) -> Self {
+        Self::connect(tx, url, None).await
+    }
+
+    /// Creates a client which validates `book` messages with their checksums.
+    pub async fn new_with_checksum(
+        tx: std::sync::mpsc::Sender<String>,
+        url: Option<&str>,
+        config: ChecksumConfig,
+    ) -> Self {
+        Self::connect(tx, url, Some(ChecksumValidator::new(EXCHANGE_NAME, config))).await
+    }
+
+    async fn connect(
+        tx: std::sync::mpsc::Sender<String>,
+        url: Option<&str>,
+        checksum: Option<ChecksumValidator>,
+    ) -> Self {
+        let real_url = match url {
+            Some(endpoint) => endpoint,
+            None => WEBSOCKET_URL,
+        };
+        KrakenSpotWSClient {
+            client: WSClientInternal::connect(
+                EXCHANGE_NAME,
+                real_url,
+                KrakenMessageHandler { checksum },
+                None,
+                tx,
+            )
+            .await,
+            translator: KrakenCommandTranslator {},
+        }
+    }
+}
 
 #[rustfmt::skip]
 impl_trait!(Trade, KrakenSpotWSClient, subscribe_trade, "trade");
@@ -52,9 +82,76 @@ panic_l3_orderbook!(KrakenSpotWSClient);
 
 impl_ws_client_trait!(KrakenSpotWSClient);
 
-struct KrakenMessageHandler {}
+struct KrakenMessageHandler {
+    checksum: Option<ChecksumValidator>,
+}
 struct KrakenCommandTranslator {}
 
+impl KrakenMessageHandler {
+    // Top 10 asks then top 10 bids, the decimal point and leading zeros are removed
+    // see https://docs.kraken.com/websockets/#book-checksum
+    fn calc_checksum(book: &L2Book) -> u32 {
+        let mut s = String::new();
+        for (price, quantity) in book.asks().take(10).chain(book.bids().take(10)) {
+            s.push_str(price.replace('.', "").trim_start_matches('0'));
+            s.push_str(quantity.replace('.', "").trim_start_matches('0'));
+        }
+        crc32(&s)
+    }
+
+    // Returns resubscribe commands if the checksum mismatches and resubscription is
+    // enabled.
+    fn validate_checksum(validator: &mut ChecksumValidator, arr: &[Value]) -> Option<Vec<String>> {
+        // [channelID, {"a":[...]}, {"b":[...], "c":"..."}, "book-25", "XBT/USD"]
+        if arr.len() < 4 {
+            return None;
+        }
+        let channel_name = arr[arr.len() - 2].as_str()?;
+        let depth = channel_name.strip_prefix("book-")?.parse::<usize>().ok()?;
+        let symbol = arr[arr.len() - 1].as_str()?;
+        let payloads = &arr[1..arr.len() - 2];
+
+        let book = if payloads[0].get("as").is_some() || payloads[0].get("bs").is_some() {
+            validator.reset(channel_name, symbol)
+        } else {
+            validator.book(channel_name, symbol)? // no snapshot yet
+        };
+        let mut expected: Option<u32> = None;
+        for payload in payloads {
+            for (side, is_bid) in [("as", false), ("bs", true), ("a", false), ("b", true)] {
+                if let Some(levels) = payload.get(side).and_then(|x| x.as_array()) {
+                    for level in levels {
+                        if let (Some(price), Some(quantity)) =
+                            (level[0].as_str(), level[1].as_str())
+                        {
+                            book.update(is_bid, price, quantity);
+                        }
+                    }
+                }
+            }
+            if let Some(c) = payload.get("c") {
+                expected = c.as_str().and_then(|x| x.parse::<u32>().ok());
+            }
+        }
+        // price levels out of scope are not deleted by Kraken
+        book.truncate(depth);
+
+        let actual = Self::calc_checksum(book);
+        if let Some(expected) = expected {
+            if !validator.verify(channel_name, symbol, expected, actual) && validator.resubscribe()
+            {
+                // resubscribe at the same depth
+                let symbols = [symbol.to_string()];
+                return Some(vec![
+                    KrakenCommandTranslator::book_command(&symbols, depth, false),
+                    KrakenCommandTranslator::book_command(&symbols, depth, true),
+                ]);
+            }
+        }
+        None
+    }
+}
+
 impl MessageHandler for KrakenMessageHandler {
     fn handle_message(&mut self, msg: &str) -> MiscMessage {
         let resp = serde_json::from_str::<Value>(msg);
@@ -66,7 +163,7 @@ impl MessageHandler for KrakenMessageHandler {
 
         if value.is_object() {
             let obj = value.as_object().unwrap();
-            let event = obj.get("event").unwrap().as_str().unwrap();
+            let event = obj.get("event").and_then(|x| x.as_str()).unwrap_or_default();
             match event {
                 "heartbeat" => {
                     debug!("Received {} from {}", msg, EXCHANGE_NAME);
@@ -78,19 +175,24 @@ impl MessageHandler for KrakenMessageHandler {
                 }
                 "pong" => MiscMessage::Pong,
                 "subscriptionStatus" => {
-                    let status = obj.get("status").unwrap().as_str().unwrap();
+                    let status = obj.get("status").and_then(|x| x.as_str()).unwrap_or_default();
                     match status {
                         "subscribed" | "unsubscribed" => {
                             info!("Received {} from {}", msg, EXCHANGE_NAME)
                         }
                         "error" => {
-                            let error_msg = obj.get("errorMessage").unwrap().as_str().unwrap();
+                            let error_msg = obj
+                                .get("errorMessage")
+                                .and_then(|x| x.as_str())
+                                .unwrap_or_default();
                             if error_msg.starts_with("Currency pair not supported") {
                                 // Sometimes currency pairs returned from RESTful API don't exist in
                                 // WebSocket yet
                                 error!("Received {} from {}", msg, EXCHANGE_NAME)
                             } else {
-                                panic!("Received {msg} from {EXCHANGE_NAME}");
+                                return MiscMessage::Error(format!(
+                                    "Received {msg} from {EXCHANGE_NAME}"
+                                ));
                             }
                         }
                         _ => warn!("Received {} from {}", msg, EXCHANGE_NAME),
@@ -99,7 +201,7 @@ impl MessageHandler for KrakenMessageHandler {
                     MiscMessage::Other
                 }
                 "systemStatus" => {
-                    let status = obj.get("status").unwrap().as_str().unwrap();
+                    let status = obj.get("status").and_then(|x| x.as_str()).unwrap_or_default();
                     match status {
                         "maintenance" | "cancel_only" => {
                             warn!("Received {}, which means Kraken is in maintenance mode", msg);
@@ -117,8 +219,16 @@ impl MessageHandler for KrakenMessageHandler {
                     MiscMessage::Other
                 }
             }
-        } else {
+        } else if let Some(arr) = value.as_array() {
+            if let Some(validator) = self.checksum.as_mut() {
+                if let Some(commands) = Self::validate_checksum(validator, arr) {
+                    return MiscMessage::Resubscribe(commands);
+                }
+            }
             MiscMessage::Normal
+        } else {
+            warn!("Received {} from {}", msg, EXCHANGE_NAME);
+            MiscMessage::Other
         }
     }
 
@@ -129,15 +239,27 @@ impl MessageHandler for KrakenMessageHandler {
     }
 }
 
+impl_fuzz_message_handler!(KrakenMessageHandler {
+    checksum: Some(ChecksumValidator::new(
+        EXCHANGE_NAME,
+        ChecksumConfig { resubscribe: true, ..Default::default() }
+    )),
+});
+impl_new_message_handler!(KrakenMessageHandler { checksum: None });
+
 impl KrakenCommandTranslator {
+    fn book_command(symbols: &[String], depth: usize, subscribe: bool) -> String {
+        format!(
+            r#"{{"event":"{}","pair":{},"subscription":{{"name":"book","depth":{}}}}}"#,
+            if subscribe { "subscribe" } else { "unsubscribe" },
+            serde_json::to_string(symbols).unwrap(),
+            depth
+        )
+    }
+
     fn name_symbols_to_command(name: &str, symbols: &[String], subscribe: bool) -> String {
         if name == "book" {
-            format!(
-                r#"{{"event":"{}","pair":{},"subscription":{{"name":"{}","depth":25}}}}"#,
-                if subscribe { "subscribe" } else { "unsubscribe" },
-                serde_json::to_string(symbols).unwrap(),
-                name
-            )
+            Self::book_command(symbols, 25, subscribe)
         } else {
             format!(
                 r#"{{"event":"{}","pair":{},"subscription":{{"name":"{}"}}}}"#,
@@ -190,24 +312,6 @@ impl CommandTranslator for KrakenCommandTranslator {
         subscribe: bool,
         symbol_interval_list: &[(String, usize)],
     ) -> Vec<String> {
-        let valid_set: Vec<usize> =
-            vec![1, 5, 15, 30, 60, 240, 1440, 10080, 21600].into_iter().map(|x| x * 60).collect();
-        let invalid_intervals = symbol_interval_list
-            .iter()
-            .map(|(_, interval)| *interval)
-            .filter(|x| !valid_set.contains(x))
-            .collect::<Vec<usize>>();
-        if !invalid_intervals.is_empty() {
-            panic!(
-                "Invalid intervals: {}, available intervals: {}",
-                invalid_intervals
-                    .into_iter()
-                    .map(|x| x.to_string())
-                    .collect::<Vec<String>>()
-                    .join(","),
-                valid_set.into_iter().map(|x| x.to_string()).collect::<Vec<String>>().join(",")
-            );
-        }
         let symbols_interval_list = Self::convert_symbol_interval_list(symbol_interval_list);
         let commands: Vec<String> = symbols_interval_list
             .into_iter()
@@ -259,4 +363,71 @@ mod tests {
             commands[0]
         );
     }
+
+    #[test]
+    fn test_checksum() {
+        use crate::common::{
+            checksum::{ChecksumConfig, ChecksumValidator},
+            message_handler::{MessageHandler, MiscMessage},
+        };
+
+        let mut handler = super::KrakenMessageHandler {
+            checksum: Some(ChecksumValidator::new(
+                super::EXCHANGE_NAME,
+                ChecksumConfig { tx: None, resubscribe: true },
+            )),
+        };
+
+        let snapshot = r#"[0,{"as":[["5541.30000","2.50700000","1534614248.123678"],["5541.80000","0.33000000","1534614098.345543"]],"bs":[["5541.20000","1.52900000","1534614248.765567"],["5539.90000","0.30000000","1534614241.769870"]]},"book-25","XBT/USD"]"#;
+        assert!(matches!(handler.handle_message(snapshot), MiscMessage::Normal));
+
+        let update = r#"[0,{"a":[["5541.80000","0.00000000","1534614335.345903"]],"c":"3225505914"},"book-25","XBT/USD"]"#;
+        assert!(matches!(handler.handle_message(update), MiscMessage::Normal));
+
+        let update = r#"[0,{"a":[["5541.30000","2.50000000","1534614335.345903"]]},{"b":[["5541.20000","1.00000000","1534614335.345903"]],"c":"3225505914"},"book-25","XBT/USD"]"#;
+        match handler.handle_message(update) {
+            MiscMessage::Resubscribe(commands) => {
+                assert_eq!(
+                    vec![
+                        r#"{"event":"unsubscribe","pair":["XBT/USD"],"subscription":{"name":"book","depth":25}}"#,
+                        r#"{"event":"subscribe","pair":["XBT/USD"],"subscription":{"name":"book","depth":25}}"#,
+                    ],
+                    commands
+                );
+            }
+            other => panic!("Unexpected {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_checksum_depth() {
+        use crate::common::{
+            checksum::{ChecksumConfig, ChecksumValidator},
+            message_handler::{MessageHandler, MiscMessage},
+        };
+
+        let mut handler = super::KrakenMessageHandler {
+            checksum: Some(ChecksumValidator::new(
+                super::EXCHANGE_NAME,
+                ChecksumConfig { tx: None, resubscribe: true },
+            )),
+        };
+
+        let snapshot = r#"[0,{"as":[["5541.30000","2.50700000","1534614248.123678"]],"bs":[["5541.20000","1.52900000","1534614248.765567"]]},"book-10","XBT/USD"]"#;
+        assert!(matches!(handler.handle_message(snapshot), MiscMessage::Normal));
+
+        let update = r#"[0,{"a":[["5541.30000","2.50000000","1534614335.345903"]],"c":"1"},"book-10","XBT/USD"]"#;
+        match handler.handle_message(update) {
+            MiscMessage::Resubscribe(commands) => {
+                assert_eq!(
+                    vec![
+                        r#"{"event":"unsubscribe","pair":["XBT/USD"],"subscription":{"name":"book","depth":10}}"#,
+                        r#"{"event":"subscribe","pair":["XBT/USD"],"subscription":{"name":"book","depth":10}}"#,
+                    ],
+                    commands
+                );
+            }
+            other => panic!("Unexpected {other:?}"),
+        }
+    }
 }
@@ -0,0 +1,340 @@
+use async_trait::async_trait;
+use std::{
+    collections::BTreeMap,
+    sync::atomic::{AtomicU64, Ordering},
+};
+use tokio_tungstenite::tungstenite::Message;
+
+use super::{CANDLESTICK_INTERVALS, EXCHANGE_NAME};
+use crate::{
+    clients::common_traits::{
+        Candlestick, Level3OrderBook, OrderBook, OrderBookTopK, Ticker, Trade, BBO,
+    },
+    common::{
+        command_translator::CommandTranslator,
+        message_handler::{MessageHandler, MiscMessage},
+        ws_client_internal::WSClientInternal,
+    },
+    WSClient,
+};
+
+use log::*;
+use serde_json::{json, Value};
+
+const WEBSOCKET_URL: &str = "wss://ws.kraken.com/v2";
+
+// Valid values of the `depth` parameter of the book channel,
+// see https://docs.kraken.com/api/docs/websocket-v2/book
+const BOOK_DEPTHS: [usize; 5] = [10, 25, 100, 500, 1000];
+const DEFAULT_BOOK_DEPTH: usize = 25;
+
+/// The WebSocket client for Kraken Spot market, using the v2 API.
+///
+/// Unlike [`KrakenSpotWSClient`](super::KrakenSpotWSClient) which speaks the
+/// legacy array-formatted v1 protocol, all messages of the v2 API are JSON
+/// objects, and every request carries a `req_id` which is echoed back in its
+/// acknowledgement.
+///
+/// Symbols use the ISO 4217-A3 format, e.g., `BTC/USD`, `ETH/USD`.
+///
+/// The `book` channel accepts an optional depth suffix, e.g.,
+/// `("book.100", "BTC/USD")`, the default depth is 25. The `instrument`
+/// channel takes no symbol, subscribe it via
+/// [`subscribe_instrument()`](KrakenSpotV2WSClient::subscribe_instrument).
+///
+///   * WebSocket API doc: <https://docs.kraken.com/api/docs/guides/spot-ws-intro>
+///   * Trading at: <https://pro.kraken.com/>
+pub struct KrakenSpotV2WSClient {
+    client: WSClientInternal<KrakenV2MessageHandler>,
+    translator: KrakenV2CommandTranslator,
+}
+
+impl_new_constructor!(
+    KrakenSpotV2WSClient,
+    EXCHANGE_NAME,
+    WEBSOCKET_URL,
+    KrakenV2MessageHandler {},
+    KrakenV2CommandTranslator { req_id: AtomicU64::new(1) }
+);
+
+impl KrakenSpotV2WSClient {
+    /// Subscribes to incremental level2 orderbook channels with a given depth.
+    ///
+    /// Valid depths are 10, 25, 100, 500 and 1000.
+    pub async fn subscribe_orderbook_with_depth(&self, symbols: &[String], depth: usize) {
+        let channel = format!("book.{depth}");
+        let topics = symbols
+            .iter()
+            .map(|symbol| (channel.clone(), symbol.to_string()))
+            .collect::<Vec<(String, String)>>();
+        self.subscribe(&topics).await;
+    }
+
+    /// Subscribes to the `instrument` channel, which sends reference data of
+    /// all assets and trading pairs.
+    pub async fn subscribe_instrument(&self) {
+        self.subscribe(&[("instrument".to_string(), "".to_string())]).await;
+    }
+}
+
+#[rustfmt::skip]
+impl_trait!(Trade, KrakenSpotV2WSClient, subscribe_trade, "trade");
+impl_trait!(OrderBook, KrakenSpotV2WSClient, subscribe_orderbook, "book");
+#[rustfmt::skip]
+impl_trait!(Ticker, KrakenSpotV2WSClient, subscribe_ticker, "ticker");
+impl_candlestick!(KrakenSpotV2WSClient);
+
+panic_bbo!(KrakenSpotV2WSClient);
+panic_l2_topk!(KrakenSpotV2WSClient);
+panic_l3_orderbook!(KrakenSpotV2WSClient);
+
+impl_ws_client_trait!(KrakenSpotV2WSClient);
+
+struct KrakenV2MessageHandler {}
+struct KrakenV2CommandTranslator {
+    req_id: AtomicU64, // every request gets a unique req_id
+}
+
+impl MessageHandler for KrakenV2MessageHandler {
+    fn handle_message(&mut self, msg: &str) -> MiscMessage {
+        let resp = serde_json::from_str::<BTreeMap<String, Value>>(msg);
+        if resp.is_err() {
+            error!("{} is not a JSON object, {}", msg, EXCHANGE_NAME);
+            return MiscMessage::Other;
+        }
+        let obj = resp.unwrap();
+
+        if let Some(method) = obj.get("method").and_then(|x| x.as_str()) {
+            // Responses to requests, see https://docs.kraken.com/api/docs/websocket-v2/subscribe
+            let req_id = obj.get("req_id").and_then(|x| x.as_u64()).unwrap_or_default();
+            match method {
+                "pong" => MiscMessage::Pong,
+                "subscribe" | "unsubscribe" => {
+                    let success = obj.get("success").and_then(|x| x.as_bool()).unwrap_or(false);
+                    if success {
+                        info!("Request {} succeeded, {} from {}", req_id, msg, EXCHANGE_NAME);
+                    } else {
+                        let error_msg = obj.get("error").and_then(|x| x.as_str()).unwrap_or("");
+                        if error_msg.starts_with("Currency pair not supported") {
+                            // Sometimes currency pairs returned from RESTful API don't exist in
+                            // WebSocket yet
+                            error!("Request {} failed, {} from {}", req_id, msg, EXCHANGE_NAME);
+                        } else {
+                            return MiscMessage::Error(format!(
+                                "Request {req_id} failed, {msg} from {EXCHANGE_NAME}"
+                            ));
+                        }
+                    }
+                    MiscMessage::Other
+                }
+                _ => {
+                    warn!("Received {} from {}", msg, EXCHANGE_NAME);
+                    MiscMessage::Other
+                }
+            }
+        } else if let Some(channel) = obj.get("channel").and_then(|x| x.as_str()) {
+            match channel {
+                "heartbeat" => {
+                    debug!("Received {} from {}", msg, EXCHANGE_NAME);
+                    MiscMessage::Other
+                }
+                "status" => {
+                    // https://docs.kraken.com/api/docs/websocket-v2/status
+                    let system = obj
+                        .get("data")
+                        .and_then(|x| x.as_array())
+                        .and_then(|arr| arr.first())
+                        .and_then(|x| x.get("system"))
+                        .and_then(|x| x.as_str())
+                        .unwrap_or("online");
+                    match system {
+                        "maintenance" | "cancel_only" | "post_only" => {
+                            warn!("Received {}, which means Kraken is in maintenance mode", msg);
+                            std::thread::sleep(std::time::Duration::from_secs(20));
+                            MiscMessage::Reconnect
+                        }
+                        _ => {
+                            info!("Received {} from {}", msg, EXCHANGE_NAME);
+                            MiscMessage::Other
+                        }
+                    }
+                }
+                _ => {
+                    if obj.contains_key("data") {
+                        MiscMessage::Normal
+                    } else {
+                        warn!("Received {} from {}", msg, EXCHANGE_NAME);
+                        MiscMessage::Other
+                    }
+                }
+            }
+        } else {
+            warn!("Received {} from {}", msg, EXCHANGE_NAME);
+            MiscMessage::Other
+        }
+    }
+
+    fn get_ping_msg_and_interval(&self) -> Option<(Message, u64)> {
+        // Client can ping server to determine whether connection is alive
+        // https://docs.kraken.com/api/docs/websocket-v2/ping
+        Some((Message::Text(r#"{"method":"ping"}"#.to_string()), 10))
+    }
+}
+
+impl_fuzz_message_handler!(KrakenV2MessageHandler {});
+impl_new_message_handler!(KrakenV2MessageHandler {});
+
+impl KrakenV2CommandTranslator {
+    fn next_req_id(&self) -> u64 {
+        self.req_id.fetch_add(1, Ordering::SeqCst)
+    }
+
+    /// Splits an optional depth suffix from a book channel, e.g., `book.100`.
+    fn parse_channel(channel: &str) -> (&str, Option<usize>) {
+        if let Some(depth) = channel.strip_prefix("book.") {
+            let depth = depth.parse::<usize>().unwrap();
+            if !BOOK_DEPTHS.contains(&depth) {
+                panic!("Invalid book depth {}, available depths: {:?}", depth, BOOK_DEPTHS);
+            }
+            ("book", Some(depth))
+        } else if channel == "book" {
+            ("book", Some(DEFAULT_BOOK_DEPTH))
+        } else {
+            (channel, None)
+        }
+    }
+
+    fn to_command(&self, params: Value, subscribe: bool) -> String {
+        json!({
+            "method": if subscribe { "subscribe" } else { "unsubscribe" },
+            "params": params,
+            "req_id": self.next_req_id(),
+        })
+        .to_string()
+    }
+}
+
+impl CommandTranslator for KrakenV2CommandTranslator {
+    fn translate_to_commands(&self, subscribe: bool, topics: &[(String, String)]) -> Vec<String> {
+        // group symbols by channel, BTreeMap keeps the order of commands stable
+        let mut channel_symbols = BTreeMap::<String, Vec<String>>::new();
+        for (channel, symbol) in topics {
+            let symbols = channel_symbols.entry(channel.to_string()).or_default();
+            if !symbol.is_empty() {
+                symbols.push(symbol.to_string());
+            }
+        }
+
+        channel_symbols
+            .into_iter()
+            .map(|(channel, symbols)| {
+                let (name, depth) = Self::parse_channel(&channel);
+                let mut params = json!({ "channel": name });
+                if !symbols.is_empty() {
+                    params["symbol"] = json!(symbols);
+                }
+                if let Some(depth) = depth {
+                    params["depth"] = json!(depth);
+                }
+                self.to_command(params, subscribe)
+            })
+            .collect()
+    }
+
+    fn translate_to_candlestick_commands(
+        &self,
+        subscribe: bool,
+        symbol_interval_list: &[(String, usize)],
+    ) -> Vec<String> {
+        // https://docs.kraken.com/api/docs/websocket-v2/ohlc
+        let mut interval_symbols = BTreeMap::<usize, Vec<String>>::new();
+        for (symbol, interval) in symbol_interval_list {
+            interval_symbols.entry(*interval).or_default().push(symbol.to_string());
+        }
+        interval_symbols
+            .into_iter()
+            .map(|(interval, symbols)| {
+                let params = json!({
+                    "channel": "ohlc",
+                    "symbol": symbols,
+                    "interval": interval / 60,
+                });
+                self.to_command(params, subscribe)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::common::command_translator::CommandTranslator;
+    use std::sync::atomic::AtomicU64;
+
+    #[test]
+    fn test_two_symbols() {
+        let translator = super::KrakenV2CommandTranslator { req_id: AtomicU64::new(1) };
+        let commands = translator.translate_to_commands(
+            true,
+            &[
+                ("trade".to_string(), "BTC/USD".to_string()),
+                ("trade".to_string(), "ETH/USD".to_string()),
+            ],
+        );
+
+        assert_eq!(1, commands.len());
+        assert_eq!(
+            r#"{"method":"subscribe","params":{"channel":"trade","symbol":["BTC/USD","ETH/USD"]},"req_id":1}"#,
+            commands[0]
+        );
+    }
+
+    #[test]
+    fn test_book_depth() {
+        let translator = super::KrakenV2CommandTranslator { req_id: AtomicU64::new(1) };
+        let commands = translator.translate_to_commands(
+            true,
+            &[
+                ("book".to_string(), "BTC/USD".to_string()),
+                ("book.100".to_string(), "ETH/USD".to_string()),
+            ],
+        );
+
+        assert_eq!(2, commands.len());
+        assert_eq!(
+            r#"{"method":"subscribe","params":{"channel":"book","depth":25,"symbol":["BTC/USD"]},"req_id":1}"#,
+            commands[0]
+        );
+        assert_eq!(
+            r#"{"method":"subscribe","params":{"channel":"book","depth":100,"symbol":["ETH/USD"]},"req_id":2}"#,
+            commands[1]
+        );
+    }
+
+    #[test]
+    fn test_instrument() {
+        let translator = super::KrakenV2CommandTranslator { req_id: AtomicU64::new(1) };
+        let commands =
+            translator.translate_to_commands(false, &[("instrument".to_string(), "".to_string())]);
+
+        assert_eq!(1, commands.len());
+        assert_eq!(
+            r#"{"method":"unsubscribe","params":{"channel":"instrument"},"req_id":1}"#,
+            commands[0]
+        );
+    }
+
+    #[test]
+    fn test_candlestick() {
+        let translator = super::KrakenV2CommandTranslator { req_id: AtomicU64::new(1) };
+        let commands = translator.translate_to_candlestick_commands(
+            true,
+            &[("BTC/USD".to_string(), 60), ("ETH/USD".to_string(), 60)],
+        );
+
+        assert_eq!(1, commands.len());
+        assert_eq!(
+            r#"{"method":"subscribe","params":{"channel":"ohlc","interval":1,"symbol":["BTC/USD","ETH/USD"]},"req_id":1}"#,
+            commands[0]
+        );
+    }
+}
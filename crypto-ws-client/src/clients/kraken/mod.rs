@@ -1,7 +1,23 @@
-mod kraken_futures;
-mod kraken_spot;
+pub(super) mod kraken_futures;
+pub(super) mod kraken_spot;
+pub(super) mod kraken_spot_v2;
+
+use crate::CandleInterval;
 
 const EXCHANGE_NAME: &str = "kraken";
 
+pub(super) const CANDLESTICK_INTERVALS: &[CandleInterval] = &[
+    CandleInterval::MIN1,
+    CandleInterval::MIN5,
+    CandleInterval::MIN15,
+    CandleInterval::MIN30,
+    CandleInterval::HOUR1,
+    CandleInterval::HOUR4,
+    CandleInterval::DAY1,
+    CandleInterval::WEEK1,
+    CandleInterval::from_secs(1296000), // 15 days
+];
+
 pub use kraken_futures::KrakenFuturesWSClient;
 pub use kraken_spot::KrakenSpotWSClient;
+pub use kraken_spot_v2::KrakenSpotV2WSClient;
@@ -1,14 +1,29 @@
-use super::utils::{fetch_ws_token, KucoinMessageHandler, EXCHANGE_NAME, UPLINK_LIMIT};
+use super::utils::{connect, KucoinMessageHandler, EXCHANGE_NAME};
 use crate::{
     clients::common_traits::{
         Candlestick, Level3OrderBook, OrderBook, OrderBookTopK, Ticker, Trade, BBO,
     },
     common::{command_translator::CommandTranslator, ws_client_internal::WSClientInternal},
-    WSClient,
+    CandleInterval, Error, WSClient,
 };
 use async_trait::async_trait;
+use crypto_market_type::MarketType;
 use std::sync::mpsc::Sender;
 
+pub(crate) const CANDLESTICK_INTERVALS: &[CandleInterval] = &[
+    CandleInterval::MIN1,
+    CandleInterval::MIN5,
+    CandleInterval::MIN15,
+    CandleInterval::MIN30,
+    CandleInterval::HOUR1,
+    CandleInterval::HOUR2,
+    CandleInterval::HOUR4,
+    CandleInterval::HOUR8,
+    CandleInterval::HOUR12,
+    CandleInterval::DAY1,
+    CandleInterval::WEEK1,
+];
+
 /// The WebSocket client for KuCoin Swap markets.
 ///
 /// * WebSocket API doc: <https://docs.kucoin.cc/futures/#websocket-2>
@@ -25,26 +40,19 @@ impl KuCoinSwapWSClient {
     ///
     /// * `tx` - The sending part of a channel
     /// * `url` - Optional server url, usually you don't need specify it
+    ///
+    /// Panics if it fails to connect, see `try_new()`.
     pub async fn new(tx: Sender<String>, url: Option<&str>) -> Self {
-        let real_url = match url {
-            Some(endpoint) => endpoint.to_string(),
-            None => {
-                let ws_token = fetch_ws_token().await;
-                let ws_url = format!("{}?token={}", ws_token.endpoint, ws_token.token);
-                ws_url
-            }
-        };
-        KuCoinSwapWSClient {
-            client: WSClientInternal::connect(
-                EXCHANGE_NAME,
-                &real_url,
-                KucoinMessageHandler {},
-                Some(UPLINK_LIMIT),
-                tx,
-            )
-            .await,
+        Self::try_new(tx, url).await.unwrap_or_else(|err| panic!("{err}"))
+    }
+
+    /// Same as `new()` but returns an error if it fails to fetch a token or to
+    /// connect to all instance servers.
+    pub async fn try_new(tx: Sender<String>, url: Option<&str>) -> Result<Self, Error> {
+        Ok(KuCoinSwapWSClient {
+            client: connect(MarketType::InverseSwap, url, tx).await?,
             translator: KucoinCommandTranslator {},
-        }
+        })
     }
 }
 
@@ -69,13 +77,6 @@ struct KucoinCommandTranslator {}
 
 impl KucoinCommandTranslator {
     fn to_candlestick_channel(symbol: &str, interval: usize) -> String {
-        let valid_set: Vec<usize> =
-            vec![60, 300, 900, 1800, 3600, 7200, 14400, 28800, 43200, 86400, 604800];
-        if !valid_set.contains(&interval) {
-            let joined =
-                valid_set.into_iter().map(|x| x.to_string()).collect::<Vec<String>>().join(",");
-            panic!("KuCoin Swap available intervals {joined}");
-        }
         format!("{}_{}", symbol, interval / 60)
     }
 }
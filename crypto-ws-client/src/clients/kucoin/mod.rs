@@ -1,6 +1,6 @@
-mod kucoin_spot;
-mod kucoin_swap;
-mod utils;
+pub(super) mod kucoin_spot;
+pub(super) mod kucoin_swap;
+pub(super) mod utils;
 
 pub use kucoin_spot::KuCoinSpotWSClient;
 pub use kucoin_swap::KuCoinSwapWSClient;
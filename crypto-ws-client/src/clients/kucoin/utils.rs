@@ -1,15 +1,28 @@
 use std::{
     collections::{BTreeMap, HashMap},
     num::NonZeroU32,
+    sync::mpsc::Sender,
+    time::{Duration, SystemTime, UNIX_EPOCH},
 };
 
+use base64::{engine::general_purpose, Engine as _};
+use crypto_market_type::MarketType;
+use hmac::{Hmac, Mac};
 use log::*;
 use nonzero_ext::nonzero;
-use reqwest::{header, Result};
+use rand::seq::SliceRandom;
+use reqwest::header;
 use serde_json::Value;
+use sha2::Sha256;
 use tokio_tungstenite::tungstenite::Message;
 
-use crate::common::message_handler::{MessageHandler, MiscMessage};
+use crate::{
+    common::{
+        message_handler::{MessageHandler, MiscMessage},
+        ws_client_internal::WSClientInternal,
+    },
+    Error,
+};
 
 pub(super) const EXCHANGE_NAME: &str = "kucoin";
 
@@ -21,13 +34,24 @@ const MAX_TOPICS_PER_COMMAND: usize = 100;
 pub(super) const UPLINK_LIMIT: (NonZeroU32, std::time::Duration) =
     (nonzero!(100u32), std::time::Duration::from_secs(10));
 
+// Used if the url is specified by the caller, in which case there is no bullet
+// response
+const DEFAULT_PING_INTERVAL: u64 = 60;
+
+const MAX_TOKEN_RETRIES: u32 = 3;
+
+pub(super) struct InstanceServer {
+    pub endpoint: String,
+    pub ping_interval: u64, // milliseconds
+    pub ping_timeout: u64,  // milliseconds
+}
+
 pub(super) struct WebsocketToken {
     pub token: String,
-    pub endpoint: String,
+    pub servers: Vec<InstanceServer>,
 }
 
-async fn http_post(url: &str) -> Result<String> {
-    let mut headers = header::HeaderMap::new();
+async fn http_post(url: &str, mut headers: header::HeaderMap) -> reqwest::Result<String> {
     headers.insert(header::CONTENT_TYPE, header::HeaderValue::from_static("application/json"));
 
     let client = reqwest::Client::builder()
@@ -43,23 +67,169 @@ async fn http_post(url: &str) -> Result<String> {
     }
 }
 
+// API key, secret and passphrase from the same environment variables as
+// crypto-rest-client, None if any of them is missing.
+fn get_credentials() -> Option<(String, String, String)> {
+    let api_key = std::env::var("KC-API-KEY").ok()?;
+    let api_secret = std::env::var("KC-API-SECRET").ok()?;
+    let api_passphrase = std::env::var("KC-API-PASSPHRASE").ok()?;
+    Some((api_key, api_secret, api_passphrase))
+}
+
+fn hmac_sha256_base64(secret: &str, message: &str) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("Creating HMAC failed");
+    mac.update(message.as_bytes());
+    general_purpose::STANDARD.encode(mac.finalize().into_bytes())
+}
+
+// See <https://docs.kucoin.com/#authentication>
+fn sign_headers(
+    api_key: &str,
+    api_secret: &str,
+    api_passphrase: &str,
+    endpoint: &str,
+) -> header::HeaderMap {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("Time went backwards")
+        .as_millis()
+        .to_string();
+    let sign = hmac_sha256_base64(api_secret, &format!("{timestamp}POST{endpoint}"));
+    let passphrase = hmac_sha256_base64(api_secret, api_passphrase);
+
+    let mut headers = header::HeaderMap::new();
+    for (name, value) in [
+        ("KC-API-KEY", api_key),
+        ("KC-API-SIGN", sign.as_str()),
+        ("KC-API-TIMESTAMP", timestamp.as_str()),
+        ("KC-API-PASSPHRASE", passphrase.as_str()),
+        ("KC-API-KEY-VERSION", "2"),
+    ] {
+        headers.insert(name, header::HeaderValue::from_str(value).unwrap());
+    }
+    headers
+}
+
+// Private tokens of spot and futures markets come from different hosts
+fn private_api_host(market_type: MarketType) -> &'static str {
+    if market_type == MarketType::Spot {
+        "https://api.kucoin.com"
+    } else {
+        "https://api-futures.kucoin.com"
+    }
+}
+
 // See <https://docs.kucoin.com/#apply-connect-token>
-pub(super) async fn fetch_ws_token() -> WebsocketToken {
-    let txt = http_post("https://openapi-v2.kucoin.com/api/v1/bullet-public").await.unwrap();
-    let obj = serde_json::from_str::<HashMap<String, Value>>(&txt).unwrap();
-    let code = obj.get("code").unwrap().as_str().unwrap();
+async fn fetch_ws_token_once(market_type: MarketType) -> Result<WebsocketToken, String> {
+    let txt = if let Some((api_key, api_secret, api_passphrase)) = get_credentials() {
+        let endpoint = "/api/v1/bullet-private";
+        let headers = sign_headers(&api_key, &api_secret, &api_passphrase, endpoint);
+        let host = private_api_host(market_type);
+        http_post(format!("{host}{endpoint}").as_str(), headers).await
+    } else {
+        http_post("https://openapi-v2.kucoin.com/api/v1/bullet-public", header::HeaderMap::new())
+            .await
+    }
+    .map_err(|err| err.to_string())?;
+
+    let obj =
+        serde_json::from_str::<HashMap<String, Value>>(&txt).map_err(|err| err.to_string())?;
+    let code = obj.get("code").and_then(|x| x.as_str()).unwrap_or_default();
     if code != "200000" {
-        panic!("Failed to get token, code is {code}");
+        return Err(format!("Failed to get token, response is {txt}"));
     }
-    let data = obj.get("data").unwrap().as_object().unwrap();
-    let token = data.get("token").unwrap().as_str().unwrap();
-    let servers = data.get("instanceServers").unwrap().as_array().unwrap();
-    let server = servers[0].as_object().unwrap();
+    let data = obj.get("data").and_then(|x| x.as_object()).ok_or(format!("No data in {txt}"))?;
+    let token = data.get("token").and_then(|x| x.as_str()).ok_or(format!("No token in {txt}"))?;
+    let servers = data
+        .get("instanceServers")
+        .and_then(|x| x.as_array())
+        .ok_or(format!("No instanceServers in {txt}"))?
+        .iter()
+        .filter_map(|server| {
+            Some(InstanceServer {
+                endpoint: server.get("endpoint")?.as_str()?.to_string(),
+                ping_interval: server.get("pingInterval")?.as_u64()?,
+                ping_timeout: server.get("pingTimeout")?.as_u64()?,
+            })
+        })
+        .collect::<Vec<InstanceServer>>();
+    if servers.is_empty() {
+        return Err(format!("No valid instance servers in {txt}"));
+    }
+
+    Ok(WebsocketToken { token: token.to_string(), servers })
+}
 
-    WebsocketToken {
-        token: token.to_string(),
-        endpoint: server.get("endpoint").unwrap().as_str().unwrap().to_string(),
+/// Applies for a token of the spot or futures market, retries with
+/// exponential backoff.
+pub(super) async fn fetch_ws_token(market_type: MarketType) -> Result<WebsocketToken, String> {
+    let mut last_err = String::new();
+    for i in 0..MAX_TOKEN_RETRIES {
+        match fetch_ws_token_once(market_type).await {
+            Ok(ws_token) => return Ok(ws_token),
+            Err(err) => {
+                warn!("Failed to fetch websocket token from {}, {}", EXCHANGE_NAME, err);
+                last_err = err;
+                if i + 1 < MAX_TOKEN_RETRIES {
+                    tokio::time::sleep(Duration::from_secs(1 << i)).await;
+                }
+            }
+        }
+    }
+    Err(last_err)
+}
+
+/// Connects to the given url, or otherwise applies for a fresh token and
+/// tries all instance servers in random order until one succeeds.
+///
+/// Tokens are never cached, so every new connection, including the one
+/// after a reconnect, uses a fresh token.
+///
+/// Returns an error if no token can be fetched or all servers fail.
+pub(super) async fn connect(
+    market_type: MarketType,
+    url: Option<&str>,
+    tx: Sender<String>,
+) -> Result<WSClientInternal<KucoinMessageHandler>, Error> {
+    if let Some(endpoint) = url {
+        let handler = KucoinMessageHandler { ping_interval: DEFAULT_PING_INTERVAL };
+        return WSClientInternal::try_connect(
+            EXCHANGE_NAME,
+            endpoint,
+            handler,
+            Some(UPLINK_LIMIT),
+            tx,
+        )
+        .await
+        .map_err(|err| Error(format!("Failed to connect to {endpoint}, error: {err}")));
+    }
+
+    let mut ws_token = fetch_ws_token(market_type).await.map_err(|err| {
+        Error(format!("Failed to fetch websocket token from {EXCHANGE_NAME}, {err}"))
+    })?;
+    ws_token.servers.shuffle(&mut rand::thread_rng());
+    for server in ws_token.servers.iter() {
+        let ws_url = format!("{}?token={}", server.endpoint, ws_token.token);
+        // The client must send a ping within ping_interval, while the server waits
+        // ping_timeout more before disconnecting
+        let handler = KucoinMessageHandler { ping_interval: server.ping_interval / 1000 };
+        match WSClientInternal::try_connect(
+            EXCHANGE_NAME,
+            &ws_url,
+            handler,
+            Some(UPLINK_LIMIT),
+            tx.clone(),
+        )
+        .await
+        {
+            Ok(client) => return Ok(client),
+            Err(err) => warn!(
+                "Failed to connect to {} (ping_timeout {}ms), error: {}, trying the next server",
+                server.endpoint, server.ping_timeout, err
+            ),
+        }
     }
+    Err(Error(format!("Failed to connect to all instance servers of {EXCHANGE_NAME}")))
 }
 
 fn channel_symbols_to_command(channel: &str, symbols: &[String], subscribe: bool) -> String {
@@ -101,12 +271,19 @@ pub(super) fn topics_to_commands(topics: &[(String, String)], subscribe: bool) -
     commands
 }
 
-pub(super) struct KucoinMessageHandler {}
+pub(super) struct KucoinMessageHandler {
+    ping_interval: u64, // seconds
+}
 
 impl MessageHandler for KucoinMessageHandler {
     fn handle_message(&mut self, msg: &str) -> MiscMessage {
-        let obj = serde_json::from_str::<HashMap<String, Value>>(msg).unwrap();
-        let msg_type = obj.get("type").unwrap().as_str().unwrap();
+        let resp = serde_json::from_str::<HashMap<String, Value>>(msg);
+        if resp.is_err() {
+            error!("{} is not a JSON string, {}", msg, EXCHANGE_NAME);
+            return MiscMessage::Other;
+        }
+        let obj = resp.unwrap();
+        let msg_type = obj.get("type").and_then(|x| x.as_str()).unwrap_or_default();
         match msg_type {
             "pong" => MiscMessage::Pong,
             "welcome" | "ack" => {
@@ -118,11 +295,10 @@ impl MessageHandler for KucoinMessageHandler {
                 MiscMessage::Other
             }
             "message" => MiscMessage::Normal,
-            "error" => {
-                panic!("Received {msg} from {EXCHANGE_NAME}");
-            }
+            "error" => MiscMessage::Error(format!("Received {msg} from {EXCHANGE_NAME}")),
             _ => {
-                panic!("Received {msg} from {EXCHANGE_NAME}");
+                warn!("Received {} from {}", msg, EXCHANGE_NAME);
+                MiscMessage::Other
             }
         }
     }
@@ -132,18 +308,37 @@ impl MessageHandler for KucoinMessageHandler {
         // - https://docs.kucoin.com/#ping
         // - https://docs.kucoin.cc/futures/#ping
         //
-        // If the server has not received the ping from the client for 60 seconds , the
-        // connection will be disconnected.
-        Some((Message::Text(r#"{"type":"ping", "id": "crypto-ws-client"}"#.to_string()), 60))
+        // The bullet response tells how often the client should send a ping, the
+        // connection will be disconnected if the server doesn't receive it in time.
+        Some((
+            Message::Text(r#"{"type":"ping", "id": "crypto-ws-client"}"#.to_string()),
+            self.ping_interval,
+        ))
     }
 }
 
+impl_fuzz_message_handler!(KucoinMessageHandler { ping_interval: DEFAULT_PING_INTERVAL });
+impl_new_message_handler!(KucoinMessageHandler { ping_interval: DEFAULT_PING_INTERVAL });
+
 #[cfg(test)]
 mod tests {
+    use crypto_market_type::MarketType;
+
+    #[test]
+    fn private_api_host() {
+        assert_eq!("https://api.kucoin.com", super::private_api_host(MarketType::Spot));
+        assert_eq!(
+            "https://api-futures.kucoin.com",
+            super::private_api_host(MarketType::LinearSwap)
+        );
+    }
+
     #[tokio::test(flavor = "multi_thread")]
     async fn fetch_ws_token() {
-        let ws_token = super::fetch_ws_token().await;
-        assert!(!ws_token.token.is_empty())
+        let ws_token = super::fetch_ws_token(MarketType::Spot).await.unwrap();
+        assert!(!ws_token.token.is_empty());
+        assert!(!ws_token.servers.is_empty());
+        assert!(ws_token.servers[0].ping_interval > 0);
     }
 
     #[test]
@@ -12,13 +12,26 @@ use crate::{
         message_handler::{MessageHandler, MiscMessage},
         ws_client_internal::WSClientInternal,
     },
-    WSClient,
+    CandleInterval, WSClient,
 };
 use log::*;
 use serde_json::Value;
 
 pub(super) const SPOT_WEBSOCKET_URL: &str = "wss://wbs.mexc.com/raw/ws";
 
+pub(crate) const CANDLESTICK_INTERVALS: &[CandleInterval] = &[
+    CandleInterval::MIN1,
+    CandleInterval::MIN5,
+    CandleInterval::MIN15,
+    CandleInterval::MIN30,
+    CandleInterval::HOUR1,
+    CandleInterval::HOUR4,
+    CandleInterval::HOUR8,
+    CandleInterval::DAY1,
+    CandleInterval::WEEK1,
+    CandleInterval::MONTH1,
+];
+
 /// MEXC Spot market.
 ///
 ///   * WebSocket API doc: <https://github.com/mxcdevelop/APIDoc/blob/master/websocket/spot/websocket-api.md>
@@ -60,7 +73,7 @@ impl MessageHandler for MexcMessageHandler {
         }
         if let Ok(obj) = serde_json::from_str::<HashMap<String, Value>>(msg) {
             if obj.contains_key("channel") && obj.contains_key("data") {
-                let channel = obj.get("channel").unwrap().as_str().unwrap();
+                let channel = obj.get("channel").and_then(|x| x.as_str()).unwrap_or_default();
                 match channel {
                     "push.deal" | "push.depth" | "push.limit.depth" | "push.kline" => {
                         if obj.contains_key("symbol") {
@@ -91,6 +104,9 @@ impl MessageHandler for MexcMessageHandler {
     }
 }
 
+impl_fuzz_message_handler!(MexcMessageHandler {});
+impl_new_message_handler!(MexcMessageHandler {});
+
 impl MexcCommandTranslator {
     fn topic_to_command(channel: &str, symbol: &str, subscribe: bool) -> String {
         if channel == "limit.depth" {
@@ -12,7 +12,7 @@ use crate::{
         message_handler::{MessageHandler, MiscMessage},
         ws_client_internal::WSClientInternal,
     },
-    WSClient,
+    CandleInterval, WSClient,
 };
 
 use log::*;
@@ -20,6 +20,19 @@ use serde_json::Value;
 
 pub(super) const SWAP_WEBSOCKET_URL: &str = "wss://contract.mexc.com/ws";
 
+pub(crate) const CANDLESTICK_INTERVALS: &[CandleInterval] = &[
+    CandleInterval::MIN1,
+    CandleInterval::MIN5,
+    CandleInterval::MIN15,
+    CandleInterval::MIN30,
+    CandleInterval::HOUR1,
+    CandleInterval::HOUR4,
+    CandleInterval::HOUR8,
+    CandleInterval::DAY1,
+    CandleInterval::WEEK1,
+    CandleInterval::MONTH1,
+];
+
 /// MEXC Swap market.
 ///
 ///   * WebSocket API doc: <https://mxcdevelop.github.io/APIDoc/contract.api.en.html#websocket-api>
@@ -57,15 +70,17 @@ struct MexcCommandTranslator {}
 
 impl MessageHandler for MexcMessageHandler {
     fn handle_message(&mut self, msg: &str) -> MiscMessage {
-        let obj = serde_json::from_str::<HashMap<String, Value>>(msg).unwrap();
+        let resp = serde_json::from_str::<HashMap<String, Value>>(msg);
+        if resp.is_err() {
+            error!("{} is not a JSON string, {}", msg, EXCHANGE_NAME);
+            return MiscMessage::Other;
+        }
+        let obj = resp.unwrap();
         if obj.contains_key("channel") && obj.contains_key("data") && obj.contains_key("ts") {
-            let channel = obj.get("channel").unwrap().as_str().unwrap();
+            let channel = obj.get("channel").and_then(|x| x.as_str()).unwrap_or_default();
             match channel {
                 "pong" => MiscMessage::Pong,
-                "rs.error" => {
-                    error!("Received {} from {}", msg, EXCHANGE_NAME);
-                    panic!("Received {msg} from {EXCHANGE_NAME}");
-                }
+                "rs.error" => MiscMessage::Error(format!("Received {msg} from {EXCHANGE_NAME}")),
                 _ => {
                     if obj.contains_key("symbol") && channel.starts_with("push.") {
                         MiscMessage::Normal
@@ -87,6 +102,9 @@ impl MessageHandler for MexcMessageHandler {
     }
 }
 
+impl_fuzz_message_handler!(MexcMessageHandler {});
+impl_new_message_handler!(MexcMessageHandler {});
+
 impl MexcCommandTranslator {
     fn topic_to_command(channel: &str, symbol: &str, subscribe: bool) -> String {
         format!(
@@ -1,5 +1,5 @@
-mod mexc_spot;
-mod mexc_swap;
+pub(super) mod mexc_spot;
+pub(super) mod mexc_swap;
 
 pub(super) const EXCHANGE_NAME: &str = "mexc";
 
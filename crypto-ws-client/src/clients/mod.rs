@@ -10,6 +10,7 @@ pub(super) mod bitmex;
 pub(super) mod bitstamp;
 pub(super) mod bitz;
 pub(super) mod bybit;
+pub(super) mod coinbase;
 pub(super) mod coinbase_pro;
 pub(super) mod deribit;
 pub(super) mod dydx;
@@ -22,3 +23,294 @@ pub(super) mod mexc;
 pub(super) mod okx;
 pub(super) mod zb;
 pub(super) mod zbg;
+
+use crate::{common::message_handler::BoxedMessageHandler, CandleInterval};
+use crypto_market_type::MarketType;
+
+/// Candlestick intervals supported by the websocket API of an exchange.
+///
+/// An empty list is returned if the market has no candlestick channel.
+pub fn supported_candlestick_intervals(
+    exchange: &str,
+    market_type: MarketType,
+) -> Vec<CandleInterval> {
+    let intervals: &[CandleInterval] = match (exchange, market_type) {
+        ("binance", MarketType::EuropeanOption) => binance_option::CANDLESTICK_INTERVALS,
+        ("binance", _) => binance::CANDLESTICK_INTERVALS,
+        ("bitfinex", _) => bitfinex::CANDLESTICK_INTERVALS,
+        ("bitget", _) => bitget::utils::CANDLESTICK_INTERVALS,
+        ("bitmex", _) => bitmex::CANDLESTICK_INTERVALS,
+        ("bitz", MarketType::Spot) => bitz::bitz_spot::CANDLESTICK_INTERVALS,
+        ("bybit", MarketType::Spot) => bybit::bybit_v5::CANDLESTICK_INTERVALS,
+        ("bybit", MarketType::InverseSwap | MarketType::InverseFuture) => {
+            bybit::bybit_inverse::CANDLESTICK_INTERVALS
+        }
+        ("bybit", MarketType::LinearSwap) => bybit::bybit_linear_swap::CANDLESTICK_INTERVALS,
+        ("coinbase", _) => coinbase::CANDLESTICK_INTERVALS,
+        ("deribit", _) => deribit::CANDLESTICK_INTERVALS,
+        ("dydx", _) => dydx::dydx_swap::CANDLESTICK_INTERVALS,
+        ("gate", _) => gate::utils::CANDLESTICK_INTERVALS,
+        ("huobi", _) => huobi::CANDLESTICK_INTERVALS,
+        ("kraken", MarketType::Spot) => kraken::CANDLESTICK_INTERVALS,
+        ("kucoin", MarketType::Spot) => kucoin::kucoin_spot::CANDLESTICK_INTERVALS,
+        ("kucoin", _) => kucoin::kucoin_swap::CANDLESTICK_INTERVALS,
+        ("mexc", MarketType::Spot) => mexc::mexc_spot::CANDLESTICK_INTERVALS,
+        ("mexc", _) => mexc::mexc_swap::CANDLESTICK_INTERVALS,
+        ("okx", _) => okx::CANDLESTICK_INTERVALS,
+        ("zb", MarketType::Spot) => zb::zb_spot::CANDLESTICK_INTERVALS,
+        ("zb", MarketType::LinearSwap) => zb::zb_swap::CANDLESTICK_INTERVALS,
+        ("zbg", MarketType::Spot) => zbg::zbg_spot::CANDLESTICK_INTERVALS,
+        ("zbg", _) => zbg::zbg_swap::CANDLESTICK_INTERVALS,
+        _ => &[],
+    };
+    intervals.to_vec()
+}
+
+/// Creates the message handler of the client which connects to `url`,
+/// configured as by `new()` of the client.
+///
+/// Returns None if the exchange is unknown.
+pub(crate) fn new_message_handler(exchange: &str, url: &str) -> Option<BoxedMessageHandler> {
+    let handler = match exchange {
+        "binance" if url.contains("opsnest") || url.contains("eoptions") => {
+            binance_option::new_message_handler()
+        }
+        "binance" => binance::new_message_handler(),
+        "bitfinex" => bitfinex::new_message_handler(),
+        "bitget" => bitget::utils::new_message_handler(),
+        "bithumb" => bithumb::new_message_handler(),
+        "bitmex" => bitmex::new_message_handler(),
+        "bitstamp" => bitstamp::new_message_handler(),
+        "bitz" => bitz::bitz_spot::new_message_handler(),
+        "bybit" if url.contains("/v5/") => bybit::bybit_v5::new_message_handler(),
+        "bybit" => bybit::utils::new_message_handler(),
+        "coinbase" => coinbase::new_message_handler(),
+        "coinbase_pro" => coinbase_pro::new_message_handler(),
+        "deribit" => deribit::new_message_handler(),
+        "dydx" => dydx::dydx_swap::new_message_handler(),
+        "ftx" => ftx::new_message_handler(),
+        "gate" if url.contains("fx-ws") => gate::utils::new_futures_message_handler(),
+        "gate" => gate::utils::new_spot_message_handler(),
+        "huobi" => huobi::new_message_handler(),
+        "kraken" if url.contains("futures") => kraken::kraken_futures::new_message_handler(),
+        "kraken" if url.trim_end_matches('/').ends_with("/v2") => {
+            kraken::kraken_spot_v2::new_message_handler()
+        }
+        "kraken" => kraken::kraken_spot::new_message_handler(),
+        "kucoin" => kucoin::utils::new_message_handler(),
+        "mexc" if url.contains("contract") => mexc::mexc_swap::new_message_handler(),
+        "mexc" => mexc::mexc_spot::new_message_handler(),
+        "okx" => okx::new_message_handler(),
+        "zb" if url.contains("fapi") => zb::zb_swap::new_message_handler(),
+        "zb" => zb::zb_spot::new_message_handler(),
+        "zbg" if url.contains("futurews") => zbg::zbg_swap::new_message_handler(),
+        "zbg" => zbg::zbg_spot::new_message_handler(),
+        _ => return None,
+    };
+    Some(handler)
+}
+
+/// Names of message handlers accepted by `fuzz_message_handler()`.
+#[cfg(any(test, feature = "fuzzing"))]
+#[doc(hidden)]
+pub const MESSAGE_HANDLERS: &[&str] = &[
+    "binance",
+    "binance_option",
+    "bitfinex",
+    "bitget",
+    "bithumb",
+    "bitmex",
+    "bitstamp",
+    "bitz",
+    "bybit",
+    "bybit_v5",
+    "coinbase",
+    "coinbase_pro",
+    "deribit",
+    "dydx",
+    "ftx",
+    "gate",
+    "huobi",
+    "kraken_futures",
+    "kraken_spot",
+    "kraken_spot_v2",
+    "kucoin",
+    "mexc_spot",
+    "mexc_swap",
+    "okx",
+    "zb_spot",
+    "zb_swap",
+    "zbg_spot",
+    "zbg_swap",
+];
+
+/// Feeds messages in order to a new message handler, used by fuzz targets.
+///
+/// Message handlers must never panic, whatever they receive.
+#[cfg(any(test, feature = "fuzzing"))]
+#[doc(hidden)]
+pub fn fuzz_message_handler(handler: &str, msgs: &[&str]) {
+    match handler {
+        "binance" => binance::fuzz_message_handler(msgs),
+        "binance_option" => binance_option::fuzz_message_handler(msgs),
+        "bitfinex" => bitfinex::fuzz_message_handler(msgs),
+        "bitget" => bitget::utils::fuzz_message_handler(msgs),
+        "bithumb" => bithumb::fuzz_message_handler(msgs),
+        "bitmex" => bitmex::fuzz_message_handler(msgs),
+        "bitstamp" => bitstamp::fuzz_message_handler(msgs),
+        "bitz" => bitz::bitz_spot::fuzz_message_handler(msgs),
+        "bybit" => bybit::utils::fuzz_message_handler(msgs),
+        "bybit_v5" => bybit::bybit_v5::fuzz_message_handler(msgs),
+        "coinbase" => coinbase::fuzz_message_handler(msgs),
+        "coinbase_pro" => coinbase_pro::fuzz_message_handler(msgs),
+        "deribit" => deribit::fuzz_message_handler(msgs),
+        "dydx" => dydx::dydx_swap::fuzz_message_handler(msgs),
+        "ftx" => ftx::fuzz_message_handler(msgs),
+        "gate" => gate::utils::fuzz_message_handler(msgs),
+        "huobi" => huobi::fuzz_message_handler(msgs),
+        "kraken_futures" => kraken::kraken_futures::fuzz_message_handler(msgs),
+        "kraken_spot" => kraken::kraken_spot::fuzz_message_handler(msgs),
+        "kraken_spot_v2" => kraken::kraken_spot_v2::fuzz_message_handler(msgs),
+        "kucoin" => kucoin::utils::fuzz_message_handler(msgs),
+        "mexc_spot" => mexc::mexc_spot::fuzz_message_handler(msgs),
+        "mexc_swap" => mexc::mexc_swap::fuzz_message_handler(msgs),
+        "okx" => okx::fuzz_message_handler(msgs),
+        "zb_spot" => zb::zb_spot::fuzz_message_handler(msgs),
+        "zb_swap" => zb::zb_swap::fuzz_message_handler(msgs),
+        "zbg_spot" => zbg::zbg_spot::fuzz_message_handler(msgs),
+        "zbg_swap" => zbg::zbg_swap::fuzz_message_handler(msgs),
+        _ => panic!("Unknown message handler {handler}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::{
+        fuzz_message_handler, new_message_handler, supported_candlestick_intervals,
+        MESSAGE_HANDLERS,
+    };
+    use crate::CandleInterval;
+    use crypto_market_type::MarketType;
+    use serde_json::{json, Value};
+
+    #[test]
+    fn test_supported_candlestick_intervals() {
+        let intervals = supported_candlestick_intervals("binance", MarketType::Spot);
+        assert!(intervals.contains(&CandleInterval::MIN1));
+        assert!(intervals.contains(&CandleInterval::MONTH1));
+
+        let intervals = supported_candlestick_intervals("coinbase", MarketType::Spot);
+        assert_eq!(vec![CandleInterval::MIN5], intervals);
+
+        assert!(supported_candlestick_intervals("kraken", MarketType::InverseFuture).is_empty());
+        assert!(supported_candlestick_intervals("bitstamp", MarketType::Spot).is_empty());
+    }
+
+    #[test]
+    fn test_new_message_handler() {
+        for exchange in [
+            "binance",
+            "bitfinex",
+            "bitget",
+            "bithumb",
+            "bitmex",
+            "bitstamp",
+            "bitz",
+            "bybit",
+            "coinbase",
+            "coinbase_pro",
+            "deribit",
+            "dydx",
+            "ftx",
+            "gate",
+            "huobi",
+            "kraken",
+            "kucoin",
+            "mexc",
+            "okx",
+            "zb",
+            "zbg",
+        ] {
+            assert!(new_message_handler(exchange, "wss://example.com/ws").is_some());
+        }
+        assert!(new_message_handler("unknown", "wss://example.com/ws").is_none());
+    }
+
+    // Replaces or removes one node of a JSON value at a time.
+    fn mutate(value: &Value, out: &mut Vec<Value>) {
+        let replacements = [json!(null), json!(true), json!(-1), json!("x"), json!([]), json!({})];
+        out.extend(replacements.iter().cloned());
+        match value {
+            Value::Array(arr) => {
+                for i in 0..arr.len() {
+                    let mut removed = arr.clone();
+                    removed.remove(i);
+                    out.push(Value::Array(removed));
+
+                    let mut children = Vec::new();
+                    mutate(&arr[i], &mut children);
+                    for child in children {
+                        let mut mutated = arr.clone();
+                        mutated[i] = child;
+                        out.push(Value::Array(mutated));
+                    }
+                }
+            }
+            Value::Object(obj) => {
+                for key in obj.keys() {
+                    let mut removed = obj.clone();
+                    removed.remove(key);
+                    out.push(Value::Object(removed));
+
+                    let mut children = Vec::new();
+                    mutate(&obj[key], &mut children);
+                    for child in children {
+                        let mut mutated = obj.clone();
+                        mutated.insert(key.clone(), child);
+                        out.push(Value::Object(mutated));
+                    }
+                }
+            }
+            _ => (),
+        }
+    }
+
+    #[test]
+    fn message_handlers_never_panic() {
+        let corpus_dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("fuzz").join("corpus");
+        for handler in MESSAGE_HANDLERS {
+            let dir = corpus_dir.join(handler);
+            let seeds = std::fs::read_dir(&dir)
+                .unwrap_or_else(|err| panic!("Failed to read {}, {}", dir.display(), err));
+            for seed in seeds {
+                let text = std::fs::read_to_string(seed.unwrap().path()).unwrap();
+                let lines = text.lines().collect::<Vec<&str>>();
+                fuzz_message_handler(handler, &lines);
+
+                // mutate one line at a time after its preceding lines, so that
+                // stateful handlers see it in the right state
+                for (i, line) in lines.iter().enumerate() {
+                    let truncated = line.get(..line.len() / 2).unwrap_or_default();
+                    let mut mutations = vec![
+                        "".to_string(),
+                        "{".to_string(),
+                        "null".to_string(),
+                        truncated.to_string(),
+                    ];
+                    if let Ok(value) = serde_json::from_str::<Value>(line) {
+                        let mut values = Vec::new();
+                        mutate(&value, &mut values);
+                        mutations.extend(values.iter().map(|x| x.to_string()));
+                    }
+                    for msg in mutations.iter() {
+                        let mut msgs = lines[..i].to_vec();
+                        msgs.push(msg);
+                        fuzz_message_handler(handler, &msgs);
+                    }
+                }
+            }
+        }
+    }
+}
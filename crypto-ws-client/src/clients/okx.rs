@@ -14,12 +14,13 @@ use crate::{
         Candlestick, Level3OrderBook, OrderBook, OrderBookTopK, Ticker, Trade, BBO,
     },
     common::{
+        checksum::{crc32, interleave_levels, ChecksumConfig, ChecksumValidator},
         command_translator::CommandTranslator,
         message_handler::{MessageHandler, MiscMessage},
         utils::ensure_frame_size,
         ws_client_internal::WSClientInternal,
     },
-    WSClient,
+    CandleInterval, WSClient,
 };
 
 pub(crate) const EXCHANGE_NAME: &str = "okx";
@@ -35,6 +36,25 @@ const WS_FRAME_SIZE: usize = 4096;
 const UPLINK_LIMIT: (NonZeroU32, std::time::Duration) =
     (nonzero!(240u32), std::time::Duration::from_secs(3600));
 
+pub(super) const CANDLESTICK_INTERVALS: &[CandleInterval] = &[
+    CandleInterval::MIN1,
+    CandleInterval::MIN3,
+    CandleInterval::MIN5,
+    CandleInterval::MIN15,
+    CandleInterval::MIN30,
+    CandleInterval::HOUR1,
+    CandleInterval::HOUR2,
+    CandleInterval::HOUR4,
+    CandleInterval::HOUR6,
+    CandleInterval::HOUR12,
+    CandleInterval::DAY1,
+    CandleInterval::DAY2,
+    CandleInterval::DAY3,
+    CandleInterval::DAY5,
+    CandleInterval::WEEK1,
+    CandleInterval::MONTH1,
+];
+
 /// The WebSocket client for OKX.
 ///
 /// OKX has Spot, Future, Swap and Option markets.
@@ -52,6 +72,23 @@ pub struct OkxWSClient {
 
 impl OkxWSClient {
     pub async fn new(tx: std::sync::mpsc::Sender<String>, url: Option<&str>) -> Self {
+        Self::connect(tx, url, None).await
+    }
+
+    /// Creates a client which validates `books` messages with their checksums.
+    pub async fn new_with_checksum(
+        tx: std::sync::mpsc::Sender<String>,
+        url: Option<&str>,
+        config: ChecksumConfig,
+    ) -> Self {
+        Self::connect(tx, url, Some(ChecksumValidator::new(EXCHANGE_NAME, config))).await
+    }
+
+    async fn connect(
+        tx: std::sync::mpsc::Sender<String>,
+        url: Option<&str>,
+        checksum: Option<ChecksumValidator>,
+    ) -> Self {
         let real_url = match url {
             Some(endpoint) => endpoint,
             None => WEBSOCKET_URL,
@@ -60,7 +97,7 @@ impl OkxWSClient {
             client: WSClientInternal::connect(
                 EXCHANGE_NAME,
                 real_url,
-                OkxMessageHandler {},
+                OkxMessageHandler { checksum },
                 Some(UPLINK_LIMIT),
                 tx,
             )
@@ -83,7 +120,9 @@ panic_l3_orderbook!(OkxWSClient);
 
 impl_ws_client_trait!(OkxWSClient);
 
-struct OkxMessageHandler {}
+struct OkxMessageHandler {
+    checksum: Option<ChecksumValidator>,
+}
 struct OkxCommandTranslator {}
 
 impl OkxCommandTranslator {
@@ -129,6 +168,55 @@ impl OkxCommandTranslator {
     }
 }
 
+impl OkxMessageHandler {
+    // see https://www.okx.com/docs-v5/en/#order-book-trading-market-data-ws-order-book-channel
+    //
+    // Returns resubscribe commands if the checksum mismatches and resubscription is
+    // enabled.
+    fn validate_checksum(
+        validator: &mut ChecksumValidator,
+        obj: &HashMap<String, Value>,
+    ) -> Option<Vec<String>> {
+        let arg = obj.get("arg")?;
+        let channel = arg["channel"].as_str()?;
+        // books5 and bbo-tbt are always snapshots without checksums
+        if channel != "books" && channel != "books-l2-tbt" && channel != "books50-l2-tbt" {
+            return None;
+        }
+        let symbol = arg["instId"].as_str()?;
+        let action = obj.get("action").and_then(|x| x.as_str()).unwrap_or("snapshot");
+
+        for data in obj.get("data")?.as_array()? {
+            let book = if action == "snapshot" {
+                validator.reset(channel, symbol)
+            } else {
+                validator.book(channel, symbol)? // no snapshot yet
+            };
+            for (side, is_bid) in [("bids", true), ("asks", false)] {
+                for level in data[side].as_array().into_iter().flatten() {
+                    if let (Some(price), Some(quantity)) = (level[0].as_str(), level[1].as_str()) {
+                        book.update(is_bid, price, quantity);
+                    }
+                }
+            }
+            if let Some(expected) = data.get("checksum").and_then(|x| x.as_i64()) {
+                let actual = crc32(&interleave_levels(book, 25));
+                if !validator.verify(channel, symbol, expected as i32 as u32, actual) {
+                    if validator.resubscribe() {
+                        let topic = [(channel.to_string(), symbol.to_string())];
+                        return Some(vec![
+                            OkxCommandTranslator::topics_to_command(&topic, false),
+                            OkxCommandTranslator::topics_to_command(&topic, true),
+                        ]);
+                    }
+                    break;
+                }
+            }
+        }
+        None
+    }
+}
+
 impl MessageHandler for OkxMessageHandler {
     fn handle_message(&mut self, msg: &str) -> MiscMessage {
         if msg == "pong" {
@@ -142,17 +230,24 @@ impl MessageHandler for OkxMessageHandler {
         let obj = resp.unwrap();
 
         if let Some(event) = obj.get("event") {
-            match event.as_str().unwrap() {
+            match event.as_str().unwrap_or_default() {
                 "error" => {
-                    let error_code =
-                        obj.get("code").unwrap().as_str().unwrap().parse::<i64>().unwrap();
+                    let error_code = obj
+                        .get("code")
+                        .and_then(|x| x.as_str())
+                        .and_then(|x| x.parse::<i64>().ok())
+                        .unwrap_or_default();
                     match error_code {
                         30040 => {
                             // channel doesn't exist, ignore because some symbols don't exist in
                             // websocket while they exist in `/v3/instruments`
                             error!("Received {} from {}", msg, EXCHANGE_NAME);
                         }
-                        _ => panic!("Received {msg} from {EXCHANGE_NAME}"),
+                        _ => {
+                            return MiscMessage::Error(format!(
+                                "Received {msg} from {EXCHANGE_NAME}"
+                            ))
+                        }
                     }
                 }
                 "subscribe" => info!("Received {} from {}", msg, EXCHANGE_NAME),
@@ -164,6 +259,11 @@ impl MessageHandler for OkxMessageHandler {
             error!("Received {} from {}", msg, EXCHANGE_NAME);
             MiscMessage::Other
         } else {
+            if let Some(validator) = self.checksum.as_mut() {
+                if let Some(commands) = Self::validate_checksum(validator, &obj) {
+                    return MiscMessage::Resubscribe(commands);
+                }
+            }
             MiscMessage::Normal
         }
     }
@@ -174,6 +274,14 @@ impl MessageHandler for OkxMessageHandler {
     }
 }
 
+impl_fuzz_message_handler!(OkxMessageHandler {
+    checksum: Some(ChecksumValidator::new(
+        EXCHANGE_NAME,
+        ChecksumConfig { resubscribe: true, ..Default::default() }
+    )),
+});
+impl_new_message_handler!(OkxMessageHandler { checksum: None });
+
 impl CommandTranslator for OkxCommandTranslator {
     fn translate_to_commands(&self, subscribe: bool, topics: &[(String, String)]) -> Vec<String> {
         ensure_frame_size(topics, subscribe, Self::topics_to_command, WS_FRAME_SIZE, None)
@@ -229,4 +337,41 @@ mod tests {
             commands[0]
         );
     }
+
+    #[test]
+    fn test_checksum() {
+        use crate::common::{
+            checksum::{ChecksumConfig, ChecksumValidator},
+            message_handler::{MessageHandler, MiscMessage},
+        };
+
+        let (tx, rx) = std::sync::mpsc::channel();
+        let mut handler = super::OkxMessageHandler {
+            checksum: Some(ChecksumValidator::new(
+                super::EXCHANGE_NAME,
+                ChecksumConfig { tx: Some(tx), resubscribe: true },
+            )),
+        };
+
+        let snapshot = r#"{"arg":{"channel":"books","instId":"BTC-USDT"},"action":"snapshot","data":[{"asks":[["3366.8","9","10","3"],["3368","8","3","4"]],"bids":[["3366.1","7","0","3"],["3366","6","3","4"]],"ts":"1597026383085","checksum":-1881014294}]}"#;
+        assert!(matches!(handler.handle_message(snapshot), MiscMessage::Normal));
+
+        // removes 3366 from bids
+        let update = r#"{"arg":{"channel":"books","instId":"BTC-USDT"},"action":"update","data":[{"asks":[],"bids":[["3366","0","0","0"]],"ts":"1597026383086","checksum":-1881014294}]}"#;
+        match handler.handle_message(update) {
+            MiscMessage::Resubscribe(commands) => {
+                assert_eq!(
+                    vec![
+                        r#"{"op":"unsubscribe","args":[{"channel":"books","instId":"BTC-USDT"}]}"#,
+                        r#"{"op":"subscribe","args":[{"channel":"books","instId":"BTC-USDT"}]}"#,
+                    ],
+                    commands
+                );
+            }
+            other => panic!("Unexpected {other:?}"),
+        }
+        let mismatch = rx.try_recv().unwrap();
+        assert_eq!("BTC-USDT", mismatch.symbol);
+        assert_eq!(-1881014294i32 as u32, mismatch.expected);
+    }
 }
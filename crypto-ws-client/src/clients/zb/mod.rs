@@ -1,5 +1,5 @@
-mod zb_spot;
-mod zb_swap;
+pub(super) mod zb_spot;
+pub(super) mod zb_swap;
 
 pub use zb_spot::ZbSpotWSClient;
 pub use zb_swap::ZbSwapWSClient;
@@ -14,7 +14,7 @@ use crate::{
         message_handler::{MessageHandler, MiscMessage},
         ws_client_internal::WSClientInternal,
     },
-    WSClient,
+    CandleInterval, WSClient,
 };
 
 use super::EXCHANGE_NAME;
@@ -22,6 +22,22 @@ use super::EXCHANGE_NAME;
 // If you're in China, use wss://api.zbex.site/websocket instead
 const WEBSOCKET_URL: &str = "wss://api.zb.com/websocket";
 
+pub(crate) const CANDLESTICK_INTERVALS: &[CandleInterval] = &[
+    CandleInterval::MIN1,
+    CandleInterval::MIN3,
+    CandleInterval::MIN5,
+    CandleInterval::MIN15,
+    CandleInterval::MIN30,
+    CandleInterval::HOUR1,
+    CandleInterval::HOUR2,
+    CandleInterval::HOUR4,
+    CandleInterval::HOUR6,
+    CandleInterval::HOUR12,
+    CandleInterval::DAY1,
+    CandleInterval::DAY3,
+    CandleInterval::WEEK1,
+];
+
 /// The WebSocket client for ZB spot market.
 ///
 /// * WebSocket API doc: <https://www.zb.com/en/api>
@@ -58,17 +74,26 @@ struct ZbCommandTranslator {}
 
 impl MessageHandler for ZbMessageHandler {
     fn handle_message(&mut self, msg: &str) -> MiscMessage {
-        let obj = serde_json::from_str::<HashMap<String, Value>>(msg).unwrap();
-        let channel = obj["channel"].as_str().unwrap();
+        let resp = serde_json::from_str::<HashMap<String, Value>>(msg);
+        if resp.is_err() {
+            error!("{} is not a JSON string, {}", msg, EXCHANGE_NAME);
+            return MiscMessage::Other;
+        }
+        let obj = resp.unwrap();
 
-        if channel == "pong" {
-            return MiscMessage::Pong;
+        match obj.get("channel").and_then(|x| x.as_str()) {
+            Some("pong") => return MiscMessage::Pong,
+            Some(_) => (),
+            None => {
+                warn!("Received {} from {}", msg, EXCHANGE_NAME);
+                return MiscMessage::Other;
+            }
         }
         if let Some(code) = obj.get("code") {
-            let code = code.as_i64().unwrap();
+            let code = code.as_i64().unwrap_or_default();
             if code != 1000 {
                 if code == 1007 {
-                    panic!("Received {msg} from {EXCHANGE_NAME}");
+                    return MiscMessage::Error(format!("Received {msg} from {EXCHANGE_NAME}"));
                 } else {
                     error!("Received {} from {}", msg, EXCHANGE_NAME);
                 }
@@ -83,6 +108,9 @@ impl MessageHandler for ZbMessageHandler {
     }
 }
 
+impl_fuzz_message_handler!(ZbMessageHandler {});
+impl_new_message_handler!(ZbMessageHandler {});
+
 impl ZbCommandTranslator {
     fn to_candlestick_raw_channel(&self, symbol: &str, interval: usize) -> String {
         let interval_str = match interval {
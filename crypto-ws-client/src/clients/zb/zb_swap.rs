@@ -14,7 +14,7 @@ use crate::{
         message_handler::{MessageHandler, MiscMessage},
         ws_client_internal::WSClientInternal,
     },
-    WSClient,
+    CandleInterval, WSClient,
 };
 use log::*;
 
@@ -29,6 +29,17 @@ const WEBSOCKET_URL: &str = "wss://fapi.zb.com/ws/public/v1";
 const UPLINK_LIMIT: (NonZeroU32, std::time::Duration) =
     (nonzero!(200u32), std::time::Duration::from_secs(2));
 
+pub(crate) const CANDLESTICK_INTERVALS: &[CandleInterval] = &[
+    CandleInterval::MIN1,
+    CandleInterval::MIN5,
+    CandleInterval::MIN15,
+    CandleInterval::MIN30,
+    CandleInterval::HOUR1,
+    CandleInterval::HOUR6,
+    CandleInterval::DAY1,
+    CandleInterval::DAY5,
+];
+
 /// The WebSocket client for ZB swap market.
 ///
 /// * WebSocket API doc: <https://github.com/ZBFuture/docs/blob/main/API%20V2%20_en.md>
@@ -85,7 +96,12 @@ impl MessageHandler for ZbMessageHandler {
             error!("Received {} from {}", msg, EXCHANGE_NAME);
             return MiscMessage::Other;
         }
-        let obj = serde_json::from_str::<HashMap<String, Value>>(msg).unwrap();
+        let resp = serde_json::from_str::<HashMap<String, Value>>(msg);
+        if resp.is_err() {
+            error!("{} is not a JSON string, {}", msg, EXCHANGE_NAME);
+            return MiscMessage::Other;
+        }
+        let obj = resp.unwrap();
         if obj.contains_key("channel") && obj.contains_key("data") {
             MiscMessage::Normal
         } else {
@@ -100,6 +116,9 @@ impl MessageHandler for ZbMessageHandler {
     }
 }
 
+impl_fuzz_message_handler!(ZbMessageHandler {});
+impl_new_message_handler!(ZbMessageHandler {});
+
 impl ZbCommandTranslator {
     fn to_candlestick_raw_channel(&self, symbol: &str, interval: usize) -> String {
         let interval_str = match interval {
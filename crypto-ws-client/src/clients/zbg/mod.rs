@@ -1,6 +1,6 @@
 mod utils;
-mod zbg_spot;
-mod zbg_swap;
+pub(super) mod zbg_spot;
+pub(super) mod zbg_swap;
 
 pub use zbg_spot::ZbgSpotWSClient;
 pub use zbg_swap::ZbgSwapWSClient;
@@ -11,13 +11,24 @@ use crate::{
         message_handler::{MessageHandler, MiscMessage},
         ws_client_internal::WSClientInternal,
     },
-    WSClient,
+    CandleInterval, WSClient,
 };
 
 use super::{utils::fetch_symbol_id_map_spot, EXCHANGE_NAME};
 
 const WEBSOCKET_URL: &str = "wss://kline.zbg.com/websocket";
 
+pub(crate) const CANDLESTICK_INTERVALS: &[CandleInterval] = &[
+    CandleInterval::MIN1,
+    CandleInterval::MIN5,
+    CandleInterval::MIN15,
+    CandleInterval::MIN30,
+    CandleInterval::HOUR1,
+    CandleInterval::HOUR4,
+    CandleInterval::DAY1,
+    CandleInterval::WEEK1,
+];
+
 /// The WebSocket client for ZBG spot market.
 ///
 /// * WebSocket API doc: <https://www.zbg.com/docs/spot/v1/en/#websocket-market-data>
@@ -56,7 +67,11 @@ struct ZbgCommandTranslator {
 
 impl MessageHandler for ZbgMessageHandler {
     fn handle_message(&mut self, msg: &str) -> MiscMessage {
-        if msg.contains(r#"action":"PING"#) { MiscMessage::Pong } else { MiscMessage::Normal }
+        if msg.contains(r#"action":"PING"#) {
+            MiscMessage::Pong
+        } else {
+            MiscMessage::Normal
+        }
     }
 
     fn get_ping_msg_and_interval(&self) -> Option<(Message, u64)> {
@@ -64,6 +79,9 @@ impl MessageHandler for ZbgMessageHandler {
     }
 }
 
+impl_fuzz_message_handler!(ZbgMessageHandler {});
+impl_new_message_handler!(ZbgMessageHandler {});
+
 impl ZbgCommandTranslator {
     async fn new() -> Self {
         let symbol_id_map = fetch_symbol_id_map_spot().await;
@@ -11,7 +11,7 @@ use crate::{
         message_handler::{MessageHandler, MiscMessage},
         ws_client_internal::WSClientInternal,
     },
-    WSClient,
+    CandleInterval, WSClient,
 };
 
 use log::*;
@@ -20,6 +20,21 @@ use super::{utils::fetch_symbol_contract_id_map_swap, EXCHANGE_NAME};
 
 const WEBSOCKET_URL: &str = "wss://kline.zbg.com/exchange/v1/futurews";
 
+pub(crate) const CANDLESTICK_INTERVALS: &[CandleInterval] = &[
+    CandleInterval::MIN1,
+    CandleInterval::MIN3,
+    CandleInterval::MIN5,
+    CandleInterval::MIN15,
+    CandleInterval::MIN30,
+    CandleInterval::HOUR1,
+    CandleInterval::HOUR2,
+    CandleInterval::HOUR4,
+    CandleInterval::HOUR6,
+    CandleInterval::HOUR12,
+    CandleInterval::DAY1,
+    CandleInterval::WEEK1,
+];
+
 /// The WebSocket client for ZBG swap market.
 ///
 /// * WebSocket API doc: <https://www.zbgpro.com/docs/future/v1/cn/#300f34d976>,
@@ -75,6 +90,9 @@ impl MessageHandler for ZbgMessageHandler {
     }
 }
 
+impl_fuzz_message_handler!(ZbgMessageHandler {});
+impl_new_message_handler!(ZbgMessageHandler {});
+
 impl ZbgCommandTranslator {
     async fn new() -> Self {
         let symbol_id_map = fetch_symbol_contract_id_map_swap().await;
@@ -90,14 +108,6 @@ impl ZbgCommandTranslator {
     }
 
     fn to_candlestick_raw_channel(&self, pair: &str, interval: usize) -> String {
-        let valid_set: Vec<usize> =
-            vec![60, 180, 300, 900, 1800, 3600, 7200, 14400, 21600, 43200, 86400, 604800];
-        if !valid_set.contains(&interval) {
-            let joined =
-                valid_set.into_iter().map(|x| x.to_string()).collect::<Vec<String>>().join(",");
-            panic!("ZBG Swap available intervals {joined}");
-        }
-
         let contract_id = self
             .symbol_id_map
             .get(pair)
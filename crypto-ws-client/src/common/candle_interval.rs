@@ -0,0 +1,133 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use super::error::Error;
+
+/// The interval of candlesticks, in seconds.
+///
+/// Constants cover the intervals which are common among exchanges, others can
+/// be created by `from_secs()`. Not every exchange supports every interval,
+/// see `supported_candlestick_intervals()`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct CandleInterval(usize);
+
+impl CandleInterval {
+    pub const MIN1: CandleInterval = CandleInterval(60);
+    pub const MIN3: CandleInterval = CandleInterval(180);
+    pub const MIN5: CandleInterval = CandleInterval(300);
+    pub const MIN10: CandleInterval = CandleInterval(600);
+    pub const MIN15: CandleInterval = CandleInterval(900);
+    pub const MIN30: CandleInterval = CandleInterval(1800);
+    pub const HOUR1: CandleInterval = CandleInterval(3600);
+    pub const HOUR2: CandleInterval = CandleInterval(7200);
+    pub const HOUR3: CandleInterval = CandleInterval(10800);
+    pub const HOUR4: CandleInterval = CandleInterval(14400);
+    pub const HOUR6: CandleInterval = CandleInterval(21600);
+    pub const HOUR8: CandleInterval = CandleInterval(28800);
+    pub const HOUR12: CandleInterval = CandleInterval(43200);
+    pub const DAY1: CandleInterval = CandleInterval(86400);
+    pub const DAY2: CandleInterval = CandleInterval(172800);
+    pub const DAY3: CandleInterval = CandleInterval(259200);
+    pub const DAY5: CandleInterval = CandleInterval(432000);
+    pub const WEEK1: CandleInterval = CandleInterval(604800);
+    pub const WEEK2: CandleInterval = CandleInterval(1209600);
+    /// Exchanges count a month as 30 days.
+    pub const MONTH1: CandleInterval = CandleInterval(2592000);
+
+    pub const fn from_secs(secs: usize) -> Self {
+        CandleInterval(secs)
+    }
+
+    pub const fn as_secs(&self) -> usize {
+        self.0
+    }
+}
+
+impl fmt::Display for CandleInterval {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        const UNITS: [(usize, &str); 5] =
+            [(2592000, "M"), (604800, "w"), (86400, "d"), (3600, "h"), (60, "m")];
+        for (unit, suffix) in UNITS {
+            let (quotient, remainder) = (self.0 / unit, self.0 % unit);
+            if quotient > 0 && remainder == 0 {
+                return write!(f, "{quotient}{suffix}");
+            }
+        }
+        write!(f, "{}s", self.0)
+    }
+}
+
+/// Converts typed intervals to seconds, which are used by command translators.
+///
+/// Returns an error listing all unsupported intervals if any.
+pub(crate) fn check_candlestick_intervals(
+    exchange: &str,
+    supported: &[CandleInterval],
+    symbol_interval_list: &[(String, CandleInterval)],
+) -> Result<Vec<(String, usize)>, Error> {
+    let mut unsupported = symbol_interval_list
+        .iter()
+        .map(|(_, interval)| *interval)
+        .filter(|interval| !supported.contains(interval))
+        .collect::<Vec<CandleInterval>>();
+    if unsupported.is_empty() {
+        Ok(symbol_interval_list
+            .iter()
+            .map(|(symbol, interval)| (symbol.to_string(), interval.as_secs()))
+            .collect())
+    } else {
+        unsupported.sort();
+        unsupported.dedup();
+        let join = |intervals: &[CandleInterval]| {
+            intervals.iter().map(|x| x.to_string()).collect::<Vec<String>>().join(",")
+        };
+        Err(Error(format!(
+            "{} does NOT support candlestick intervals {}, available intervals: {}",
+            exchange,
+            join(&unsupported),
+            join(supported)
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{check_candlestick_intervals, CandleInterval};
+
+    #[test]
+    fn test_display() {
+        assert_eq!("10s", CandleInterval::from_secs(10).to_string());
+        assert_eq!("1m", CandleInterval::MIN1.to_string());
+        assert_eq!("4h", CandleInterval::HOUR4.to_string());
+        assert_eq!("3d", CandleInterval::DAY3.to_string());
+        assert_eq!("2w", CandleInterval::WEEK2.to_string());
+        assert_eq!("1M", CandleInterval::MONTH1.to_string());
+    }
+
+    #[test]
+    fn test_check_intervals() {
+        let supported = [CandleInterval::MIN1, CandleInterval::MIN5];
+        let list = check_candlestick_intervals(
+            "binance",
+            &supported,
+            &[("BTCUSDT".to_string(), CandleInterval::MIN5)],
+        )
+        .unwrap();
+        assert_eq!(vec![("BTCUSDT".to_string(), 300)], list);
+
+        let err = check_candlestick_intervals(
+            "binance",
+            &supported,
+            &[
+                ("BTCUSDT".to_string(), CandleInterval::MIN3),
+                ("ETHUSDT".to_string(), CandleInterval::MIN3),
+            ],
+        )
+        .unwrap_err();
+        assert_eq!(
+            "binance does NOT support candlestick intervals 3m, available intervals: 1m,5m",
+            err.0
+        );
+    }
+}
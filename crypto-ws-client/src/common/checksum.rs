@@ -0,0 +1,233 @@
+use std::{
+    cmp::Ordering,
+    collections::{BTreeMap, HashMap},
+    sync::mpsc::Sender,
+};
+
+use log::*;
+use serde::{Deserialize, Serialize};
+
+/// The local orderbook of a symbol has diverged from the exchange.
+///
+/// `expected` is the checksum sent by the exchange and `actual` is computed
+/// from the local book. Exchanges that send signed 32-bit integers, e.g., OKX
+/// and Bitfinex, are reinterpreted as unsigned.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChecksumMismatch {
+    pub exchange: String,
+    /// The channel of the book, e.g., `books`, `book-25` or `P0`
+    pub channel: String,
+    pub symbol: String,
+    pub expected: u32,
+    pub actual: u32,
+}
+
+/// Options of orderbook checksum validation.
+///
+/// Only orderbook channels are validated, other channels are not affected.
+#[derive(Clone, Debug, Default)]
+pub struct ChecksumConfig {
+    /// Every mismatch is sent to this channel besides being logged.
+    pub tx: Option<Sender<ChecksumMismatch>>,
+    /// Unsubscribe and subscribe the affected symbol again to get a fresh
+    /// snapshot. The message that failed validation is dropped.
+    pub resubscribe: bool,
+}
+
+// f64 wrapper which can be used as a BTreeMap key
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Price(f64);
+
+impl Eq for Price {}
+
+impl PartialOrd for Price {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Price {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.total_cmp(&other.0)
+    }
+}
+
+/// A level2 orderbook which keeps prices and quantities as they were received,
+/// because checksums are computed from the original strings.
+#[derive(Default)]
+pub(crate) struct L2Book {
+    bids: BTreeMap<Price, (String, String)>,
+    asks: BTreeMap<Price, (String, String)>,
+}
+
+impl L2Book {
+    /// Inserts or replaces a price level, a zero quantity removes it.
+    ///
+    /// Levels which are not numbers are ignored, the checksum will mismatch.
+    pub(crate) fn update(&mut self, is_bid: bool, price: &str, quantity: &str) {
+        let (key, quantity_f64) = match (price.parse::<f64>(), quantity.parse::<f64>()) {
+            (Ok(price_f64), Ok(quantity_f64)) => (Price(price_f64), quantity_f64),
+            _ => return,
+        };
+        let side = if is_bid { &mut self.bids } else { &mut self.asks };
+        if quantity_f64 == 0.0 {
+            side.remove(&key);
+        } else {
+            side.insert(key, (price.to_string(), quantity.to_string()));
+        }
+    }
+
+    /// Removes a price level.
+    pub(crate) fn remove(&mut self, is_bid: bool, price: &str) {
+        let key = match price.parse::<f64>() {
+            Ok(price_f64) => Price(price_f64),
+            Err(_) => return,
+        };
+        if is_bid {
+            self.bids.remove(&key);
+        } else {
+            self.asks.remove(&key);
+        }
+    }
+
+    /// Keeps only the best `depth` levels on each side.
+    pub(crate) fn truncate(&mut self, depth: usize) {
+        while self.bids.len() > depth {
+            let worst = *self.bids.keys().next().unwrap();
+            self.bids.remove(&worst);
+        }
+        while self.asks.len() > depth {
+            let worst = *self.asks.keys().next_back().unwrap();
+            self.asks.remove(&worst);
+        }
+    }
+
+    /// Bids from the best to the worst, as (price, quantity).
+    pub(crate) fn bids(&self) -> impl Iterator<Item = &(String, String)> {
+        self.bids.values().rev()
+    }
+
+    /// Asks from the best to the worst, as (price, quantity).
+    pub(crate) fn asks(&self) -> impl Iterator<Item = &(String, String)> {
+        self.asks.values()
+    }
+}
+
+/// Builds the string `bid:ask:bid:ask...` from the top `depth` levels, which
+/// is used by OKX and Bitfinex. If one side is shorter the remaining levels of
+/// the other side follow.
+pub(crate) fn interleave_levels(book: &L2Book, depth: usize) -> String {
+    let bids = book.bids().take(depth).collect::<Vec<&(String, String)>>();
+    let asks = book.asks().take(depth).collect::<Vec<&(String, String)>>();
+    let mut fields: Vec<&str> = Vec::with_capacity(4 * depth);
+    for i in 0..depth {
+        if let Some((price, quantity)) = bids.get(i) {
+            fields.push(price);
+            fields.push(quantity);
+        }
+        if let Some((price, quantity)) = asks.get(i) {
+            fields.push(price);
+            fields.push(quantity);
+        }
+    }
+    fields.join(":")
+}
+
+pub(crate) fn crc32(s: &str) -> u32 {
+    crc32fast::hash(s.as_bytes())
+}
+
+/// Book state per (channel, symbol) and the validation config, owned by a
+/// message handler.
+///
+/// Books are keyed by channel too, because a symbol can be subscribed with
+/// different precisions or depths on one connection, e.g., Bitfinex P0 and P1.
+pub(crate) struct ChecksumValidator {
+    exchange: &'static str,
+    config: ChecksumConfig,
+    books: HashMap<(String, String), L2Book>,
+}
+
+impl ChecksumValidator {
+    pub(crate) fn new(exchange: &'static str, config: ChecksumConfig) -> Self {
+        ChecksumValidator { exchange, config, books: HashMap::new() }
+    }
+
+    pub(crate) fn resubscribe(&self) -> bool {
+        self.config.resubscribe
+    }
+
+    /// Starts a new book from a snapshot.
+    pub(crate) fn reset(&mut self, channel: &str, symbol: &str) -> &mut L2Book {
+        let key = (channel.to_string(), symbol.to_string());
+        self.books.insert(key.clone(), L2Book::default());
+        self.books.get_mut(&key).unwrap()
+    }
+
+    /// The book of a symbol, None if no snapshot has been received yet.
+    pub(crate) fn book(&mut self, channel: &str, symbol: &str) -> Option<&mut L2Book> {
+        self.books.get_mut(&(channel.to_string(), symbol.to_string()))
+    }
+
+    pub(crate) fn remove(&mut self, channel: &str, symbol: &str) {
+        self.books.remove(&(channel.to_string(), symbol.to_string()));
+    }
+
+    /// Compares checksums, on mismatch the book is discarded until the next
+    /// snapshot and the mismatch is reported.
+    ///
+    /// Returns true if both checksums match.
+    pub(crate) fn verify(
+        &mut self,
+        channel: &str,
+        symbol: &str,
+        expected: u32,
+        actual: u32,
+    ) -> bool {
+        if expected == actual {
+            return true;
+        }
+        error!(
+            "Checksum mismatch of {} {} from {}, expected {}, actual {}",
+            channel, symbol, self.exchange, expected, actual
+        );
+        self.remove(channel, symbol);
+        if let Some(tx) = self.config.tx.as_ref() {
+            _ = tx.send(ChecksumMismatch {
+                exchange: self.exchange.to_string(),
+                channel: channel.to_string(),
+                symbol: symbol.to_string(),
+                expected,
+                actual,
+            });
+        }
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{crc32, interleave_levels, L2Book};
+
+    #[test]
+    fn test_book_update() {
+        let mut book = L2Book::default();
+        book.update(true, "100.0", "1");
+        book.update(true, "101.0", "2");
+        book.update(false, "102.5", "3");
+        book.update(false, "103", "4");
+        book.update(true, "100.0", "0");
+
+        assert_eq!("101.0:2:102.5:3:103:4", interleave_levels(&book, 25));
+
+        book.update(false, "104", "5");
+        book.truncate(1);
+        assert_eq!("101.0:2:102.5:3", interleave_levels(&book, 25));
+    }
+
+    #[test]
+    fn test_crc32() {
+        // the standard check value of CRC-32/ISO-HDLC
+        assert_eq!(0xCBF43926, crc32("123456789"));
+    }
+}
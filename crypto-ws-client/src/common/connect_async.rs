@@ -4,16 +4,21 @@ use governor::{Quota, RateLimiter};
 use log::*;
 use nonzero_ext::*;
 use reqwest::Url;
-use std::{env, num::NonZeroU32};
+use std::{env, num::NonZeroU32, path::PathBuf, sync::Arc, time::Instant};
 use tokio::{
     io::{AsyncRead, AsyncWrite},
     sync::mpsc::{Receiver, Sender},
 };
 use tokio_tungstenite::{
-    tungstenite::{Error, Message},
+    tungstenite::{error::UrlError, Error, Message},
     MaybeTlsStream, WebSocketStream,
 };
 
+use super::{
+    metrics::{ConnectionInfo, ConnectionObserver},
+    recorder::{Direction, Recorder},
+};
+
 /// Wraps a websocket client inside an event loop, returns a message_rx to
 /// receive messages and a command_tx to send commands to the websocket server.
 ///
@@ -21,44 +26,65 @@ use tokio_tungstenite::{
 /// command_tx.
 ///
 /// `limit`, max number of uplink messsages, for example, 100 per 10 seconds
+///
+/// Frames in both directions are recorded into `record_dir` if set.
+///
+/// Time spent waiting for the uplink limiter is reported to `observer`.
 pub async fn connect_async(
-    url: &str,
+    conn: Arc<ConnectionInfo>,
     uplink_limit: Option<(NonZeroU32, std::time::Duration)>,
+    observer: Option<Arc<dyn ConnectionObserver>>,
+    record_dir: Option<PathBuf>,
 ) -> Result<(Receiver<Message>, Sender<Message>), Error> {
+    let (exchange, url) = (conn.exchange.as_str(), conn.url.as_str());
     if let Ok(proxy_env) = env::var("https_proxy").or_else(|_| env::var("http_proxy")) {
-        let proxy_url = Url::parse(&proxy_env).unwrap();
-        let proxy_scheme = proxy_url.scheme().to_lowercase();
-        if proxy_scheme.as_str() != "socks5" {
-            panic!("Unsupported proxy scheme {proxy_scheme}");
-        }
-        let proxy_addr = format!(
-            "{}:{}",
-            proxy_url.host_str().unwrap(),
-            proxy_url.port_or_known_default().unwrap()
-        );
-        let connect_url = Url::parse(url).unwrap();
-        let proxy_stream = Socks5Stream::connect(
-            proxy_addr.to_string(),
-            connect_url.host_str().unwrap().to_string(),
-            connect_url.port_or_known_default().unwrap(),
-            Config::default(),
-        )
-        .await
-        .unwrap();
+        let proxy_addr = socks5_proxy_addr(&proxy_env).map_err(Error::Io)?;
+        let connect_url = Url::parse(url)
+            .map_err(|err| Error::Url(UrlError::UnableToConnect(format!("{url}, {err}"))))?;
+        let (Some(host), Some(port)) =
+            (connect_url.host_str(), connect_url.port_or_known_default())
+        else {
+            return Err(Error::Url(UrlError::NoHostName));
+        };
+        let proxy_stream =
+            Socks5Stream::connect(proxy_addr, host.to_string(), port, Config::default())
+                .await
+                .map_err(|err| Error::Io(std::io::Error::other(err.to_string())))?;
         let (ws_stream, _) = tokio_tungstenite::client_async_tls(connect_url, proxy_stream).await?;
         // replaced
         // let ret = tokio_tungstenite::connect_async(url).await;
-        connect_async_internal(ws_stream, uplink_limit).await
+        let recorder = Recorder::open(record_dir.as_deref(), exchange, url);
+        connect_async_internal(ws_stream, uplink_limit, recorder, conn.clone(), observer).await
     } else {
         let (ws_stream, _) = tokio_tungstenite::connect_async(url).await?;
 
-        connect_async_internal(ws_stream, uplink_limit).await
+        let recorder = Recorder::open(record_dir.as_deref(), exchange, url);
+        connect_async_internal(ws_stream, uplink_limit, recorder, conn.clone(), observer).await
+    }
+}
+
+// The host:port of a socks5:// proxy, a misconfigured proxy fails the
+// connection instead of panicking
+fn socks5_proxy_addr(proxy: &str) -> Result<String, std::io::Error> {
+    let invalid_input = |msg: String| std::io::Error::new(std::io::ErrorKind::InvalidInput, msg);
+    let proxy_url =
+        Url::parse(proxy).map_err(|err| invalid_input(format!("Invalid proxy {proxy}, {err}")))?;
+    let proxy_scheme = proxy_url.scheme().to_lowercase();
+    if proxy_scheme.as_str() != "socks5" {
+        return Err(invalid_input(format!("Unsupported proxy scheme {proxy_scheme}")));
+    }
+    match (proxy_url.host_str(), proxy_url.port_or_known_default()) {
+        (Some(host), Some(port)) => Ok(format!("{host}:{port}")),
+        _ => Err(invalid_input(format!("Invalid proxy {proxy}, no host or port"))),
     }
 }
 
 async fn connect_async_internal<S: AsyncRead + AsyncWrite + Unpin + Send + 'static>(
     ws_stream: WebSocketStream<MaybeTlsStream<S>>,
     uplink_limit: Option<(NonZeroU32, std::time::Duration)>,
+    mut recorder: Option<Recorder>,
+    conn: Arc<ConnectionInfo>,
+    observer: Option<Arc<dyn ConnectionObserver>>,
 ) -> Result<(Receiver<Message>, Sender<Message>), Error> {
     let (command_tx, mut command_rx) = tokio::sync::mpsc::channel::<Message>(1);
     let (message_tx, message_rx) = tokio::sync::mpsc::channel::<Message>(32);
@@ -92,7 +118,14 @@ async fn connect_async_internal<S: AsyncRead + AsyncWrite + Unpin + Send + 'stat
                         break; // close the connection and break the loop
                       }
                       _ => {
+                        let start = Instant::now();
                         limiter.until_ready().await;
+                        if let Some(observer) = observer.as_ref() {
+                          observer.on_uplink_wait(&conn, start.elapsed());
+                        }
+                        if let Some(recorder) = recorder.as_mut() {
+                          recorder.record(Direction::Out, &command);
+                        }
                         if let Err(err) =write.send(command).await {
                           error!("Failed to send, error: {}", err);
                         }
@@ -107,6 +140,9 @@ async fn connect_async_internal<S: AsyncRead + AsyncWrite + Unpin + Send + 'stat
               }
               msg = read.next() => match msg {
                 Some(Ok(msg)) => {
+                  if let Some(recorder) = recorder.as_mut() {
+                    recorder.record(Direction::In, &msg);
+                  }
                   let _= message_tx.send(msg).await;
                 }
                 Some(Err(err)) => {
@@ -120,8 +156,25 @@ async fn connect_async_internal<S: AsyncRead + AsyncWrite + Unpin + Send + 'stat
               }
             };
         }
+        if let Some(recorder) = recorder.as_mut() {
+            recorder.record(Direction::Out, &Message::Close(None));
+        }
         _ = write.send(Message::Close(None)).await;
     });
 
     Ok((message_rx, command_tx))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::socks5_proxy_addr;
+
+    #[test]
+    fn test_socks5_proxy_addr() {
+        assert_eq!("127.0.0.1:9050", socks5_proxy_addr("socks5://127.0.0.1:9050").unwrap());
+        let err = socks5_proxy_addr("http://127.0.0.1:8080").unwrap_err();
+        assert_eq!("Unsupported proxy scheme http", err.to_string());
+        assert!(socks5_proxy_addr("127.0.0.1:9050").is_err());
+        assert!(socks5_proxy_addr("socks5://").is_err());
+    }
+}
@@ -2,21 +2,30 @@ use tokio_tungstenite::tungstenite::Message;
 
 #[derive(Debug)]
 pub(crate) enum MiscMessage {
-    Normal,             // A normal websocket message which contains a JSON string
-    Mutated(String),    // A JSON string mutated by a handler, e.g., bitfinex
-    WebSocket(Message), // WebSocket message that needs to be sent to the server
-    Pong,               // Pong message from the server
-    Reconnect,          // Needs to reconnect
-    Other,              // Other messages will be ignored
+    Normal,                   // A normal websocket message which contains a JSON string
+    Mutated(String),          // A JSON string mutated by a handler, e.g., bitfinex
+    WebSocket(Message),       // WebSocket message that needs to be sent to the server
+    Pong,                     // Pong message from the server
+    Reconnect,                // Needs to reconnect
+    Resubscribe(Vec<String>), // Commands to resubscribe, e.g., after a checksum mismatch
+    Error(String),            // A fatal error from the exchange, run() will return
+    Other,                    // Other messages will be ignored
 }
 
 /// Exchange-specific message handler.
 pub(crate) trait MessageHandler {
     /// Given a message from the exchange, return a MiscMessage which will be
     /// procesed in run().
+    ///
+    /// Implementations must not panic, malformed or unknown messages should be
+    /// logged and returned as `MiscMessage::Other`, fatal errors from the
+    /// exchange as `MiscMessage::Error`.
     fn handle_message(&mut self, msg: &str) -> MiscMessage;
     /// To keep the connection alive, how often should the client send a ping?
     /// None means the client doesn't need to send ping, instead the server will
     /// send ping and the client just needs to reply a pong
     fn get_ping_msg_and_interval(&self) -> Option<(Message, u64)>;
 }
+
+/// A message handler picked at runtime, e.g., by `ReplayWSClient`.
+pub(crate) type BoxedMessageHandler = Box<dyn MessageHandler + Send>;
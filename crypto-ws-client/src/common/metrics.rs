@@ -0,0 +1,209 @@
+use std::{
+    collections::HashMap,
+    fmt,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex, RwLock,
+    },
+    time::Duration,
+};
+
+/// A websocket connection reported to `ConnectionObserver`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ConnectionInfo {
+    /// Unique within the process, increases with every connection attempt
+    pub id: u64,
+    pub exchange: String,
+    pub url: String,
+}
+
+/// Why a connection was closed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DisconnectReason {
+    /// Closed by `close()`
+    Closed,
+    /// The connection was dropped without a close frame
+    ConnectionLost,
+    /// The server sent a close frame
+    CloseFrame(String),
+    /// The exchange asked to reconnect, e.g., maintenance or a sequence gap
+    Reconnect,
+    /// A fatal error from the exchange
+    Error(String),
+    /// The receiving part of the message channel was dropped
+    ReceiverDropped,
+}
+
+impl DisconnectReason {
+    /// A short name which can be used as a metric label.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DisconnectReason::Closed => "closed",
+            DisconnectReason::ConnectionLost => "connection_lost",
+            DisconnectReason::CloseFrame(_) => "close_frame",
+            DisconnectReason::Reconnect => "reconnect",
+            DisconnectReason::Error(_) => "error",
+            DisconnectReason::ReceiverDropped => "receiver_dropped",
+        }
+    }
+}
+
+impl fmt::Display for DisconnectReason {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DisconnectReason::CloseFrame(detail) | DisconnectReason::Error(detail) => {
+                write!(f, "{}, {}", self.as_str(), detail)
+            }
+            _ => write!(f, "{}", self.as_str()),
+        }
+    }
+}
+
+/// Receives lifecycle events and traffic statistics of all websocket
+/// connections.
+///
+/// `channel` is the subscribed channel a message belongs to, for example,
+/// `aggTrade` of Binance. It is `unknown` for messages of channels subscribed
+/// by `subscribe_candlestick()` or `send()`, and empty for acks, heartbeats and
+/// other messages which are not forwarded to the receiver.
+///
+/// Methods are called on the hot path, implementations should be cheap and
+/// must not block. All methods do nothing by default.
+#[allow(unused_variables)]
+pub trait ConnectionObserver: Send + Sync {
+    /// A connection was established.
+    fn on_connect(&self, conn: &ConnectionInfo) {}
+
+    /// Failed to connect.
+    fn on_connect_error(&self, conn: &ConnectionInfo, error: &str) {}
+
+    /// Connecting to an exchange while a previous connection of this exchange
+    /// is down, called before the attempt.
+    fn on_reconnect_attempt(&self, conn: &ConnectionInfo) {}
+
+    /// A connection was closed, either by the server or by the client.
+    fn on_disconnect(&self, conn: &ConnectionInfo, reason: &DisconnectReason) {}
+
+    /// A message was received, `bytes` is the size on the wire.
+    fn on_message_in(&self, conn: &ConnectionInfo, channel: &str, bytes: usize) {}
+
+    /// A message was sent, heartbeats are reported by `on_ping()` instead.
+    fn on_message_out(&self, conn: &ConnectionInfo, channel: &str, bytes: usize) {}
+
+    /// A binary message could not be decompressed and was dropped.
+    fn on_decompression_failure(&self, conn: &ConnectionInfo) {}
+
+    /// A heartbeat was sent.
+    fn on_ping(&self, conn: &ConnectionInfo) {}
+
+    /// A pong frame or a pong message was received.
+    fn on_pong(&self, conn: &ConnectionInfo) {}
+
+    /// An outgoing message waited for the uplink rate limiter.
+    fn on_uplink_wait(&self, conn: &ConnectionInfo, wait: Duration) {}
+}
+
+static OBSERVER: RwLock<Option<Arc<dyn ConnectionObserver>>> = RwLock::new(None);
+
+static NEXT_CONNECTION_ID: AtomicU64 = AtomicU64::new(1);
+
+/// Number of connections per exchange that were disconnected but not
+/// replaced by a new connection yet.
+static DOWN_CONNECTIONS: Mutex<Option<HashMap<String, usize>>> = Mutex::new(None);
+
+/// Reports events of all websocket clients created afterwards to `observer`.
+///
+/// Clients created before keep reporting to the previous observer, if any.
+pub fn set_connection_observer(observer: Arc<dyn ConnectionObserver>) {
+    *OBSERVER.write().unwrap() = Some(observer);
+}
+
+pub(crate) fn connection_observer() -> Option<Arc<dyn ConnectionObserver>> {
+    OBSERVER.read().unwrap().clone()
+}
+
+pub(crate) fn new_connection_info(exchange: &str, url: &str) -> ConnectionInfo {
+    ConnectionInfo {
+        id: NEXT_CONNECTION_ID.fetch_add(1, Ordering::Relaxed),
+        exchange: exchange.to_string(),
+        url: url.to_string(),
+    }
+}
+
+/// Returns true if a connection of this exchange is down.
+pub(crate) fn is_reconnecting(exchange: &str) -> bool {
+    let guard = DOWN_CONNECTIONS.lock().unwrap();
+    guard.as_ref().and_then(|map| map.get(exchange)).map(|n| *n > 0).unwrap_or(false)
+}
+
+pub(crate) fn mark_down(exchange: &str) {
+    let mut guard = DOWN_CONNECTIONS.lock().unwrap();
+    *guard.get_or_insert_with(HashMap::new).entry(exchange.to_string()).or_default() += 1;
+}
+
+pub(crate) fn mark_up(exchange: &str) {
+    let mut guard = DOWN_CONNECTIONS.lock().unwrap();
+    if let Some(n) = guard.as_mut().and_then(|map| map.get_mut(exchange)) {
+        *n = n.saturating_sub(1);
+    }
+}
+
+/// Channel of forwarded messages which don't belong to any subscribed channel.
+pub(crate) const UNKNOWN_CHANNEL: &str = "unknown";
+
+/// Finds the subscribed channel a message belongs to, returns an empty string
+/// if not found.
+///
+/// The longest channel contained in the head or the tail of the message wins,
+/// so that `trade.detail` is preferred over `trade`. Only the head and the tail
+/// are searched because exchanges put the channel name at the start of a
+/// message, except Kraken which puts it at the end.
+pub(crate) fn find_channel<'a>(channels: &'a [String], msg: &str) -> &'a str {
+    const HEAD: usize = 256;
+    const TAIL: usize = 64;
+    let head = floor_char_boundary(msg, HEAD.min(msg.len()));
+    let tail = ceil_char_boundary(msg, msg.len().saturating_sub(TAIL).max(head));
+    channels
+        .iter()
+        .filter(|channel| {
+            msg[..head].contains(channel.as_str()) || msg[tail..].contains(channel.as_str())
+        })
+        .max_by_key(|channel| channel.len())
+        .map(|channel| channel.as_str())
+        .unwrap_or_default()
+}
+
+fn floor_char_boundary(s: &str, mut index: usize) -> usize {
+    while !s.is_char_boundary(index) {
+        index -= 1;
+    }
+    index
+}
+
+fn ceil_char_boundary(s: &str, mut index: usize) -> usize {
+    while !s.is_char_boundary(index) {
+        index += 1;
+    }
+    index
+}
+
+#[cfg(test)]
+mod tests {
+    use super::find_channel;
+
+    #[test]
+    fn test_find_channel() {
+        let channels =
+            vec!["trade".to_string(), "trade.detail".to_string(), "depth.size_20".to_string()];
+        assert_eq!(
+            "trade.detail",
+            find_channel(&channels, r#"{"ch":"market.btcusdt.trade.detail","ts":1677000000123}"#)
+        );
+        assert_eq!("", find_channel(&channels, r#"{"ping":1677000005000}"#));
+
+        let kraken = format!(r#"[336,{{"a":[{}]}},"trade","XBT/USD"]"#, "0,".repeat(200) + "0");
+        assert_eq!("trade", find_channel(&channels, &kraken));
+        // multi-byte characters around the boundaries
+        assert_eq!("", find_channel(&channels, &"币".repeat(200)));
+    }
+}
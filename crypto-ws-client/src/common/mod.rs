@@ -1,6 +1,13 @@
+pub(crate) mod candle_interval;
+pub(crate) mod checksum;
 pub(crate) mod command_translator;
 pub(crate) mod connect_async;
+pub(crate) mod error;
 pub(crate) mod message_handler;
+pub(crate) mod metrics;
+pub(crate) mod prometheus;
+pub(crate) mod recorder;
+pub(crate) mod replay;
 pub(super) mod utils;
 pub(crate) mod ws_client;
 pub(super) mod ws_client_internal;
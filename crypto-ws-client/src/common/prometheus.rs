@@ -0,0 +1,328 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::Write as _,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use log::*;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+use super::metrics::{ConnectionInfo, ConnectionObserver, DisconnectReason};
+
+#[derive(Default)]
+struct Metrics {
+    // key: exchange
+    connects: BTreeMap<String, u64>,
+    connect_errors: BTreeMap<String, u64>,
+    reconnect_attempts: BTreeMap<String, u64>,
+    decompression_failures: BTreeMap<String, u64>,
+    pings: BTreeMap<String, u64>,
+    pongs: BTreeMap<String, u64>,
+    uplink_wait: BTreeMap<String, Duration>,
+    // key: (exchange, reason)
+    disconnects: BTreeMap<(String, &'static str), u64>,
+    // key: (exchange, channel, direction), value: (messages, bytes)
+    traffic: BTreeMap<(String, String, &'static str), (u64, u64)>,
+    // key: connection id, value: (exchange, time of the last message)
+    last_message: HashMap<u64, (String, Instant)>,
+}
+
+/// A `ConnectionObserver` which exports metrics in the Prometheus text format.
+///
+/// ```no_run
+/// use std::sync::Arc;
+///
+/// use crypto_ws_client::{set_connection_observer, PrometheusExporter};
+///
+/// # async fn example() {
+/// let exporter = Arc::new(PrometheusExporter::new());
+/// set_connection_observer(exporter.clone());
+/// tokio::task::spawn(exporter.serve("0.0.0.0:9898"));
+/// // create websocket clients
+/// # }
+/// ```
+///
+/// `crypto_ws_last_message_age_seconds` is the age of the stalest open
+/// connection of an exchange, only messages forwarded to the receiver count,
+/// connections without any message yet count from the time they were
+/// established. Alert on it to detect a stalled feed.
+#[derive(Default)]
+pub struct PrometheusExporter {
+    metrics: Mutex<Metrics>,
+}
+
+fn increase(map: &mut BTreeMap<String, u64>, exchange: &str) {
+    *map.entry(exchange.to_string()).or_default() += 1;
+}
+
+// Escapes a label value, see https://prometheus.io/docs/instrumenting/exposition_formats/
+fn escape(value: &str) -> String {
+    value.replace('\\', r"\\").replace('"', r#"\""#).replace('\n', r"\n")
+}
+
+fn write_header(out: &mut String, name: &str, metric_type: &str, help: &str) {
+    _ = writeln!(out, "# HELP {name} {help}");
+    _ = writeln!(out, "# TYPE {name} {metric_type}");
+}
+
+fn write_per_exchange<V: std::fmt::Display>(
+    out: &mut String,
+    name: &str,
+    metric_type: &str,
+    help: &str,
+    values: impl Iterator<Item = (String, V)>,
+) {
+    write_header(out, name, metric_type, help);
+    for (exchange, value) in values {
+        _ = writeln!(out, "{}{{exchange=\"{}\"}} {}", name, escape(&exchange), value);
+    }
+}
+
+impl PrometheusExporter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Renders all metrics in the Prometheus text format.
+    pub fn render(&self) -> String {
+        let metrics = self.metrics.lock().unwrap();
+        let mut out = String::new();
+
+        let counters = [
+            ("crypto_ws_connects_total", "Connections established", &metrics.connects),
+            (
+                "crypto_ws_connect_errors_total",
+                "Failed connection attempts",
+                &metrics.connect_errors,
+            ),
+            (
+                "crypto_ws_reconnect_attempts_total",
+                "Connection attempts while a connection of the exchange is down",
+                &metrics.reconnect_attempts,
+            ),
+            (
+                "crypto_ws_decompression_failures_total",
+                "Binary messages which failed to decompress",
+                &metrics.decompression_failures,
+            ),
+            ("crypto_ws_pings_total", "Heartbeats sent", &metrics.pings),
+            ("crypto_ws_pongs_total", "Pongs received", &metrics.pongs),
+        ];
+        for (name, help, values) in counters {
+            write_per_exchange(
+                &mut out,
+                name,
+                "counter",
+                help,
+                values.iter().map(|(k, v)| (k.clone(), *v)),
+            );
+        }
+
+        write_per_exchange(
+            &mut out,
+            "crypto_ws_uplink_wait_seconds_total",
+            "counter",
+            "Time outgoing messages waited for the uplink rate limiter",
+            metrics.uplink_wait.iter().map(|(k, v)| (k.clone(), v.as_secs_f64())),
+        );
+
+        write_header(&mut out, "crypto_ws_disconnects_total", "counter", "Closed connections");
+        for ((exchange, reason), value) in metrics.disconnects.iter() {
+            _ = writeln!(
+                out,
+                "crypto_ws_disconnects_total{{exchange=\"{}\",reason=\"{}\"}} {}",
+                escape(exchange),
+                reason,
+                value
+            );
+        }
+
+        for (name, help, index) in [
+            ("crypto_ws_messages_total", "Messages received and sent", 0),
+            ("crypto_ws_bytes_total", "Bytes received and sent on the wire", 1),
+        ] {
+            write_header(&mut out, name, "counter", help);
+            for ((exchange, channel, direction), (messages, bytes)) in metrics.traffic.iter() {
+                _ = writeln!(
+                    out,
+                    "{}{{exchange=\"{}\",channel=\"{}\",direction=\"{}\"}} {}",
+                    name,
+                    escape(exchange),
+                    escape(channel),
+                    direction,
+                    if index == 0 { messages } else { bytes }
+                );
+            }
+        }
+
+        let mut open_connections = BTreeMap::<String, u64>::new();
+        let mut last_message_age = BTreeMap::<String, f64>::new();
+        for (exchange, timestamp) in metrics.last_message.values() {
+            increase(&mut open_connections, exchange);
+            let age = timestamp.elapsed().as_secs_f64();
+            let max_age = last_message_age.entry(exchange.clone()).or_default();
+            *max_age = max_age.max(age);
+        }
+        write_per_exchange(
+            &mut out,
+            "crypto_ws_connections",
+            "gauge",
+            "Open connections",
+            open_connections.into_iter(),
+        );
+        write_per_exchange(
+            &mut out,
+            "crypto_ws_last_message_age_seconds",
+            "gauge",
+            "Seconds since the last message of the stalest open connection",
+            last_message_age.into_iter(),
+        );
+
+        out
+    }
+
+    /// Serves `render()` over HTTP on `addr`, every request gets the metrics
+    /// regardless of its path.
+    pub async fn serve(self: Arc<Self>, addr: &str) -> std::io::Result<()> {
+        let listener = tokio::net::TcpListener::bind(addr).await?;
+        info!("Serving Prometheus metrics on {}", listener.local_addr()?);
+        loop {
+            let (mut stream, peer) = listener.accept().await?;
+            let exporter = self.clone();
+            tokio::task::spawn(async move {
+                // the request is ignored, one read is enough for a GET request
+                let mut buf = [0u8; 1024];
+                if let Err(err) = stream.read(&mut buf).await {
+                    warn!("Failed to read the request from {}, {}", peer, err);
+                    return;
+                }
+                let body = exporter.render();
+                let resp = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    body.len(),
+                    body
+                );
+                if let Err(err) = stream.write_all(resp.as_bytes()).await {
+                    warn!("Failed to send metrics to {}, {}", peer, err);
+                }
+                _ = stream.shutdown().await;
+            });
+        }
+    }
+
+    fn record_traffic(
+        &self,
+        conn: &ConnectionInfo,
+        channel: &str,
+        bytes: usize,
+        direction: &'static str,
+    ) {
+        let mut metrics = self.metrics.lock().unwrap();
+        let entry = metrics
+            .traffic
+            .entry((conn.exchange.clone(), channel.to_string(), direction))
+            .or_default();
+        entry.0 += 1;
+        entry.1 += bytes as u64;
+    }
+}
+
+impl ConnectionObserver for PrometheusExporter {
+    fn on_connect(&self, conn: &ConnectionInfo) {
+        let mut metrics = self.metrics.lock().unwrap();
+        increase(&mut metrics.connects, &conn.exchange);
+        metrics.last_message.insert(conn.id, (conn.exchange.clone(), Instant::now()));
+    }
+
+    fn on_connect_error(&self, conn: &ConnectionInfo, _error: &str) {
+        increase(&mut self.metrics.lock().unwrap().connect_errors, &conn.exchange);
+    }
+
+    fn on_reconnect_attempt(&self, conn: &ConnectionInfo) {
+        increase(&mut self.metrics.lock().unwrap().reconnect_attempts, &conn.exchange);
+    }
+
+    fn on_disconnect(&self, conn: &ConnectionInfo, reason: &DisconnectReason) {
+        let mut metrics = self.metrics.lock().unwrap();
+        *metrics.disconnects.entry((conn.exchange.clone(), reason.as_str())).or_default() += 1;
+        metrics.last_message.remove(&conn.id);
+    }
+
+    fn on_message_in(&self, conn: &ConnectionInfo, channel: &str, bytes: usize) {
+        self.record_traffic(conn, channel, bytes, "in");
+        // acks and heartbeats don't mean the feed is alive
+        if !channel.is_empty() {
+            if let Some((_, timestamp)) =
+                self.metrics.lock().unwrap().last_message.get_mut(&conn.id)
+            {
+                *timestamp = Instant::now();
+            }
+        }
+    }
+
+    fn on_message_out(&self, conn: &ConnectionInfo, channel: &str, bytes: usize) {
+        self.record_traffic(conn, channel, bytes, "out");
+    }
+
+    fn on_decompression_failure(&self, conn: &ConnectionInfo) {
+        increase(&mut self.metrics.lock().unwrap().decompression_failures, &conn.exchange);
+    }
+
+    fn on_ping(&self, conn: &ConnectionInfo) {
+        increase(&mut self.metrics.lock().unwrap().pings, &conn.exchange);
+    }
+
+    fn on_pong(&self, conn: &ConnectionInfo) {
+        increase(&mut self.metrics.lock().unwrap().pongs, &conn.exchange);
+    }
+
+    fn on_uplink_wait(&self, conn: &ConnectionInfo, wait: Duration) {
+        *self.metrics.lock().unwrap().uplink_wait.entry(conn.exchange.clone()).or_default() += wait;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::PrometheusExporter;
+    use crate::common::metrics::{ConnectionInfo, ConnectionObserver, DisconnectReason};
+
+    #[test]
+    fn test_render() {
+        let exporter = PrometheusExporter::new();
+        let conn = ConnectionInfo {
+            id: 1,
+            exchange: "binance".to_string(),
+            url: "wss://stream.binance.com:9443/ws".to_string(),
+        };
+        exporter.on_connect(&conn);
+        exporter.on_message_out(&conn, "aggTrade", 60);
+        exporter.on_message_in(&conn, "aggTrade", 200);
+        exporter.on_message_in(&conn, "aggTrade", 210);
+        exporter.on_ping(&conn);
+        exporter.on_uplink_wait(&conn, Duration::from_millis(1500));
+
+        let text = exporter.render();
+        assert!(text.contains("# TYPE crypto_ws_connects_total counter\n"));
+        assert!(text.contains("crypto_ws_connects_total{exchange=\"binance\"} 1\n"));
+        assert!(text.contains(
+            "crypto_ws_messages_total{exchange=\"binance\",channel=\"aggTrade\",direction=\"in\"} 2\n"
+        ));
+        assert!(text.contains(
+            "crypto_ws_bytes_total{exchange=\"binance\",channel=\"aggTrade\",direction=\"in\"} 410\n"
+        ));
+        assert!(text.contains("crypto_ws_uplink_wait_seconds_total{exchange=\"binance\"} 1.5\n"));
+        assert!(text.contains("crypto_ws_connections{exchange=\"binance\"} 1\n"));
+        assert!(text.contains("crypto_ws_last_message_age_seconds{exchange=\"binance\"} "));
+
+        exporter.on_disconnect(&conn, &DisconnectReason::CloseFrame("1001".to_string()));
+        let text = exporter.render();
+        assert!(text.contains(
+            "crypto_ws_disconnects_total{exchange=\"binance\",reason=\"close_frame\"} 1\n"
+        ));
+        assert!(!text.contains("crypto_ws_connections{"));
+        assert!(!text.contains("crypto_ws_last_message_age_seconds{"));
+    }
+}
@@ -0,0 +1,160 @@
+use std::{
+    fs::File,
+    future::Future,
+    io::{LineWriter, Write},
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use base64::{engine::general_purpose, Engine as _};
+use log::*;
+use serde::{Deserialize, Serialize};
+use tokio_tungstenite::tungstenite::Message;
+
+/// If this environment variable is set, every websocket connection is recorded
+/// into a new file in this directory, which can be replayed by
+/// `ReplayWSClient`.
+pub(crate) const RECORD_DIR_ENV: &str = "CRYPTO_WS_RECORD_DIR";
+
+tokio::task_local! {
+    static RECORD_DIR: PathBuf;
+}
+
+/// Records connections of clients created inside `f` into `dir`.
+///
+/// Each client remembers the directory when it connects, so clients created
+/// inside and outside of `f`, or inside of two calls with different
+/// directories, are recorded independently. It takes precedence over
+/// `CRYPTO_WS_RECORD_DIR`.
+///
+/// ```no_run
+/// use crypto_ws_client::{record_to, BinanceSpotWSClient};
+///
+/// # async fn example() {
+/// let (tx, _rx) = std::sync::mpsc::channel();
+/// let ws_client = record_to("/tmp/recordings", BinanceSpotWSClient::new(tx, None)).await;
+/// # }
+/// ```
+pub async fn record_to<F: Future>(dir: impl Into<PathBuf>, f: F) -> F::Output {
+    RECORD_DIR.scope(dir.into(), f).await
+}
+
+/// The directory to record a new connection into, from `record_to()` or
+/// `CRYPTO_WS_RECORD_DIR`.
+pub(crate) fn record_dir() -> Option<PathBuf> {
+    RECORD_DIR
+        .try_with(|dir| dir.clone())
+        .ok()
+        .or_else(|| std::env::var_os(RECORD_DIR_ENV).map(PathBuf::from))
+}
+
+/// The first line of a recording.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+pub(crate) struct RecordHeader {
+    pub exchange: String,
+    pub url: String,
+    pub timestamp: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum Direction {
+    /// From the server to the client
+    In,
+    /// From the client to the server
+    Out,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum Opcode {
+    Text,
+    Binary,
+    Ping,
+    Pong,
+    Close,
+}
+
+/// Following lines of a recording, one per frame.
+///
+/// `payload` is the text of text frames, the reason of close frames and base64
+/// encoded bytes for other frames.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+pub(crate) struct RecordedFrame {
+    pub timestamp: u64,
+    pub direction: Direction,
+    pub opcode: Opcode,
+    pub payload: String,
+}
+
+impl RecordedFrame {
+    fn new(direction: Direction, msg: &Message) -> Option<Self> {
+        let (opcode, payload) = match msg {
+            Message::Text(txt) => (Opcode::Text, txt.clone()),
+            Message::Binary(bytes) => (Opcode::Binary, general_purpose::STANDARD.encode(bytes)),
+            Message::Ping(bytes) => (Opcode::Ping, general_purpose::STANDARD.encode(bytes)),
+            Message::Pong(bytes) => (Opcode::Pong, general_purpose::STANDARD.encode(bytes)),
+            Message::Close(frame) => {
+                (Opcode::Close, frame.as_ref().map(|x| x.reason.to_string()).unwrap_or_default())
+            }
+            Message::Frame(_) => return None,
+        };
+        Some(RecordedFrame { timestamp: now(), direction, opcode, payload })
+    }
+
+    /// Returns the bytes of binary, ping and pong frames.
+    pub fn bytes(&self) -> Result<Vec<u8>, base64::DecodeError> {
+        general_purpose::STANDARD.decode(&self.payload)
+    }
+}
+
+/// Writes frames of one connection into a JSON Lines file.
+pub(crate) struct Recorder {
+    path: PathBuf,
+    writer: LineWriter<File>,
+}
+
+impl Recorder {
+    /// Creates a recorder if `dir` is set, errors are logged.
+    pub fn open(dir: Option<&Path>, exchange: &str, url: &str) -> Option<Self> {
+        let dir = dir?;
+        match Self::create(dir, exchange, url) {
+            Ok(recorder) => {
+                info!("Recording {} to {}", url, recorder.path().display());
+                Some(recorder)
+            }
+            Err(err) => {
+                error!("Failed to create a recording file in {}, {}", dir.display(), err);
+                None
+            }
+        }
+    }
+
+    /// Creates a new file named `exchange.timestamp.random.jsonl` in `dir`.
+    pub fn create(dir: &Path, exchange: &str, url: &str) -> std::io::Result<Self> {
+        std::fs::create_dir_all(dir)?;
+        let header =
+            RecordHeader { exchange: exchange.to_string(), url: url.to_string(), timestamp: now() };
+        let path =
+            dir.join(format!("{}.{}.{}.jsonl", exchange, header.timestamp, rand::random::<u32>()));
+        let mut writer = LineWriter::new(File::create(&path)?);
+        writeln!(writer, "{}", serde_json::to_string(&header).unwrap())?;
+        Ok(Recorder { path, writer })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn record(&mut self, direction: Direction, msg: &Message) {
+        if let Some(frame) = RecordedFrame::new(direction, msg) {
+            if let Err(err) = writeln!(self.writer, "{}", serde_json::to_string(&frame).unwrap()) {
+                error!("Failed to write to {}, {}", self.path.display(), err);
+            }
+        }
+    }
+}
+
+pub(crate) fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_millis() as u64
+}
@@ -0,0 +1,374 @@
+use std::{
+    fs::File,
+    io::{BufRead, BufReader},
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use log::*;
+
+use super::{
+    message_handler::{BoxedMessageHandler, MiscMessage},
+    recorder::{Direction, Opcode, RecordHeader, RecordedFrame},
+    ws_client_internal::decompress,
+};
+use crate::{CandleInterval, Error, WSClient};
+
+/// How fast `ReplayWSClient` emits messages.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ReplaySpeed {
+    /// Emit messages without waiting
+    AsFastAsPossible,
+    /// Wait between messages as long as they were received
+    Recorded,
+}
+
+/// Replays a websocket session recorded with `CRYPTO_WS_RECORD_DIR`.
+///
+/// Set the environment variable `CRYPTO_WS_RECORD_DIR` to a directory, every
+/// connection of every client is recorded into a new file, one JSON object per
+/// line, the first line contains the exchange and URL, each following line
+/// contains a frame with its timestamp, direction and opcode.
+///
+/// `run()` passes received text and binary frames through the message handler
+/// of the exchange, the same as the original client, so that only messages the
+/// client would have forwarded are sent into `tx`, and acks and heartbeats are
+/// dropped. Handlers are configured as by `new()` of the client, e.g., without
+/// checksum validation. Commands a handler would send to the server are
+/// ignored. Subscribing methods do nothing because the recording decides what
+/// to replay.
+pub struct ReplayWSClient {
+    path: PathBuf,
+    header: RecordHeader,
+    speed: ReplaySpeed,
+    tx: std::sync::mpsc::Sender<String>,
+    close_tx: tokio::sync::watch::Sender<bool>,
+    handler: std::sync::Mutex<Option<BoxedMessageHandler>>,
+}
+
+impl ReplayWSClient {
+    pub fn new(
+        tx: std::sync::mpsc::Sender<String>,
+        path: impl AsRef<Path>,
+        speed: ReplaySpeed,
+    ) -> Result<Self, Error> {
+        let path = path.as_ref().to_path_buf();
+        let file = File::open(&path)
+            .map_err(|err| Error(format!("Failed to open {}, {}", path.display(), err)))?;
+        let mut line = String::new();
+        BufReader::new(file)
+            .read_line(&mut line)
+            .map_err(|err| Error(format!("Failed to read {}, {}", path.display(), err)))?;
+        let header = serde_json::from_str::<RecordHeader>(&line)
+            .map_err(|err| Error(format!("Invalid recording header {line}, {err}")))?;
+        let handler = crate::clients::new_message_handler(&header.exchange, &header.url)
+            .ok_or_else(|| Error(format!("Unknown exchange {}", header.exchange)))?;
+        let (close_tx, _) = tokio::sync::watch::channel(false);
+        Ok(ReplayWSClient {
+            path,
+            header,
+            speed,
+            tx,
+            close_tx,
+            handler: std::sync::Mutex::new(Some(handler)),
+        })
+    }
+
+    /// The exchange of the recorded session.
+    pub fn exchange(&self) -> &str {
+        &self.header.exchange
+    }
+
+    /// The websocket URL of the recorded session.
+    pub fn url(&self) -> &str {
+        &self.header.url
+    }
+
+    fn to_text(&self, frame: &RecordedFrame) -> Result<Option<String>, Error> {
+        match frame.opcode {
+            Opcode::Text => Ok(Some(frame.payload.clone())),
+            Opcode::Binary => {
+                let bytes = match frame.bytes() {
+                    Ok(bytes) => bytes,
+                    Err(err) => {
+                        error!("Invalid binary frame {}, {}", frame.payload, err);
+                        return Ok(None);
+                    }
+                };
+                match decompress(&self.header.exchange, &bytes) {
+                    Some(Ok(txt)) => Ok(Some(txt)),
+                    Some(Err(err)) => {
+                        error!("Decompression failed, {}", err);
+                        Ok(None)
+                    }
+                    None => Err(Error(format!("Unknown binary format from {}", self.header.url))),
+                }
+            }
+            Opcode::Ping | Opcode::Pong | Opcode::Close => Ok(None),
+        }
+    }
+
+    /// Same as `run()` but returns the error which ends the replay, i.e., a
+    /// file which can't be read, a binary frame the exchange doesn't send or a
+    /// fatal error message from the exchange.
+    ///
+    /// A client can replay only once.
+    pub async fn replay(&self) -> Result<(), Error> {
+        let mut handler = self
+            .handler
+            .lock()
+            .unwrap()
+            .take()
+            .ok_or_else(|| Error(format!("{} was replayed already", self.path.display())))?;
+        let reader = File::open(&self.path)
+            .map(BufReader::new)
+            .map_err(|err| Error(format!("Failed to open {}, {}", self.path.display(), err)))?;
+        let mut close_rx = self.close_tx.subscribe();
+        let mut prev_timestamp: Option<u64> = None;
+        // skip the header
+        for line in reader.lines().skip(1) {
+            if *close_rx.borrow() {
+                break;
+            }
+            let line = line
+                .map_err(|err| Error(format!("Failed to read {}, {}", self.path.display(), err)))?;
+            if line.is_empty() {
+                continue;
+            }
+            let frame = match serde_json::from_str::<RecordedFrame>(&line) {
+                Ok(frame) => frame,
+                Err(err) => {
+                    error!("Invalid frame {}, {}", line, err);
+                    continue;
+                }
+            };
+            if frame.direction != Direction::In {
+                continue;
+            }
+            if let Some(txt) = self.to_text(&frame)? {
+                if self.speed == ReplaySpeed::Recorded {
+                    if let Some(prev) = prev_timestamp {
+                        let delay = Duration::from_millis(frame.timestamp.saturating_sub(prev));
+                        tokio::select! {
+                            _ = tokio::time::sleep(delay) => (),
+                            _ = close_rx.changed() => break,
+                        }
+                    }
+                    prev_timestamp = Some(frame.timestamp);
+                }
+                let txt = txt.trim().to_string();
+                let forwarded = match handler.handle_message(&txt) {
+                    MiscMessage::Normal => txt,
+                    MiscMessage::Mutated(new_txt) => new_txt,
+                    MiscMessage::Error(err) => return Err(Error(err)),
+                    // the original connection ended here
+                    MiscMessage::Reconnect => break,
+                    MiscMessage::WebSocket(_)
+                    | MiscMessage::Resubscribe(_)
+                    | MiscMessage::Pong
+                    | MiscMessage::Other => continue,
+                };
+                if self.tx.send(forwarded).is_err() {
+                    break; // break the loop if there is no receiver
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl WSClient for ReplayWSClient {
+    async fn subscribe_trade(&self, _symbols: &[String]) {}
+
+    async fn subscribe_bbo(&self, _symbols: &[String]) {}
+
+    async fn subscribe_orderbook(&self, _symbols: &[String]) {}
+
+    async fn subscribe_orderbook_topk(&self, _symbols: &[String]) {}
+
+    async fn subscribe_l3_orderbook(&self, _symbols: &[String]) {}
+
+    async fn subscribe_ticker(&self, _symbols: &[String]) {}
+
+    async fn subscribe_candlestick(
+        &self,
+        _symbol_interval_list: &[(String, CandleInterval)],
+    ) -> Result<(), Error> {
+        Ok(())
+    }
+
+    async fn subscribe(&self, _topics: &[(String, String)]) {}
+
+    async fn unsubscribe(&self, _topics: &[(String, String)]) {}
+
+    async fn send(&self, _commands: &[String]) {}
+
+    async fn run(&self) {
+        if let Err(err) = self.replay().await {
+            error!("{}", err);
+        }
+    }
+
+    async fn close(&self) {
+        self.close_tx.send_replace(true);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::Write,
+        path::PathBuf,
+        time::{Duration, Instant},
+    };
+
+    use flate2::{write::GzEncoder, Compression};
+    use tokio_tungstenite::tungstenite::Message;
+
+    use super::{ReplaySpeed, ReplayWSClient};
+    use crate::{
+        common::recorder::{self, Direction, Recorder},
+        WSClient,
+    };
+
+    fn record(exchange: &str, frames: &[(Direction, Message)]) -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join("crypto-ws-client-replay");
+        let mut recorder = Recorder::create(&dir, exchange, "wss://example.com/ws").unwrap();
+        for (direction, msg) in frames {
+            recorder.record(*direction, msg);
+            std::thread::sleep(Duration::from_millis(100));
+        }
+        recorder.path().to_path_buf()
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn replay_as_fast_as_possible() {
+        let trade = r#"{"ch":"market.btcusdt.trade.detail","ts":1677000004000,"tick":{"data":[]}}"#;
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(trade.as_bytes()).unwrap();
+        let path = record(
+            "huobi",
+            &[
+                (
+                    Direction::Out,
+                    Message::Text(r#"{"sub":"market.btcusdt.trade.detail"}"#.to_string()),
+                ),
+                (
+                    Direction::In,
+                    Message::Text(
+                        r#"{"id":"1","status":"ok","subbed":"market.btcusdt.trade.detail","ts":1677000003000}"#
+                            .to_string(),
+                    ),
+                ),
+                (Direction::In, Message::Ping(Vec::new())),
+                (Direction::In, Message::Binary(encoder.finish().unwrap())),
+                (Direction::In, Message::Text(r#"{"ping":1677000005000}"#.to_string())),
+            ],
+        );
+
+        let (tx, rx) = std::sync::mpsc::channel();
+        let ws_client = ReplayWSClient::new(tx, &path, ReplaySpeed::AsFastAsPossible).unwrap();
+        assert_eq!("huobi", ws_client.exchange());
+        assert_eq!("wss://example.com/ws", ws_client.url());
+        let start = Instant::now();
+        ws_client.run().await;
+        assert!(start.elapsed() < Duration::from_millis(100));
+        drop(ws_client);
+        // the ack and heartbeats are dropped by the message handler
+        assert_eq!(vec![trade.to_string()], rx.iter().collect::<Vec<String>>());
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn replay_at_recorded_speed() {
+        let trade = |i: i32| format!(r#"{{"table":"trade","action":"insert","data":[{i}]}}"#);
+        let path = record(
+            "bitmex",
+            &[
+                (Direction::In, Message::Text(trade(1))),
+                (Direction::In, Message::Text(trade(2))),
+                (Direction::In, Message::Text(trade(3))),
+            ],
+        );
+
+        let (tx, rx) = std::sync::mpsc::channel();
+        let ws_client = ReplayWSClient::new(tx, &path, ReplaySpeed::Recorded).unwrap();
+        let start = Instant::now();
+        ws_client.run().await;
+        assert!(start.elapsed() >= Duration::from_millis(200));
+        drop(ws_client);
+        assert_eq!(vec![trade(1), trade(2), trade(3)], rx.iter().collect::<Vec<String>>());
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn replay_mutated() {
+        let path = record(
+            "bitfinex",
+            &[
+                (
+                    Direction::In,
+                    Message::Text(
+                        r#"{"event":"subscribed","channel":"trades","chanId":19111,"symbol":"tBTCUSD","pair":"BTCUSD"}"#
+                            .to_string(),
+                    ),
+                ),
+                (
+                    Direction::In,
+                    Message::Text(r#"[19111,"te",[1,1574694475039,0.005,7244.9]]"#.to_string()),
+                ),
+                (Direction::In, Message::Text(r#"[19111,"hb"]"#.to_string())),
+            ],
+        );
+
+        let (tx, rx) = std::sync::mpsc::channel();
+        let ws_client = ReplayWSClient::new(tx, &path, ReplaySpeed::AsFastAsPossible).unwrap();
+        ws_client.replay().await.unwrap();
+        drop(ws_client);
+        assert_eq!(
+            vec![
+                r#"[{"channel":"trades","symbol":"tBTCUSD"},"te",[1,1574694475039,0.005,7244.9]]"#
+                    .to_string()
+            ],
+            rx.iter().collect::<Vec<String>>()
+        );
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn unknown_binary_format() {
+        let path = record("bitmex", &[(Direction::In, Message::Binary(vec![1, 2, 3]))]);
+
+        let (tx, _rx) = std::sync::mpsc::channel();
+        let ws_client = ReplayWSClient::new(tx, &path, ReplaySpeed::AsFastAsPossible).unwrap();
+        assert!(ws_client.replay().await.is_err());
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn record_to() {
+        let (dir1, dir2) = (PathBuf::from("/tmp/record1"), PathBuf::from("/tmp/record2"));
+        let (first, second) = tokio::join!(
+            crate::record_to(&dir1, async { recorder::record_dir() }),
+            crate::record_to(&dir2, async { recorder::record_dir() }),
+        );
+        assert_eq!(Some(dir1), first);
+        assert_eq!(Some(dir2), second);
+    }
+
+    #[test]
+    fn unknown_exchange() {
+        let path = record("unknown", &[]);
+        let (tx, _rx) = std::sync::mpsc::channel();
+        assert!(ReplayWSClient::new(tx, &path, ReplaySpeed::Recorded).is_err());
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn invalid_file() {
+        let (tx, _rx) = std::sync::mpsc::channel();
+        assert!(ReplayWSClient::new(tx, "/non/existent.jsonl", ReplaySpeed::Recorded).is_err());
+    }
+}
@@ -1,5 +1,7 @@
 use async_trait::async_trait;
 
+use crate::{CandleInterval, Error};
+
 /// The public interface of every WebSocket client.
 #[async_trait]
 pub trait WSClient {
@@ -93,11 +95,16 @@ pub trait WSClient {
     /// The candlestick channel sends OHLCV messages at interval.
     ///
     /// `symbol_interval_list` is a list of symbols and intervals of
-    /// candlesticks in seconds.
+    /// candlesticks. If any interval is not supported by the exchange, an
+    /// error is returned and nothing is subscribed, call
+    /// `supported_candlestick_intervals()` to get available intervals.
     ///
     /// Not all exchanges have candlestick channels, for example, Bitstamp
     /// and CoinbasePro.
-    async fn subscribe_candlestick(&self, symbol_interval_list: &[(String, usize)]);
+    async fn subscribe_candlestick(
+        &self,
+        symbol_interval_list: &[(String, CandleInterval)],
+    ) -> Result<(), Error>;
 
     /// Subscribe to multiple topics.
     ///
@@ -134,6 +141,9 @@ pub trait WSClient {
     async fn send(&self, commands: &[String]);
 
     /// Starts the infinite event loop.
+    ///
+    /// Returns if the connection is closed by `close()` or lost, or the
+    /// exchange sends a fatal error or asks to reconnect.
     async fn run(&self);
 
     /// Close the connection and break the loop in Run().
@@ -2,8 +2,8 @@ use std::{
     io::prelude::*,
     num::NonZeroU32,
     sync::{
-        atomic::{AtomicIsize, Ordering},
-        Arc,
+        atomic::{AtomicBool, AtomicIsize, Ordering},
+        Arc, RwLock,
     },
     time::Duration,
 };
@@ -13,7 +13,14 @@ use log::*;
 use reqwest::StatusCode;
 use tokio_tungstenite::tungstenite::{Error, Message};
 
-use crate::common::message_handler::{MessageHandler, MiscMessage};
+use crate::common::{
+    message_handler::{MessageHandler, MiscMessage},
+    metrics::{self, ConnectionInfo, ConnectionObserver, DisconnectReason},
+    recorder,
+};
+
+// Seconds to wait after a 429 response without a valid retry-after header
+const DEFAULT_RETRY_AFTER: u64 = 60;
 
 // `WSClientInternal` should be Sync + Send so that it can be put into Arc
 // directly.
@@ -30,6 +37,10 @@ pub(crate) struct WSClientInternal<H: MessageHandler> {
         )>,
     >,
     command_tx: tokio::sync::mpsc::Sender<Message>,
+    conn: Arc<ConnectionInfo>,
+    observer: Option<Arc<dyn ConnectionObserver>>,
+    channels: RwLock<Vec<String>>, // subscribed channels, for metrics only
+    closing: AtomicBool,           // close() was called
 }
 
 impl<H: MessageHandler> WSClientInternal<H> {
@@ -40,6 +51,24 @@ impl<H: MessageHandler> WSClientInternal<H> {
         uplink_limit: Option<(NonZeroU32, std::time::Duration)>,
         tx: std::sync::mpsc::Sender<String>,
     ) -> Self {
+        match Self::try_connect(exchange, url, handler, uplink_limit, tx).await {
+            Ok(client) => client,
+            Err(Error::Http(resp)) if resp.status() == StatusCode::TOO_MANY_REQUESTS => {
+                panic!("Failed to connect to {url} due to 429 too many requests")
+            }
+            Err(err) => panic!("Failed to connect to {url}, error: {err}"),
+        }
+    }
+
+    /// Same as connect() but returns the error instead of panicking, so that
+    /// callers can fail over to another server.
+    pub async fn try_connect(
+        exchange: &'static str,
+        url: &str,
+        handler: H,
+        uplink_limit: Option<(NonZeroU32, std::time::Duration)>,
+        tx: std::sync::mpsc::Sender<String>,
+    ) -> Result<Self, Error> {
         // A channel to send parameters to run()
         let (params_tx, params_rx) = tokio::sync::oneshot::channel::<(
             H,
@@ -47,47 +76,121 @@ impl<H: MessageHandler> WSClientInternal<H> {
             std::sync::mpsc::Sender<String>,
         )>();
 
-        match super::connect_async::connect_async(url, uplink_limit).await {
+        let conn = Arc::new(metrics::new_connection_info(exchange, url));
+        let observer = metrics::connection_observer();
+        if let Some(observer) = observer.as_ref() {
+            if metrics::is_reconnecting(exchange) {
+                observer.on_reconnect_attempt(&conn);
+            }
+        }
+
+        // each client is recorded into the directory in effect when it connects
+        let record_dir = recorder::record_dir();
+        match super::connect_async::connect_async(
+            conn.clone(),
+            uplink_limit,
+            observer.clone(),
+            record_dir,
+        )
+        .await
+        {
             Ok((message_rx, command_tx)) => {
                 let _ = params_tx.send((handler, message_rx, tx));
+                metrics::mark_up(exchange);
+                if let Some(observer) = observer.as_ref() {
+                    observer.on_connect(&conn);
+                }
 
-                WSClientInternal {
+                Ok(WSClientInternal {
                     exchange,
                     url: url.to_string(),
                     params_rx: std::sync::Mutex::new(params_rx),
                     command_tx,
-                }
+                    conn,
+                    observer,
+                    channels: RwLock::new(Vec::new()),
+                    closing: AtomicBool::new(false),
+                })
             }
-            Err(err) => match err {
-                Error::Http(resp) => {
+            Err(err) => {
+                if let Some(observer) = observer.as_ref() {
+                    observer.on_connect_error(&conn, &err.to_string());
+                }
+                if let Error::Http(resp) = &err {
                     if resp.status() == StatusCode::TOO_MANY_REQUESTS {
                         if let Some(retry_after) = resp.headers().get("retry-after") {
-                            let mut seconds = retry_after.to_str().unwrap().parse::<u64>().unwrap();
+                            let mut seconds = retry_after
+                                .to_str()
+                                .ok()
+                                .and_then(|value| value.parse::<u64>().ok())
+                                .unwrap_or(DEFAULT_RETRY_AFTER);
                             seconds += rand::random::<u64>() % 9 + 1; // add random seconds to avoid concurrent requests
                             error!(
-                                "The retry-after header value is {}, sleeping for {} seconds now",
-                                retry_after.to_str().unwrap(),
-                                seconds
+                                "The retry-after header value is {:?}, sleeping for {} seconds now",
+                                retry_after, seconds
                             );
                             tokio::time::sleep(Duration::from_secs(seconds)).await;
                         }
                     }
-                    panic!("Failed to connect to {url} due to 429 too many requests")
                 }
-                _ => panic!("Failed to connect to {url}, error: {err}"),
-            },
+                Err(err)
+            }
         }
     }
 
     pub async fn send(&self, commands: &[String]) {
         for command in commands {
             debug!("Sending command... {}", command,);
+            self.report_message_out(command);
             if self.command_tx.send(Message::Text(command.to_string())).await.is_err() {
                 break; // break the loop if there is no receiver
             }
         }
     }
 
+    /// Remembers subscribed channels, so that messages can be attributed to
+    /// them in metrics.
+    pub fn add_channels(&self, topics: &[(String, String)]) {
+        if self.observer.is_none() {
+            return;
+        }
+        let mut channels = self.channels.write().unwrap();
+        for (channel, _symbol) in topics {
+            if !channels.contains(channel) {
+                channels.push(channel.clone());
+            }
+        }
+    }
+
+    fn find_channel(&self, txt: &str) -> String {
+        metrics::find_channel(&self.channels.read().unwrap(), txt).to_string()
+    }
+
+    // The channel of a message forwarded to the receiver is never empty
+    fn forwarded_channel(&self, txt: &str) -> String {
+        let channel = self.find_channel(txt);
+        if channel.is_empty() {
+            metrics::UNKNOWN_CHANNEL.to_string()
+        } else {
+            channel
+        }
+    }
+
+    fn report_message_out(&self, txt: &str) {
+        if let Some(observer) = self.observer.as_ref() {
+            observer.on_message_out(&self.conn, &self.find_channel(txt), txt.len());
+        }
+    }
+
+    fn report_disconnect(&self, reason: DisconnectReason) {
+        if reason != DisconnectReason::Closed && reason != DisconnectReason::ReceiverDropped {
+            metrics::mark_down(self.exchange);
+        }
+        if let Some(observer) = self.observer.as_ref() {
+            observer.on_disconnect(&self.conn, &reason);
+        }
+    }
+
     pub async fn run(&self) {
         let (mut handler, mut message_rx, tx) = {
             let mut guard = self.params_rx.lock().unwrap();
@@ -99,6 +202,8 @@ impl<H: MessageHandler> WSClientInternal<H> {
             // send heartbeat periodically
             let command_tx_clone = self.command_tx.clone();
             let num_unanswered_ping_clone = num_unanswered_ping.clone();
+            let conn = self.conn.clone();
+            let observer = self.observer.clone();
             tokio::task::spawn(async move {
                 let mut timer = {
                     let duration = Duration::from_secs(interval / 2 + 1);
@@ -111,41 +216,42 @@ impl<H: MessageHandler> WSClientInternal<H> {
                         error!("Error sending ping {}", err);
                     } else {
                         num_unanswered_ping_clone.fetch_add(1, Ordering::SeqCst);
+                        if let Some(observer) = observer.as_ref() {
+                            observer.on_ping(&conn);
+                        }
                     }
                 }
             });
         }
 
-        while let Some(msg) = message_rx.recv().await {
+        let reason = loop {
+            let msg = match message_rx.recv().await {
+                Some(msg) => msg,
+                None if self.closing.load(Ordering::Acquire) => break DisconnectReason::Closed,
+                None => break DisconnectReason::ConnectionLost,
+            };
+            let wire_size = match &msg {
+                Message::Text(txt) => txt.len(),
+                Message::Binary(binary) => binary.len(),
+                _ => 0,
+            };
             let txt = match msg {
                 Message::Text(txt) => Some(txt),
-                Message::Binary(binary) => {
-                    let mut txt = String::new();
-                    let resp = match self.exchange {
-                        crate::clients::huobi::EXCHANGE_NAME
-                        | crate::clients::binance::EXCHANGE_NAME
-                        | "bitget"
-                        | "bitz" => {
-                            let mut decoder = GzDecoder::new(&binary[..]);
-                            decoder.read_to_string(&mut txt)
-                        }
-                        crate::clients::okx::EXCHANGE_NAME => {
-                            let mut decoder = DeflateDecoder::new(&binary[..]);
-                            decoder.read_to_string(&mut txt)
-                        }
-                        _ => {
-                            panic!("Unknown binary format from {}", self.url);
-                        }
-                    };
-
-                    match resp {
-                        Ok(_) => Some(txt),
-                        Err(err) => {
-                            error!("Decompression failed, {}", err);
-                            None
+                Message::Binary(binary) => match decompress(self.exchange, &binary) {
+                    Some(Ok(txt)) => Some(txt),
+                    Some(Err(err)) => {
+                        error!("Decompression failed, {}", err);
+                        if let Some(observer) = self.observer.as_ref() {
+                            observer.on_decompression_failure(&self.conn);
                         }
+                        None
                     }
-                }
+                    None => {
+                        let err = format!("Unknown binary format from {}", self.url);
+                        error!("{}", err);
+                        break DisconnectReason::Error(err);
+                    }
+                },
                 Message::Ping(resp) => {
                     // binance server will send a ping frame every 3 or 5 minutes
                     debug!(
@@ -168,35 +274,61 @@ impl<H: MessageHandler> WSClientInternal<H> {
                         self.exchange,
                         num_unanswered_ping.load(Ordering::Acquire)
                     );
+                    if let Some(observer) = self.observer.as_ref() {
+                        observer.on_pong(&self.conn);
+                    }
                     None
                 }
-                Message::Frame(_) => todo!(),
+                // raw frames are never returned when reading messages
+                Message::Frame(_) => None,
                 Message::Close(resp) => {
-                    match resp {
+                    let detail = match resp {
                         Some(frame) => {
                             warn!(
                                 "Received a CloseFrame: code: {}, reason: {} from {}",
                                 frame.code, frame.reason, self.url
                             );
+                            format!("code: {}, reason: {}", frame.code, frame.reason)
+                        }
+                        None => {
+                            warn!("Received a close message without CloseFrame");
+                            String::new()
                         }
-                        None => warn!("Received a close message without CloseFrame"),
+                    };
+                    if self.closing.load(Ordering::Acquire) {
+                        break DisconnectReason::Closed; // the reply to close()
                     }
-                    // break;
-                    panic!("Received a CloseFrame"); //fail fast so that pm2 can restart the process
+                    break DisconnectReason::CloseFrame(detail);
                 }
             };
 
             if let Some(txt) = txt {
                 let txt = txt.as_str().trim().to_string();
-                match handler.handle_message(&txt) {
+                let misc_msg = handler.handle_message(&txt);
+                if let Some(observer) = self.observer.as_ref() {
+                    let channel = match &misc_msg {
+                        MiscMessage::Normal => self.forwarded_channel(&txt),
+                        MiscMessage::Mutated(new_txt) => self.forwarded_channel(new_txt),
+                        _ => String::new(), // not forwarded
+                    };
+                    observer.on_message_in(&self.conn, &channel, wire_size);
+                }
+                match misc_msg {
                     MiscMessage::Normal => {
                         // the receiver might get dropped earlier than this loop
                         if tx.send(txt).is_err() {
-                            break; // break the loop if there is no receiver
+                            // break the loop if there is no receiver
+                            break DisconnectReason::ReceiverDropped;
                         }
                     }
                     MiscMessage::Mutated(txt) => _ = tx.send(txt),
-                    MiscMessage::WebSocket(ws_msg) => _ = self.command_tx.send(ws_msg).await,
+                    MiscMessage::WebSocket(ws_msg) => {
+                        if let Message::Text(command) = &ws_msg {
+                            self.report_message_out(command);
+                        }
+                        _ = self.command_tx.send(ws_msg).await
+                    }
+                    MiscMessage::Resubscribe(commands) => self.send(&commands).await,
                     MiscMessage::Pong => {
                         num_unanswered_ping.store(0, Ordering::Release);
                         debug!(
@@ -205,17 +337,47 @@ impl<H: MessageHandler> WSClientInternal<H> {
                             self.exchange,
                             num_unanswered_ping.load(Ordering::Acquire)
                         );
+                        if let Some(observer) = self.observer.as_ref() {
+                            observer.on_pong(&self.conn);
+                        }
                     }
-                    MiscMessage::Reconnect => break, /* fail fast, pm2 will restart, restart is */
-                    // reconnect
+                    MiscMessage::Error(err) => {
+                        error!("{}", err);
+                        break DisconnectReason::Error(err);
+                    }
+                    // fail fast, pm2 will restart, restart is reconnect
+                    MiscMessage::Reconnect => break DisconnectReason::Reconnect,
                     MiscMessage::Other => (), // ignore
                 }
             }
-        }
+        };
+        self.report_disconnect(reason);
     }
 
     pub async fn close(&self) {
         // close the websocket connection and break the while loop in run()
+        self.closing.store(true, Ordering::Release);
         _ = self.command_tx.send(Message::Close(None)).await;
     }
 }
+
+/// Decompresses a binary message, returns None if the exchange doesn't send
+/// binary messages.
+pub(crate) fn decompress(exchange: &str, binary: &[u8]) -> Option<std::io::Result<String>> {
+    let mut txt = String::new();
+    let resp = match exchange {
+        crate::clients::huobi::EXCHANGE_NAME
+        | crate::clients::binance::EXCHANGE_NAME
+        | "bitget"
+        | "bitz" => {
+            let mut decoder = GzDecoder::new(binary);
+            decoder.read_to_string(&mut txt)
+        }
+        crate::clients::okx::EXCHANGE_NAME => {
+            let mut decoder = DeflateDecoder::new(binary);
+            decoder.read_to_string(&mut txt)
+        }
+        _ => return None,
+    };
+    Some(resp.map(|_| txt))
+}
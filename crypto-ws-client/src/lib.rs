@@ -32,7 +32,7 @@
 //! * `subscribe_bbo(&self, symbols: &[String])`
 //! * `subscribe_orderbook(&self, symbols: &[String])`
 //! * `subscribe_ticker(&self, symbols: &[String])`
-//! * `subscribe_candlestick(&self, symbol_interval_list: &[(String, usize)])`
+//! * `subscribe_candlestick(&self, symbol_interval_list: &[(String, CandleInterval)])`
 //!
 //! They are easier to use and cover most user scenarios.
 //!
@@ -58,14 +58,46 @@
 //!   original orderbook.
 //! * Level2 data is aggregated by price level, updated per tick.
 //! * Level3 data is the original orderbook, which is not aggregated.
+//!
+//! ## Record and Replay
+//!
+//! Clients created inside `record_to(dir, ...)` write inbound and outbound
+//! frames of each connection into a new file in `dir`. If the environment
+//! variable `CRYPTO_WS_RECORD_DIR` is set, clients created elsewhere are
+//! recorded into this directory. `ReplayWSClient` reads such a file, passes the
+//! received messages through the message handler of the exchange and sends
+//! them into the channel again, either as fast as possible or at the recorded
+//! speed, which reproduces a session without network access.
+//!
+//! ## Metrics
+//!
+//! Implement `ConnectionObserver` and register it by
+//! `set_connection_observer()` to receive connects, disconnects, reconnect
+//! attempts, traffic per channel, heartbeats and uplink rate limiter waits of
+//! all clients created afterwards. `PrometheusExporter` is a ready-made
+//! observer which serves these metrics in the Prometheus text format.
 
 mod clients;
 mod common;
 
-pub use common::ws_client::WSClient;
+pub use common::{
+    candle_interval::CandleInterval,
+    checksum::{ChecksumConfig, ChecksumMismatch},
+    error::Error,
+    metrics::{set_connection_observer, ConnectionInfo, ConnectionObserver, DisconnectReason},
+    prometheus::PrometheusExporter,
+    recorder::record_to,
+    replay::{ReplaySpeed, ReplayWSClient},
+    ws_client::WSClient,
+};
 
 pub use clients::{
     binance::*, binance_option::*, bitfinex::*, bitget::*, bithumb::*, bitmex::*, bitstamp::*,
-    bitz::*, bybit::*, coinbase_pro::*, deribit::*, dydx::*, ftx::*, gate::*, huobi::*, kraken::*,
-    kucoin::*, mexc::*, okx::*, zb::*, zbg::*,
+    bitz::*, bybit::*, coinbase::*, coinbase_pro::*, deribit::*, dydx::*, ftx::*, gate::*,
+    huobi::*, kraken::*, kucoin::*, mexc::*, okx::*, supported_candlestick_intervals, zb::*,
+    zbg::*,
 };
+
+#[cfg(feature = "fuzzing")]
+#[doc(hidden)]
+pub use clients::{fuzz_message_handler, MESSAGE_HANDLERS};
@@ -17,6 +17,29 @@ mod binance_spot {
         );
     }
 
+    #[tokio::test(flavor = "multi_thread")]
+    async fn subscribe_in_url() {
+        let (tx, rx) = std::sync::mpsc::channel();
+        tokio::task::spawn(async move {
+            let ws_clients = BinanceSpotWSClient::new_with_topics(
+                tx,
+                &[
+                    ("aggTrade".to_string(), "BTCUSDT".to_string()),
+                    ("ticker".to_string(), "BTCUSDT".to_string()),
+                ],
+            )
+            .await;
+            assert_eq!(1, ws_clients.len());
+            let ws_client = &ws_clients[0];
+            // run for 60 seconds at most
+            let _ = tokio::time::timeout(std::time::Duration::from_secs(60), ws_client.run()).await;
+            ws_client.close().await;
+        });
+
+        let msg = rx.recv().unwrap();
+        assert!(msg.contains("btcusdt@"));
+    }
+
     #[ignore = "!bookTicker has been removed since December 7, 2022"]
     #[tokio::test(flavor = "multi_thread")]
     async fn subscribe_all_bbo() {
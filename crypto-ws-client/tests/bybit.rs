@@ -120,3 +120,97 @@ mod bybit_linear_swap {
         );
     }
 }
+
+#[cfg(test)]
+mod bybit_v5_spot {
+    use crypto_ws_client::{BybitV5SpotWSClient, WSClient};
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn subscribe() {
+        gen_test_code!(
+            BybitV5SpotWSClient,
+            subscribe,
+            &[("publicTrade".to_string(), "BTCUSDT".to_string())]
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn subscribe_trade() {
+        gen_test_code!(BybitV5SpotWSClient, subscribe_trade, &["BTCUSDT".to_string()]);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn subscribe_bbo() {
+        gen_test_code!(BybitV5SpotWSClient, subscribe_bbo, &["BTCUSDT".to_string()]);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn subscribe_orderbook() {
+        gen_test_code!(BybitV5SpotWSClient, subscribe_orderbook, &["BTCUSDT".to_string()]);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn subscribe_ticker() {
+        gen_test_code!(BybitV5SpotWSClient, subscribe_ticker, &["BTCUSDT".to_string()]);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn subscribe_candlestick() {
+        gen_test_subscribe_candlestick!(BybitV5SpotWSClient, &[("BTCUSDT".to_string(), 60)]);
+    }
+}
+
+#[cfg(test)]
+mod bybit_v5_linear {
+    use crypto_ws_client::{BybitV5LinearWSClient, WSClient};
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn subscribe_trade() {
+        gen_test_code!(BybitV5LinearWSClient, subscribe_trade, &["BTCUSDT".to_string()]);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn subscribe_orderbook() {
+        gen_test_code!(BybitV5LinearWSClient, subscribe_orderbook, &["BTCUSDT".to_string()]);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn subscribe_ticker() {
+        gen_test_code!(BybitV5LinearWSClient, subscribe_ticker, &["BTCUSDT".to_string()]);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn subscribe_candlestick() {
+        gen_test_subscribe_candlestick!(BybitV5LinearWSClient, &[("BTCUSDT".to_string(), 60)]);
+    }
+}
+
+#[cfg(test)]
+mod bybit_v5_inverse {
+    use crypto_ws_client::{BybitV5InverseWSClient, WSClient};
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn subscribe_trade() {
+        gen_test_code!(BybitV5InverseWSClient, subscribe_trade, &["BTCUSD".to_string()]);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn subscribe_orderbook() {
+        gen_test_code!(BybitV5InverseWSClient, subscribe_orderbook, &["BTCUSD".to_string()]);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn subscribe_ticker() {
+        gen_test_code!(BybitV5InverseWSClient, subscribe_ticker, &["BTCUSD".to_string()]);
+    }
+}
+
+#[cfg(test)]
+mod bybit_v5_option {
+    use crypto_ws_client::{BybitV5OptionWSClient, WSClient};
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn subscribe_trade() {
+        gen_test_code!(BybitV5OptionWSClient, subscribe_trade, &["BTC".to_string()]);
+    }
+}
@@ -0,0 +1,57 @@
+use crypto_ws_client::{CoinbaseWSClient, WSClient};
+
+#[macro_use]
+mod utils;
+
+#[tokio::test(flavor = "multi_thread")]
+async fn subscribe() {
+    gen_test_code!(
+        CoinbaseWSClient,
+        subscribe,
+        &[
+            ("market_trades".to_string(), "BTC-USD".to_string()),
+            ("ticker".to_string(), "BTC-USD".to_string())
+        ]
+    );
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn subscribe_raw_json() {
+    gen_test_code!(
+        CoinbaseWSClient,
+        send,
+        &[r#"{"type":"subscribe","product_ids":["BTC-USD"],"channel":"market_trades"}"#
+            .to_string()]
+    );
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn subscribe_trade() {
+    gen_test_code!(
+        CoinbaseWSClient,
+        subscribe_trade,
+        &["BTC-USD".to_string(), "ETH-USD".to_string()]
+    );
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn subscribe_ticker() {
+    gen_test_code!(
+        CoinbaseWSClient,
+        subscribe_ticker,
+        &["BTC-USD".to_string(), "ETH-USD".to_string()]
+    );
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn subscribe_orderbook() {
+    gen_test_code!(CoinbaseWSClient, subscribe_orderbook, &["BTC-USD".to_string()]);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn subscribe_candlestick() {
+    gen_test_subscribe_candlestick!(
+        CoinbaseWSClient,
+        &[("BTC-USD".to_string(), 300), ("ETH-USD".to_string(), 300)]
+    );
+}
@@ -11,8 +11,8 @@ mod dydx_linear_swap {
             DydxSwapWSClient,
             send,
             &[
-                r#"{"type": "subscribe", "channel": "v3_trades", "id": "BTC-USD"}"#.to_string(),
-                r#"{"type": "subscribe", "channel": "v3_trades", "id": "ETH-USD"}"#.to_string()
+                r#"{"type": "subscribe", "channel": "v4_trades", "id": "BTC-USD"}"#.to_string(),
+                r#"{"type": "subscribe", "channel": "v4_trades", "id": "ETH-USD"}"#.to_string()
             ]
         );
     }
@@ -34,4 +34,21 @@ mod dydx_linear_swap {
             &["BTC-USD".to_string(), "ETH-USD".to_string()]
         );
     }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn subscribe_candlestick() {
+        gen_test_subscribe_candlestick!(
+            DydxSwapWSClient,
+            &[("BTC-USD".to_string(), 60), ("ETH-USD".to_string(), 300)]
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn subscribe_markets() {
+        gen_test_code!(
+            DydxSwapWSClient,
+            send,
+            &[r#"{"type": "subscribe", "channel": "v4_markets"}"#.to_string()]
+        );
+    }
 }
@@ -79,6 +79,59 @@ mod kraken_spot {
     }
 }
 
+#[cfg(test)]
+mod kraken_spot_v2 {
+    use crypto_ws_client::{KrakenSpotV2WSClient, WSClient};
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn subscribe() {
+        gen_test_code!(
+            KrakenSpotV2WSClient,
+            subscribe,
+            &[
+                ("trade".to_string(), "BTC/USD".to_string()),
+                ("ticker".to_string(), "BTC/USD".to_string()),
+                ("book.10".to_string(), "BTC/USD".to_string())
+            ]
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn subscribe_trade() {
+        gen_test_code!(
+            KrakenSpotV2WSClient,
+            subscribe_trade,
+            &["BTC/USD".to_string(), "ETH/USD".to_string()]
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn subscribe_ticker() {
+        gen_test_code!(
+            KrakenSpotV2WSClient,
+            subscribe_ticker,
+            &["BTC/USD".to_string(), "ETH/USD".to_string()]
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn subscribe_orderbook() {
+        gen_test_code!(
+            KrakenSpotV2WSClient,
+            subscribe_orderbook,
+            &["BTC/USD".to_string(), "ETH/USD".to_string()]
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn subscribe_candlestick() {
+        gen_test_subscribe_candlestick!(
+            KrakenSpotV2WSClient,
+            &[("BTC/USD".to_string(), 60), ("ETH/USD".to_string(), 60)]
+        );
+    }
+}
+
 #[cfg(test)]
 mod kraken_inverse_swap {
     use crypto_ws_client::{KrakenFuturesWSClient, WSClient};
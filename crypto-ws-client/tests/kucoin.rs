@@ -68,6 +68,17 @@ mod kucoin_spot {
             &[("BTC-USDT".to_string(), 60), ("BTC-USDT".to_string(), 604800)]
         );
     }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn try_new_unreachable() {
+        // a port nobody listens on
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("ws://{}", listener.local_addr().unwrap());
+        drop(listener);
+
+        let (tx, _rx) = std::sync::mpsc::channel();
+        assert!(KuCoinSpotWSClient::try_new(tx, Some(&url)).await.is_err());
+    }
 }
 
 #[cfg(test)]
@@ -0,0 +1,83 @@
+//! Metrics reported to `ConnectionObserver`, see the crypto-ws-mock crate.
+#[macro_use]
+mod utils;
+
+use std::{sync::Arc, time::Duration};
+
+use crypto_ws_client::{
+    set_connection_observer, BinanceSpotWSClient, HuobiSpotWSClient, OkxWSClient,
+    PrometheusExporter, WSClient,
+};
+use crypto_ws_mock::{exchanges, Frame, MockServer};
+
+// The observer is global, so everything runs in one test.
+#[tokio::test(flavor = "multi_thread")]
+async fn prometheus_exporter() {
+    let exporter = Arc::new(PrometheusExporter::new());
+    set_connection_observer(exporter.clone());
+
+    // huobi sends gzip compressed frames and ping messages
+    let server = MockServer::start(exchanges::huobi()).await;
+    let (tx, rx) = std::sync::mpsc::channel();
+    let ws_client = Arc::new(HuobiSpotWSClient::new(tx, Some(server.url())).await);
+    ws_client.subscribe_trade(&["btcusdt".to_string()]).await;
+    let handle = tokio::task::spawn({
+        let ws_client = ws_client.clone();
+        async move { ws_client.run().await }
+    });
+    assert!(rx.recv_timeout(Duration::from_secs(10)).is_ok());
+    for _ in 0..100 {
+        if server.received().texts.iter().any(|txt| txt.contains("pong")) {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+
+    let text = exporter.render();
+    assert!(text.contains("crypto_ws_connects_total{exchange=\"huobi\"} 1\n"));
+    assert!(text.contains("crypto_ws_connections{exchange=\"huobi\"} 1\n"));
+    assert!(text.contains(
+        "crypto_ws_messages_total{exchange=\"huobi\",channel=\"trade.detail\",direction=\"in\"} 1\n"
+    ));
+    assert!(text.contains(
+        "crypto_ws_messages_total{exchange=\"huobi\",channel=\"trade.detail\",direction=\"out\"} 1\n"
+    ));
+    // the ack and the ping message
+    assert!(text.contains(
+        "crypto_ws_messages_total{exchange=\"huobi\",channel=\"\",direction=\"in\"} 2\n"
+    ));
+    assert!(text.contains("crypto_ws_last_message_age_seconds{exchange=\"huobi\"} "));
+
+    ws_client.close().await;
+    tokio::time::timeout(Duration::from_secs(10), handle).await.unwrap().unwrap();
+    let text = exporter.render();
+    assert!(text.contains("crypto_ws_disconnects_total{exchange=\"huobi\",reason=\"closed\"} 1\n"));
+    assert!(!text.contains("crypto_ws_connections{exchange=\"huobi\"}"));
+
+    // binance drops the connection after replying
+    let server = MockServer::start(exchanges::binance().then(vec![Frame::Disconnect])).await;
+    let (tx, rx) = std::sync::mpsc::channel();
+    let ws_client = BinanceSpotWSClient::new(tx, Some(server.url())).await;
+    ws_client.subscribe_trade(&["BTCUSDT".to_string()]).await;
+    tokio::time::timeout(Duration::from_secs(10), ws_client.run()).await.unwrap();
+    assert!(rx.try_recv().is_ok());
+    let _ws_client =
+        BinanceSpotWSClient::new(std::sync::mpsc::channel().0, Some(server.url())).await;
+
+    let text = exporter.render();
+    assert!(text.contains(
+        "crypto_ws_disconnects_total{exchange=\"binance\",reason=\"connection_lost\"} 1\n"
+    ));
+    assert!(text.contains("crypto_ws_reconnect_attempts_total{exchange=\"binance\"} 1\n"));
+    assert!(text.contains("crypto_ws_connects_total{exchange=\"binance\"} 2\n"));
+    assert!(text.contains(
+        "crypto_ws_messages_total{exchange=\"binance\",channel=\"aggTrade\",direction=\"in\"} 1\n"
+    ));
+    // okx replies with an error to unknown channels
+    let server = MockServer::start(exchanges::okx()).await;
+    let ws_client = OkxWSClient::new(std::sync::mpsc::channel().0, Some(server.url())).await;
+    ws_client.subscribe(&[("nonexist".to_string(), "BTC-USDT".to_string())]).await;
+    tokio::time::timeout(Duration::from_secs(10), ws_client.run()).await.unwrap();
+    let text = exporter.render();
+    assert!(text.contains("crypto_ws_disconnects_total{exchange=\"okx\",reason=\"error\"} 1\n"));
+}
@@ -0,0 +1,797 @@
+//! Offline tests against local mock exchanges, see the crypto-ws-mock crate.
+#[macro_use]
+mod utils;
+
+use std::{
+    sync::{Arc, Mutex, OnceLock},
+    time::Duration,
+};
+
+use crypto_ws_client::{
+    set_connection_observer, ConnectionInfo, ConnectionObserver, DisconnectReason,
+};
+use crypto_ws_mock::{exchanges, Frame, MockExchange, MockServer, Received};
+
+// Starts a mock server and a client connected to it, returns the server, the
+// receiving part of messages and the handle of the task running the client.
+macro_rules! start_mock_client {
+    ($client:ident, $exchange:expr, $func_name:ident, $symbols:expr) => {{
+        let server = MockServer::start($exchange).await;
+        let url = server.url().to_string();
+        let (tx, rx) = std::sync::mpsc::channel();
+        let handle = tokio::task::spawn(async move {
+            let ws_client = $client::new(tx, Some(&url)).await;
+            ws_client.$func_name($symbols).await;
+            ws_client.run().await;
+            ws_client.close().await;
+        });
+        (server, rx, handle)
+    }};
+}
+
+// Waits until the server received a message satisfying the predicate.
+async fn wait_until(
+    server: &MockServer,
+    predicate: impl Fn(&Received) -> bool,
+) {
+    for _ in 0..100 {
+        if predicate(&server.received()) {
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    panic!("Timed out, received {:?}", server.received());
+}
+
+// Records disconnect reasons of all connections
+#[derive(Default)]
+struct DisconnectRecorder(Mutex<Vec<(String, DisconnectReason)>>);
+
+impl ConnectionObserver for DisconnectRecorder {
+    fn on_disconnect(&self, conn: &ConnectionInfo, reason: &DisconnectReason) {
+        self.0.lock().unwrap().push((conn.url.clone(), reason.clone()));
+    }
+}
+
+// Disconnect reasons of connections to the server, call it before connecting
+fn disconnect_reasons(server: &MockServer) -> Vec<DisconnectReason> {
+    static RECORDER: OnceLock<Arc<DisconnectRecorder>> = OnceLock::new();
+    let recorder = RECORDER.get_or_init(|| {
+        let recorder = Arc::new(DisconnectRecorder::default());
+        set_connection_observer(recorder.clone());
+        recorder
+    });
+    let reasons = recorder.0.lock().unwrap();
+    reasons
+        .iter()
+        .filter(|(url, _)| url.starts_with(server.url()))
+        .map(|(_, reason)| reason.clone())
+        .collect()
+}
+
+#[cfg(test)]
+mod binance_spot {
+    use super::*;
+    use crypto_ws_client::{BinanceSpotWSClient, WSClient};
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn subscribe_trade() {
+        let (server, rx, _) = start_mock_client!(
+            BinanceSpotWSClient,
+            exchanges::binance(),
+            subscribe_trade,
+            &["BTCUSDT".to_string()]
+        );
+        let msg = rx.recv_timeout(Duration::from_secs(10)).unwrap();
+        assert!(msg.contains(r#""stream":"btcusdt@aggTrade""#));
+        assert_eq!(
+            vec![r#"{"id":9527,"method":"SUBSCRIBE","params":["btcusdt@aggTrade"]}"#.to_string()],
+            server.received().texts
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn reply_ping_with_pong() {
+        let (server, _rx, _) = start_mock_client!(
+            BinanceSpotWSClient,
+            exchanges::binance(),
+            subscribe_trade,
+            &["BTCUSDT".to_string()]
+        );
+        // one unsolicited pong on start, one reply to the ping frame
+        wait_until(&server, |received| received.pongs >= 2).await;
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn unknown_channel() {
+        let (_server, _rx, handle) = start_mock_client!(
+            BinanceSpotWSClient,
+            exchanges::binance(),
+            subscribe,
+            &[("nonExist".to_string(), "BTCUSDT".to_string())]
+        );
+        // run() returns on errors from the exchange
+        tokio::time::timeout(Duration::from_secs(10), handle).await.unwrap().unwrap();
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn disconnect() {
+        let (_server, rx, handle) = start_mock_client!(
+            BinanceSpotWSClient,
+            exchanges::binance().then(vec![Frame::Disconnect]),
+            subscribe_trade,
+            &["BTCUSDT".to_string()]
+        );
+        assert!(rx.recv_timeout(Duration::from_secs(10)).is_ok());
+        // run() returns once the connection is dropped
+        tokio::time::timeout(Duration::from_secs(10), handle).await.unwrap().unwrap();
+    }
+}
+
+#[cfg(test)]
+mod huobi_spot {
+    use super::*;
+    use crypto_ws_client::{HuobiSpotWSClient, WSClient};
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn subscribe_trade() {
+        let (server, rx, _) = start_mock_client!(
+            HuobiSpotWSClient,
+            exchanges::huobi(),
+            subscribe_trade,
+            &["btcusdt".to_string()]
+        );
+        let msg = rx.recv_timeout(Duration::from_secs(10)).unwrap();
+        assert!(msg.contains(r#""ch":"market.btcusdt.trade.detail""#));
+        assert_eq!(
+            r#"{"sub":"market.btcusdt.trade.detail","id":"crypto-ws-client"}"#,
+            server.received().texts[0]
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn reply_ping_with_pong() {
+        let (server, _rx, _) = start_mock_client!(
+            HuobiSpotWSClient,
+            exchanges::huobi(),
+            subscribe_trade,
+            &["btcusdt".to_string()]
+        );
+        wait_until(&server, |received| {
+            received.texts.iter().any(|txt| txt == r#"{"pong":1677000005000}"#)
+        })
+        .await;
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn unknown_channel() {
+        let (_server, _rx, handle) = start_mock_client!(
+            HuobiSpotWSClient,
+            exchanges::huobi(),
+            subscribe,
+            &[("nonexist".to_string(), "btcusdt".to_string())]
+        );
+        // run() returns on errors from the exchange
+        tokio::time::timeout(Duration::from_secs(10), handle).await.unwrap().unwrap();
+    }
+}
+
+#[cfg(test)]
+mod okx {
+    use super::*;
+    use crypto_ws_client::{OkxWSClient, WSClient};
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn subscribe_trade() {
+        let (server, rx, _) = start_mock_client!(
+            OkxWSClient,
+            exchanges::okx(),
+            subscribe_trade,
+            &["BTC-USDT".to_string()]
+        );
+        let msg = rx.recv_timeout(Duration::from_secs(10)).unwrap();
+        assert!(msg.contains(r#""tradeId":"412873156""#));
+        wait_until(&server, |received| received.texts.iter().any(|txt| txt == "ping")).await;
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn deflate() {
+        let trade = r#"{"arg":{"channel":"trades","instId":"BTC-USDT"},"data":[]}"#;
+        let exchange = MockExchange::new(move |msg| {
+            if msg.contains("subscribe") {
+                vec![Frame::Deflate(trade.to_string())]
+            } else {
+                vec![]
+            }
+        });
+        let (_server, rx, _) =
+            start_mock_client!(OkxWSClient, exchange, subscribe_trade, &["BTC-USDT".to_string()]);
+        assert_eq!(trade, rx.recv_timeout(Duration::from_secs(10)).unwrap());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn unknown_channel() {
+        let (_server, _rx, handle) = start_mock_client!(
+            OkxWSClient,
+            exchanges::okx(),
+            subscribe,
+            &[("nonexist".to_string(), "BTC-USDT".to_string())]
+        );
+        // run() returns on errors from the exchange
+        tokio::time::timeout(Duration::from_secs(10), handle).await.unwrap().unwrap();
+    }
+}
+
+#[cfg(test)]
+mod bitmex {
+    use super::*;
+    use crypto_ws_client::{BitmexWSClient, WSClient};
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn subscribe_trade() {
+        let (server, rx, _) = start_mock_client!(
+            BitmexWSClient,
+            exchanges::bitmex(),
+            subscribe_trade,
+            &["XBTUSD".to_string()]
+        );
+        let msg = rx.recv_timeout(Duration::from_secs(10)).unwrap();
+        assert!(msg.contains(r#""table":"trade""#));
+        assert!(server
+            .received()
+            .texts
+            .contains(&r#"{"op":"subscribe","args":["trade:XBTUSD"]}"#.to_string()));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn unknown_channel() {
+        let (_server, _rx, handle) = start_mock_client!(
+            BitmexWSClient,
+            exchanges::bitmex(),
+            subscribe,
+            &[("nonexist".to_string(), "XBTUSD".to_string())]
+        );
+        // run() returns on errors from the exchange
+        tokio::time::timeout(Duration::from_secs(10), handle).await.unwrap().unwrap();
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn close() {
+        let exchange = exchanges::bitmex().then(vec![Frame::Close]);
+        let server = MockServer::start(exchange).await;
+        assert!(disconnect_reasons(&server).is_empty());
+        let url = server.url().to_string();
+        let (tx, _rx) = std::sync::mpsc::channel();
+        let handle = tokio::task::spawn(async move {
+            let ws_client = BitmexWSClient::new(tx, Some(&url)).await;
+            ws_client.subscribe_trade(&["XBTUSD".to_string()]).await;
+            ws_client.run().await;
+        });
+        // run() returns on the close frame
+        tokio::time::timeout(Duration::from_secs(10), handle).await.unwrap().unwrap();
+        assert_eq!(
+            vec![DisconnectReason::CloseFrame(String::new())],
+            disconnect_reasons(&server)
+        );
+    }
+}
+
+#[cfg(test)]
+mod kraken_spot {
+    use super::*;
+    use crypto_ws_client::{KrakenSpotWSClient, WSClient};
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn subscribe_trade() {
+        let (server, rx, _) = start_mock_client!(
+            KrakenSpotWSClient,
+            exchanges::kraken(),
+            subscribe_trade,
+            &["XBT/USD".to_string()]
+        );
+        let msg = rx.recv_timeout(Duration::from_secs(10)).unwrap();
+        assert!(msg.ends_with(r#""trade","XBT/USD"]"#));
+        // heartbeats are answered with ping events
+        wait_until(&server, |received| {
+            received.texts.iter().any(|txt| txt.contains(r#""reqid": 9527"#))
+        })
+        .await;
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn unknown_channel() {
+        let (_server, _rx, handle) = start_mock_client!(
+            KrakenSpotWSClient,
+            exchanges::kraken(),
+            subscribe,
+            &[("nonexist".to_string(), "XBT/USD".to_string())]
+        );
+        // run() returns on errors from the exchange
+        tokio::time::timeout(Duration::from_secs(10), handle).await.unwrap().unwrap();
+    }
+}
+
+#[cfg(test)]
+mod coinbase {
+    use super::*;
+    use crypto_ws_client::{CoinbaseWSClient, WSClient};
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn subscribe_trade() {
+        let (_server, rx, _) = start_mock_client!(
+            CoinbaseWSClient,
+            exchanges::coinbase(),
+            subscribe_trade,
+            &["BTC-USD".to_string()]
+        );
+        let msg = rx.recv_timeout(Duration::from_secs(10)).unwrap();
+        assert!(msg.contains(r#""channel":"market_trades""#));
+        assert!(msg.contains(r#""sequence_num":1"#));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn unknown_channel() {
+        let (_server, _rx, handle) = start_mock_client!(
+            CoinbaseWSClient,
+            exchanges::coinbase(),
+            subscribe,
+            &[("nonexist".to_string(), "BTC-USD".to_string())]
+        );
+        // run() returns on errors from the exchange
+        tokio::time::timeout(Duration::from_secs(10), handle).await.unwrap().unwrap();
+    }
+}
+
+#[cfg(test)]
+mod dydx_swap {
+    use super::*;
+    use crypto_ws_client::{DydxSwapWSClient, WSClient};
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn subscribe_trade() {
+        let (_server, rx, _) = start_mock_client!(
+            DydxSwapWSClient,
+            exchanges::dydx(),
+            subscribe_trade,
+            &["BTC-USD".to_string()]
+        );
+        let msg = rx.recv_timeout(Duration::from_secs(10)).unwrap();
+        assert!(msg.contains(r#""type":"subscribed""#));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn unknown_channel() {
+        let (_server, _rx, handle) = start_mock_client!(
+            DydxSwapWSClient,
+            exchanges::dydx(),
+            subscribe,
+            &[("nonexist".to_string(), "BTC-USD".to_string())]
+        );
+        // run() returns on errors from the exchange
+        tokio::time::timeout(Duration::from_secs(10), handle).await.unwrap().unwrap();
+    }
+}
+
+#[cfg(test)]
+mod bitfinex {
+    use super::*;
+    use crypto_ws_client::{BitfinexWSClient, WSClient};
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn subscribe_trade() {
+        let (server, rx, _) = start_mock_client!(
+            BitfinexWSClient,
+            exchanges::bitfinex(),
+            subscribe_trade,
+            &["tBTCUSD".to_string()]
+        );
+        let msg = rx.recv_timeout(Duration::from_secs(10)).unwrap();
+        assert!(msg.starts_with(r#"[{"channel":"trades","symbol":"tBTCUSD"},"te","#));
+        assert!(server.received().texts.contains(
+            &r#"{"event": "subscribe", "channel": "trades", "symbol": "tBTCUSD"}"#.to_string()
+        ));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn unknown_channel() {
+        let (_server, _rx, handle) = start_mock_client!(
+            BitfinexWSClient,
+            exchanges::bitfinex(),
+            subscribe,
+            &[("nonexist".to_string(), "tBTCUSD".to_string())]
+        );
+        // run() returns on errors from the exchange
+        tokio::time::timeout(Duration::from_secs(10), handle).await.unwrap().unwrap();
+    }
+}
+
+#[cfg(test)]
+mod bitget_spot {
+    use super::*;
+    use crypto_ws_client::{BitgetSpotWSClient, WSClient};
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn subscribe_trade() {
+        let (server, rx, _) = start_mock_client!(
+            BitgetSpotWSClient,
+            exchanges::bitget(),
+            subscribe_trade,
+            &["BTCUSDT_SPBL".to_string()]
+        );
+        let msg = rx.recv_timeout(Duration::from_secs(10)).unwrap();
+        assert!(msg.contains(r#""action":"snapshot""#));
+        assert!(server.received().texts.contains(
+            &r#"{"op":"subscribe","args":[{"channel":"trade","instId":"BTCUSDT","instType":"SP"}]}"#
+                .to_string()
+        ));
+        wait_until(&server, |received| received.texts.iter().any(|txt| txt == "ping")).await;
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn unknown_channel() {
+        let (_server, _rx, handle) = start_mock_client!(
+            BitgetSpotWSClient,
+            exchanges::bitget(),
+            subscribe,
+            &[("nonexist".to_string(), "BTCUSDT_SPBL".to_string())]
+        );
+        // run() returns on errors from the exchange
+        tokio::time::timeout(Duration::from_secs(10), handle).await.unwrap().unwrap();
+    }
+}
+
+#[cfg(test)]
+mod bitstamp {
+    use super::*;
+    use crypto_ws_client::{BitstampWSClient, WSClient};
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn subscribe_trade() {
+        let (server, rx, _) = start_mock_client!(
+            BitstampWSClient,
+            exchanges::bitstamp(),
+            subscribe_trade,
+            &["btcusd".to_string()]
+        );
+        let msg = rx.recv_timeout(Duration::from_secs(10)).unwrap();
+        assert!(msg.contains(r#""channel":"live_trades_btcusd""#));
+        assert!(server.received().texts.contains(
+            &r#"{"event":"bts:subscribe","data":{"channel":"live_trades_btcusd"}}"#.to_string()
+        ));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn unknown_channel() {
+        let (_server, _rx, handle) = start_mock_client!(
+            BitstampWSClient,
+            exchanges::bitstamp(),
+            subscribe,
+            &[("nonexist".to_string(), "btcusd".to_string())]
+        );
+        // run() returns on errors from the exchange
+        tokio::time::timeout(Duration::from_secs(10), handle).await.unwrap().unwrap();
+    }
+}
+
+#[cfg(test)]
+mod bybit_v5 {
+    use super::*;
+    use crypto_ws_client::{BybitV5LinearWSClient, WSClient};
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn subscribe_trade() {
+        let (server, rx, _) = start_mock_client!(
+            BybitV5LinearWSClient,
+            exchanges::bybit(),
+            subscribe_trade,
+            &["BTCUSDT".to_string()]
+        );
+        let msg = rx.recv_timeout(Duration::from_secs(10)).unwrap();
+        assert!(msg.contains(r#""topic":"publicTrade.BTCUSDT""#));
+        wait_until(&server, |received| {
+            received.texts.iter().any(|txt| txt.contains(r#""op":"ping""#))
+        })
+        .await;
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn unknown_channel() {
+        let (_server, _rx, handle) = start_mock_client!(
+            BybitV5LinearWSClient,
+            exchanges::bybit(),
+            subscribe,
+            &[("nonexist".to_string(), "BTCUSDT".to_string())]
+        );
+        // run() returns on errors from the exchange
+        tokio::time::timeout(Duration::from_secs(10), handle).await.unwrap().unwrap();
+    }
+}
+
+#[cfg(test)]
+mod deribit {
+    use super::*;
+    use crypto_ws_client::{DeribitWSClient, WSClient};
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn subscribe_trade() {
+        let (server, rx, _) = start_mock_client!(
+            DeribitWSClient,
+            exchanges::deribit(),
+            subscribe_trade,
+            &["BTC-PERPETUAL".to_string()]
+        );
+        let msg = rx.recv_timeout(Duration::from_secs(10)).unwrap();
+        assert!(msg.contains(r#""channel":"trades.BTC-PERPETUAL.100ms""#));
+        // test requests are answered with public/test
+        wait_until(&server, |received| {
+            received.texts.iter().any(|txt| txt.contains("public/test"))
+        })
+        .await;
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn unknown_channel() {
+        let (_server, _rx, handle) = start_mock_client!(
+            DeribitWSClient,
+            exchanges::deribit(),
+            subscribe,
+            &[("nonexist".to_string(), "BTC-PERPETUAL".to_string())]
+        );
+        // run() returns on errors from the exchange
+        tokio::time::timeout(Duration::from_secs(10), handle).await.unwrap().unwrap();
+    }
+}
+
+#[cfg(test)]
+mod gate_spot {
+    use super::*;
+    use crypto_ws_client::{GateSpotWSClient, WSClient};
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn subscribe_trade() {
+        let (server, rx, _) = start_mock_client!(
+            GateSpotWSClient,
+            exchanges::gate(),
+            subscribe_trade,
+            &["BTC_USDT".to_string()]
+        );
+        let msg = rx.recv_timeout(Duration::from_secs(10)).unwrap();
+        assert!(msg.contains(r#""channel":"spot.trades""#));
+        wait_until(&server, |received| {
+            received.texts.iter().any(|txt| txt.contains(r#""channel":"spot.ping""#))
+        })
+        .await;
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn unknown_channel() {
+        let (_server, _rx, handle) = start_mock_client!(
+            GateSpotWSClient,
+            exchanges::gate(),
+            subscribe,
+            &[("nonexist".to_string(), "BTC_USDT".to_string())]
+        );
+        // run() returns on errors from the exchange
+        tokio::time::timeout(Duration::from_secs(10), handle).await.unwrap().unwrap();
+    }
+}
+
+#[cfg(test)]
+mod gate_linear_swap {
+    use super::*;
+    use crypto_ws_client::{GateLinearSwapWSClient, WSClient};
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn subscribe_trade() {
+        let (_server, rx, _) = start_mock_client!(
+            GateLinearSwapWSClient,
+            exchanges::gate(),
+            subscribe_trade,
+            &["BTC_USDT".to_string()]
+        );
+        let msg = rx.recv_timeout(Duration::from_secs(10)).unwrap();
+        assert!(msg.contains(r#""channel":"futures.trades""#));
+    }
+}
+
+#[cfg(test)]
+mod kucoin_spot {
+    use super::*;
+    use crypto_ws_client::{KuCoinSpotWSClient, WSClient};
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn subscribe_trade() {
+        let (server, rx, _) = start_mock_client!(
+            KuCoinSpotWSClient,
+            exchanges::kucoin(),
+            subscribe_trade,
+            &["BTC-USDT".to_string()]
+        );
+        let msg = rx.recv_timeout(Duration::from_secs(10)).unwrap();
+        assert!(msg.contains(r#""topic":"/market/match:BTC-USDT""#));
+        wait_until(&server, |received| {
+            received.texts.iter().any(|txt| txt.contains(r#""type":"ping""#))
+        })
+        .await;
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn unknown_channel() {
+        let (_server, _rx, handle) = start_mock_client!(
+            KuCoinSpotWSClient,
+            exchanges::kucoin(),
+            subscribe,
+            &[("/nonexist".to_string(), "BTC-USDT".to_string())]
+        );
+        // run() returns on errors from the exchange
+        tokio::time::timeout(Duration::from_secs(10), handle).await.unwrap().unwrap();
+    }
+}
+
+#[cfg(test)]
+mod mexc_spot {
+    use super::*;
+    use crypto_ws_client::{MexcSpotWSClient, WSClient};
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn subscribe_trade() {
+        let (server, rx, _) = start_mock_client!(
+            MexcSpotWSClient,
+            exchanges::mexc_spot(),
+            subscribe_trade,
+            &["BTC_USDT".to_string()]
+        );
+        let msg = rx.recv_timeout(Duration::from_secs(10)).unwrap();
+        assert!(msg.contains(r#""channel":"push.deal""#));
+        assert!(server
+            .received()
+            .texts
+            .contains(&r#"{"op":"sub.deal","symbol":"BTC_USDT"}"#.to_string()));
+    }
+}
+
+#[cfg(test)]
+mod mexc_swap {
+    use super::*;
+    use crypto_ws_client::{MexcSwapWSClient, WSClient};
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn subscribe_trade() {
+        let (_server, rx, _) = start_mock_client!(
+            MexcSwapWSClient,
+            exchanges::mexc_swap(),
+            subscribe_trade,
+            &["BTC_USDT".to_string()]
+        );
+        let msg = rx.recv_timeout(Duration::from_secs(10)).unwrap();
+        assert!(msg.contains(r#""channel":"push.deal""#));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn unknown_channel() {
+        let (_server, _rx, handle) = start_mock_client!(
+            MexcSwapWSClient,
+            exchanges::mexc_swap(),
+            subscribe,
+            &[("nonexist".to_string(), "BTC_USDT".to_string())]
+        );
+        // run() returns on errors from the exchange
+        tokio::time::timeout(Duration::from_secs(10), handle).await.unwrap().unwrap();
+    }
+}
+
+#[cfg(test)]
+mod zb_spot {
+    use super::*;
+    use crypto_ws_client::{WSClient, ZbSpotWSClient};
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn subscribe_trade() {
+        let (_server, rx, _) = start_mock_client!(
+            ZbSpotWSClient,
+            exchanges::zb(),
+            subscribe_trade,
+            &["btc_usdt".to_string()]
+        );
+        let msg = rx.recv_timeout(Duration::from_secs(10)).unwrap();
+        assert!(msg.contains(r#""channel":"btcusdt_trades""#));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn unknown_channel() {
+        let (_server, _rx, handle) = start_mock_client!(
+            ZbSpotWSClient,
+            exchanges::zb(),
+            subscribe,
+            &[("nonexist".to_string(), "btc_usdt".to_string())]
+        );
+        // run() returns on errors from the exchange
+        tokio::time::timeout(Duration::from_secs(10), handle).await.unwrap().unwrap();
+    }
+}
+
+#[cfg(test)]
+mod zbg_spot {
+    use super::*;
+    use crypto_ws_client::{WSClient, ZbgSpotWSClient};
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn subscribe_trade() {
+        let (_server, rx, _) = start_mock_client!(
+            ZbgSpotWSClient,
+            exchanges::zbg(),
+            subscribe_trade,
+            &["btc_usdt".to_string()]
+        );
+        let msg = rx.recv_timeout(Duration::from_secs(10)).unwrap();
+        assert!(msg.starts_with(r#"["T","329""#));
+    }
+}
+
+#[cfg(test)]
+mod bithumb {
+    use super::*;
+    use crypto_ws_client::{BithumbWSClient, WSClient};
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn subscribe_trade() {
+        let (server, rx, _) = start_mock_client!(
+            BithumbWSClient,
+            exchanges::bithumb(),
+            subscribe_trade,
+            &["BTC-USDT".to_string()]
+        );
+        let msg = rx.recv_timeout(Duration::from_secs(10)).unwrap();
+        assert!(msg.contains(r#""topic":"TRADE""#));
+        assert!(server
+            .received()
+            .texts
+            .contains(&r#"{"cmd":"subscribe","args":["TRADE:BTC-USDT"]}"#.to_string()));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn unknown_channel() {
+        let (_server, _rx, handle) = start_mock_client!(
+            BithumbWSClient,
+            exchanges::bithumb(),
+            subscribe,
+            &[("NONEXIST".to_string(), "BTC-USDT".to_string())]
+        );
+        // run() returns on errors from the exchange
+        tokio::time::timeout(Duration::from_secs(10), handle).await.unwrap().unwrap();
+    }
+}
+
+#[cfg(test)]
+mod binance_option {
+    use super::*;
+    use crypto_ws_client::{BinanceOptionWSClient, WSClient};
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn subscribe_trade() {
+        let (server, rx, _) = start_mock_client!(
+            BinanceOptionWSClient,
+            exchanges::binance_option(),
+            subscribe_trade,
+            &["BTC-230331-30000-C".to_string()]
+        );
+        let msg = rx.recv_timeout(Duration::from_secs(10)).unwrap();
+        assert!(msg.contains(r#""stream":"BTC-230331-30000-C@trade""#));
+        assert!(server.received().texts.contains(
+            &r#"{"id":9527,"method":"SUBSCRIBE","params":["BTC-230331-30000-C@trade"]}"#
+                .to_string()
+        ));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn unknown_channel() {
+        let (_server, _rx, handle) = start_mock_client!(
+            BinanceOptionWSClient,
+            exchanges::binance_option(),
+            subscribe,
+            &[("nonexist".to_string(), "BTC-230331-30000-C".to_string())]
+        );
+        // run() returns on errors from the exchange
+        tokio::time::timeout(Duration::from_secs(10), handle).await.unwrap().unwrap();
+    }
+}
@@ -1,3 +1,4 @@
+#[allow(unused_macros)]
 macro_rules! gen_test_code {
     ($client:ident, $func_name:ident, $symbols:expr) => {
         let (tx, rx) = std::sync::mpsc::channel();
@@ -24,7 +25,13 @@ macro_rules! gen_test_subscribe_candlestick {
         let (tx, rx) = std::sync::mpsc::channel();
         tokio::task::spawn(async move {
             let ws_client = $client::new(tx, None).await;
-            ws_client.subscribe_candlestick($symbol_interval_list).await;
+            let symbol_interval_list = $symbol_interval_list
+                .iter()
+                .map(|(symbol, interval)| {
+                    (symbol.to_string(), crypto_ws_client::CandleInterval::from_secs(*interval))
+                })
+                .collect::<Vec<(String, crypto_ws_client::CandleInterval)>>();
+            ws_client.subscribe_candlestick(&symbol_interval_list).await.unwrap();
             // run for 60 seconds at most
             let _ = tokio::time::timeout(std::time::Duration::from_secs(60), ws_client.run()).await;
             ws_client.close().await;
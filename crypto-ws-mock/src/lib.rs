@@ -0,0 +1,767 @@
+//! A local websocket server which mimics exchanges, so that clients can be
+//! tested offline by passing `MockServer::url()` as the `url` parameter.
+
+use std::{
+    collections::HashMap,
+    io::Write,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+use flate2::{
+    write::{DeflateEncoder, GzEncoder},
+    Compression,
+};
+use futures_util::{SinkExt, StreamExt};
+use serde_json::{json, Value};
+use tokio::net::TcpListener;
+use tokio_tungstenite::tungstenite::Message;
+
+/// A frame sent by the mock server.
+#[derive(Clone, Debug)]
+pub enum Frame {
+    Text(String),
+    /// Text compressed by gzip, e.g., Huobi
+    Gzip(String),
+    /// Text compressed by raw deflate, e.g., the legacy OKEx API
+    Deflate(String),
+    /// A ping frame, which should be answered with a pong frame
+    Ping,
+    /// Sends a close frame
+    Close,
+    /// Drops the TCP connection without a close frame
+    Disconnect,
+    /// Waits before sending the next frame
+    Sleep(Duration),
+}
+
+type OnMessage = Arc<dyn Fn(&str) -> Vec<Frame> + Send + Sync>;
+
+/// The behavior of a mock exchange.
+#[derive(Clone)]
+pub struct MockExchange {
+    on_connect: Vec<Frame>,
+    on_message: OnMessage,
+    then: Vec<Frame>,
+}
+
+impl MockExchange {
+    /// `on_message` returns frames replying to a text message from the client,
+    /// e.g., acks, errors and data.
+    pub fn new(on_message: impl Fn(&str) -> Vec<Frame> + Send + Sync + 'static) -> Self {
+        MockExchange { on_connect: Vec::new(), on_message: Arc::new(on_message), then: Vec::new() }
+    }
+
+    /// Frames sent right after a client connects, e.g., welcome messages.
+    pub fn on_connect(mut self, frames: Vec<Frame>) -> Self {
+        self.on_connect = frames;
+        self
+    }
+
+    /// Frames appended to every non-empty reply, e.g., `Frame::Disconnect` to
+    /// drop the connection after the first data message.
+    pub fn then(mut self, frames: Vec<Frame>) -> Self {
+        self.then = frames;
+        self
+    }
+}
+
+/// Messages received by the mock server from all connections.
+#[derive(Clone, Debug, Default)]
+pub struct Received {
+    pub texts: Vec<String>,
+    pub pongs: usize,
+}
+
+pub struct MockServer {
+    url: String,
+    received: Arc<Mutex<Received>>,
+}
+
+impl MockServer {
+    /// Starts listening on a random local port.
+    pub async fn start(exchange: MockExchange) -> MockServer {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}", listener.local_addr().unwrap());
+        let received = Arc::new(Mutex::new(Received::default()));
+
+        let received_clone = received.clone();
+        tokio::task::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                tokio::task::spawn(serve(stream, exchange.clone(), received_clone.clone()));
+            }
+        });
+
+        MockServer { url, received }
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    pub fn received(&self) -> Received {
+        self.received.lock().unwrap().clone()
+    }
+}
+
+async fn serve(
+    stream: tokio::net::TcpStream,
+    exchange: MockExchange,
+    received: Arc<Mutex<Received>>,
+) {
+    let mut ws_stream = tokio_tungstenite::accept_async(stream).await.unwrap();
+    if !send_frames(&mut ws_stream, &exchange.on_connect).await {
+        return;
+    }
+    while let Some(Ok(msg)) = ws_stream.next().await {
+        match msg {
+            Message::Text(txt) => {
+                received.lock().unwrap().texts.push(txt.clone());
+                let mut frames = (exchange.on_message)(&txt);
+                if !frames.is_empty() {
+                    frames.extend(exchange.then.iter().cloned());
+                }
+                if !send_frames(&mut ws_stream, &frames).await {
+                    return;
+                }
+            }
+            Message::Pong(_) => received.lock().unwrap().pongs += 1,
+            Message::Close(_) => return,
+            _ => (),
+        }
+    }
+}
+
+// Returns false if the connection is closed
+async fn send_frames(
+    ws_stream: &mut tokio_tungstenite::WebSocketStream<tokio::net::TcpStream>,
+    frames: &[Frame],
+) -> bool {
+    for frame in frames {
+        let msg = match frame {
+            Frame::Text(txt) => Message::Text(txt.clone()),
+            Frame::Gzip(txt) => {
+                let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+                encoder.write_all(txt.as_bytes()).unwrap();
+                Message::Binary(encoder.finish().unwrap())
+            }
+            Frame::Deflate(txt) => {
+                let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+                encoder.write_all(txt.as_bytes()).unwrap();
+                Message::Binary(encoder.finish().unwrap())
+            }
+            Frame::Ping => Message::Ping(b"crypto-ws-client".to_vec()),
+            Frame::Close => {
+                _ = ws_stream.close(None).await;
+                // wait for the reply, so that the client reads the close frame
+                // before the connection is dropped
+                let drain = async { while let Some(Ok(_)) = ws_stream.next().await {} };
+                _ = tokio::time::timeout(Duration::from_secs(1), drain).await;
+                return false;
+            }
+            Frame::Disconnect => return false,
+            Frame::Sleep(duration) => {
+                tokio::time::sleep(*duration).await;
+                continue;
+            }
+        };
+        if ws_stream.send(msg).await.is_err() {
+            return false;
+        }
+    }
+    true
+}
+
+// Recorded messages keyed by channel
+fn load_fixtures(json: &str) -> HashMap<String, String> {
+    serde_json::from_str::<HashMap<String, Value>>(json)
+        .unwrap()
+        .into_iter()
+        .map(|(channel, msg)| (channel, msg.to_string()))
+        .collect()
+}
+
+/// Mock exchanges which understand the subscribe commands sent by clients,
+/// reply with acks, then recorded messages of subscribed channels, and with
+/// errors for unknown channels.
+pub mod exchanges {
+    use super::*;
+
+    /// Binance, sends a ping frame after every ack.
+    pub fn binance() -> MockExchange {
+        let fixtures = load_fixtures(include_str!("../fixtures/binance.json"));
+        MockExchange::new(move |msg| {
+            let command = serde_json::from_str::<Value>(msg).unwrap();
+            let id = command["id"].clone();
+            let mut frames = Vec::new();
+            for param in command["params"].as_array().unwrap() {
+                let channel = param.as_str().unwrap().split_once('@').unwrap().1;
+                match fixtures.get(channel) {
+                    Some(data) => frames.push(Frame::Text(data.clone())),
+                    None => {
+                        return vec![Frame::Text(
+                            json!({"error":{"code":2,"msg":format!("Invalid request: unknown variant `{channel}`")},"id":id})
+                                .to_string(),
+                        )]
+                    }
+                }
+            }
+            frames.insert(0, Frame::Text(json!({"result":null,"id":id}).to_string()));
+            frames.push(Frame::Ping);
+            frames
+        })
+    }
+
+    /// Huobi, all frames are gzip compressed and a ping message follows
+    /// every ack.
+    pub fn huobi() -> MockExchange {
+        let fixtures = load_fixtures(include_str!("../fixtures/huobi.json"));
+        MockExchange::new(move |msg| {
+            let command = serde_json::from_str::<Value>(msg).unwrap();
+            let topic = match command["sub"].as_str() {
+                Some(topic) => topic,
+                None => return Vec::new(), // pong
+            };
+            // market.btcusdt.trade.detail
+            let channel = topic.splitn(3, '.').nth(2).unwrap();
+            match fixtures.get(channel) {
+                Some(data) => vec![
+                    Frame::Gzip(
+                        json!({"id":command["id"],"status":"ok","subbed":topic,"ts":1677000000000i64})
+                            .to_string(),
+                    ),
+                    Frame::Gzip(data.clone()),
+                    Frame::Gzip(r#"{"ping":1677000005000}"#.to_string()),
+                ],
+                None => vec![Frame::Gzip(
+                    json!({"status":"error","ts":1677000000000i64,"id":command["id"],"err-code":"bad-request","err-msg":format!("invalid topic {topic}")})
+                        .to_string(),
+                )],
+            }
+        })
+    }
+
+    /// OKX, replies to the text ping of clients.
+    pub fn okx() -> MockExchange {
+        let fixtures = load_fixtures(include_str!("../fixtures/okx.json"));
+        MockExchange::new(move |msg| {
+            if msg == "ping" {
+                return vec![Frame::Text("pong".to_string())];
+            }
+            let command = serde_json::from_str::<Value>(msg).unwrap();
+            let mut frames = Vec::new();
+            for arg in command["args"].as_array().unwrap() {
+                let channel = arg["channel"].as_str().unwrap();
+                match fixtures.get(channel) {
+                    Some(data) => {
+                        frames.push(Frame::Text(json!({"event":"subscribe","arg":arg}).to_string()));
+                        frames.push(Frame::Text(data.clone()));
+                    }
+                    None => frames.push(Frame::Text(
+                        json!({"event":"error","code":"60018","msg":format!("Wrong URL or channel:{channel}, instId:{} doesn't exist.", arg["instId"].as_str().unwrap_or_default())})
+                            .to_string(),
+                    )),
+                }
+            }
+            frames
+        })
+    }
+
+    /// BitMEX, sends a welcome message and replies to the text ping of
+    /// clients.
+    pub fn bitmex() -> MockExchange {
+        let fixtures = load_fixtures(include_str!("../fixtures/bitmex.json"));
+        MockExchange::new(move |msg| {
+            if msg == "ping" {
+                return vec![Frame::Text("pong".to_string())];
+            }
+            let command = serde_json::from_str::<Value>(msg).unwrap();
+            let mut frames = Vec::new();
+            for arg in command["args"].as_array().unwrap() {
+                let topic = arg.as_str().unwrap();
+                let table = topic.split(':').next().unwrap();
+                match fixtures.get(table) {
+                    Some(data) => {
+                        frames.push(Frame::Text(
+                            json!({"success":true,"subscribe":topic,"request":command}).to_string(),
+                        ));
+                        frames.push(Frame::Text(data.clone()));
+                    }
+                    None => frames.push(Frame::Text(
+                        json!({"status":400,"error":format!("Unknown table: {table}"),"meta":{},"request":command})
+                            .to_string(),
+                    )),
+                }
+            }
+            frames
+        })
+        .on_connect(vec![Frame::Text(
+            r#"{"info":"Welcome to the BitMEX Realtime API.","version":"2.0.0","timestamp":"2023-02-21T17:20:00.000Z","docs":"https://www.bitmex.com/app/wsAPI","heartbeatEnabled":false,"limit":{"remaining":179}}"#
+                .to_string(),
+        )])
+    }
+
+    /// Kraken Spot v1, sends a system status on connect and a heartbeat after
+    /// every ack, answers ping events of clients.
+    pub fn kraken() -> MockExchange {
+        let fixtures = load_fixtures(include_str!("../fixtures/kraken.json"));
+        MockExchange::new(move |msg| {
+            let command = serde_json::from_str::<Value>(msg).unwrap();
+            if command["event"] == "ping" {
+                return vec![Frame::Text(
+                    json!({"event":"pong","reqid":command["reqid"]}).to_string(),
+                )];
+            }
+            let name = command["subscription"]["name"].as_str().unwrap();
+            let mut frames = Vec::new();
+            for pair in command["pair"].as_array().unwrap() {
+                match fixtures.get(name) {
+                    Some(data) => {
+                        frames.push(Frame::Text(
+                            json!({"channelID":337,"channelName":name,"event":"subscriptionStatus","pair":pair,"status":"subscribed","subscription":command["subscription"]})
+                                .to_string(),
+                        ));
+                        frames.push(Frame::Text(data.clone()));
+                    }
+                    None => frames.push(Frame::Text(
+                        json!({"errorMessage":"Subscription name invalid","event":"subscriptionStatus","pair":pair,"status":"error","subscription":command["subscription"]})
+                            .to_string(),
+                    )),
+                }
+            }
+            frames.push(Frame::Text(r#"{"event":"heartbeat"}"#.to_string()));
+            frames
+        })
+        .on_connect(vec![Frame::Text(
+            r#"{"connectionID":12345678901234567890,"event":"systemStatus","status":"online","version":"1.9.0"}"#
+                .to_string(),
+        )])
+    }
+
+    /// Coinbase Advanced Trade, every message carries an increasing
+    /// `sequence_num`, shared by all connections.
+    pub fn coinbase() -> MockExchange {
+        let fixtures = load_fixtures(include_str!("../fixtures/coinbase.json"));
+        let sequence_num = Arc::new(AtomicU64::new(0));
+        MockExchange::new(move |msg| {
+            let command = serde_json::from_str::<Value>(msg).unwrap();
+            let channel = command["channel"].as_str().unwrap();
+            let with_sequence_num = |data: &str| {
+                let mut obj = serde_json::from_str::<Value>(data).unwrap();
+                obj["sequence_num"] = json!(sequence_num.fetch_add(1, Ordering::SeqCst));
+                Frame::Text(obj.to_string())
+            };
+            match fixtures.get(channel) {
+                Some(data) => {
+                    let ack = json!({"channel":"subscriptions","client_id":"","timestamp":"2023-02-21T17:20:00.000Z","events":[{"subscriptions":{channel:command["product_ids"]}}]});
+                    vec![with_sequence_num(&ack.to_string()), with_sequence_num(data)]
+                }
+                None => vec![Frame::Text(
+                    json!({"type":"error","message":"failure to subscribe"}).to_string(),
+                )],
+            }
+        })
+    }
+
+    /// dYdX v4 indexer, the initial snapshot comes with the `subscribed`
+    /// message, followed by a ping frame.
+    pub fn dydx() -> MockExchange {
+        let fixtures = load_fixtures(include_str!("../fixtures/dydx.json"));
+        MockExchange::new(move |msg| {
+            let command = serde_json::from_str::<Value>(msg).unwrap();
+            let channel = command["channel"].as_str().unwrap();
+            match fixtures.get(channel) {
+                Some(data) => vec![Frame::Text(data.clone()), Frame::Ping],
+                None => vec![Frame::Text(
+                    json!({"type":"error","message":format!("Invalid subscribe message: channel {channel} is not valid"),"connection_id":"2d8e9c5a-0c1b-4c53-9d3e-6f2b1a4c0001","message_id":1})
+                        .to_string(),
+                )],
+            }
+        })
+        .on_connect(vec![Frame::Text(
+            r#"{"type":"connected","connection_id":"2d8e9c5a-0c1b-4c53-9d3e-6f2b1a4c0001","message_id":0}"#
+                .to_string(),
+        )])
+    }
+
+    /// Bitfinex, sends an info event on connect and a heartbeat after every
+    /// data message, all channels are assigned chanId 17.
+    pub fn bitfinex() -> MockExchange {
+        let fixtures = load_fixtures(include_str!("../fixtures/bitfinex.json"));
+        MockExchange::new(move |msg| {
+            let command = serde_json::from_str::<Value>(msg).unwrap();
+            match command["event"].as_str().unwrap() {
+                "ping" => vec![Frame::Text(json!({"event":"pong","ts":1677000000000i64}).to_string())],
+                "conf" => vec![Frame::Text(
+                    json!({"event":"conf","status":"OK","flags":command["flags"]}).to_string(),
+                )],
+                "subscribe" => {
+                    let channel = command["channel"].as_str().unwrap();
+                    match fixtures.get(channel) {
+                        Some(data) => vec![
+                            Frame::Text(
+                                json!({"event":"subscribed","channel":channel,"chanId":17,"symbol":command["symbol"],"pair":"BTCUSD"})
+                                    .to_string(),
+                            ),
+                            Frame::Text(data.clone()),
+                            Frame::Text("[17,\"hb\"]".to_string()),
+                        ],
+                        None => vec![Frame::Text(
+                            json!({"event":"error","msg":"subscribe: invalid","code":10300,"channel":channel,"symbol":command["symbol"]})
+                                .to_string(),
+                        )],
+                    }
+                }
+                _ => Vec::new(),
+            }
+        })
+        .on_connect(vec![Frame::Text(
+            r#"{"event":"info","version":2,"serverId":"b1d8a3c7-4f0e-4a8b-9c61-2f1e0d9b7a55","platform":{"status":1}}"#
+                .to_string(),
+        )])
+    }
+
+    /// Bitget, replies to the text ping of clients.
+    pub fn bitget() -> MockExchange {
+        let fixtures = load_fixtures(include_str!("../fixtures/bitget.json"));
+        MockExchange::new(move |msg| {
+            if msg == "ping" {
+                return vec![Frame::Text("pong".to_string())];
+            }
+            let command = serde_json::from_str::<Value>(msg).unwrap();
+            let mut frames = Vec::new();
+            for arg in command["args"].as_array().unwrap() {
+                let channel = arg["channel"].as_str().unwrap();
+                match fixtures.get(channel) {
+                    Some(data) => {
+                        frames.push(Frame::Text(json!({"event":"subscribe","arg":arg}).to_string()));
+                        frames.push(Frame::Text(data.clone()));
+                    }
+                    None => frames.push(Frame::Text(
+                        json!({"event":"error","code":30001,"msg":format!("instType:{},channel:{channel},instId:{} doesn't exist", arg["instType"].as_str().unwrap_or_default(), arg["instId"].as_str().unwrap_or_default())})
+                            .to_string(),
+                    )),
+                }
+            }
+            frames
+        })
+    }
+
+    /// Bitstamp, answers heartbeat events of clients.
+    pub fn bitstamp() -> MockExchange {
+        let fixtures = load_fixtures(include_str!("../fixtures/bitstamp.json"));
+        MockExchange::new(move |msg| {
+            let command = serde_json::from_str::<Value>(msg).unwrap();
+            if command["event"] == "bts:heartbeat" {
+                return vec![Frame::Text(
+                    json!({"event":"bts:heartbeat","channel":"","data":{"status":"success"}})
+                        .to_string(),
+                )];
+            }
+            // live_trades_btcusd
+            let channel = command["data"]["channel"].as_str().unwrap();
+            match fixtures.get(channel.rsplit_once('_').unwrap().0) {
+                Some(data) => vec![
+                    Frame::Text(
+                        json!({"event":"bts:subscription_succeeded","channel":channel,"data":{}})
+                            .to_string(),
+                    ),
+                    Frame::Text(data.clone()),
+                ],
+                None => vec![Frame::Text(
+                    json!({"event":"bts:error","channel":"","data":{"code":null,"message":"Bad subscription string."}})
+                        .to_string(),
+                )],
+            }
+        })
+    }
+
+    /// Bybit v5, replies to the ping op of clients.
+    pub fn bybit() -> MockExchange {
+        let fixtures = load_fixtures(include_str!("../fixtures/bybit.json"));
+        MockExchange::new(move |msg| {
+            let command = serde_json::from_str::<Value>(msg).unwrap();
+            let req_id = command["req_id"].clone();
+            if command["op"] == "ping" {
+                return vec![Frame::Text(
+                    json!({"success":true,"ret_msg":"pong","conn_id":"cfo7lr1s3c8c9k4ncjng","req_id":req_id,"op":"ping"})
+                        .to_string(),
+                )];
+            }
+            let mut frames = Vec::new();
+            for arg in command["args"].as_array().unwrap() {
+                // publicTrade.BTCUSDT
+                let topic = arg.as_str().unwrap();
+                match fixtures.get(topic.rsplit_once('.').unwrap().0) {
+                    Some(data) => frames.push(Frame::Text(data.clone())),
+                    None => {
+                        return vec![Frame::Text(
+                            json!({"success":false,"ret_msg":format!("error:handler not found,topic:{topic}"),"conn_id":"cfo7lr1s3c8c9k4ncjng","req_id":req_id,"op":"subscribe"})
+                                .to_string(),
+                        )]
+                    }
+                }
+            }
+            frames.insert(
+                0,
+                Frame::Text(
+                    json!({"success":true,"ret_msg":"","conn_id":"cfo7lr1s3c8c9k4ncjng","req_id":req_id,"op":"subscribe"})
+                        .to_string(),
+                ),
+            );
+            frames
+        })
+    }
+
+    /// Deribit, sends a `test_request` heartbeat once clients enable
+    /// heartbeats, and answers their `public/test` requests.
+    pub fn deribit() -> MockExchange {
+        let fixtures = load_fixtures(include_str!("../fixtures/deribit.json"));
+        MockExchange::new(move |msg| {
+            let command = serde_json::from_str::<Value>(msg).unwrap();
+            match command["method"].as_str().unwrap() {
+                "public/set_heartbeat" => vec![
+                    Frame::Text(r#"{"jsonrpc":"2.0","result":"ok","testnet":false}"#.to_string()),
+                    Frame::Text(
+                        r#"{"jsonrpc":"2.0","method":"heartbeat","params":{"type":"test_request"}}"#
+                            .to_string(),
+                    ),
+                ],
+                "public/test" => vec![Frame::Text(
+                    r#"{"jsonrpc":"2.0","result":{"version":"1.2.26"},"testnet":false}"#
+                        .to_string(),
+                )],
+                "public/subscribe" => {
+                    let channels = command["params"]["channels"].as_array().unwrap();
+                    let mut frames = Vec::new();
+                    for channel in channels {
+                        // trades.BTC-PERPETUAL.100ms
+                        let name = channel.as_str().unwrap().split('.').next().unwrap();
+                        match fixtures.get(name) {
+                            Some(data) => frames.push(Frame::Text(data.clone())),
+                            None => {
+                                return vec![Frame::Text(
+                                    json!({"jsonrpc":"2.0","error":{"message":"Invalid params","data":{"reason":"invalid channel","param":"channels"},"code":-32602},"testnet":false})
+                                        .to_string(),
+                                )]
+                            }
+                        }
+                    }
+                    frames.insert(
+                        0,
+                        Frame::Text(
+                            json!({"jsonrpc":"2.0","result":channels,"testnet":false}).to_string(),
+                        ),
+                    );
+                    frames
+                }
+                _ => Vec::new(),
+            }
+        })
+    }
+
+    /// Gate spot and futures, replies to the ping channels of clients.
+    pub fn gate() -> MockExchange {
+        let fixtures = load_fixtures(include_str!("../fixtures/gate.json"));
+        MockExchange::new(move |msg| {
+            let command = serde_json::from_str::<Value>(msg).unwrap();
+            // spot.trades, futures.ping
+            let channel = command["channel"].as_str().unwrap();
+            if let Some(prefix) = channel.strip_suffix(".ping") {
+                return vec![Frame::Text(
+                    json!({"time":1677000000,"channel":format!("{prefix}.pong"),"event":"","error":null,"result":null})
+                        .to_string(),
+                )];
+            }
+            match fixtures.get(channel) {
+                Some(data) => vec![
+                    Frame::Text(
+                        json!({"time":1677000000,"channel":channel,"event":"subscribe","error":null,"result":{"status":"success"}})
+                            .to_string(),
+                    ),
+                    Frame::Text(data.clone()),
+                ],
+                None => vec![Frame::Text(
+                    json!({"time":1677000000,"channel":channel,"event":"subscribe","error":{"code":2,"message":format!("unknown channel {channel}")},"result":null})
+                        .to_string(),
+                )],
+            }
+        })
+    }
+
+    /// KuCoin, sends a welcome message on connect and answers ping messages
+    /// of clients.
+    pub fn kucoin() -> MockExchange {
+        let fixtures = load_fixtures(include_str!("../fixtures/kucoin.json"));
+        MockExchange::new(move |msg| {
+            let command = serde_json::from_str::<Value>(msg).unwrap();
+            let id = command["id"].clone();
+            if command["type"] == "ping" {
+                return vec![Frame::Text(json!({"id":id,"type":"pong"}).to_string())];
+            }
+            // /market/match:BTC-USDT
+            let topic = command["topic"].as_str().unwrap();
+            match fixtures.get(topic.split_once(':').unwrap().0) {
+                Some(data) => vec![
+                    Frame::Text(json!({"id":id,"type":"ack"}).to_string()),
+                    Frame::Text(data.clone()),
+                ],
+                None => vec![Frame::Text(
+                    json!({"id":id,"type":"error","code":404,"data":format!("topic {topic} is not found")})
+                        .to_string(),
+                )],
+            }
+        })
+        .on_connect(vec![Frame::Text(r#"{"id":"hQvf8jkno","type":"welcome"}"#.to_string())])
+    }
+
+    /// MEXC spot, replies to the text ping of clients and ignores unknown
+    /// channels.
+    pub fn mexc_spot() -> MockExchange {
+        let fixtures = load_fixtures(include_str!("../fixtures/mexc.json"));
+        MockExchange::new(move |msg| {
+            if msg == "ping" {
+                return vec![Frame::Text("pong".to_string())];
+            }
+            let command = serde_json::from_str::<Value>(msg).unwrap();
+            // sub.deal
+            let channel = command["op"].as_str().unwrap().strip_prefix("sub.").unwrap();
+            match fixtures.get(&format!("spot.{channel}")) {
+                Some(data) => vec![Frame::Text(data.clone())],
+                None => Vec::new(),
+            }
+        })
+    }
+
+    /// MEXC swap, answers ping methods of clients.
+    pub fn mexc_swap() -> MockExchange {
+        let fixtures = load_fixtures(include_str!("../fixtures/mexc.json"));
+        MockExchange::new(move |msg| {
+            let command = serde_json::from_str::<Value>(msg).unwrap();
+            let method = command["method"].as_str().unwrap();
+            if method == "ping" {
+                return vec![Frame::Text(
+                    json!({"channel":"pong","data":1677000000000i64,"ts":1677000000000i64})
+                        .to_string(),
+                )];
+            }
+            let channel = method.strip_prefix("sub.").unwrap();
+            match fixtures.get(&format!("swap.{channel}")) {
+                Some(data) => vec![
+                    Frame::Text(
+                        json!({"channel":format!("rs.{method}"),"data":"success","ts":1677000000000i64})
+                            .to_string(),
+                    ),
+                    Frame::Text(data.clone()),
+                ],
+                None => vec![Frame::Text(
+                    json!({"channel":"rs.error","data":format!("unknown method {method}"),"ts":1677000000000i64})
+                        .to_string(),
+                )],
+            }
+        })
+    }
+
+    /// ZB spot, answers ping channels of clients.
+    pub fn zb() -> MockExchange {
+        let fixtures = load_fixtures(include_str!("../fixtures/zb.json"));
+        MockExchange::new(move |msg| {
+            let command = serde_json::from_str::<Value>(msg).unwrap();
+            // btcusdt_trades
+            let channel = command["channel"].as_str().unwrap();
+            if channel == "ping" {
+                return vec![Frame::Text(r#"{"channel":"pong","message":"pong"}"#.to_string())];
+            }
+            match fixtures.get(channel.split_once('_').unwrap().1) {
+                Some(data) => vec![Frame::Text(data.clone())],
+                None => vec![Frame::Text(
+                    json!({"channel":channel,"code":1007,"message":"Channel is empty","success":false})
+                        .to_string(),
+                )],
+            }
+        })
+    }
+
+    /// ZBG spot, echoes the ping action of clients and ignores unknown
+    /// channels. Commands are not valid JSON.
+    pub fn zbg() -> MockExchange {
+        let fixtures = load_fixtures(include_str!("../fixtures/zbg.json"));
+        MockExchange::new(move |msg| {
+            if msg.contains(r#""action":"PING""#) {
+                return vec![Frame::Text(msg.to_string())];
+            }
+            // {"action":"ADD", "dataType":329_TRADE_BTC_USDT}
+            let data_type = match msg.split_once(r#""dataType":"#) {
+                Some((_, data_type)) => data_type.trim_end_matches('}').trim(),
+                None => return Vec::new(),
+            };
+            match fixtures.get(data_type.split('_').nth(1).unwrap()) {
+                Some(data) => vec![Frame::Text(data.clone())],
+                None => Vec::new(),
+            }
+        })
+    }
+
+    /// Bithumb Pro, answers ping commands of clients.
+    pub fn bithumb() -> MockExchange {
+        let fixtures = load_fixtures(include_str!("../fixtures/bithumb.json"));
+        MockExchange::new(move |msg| {
+            let command = serde_json::from_str::<Value>(msg).unwrap();
+            if command["cmd"] == "ping" {
+                return vec![Frame::Text(
+                    r#"{"code":"00000","data":null,"msg":"Pong","timestamp":1677000000000}"#
+                        .to_string(),
+                )];
+            }
+            let mut frames = Vec::new();
+            for arg in command["args"].as_array().unwrap() {
+                // TRADE:BTC-USDT
+                let topic = arg.as_str().unwrap().split(':').next().unwrap();
+                match fixtures.get(topic) {
+                    Some(data) => {
+                        frames.push(Frame::Text(
+                            json!({"code":"00001","data":null,"msg":"Subscribed successfully","timestamp":1677000000000i64,"topic":topic})
+                                .to_string(),
+                        ));
+                        frames.push(Frame::Text(data.clone()));
+                    }
+                    None => frames.push(Frame::Text(
+                        json!({"code":"10002","data":null,"msg":format!("Invalid topic {topic}"),"timestamp":1677000000000i64})
+                            .to_string(),
+                    )),
+                }
+            }
+            frames
+        })
+    }
+
+    /// Binance European options, answers ping events of clients.
+    pub fn binance_option() -> MockExchange {
+        let fixtures = load_fixtures(include_str!("../fixtures/binance_option.json"));
+        MockExchange::new(move |msg| {
+            let command = serde_json::from_str::<Value>(msg).unwrap();
+            if command["event"] == "ping" {
+                return vec![Frame::Text(r#"{"event":"pong"}"#.to_string())];
+            }
+            let id = command["id"].clone();
+            let mut frames = Vec::new();
+            for param in command["params"].as_array().unwrap() {
+                let channel = param.as_str().unwrap().split_once('@').unwrap().1;
+                match fixtures.get(channel) {
+                    Some(data) => frames.push(Frame::Text(data.clone())),
+                    None => {
+                        return vec![Frame::Text(
+                            json!({"code":2,"msg":format!("Invalid request: unknown variant `{channel}`"),"id":id})
+                                .to_string(),
+                        )]
+                    }
+                }
+            }
+            frames.insert(0, Frame::Text(json!({"id":id}).to_string()));
+            frames
+        })
+    }
+}